@@ -0,0 +1,129 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use glslang_sys_2022 as glslang_sys;
+use glslang_sys::*;
+
+const SMALL_VERTEX: &str = r##"
+  #version 450
+  layout(location = 0) in vec3 in_position;
+  layout(location = 1) in vec2 in_uv;
+  layout(location = 0) out vec2 out_uv;
+  layout(set = 0, binding = 0) uniform Camera {
+    mat4 view_projection;
+  };
+  void main() {
+    out_uv = in_uv;
+    gl_Position = view_projection * vec4(in_position, 1.0);
+  }
+"##;
+
+/// The number of `FEATURE_<i>` blocks of the ubershader.
+const FEATURE_COUNT: usize = 48;
+
+/// A fragment shader with a block of lighting-like math per feature, most of them enabled with defines in a preamble, as
+/// produced by a material system.
+fn generate_ubershader() -> CompileInput {
+  let mut source = String::from(
+    r##"
+    #version 450
+    layout(location = 0) in vec2 in_uv;
+    layout(location = 1) in vec3 in_normal;
+    layout(location = 0) out vec4 out_color;
+    layout(set = 0, binding = 0) uniform sampler2D textures[16];
+    layout(set = 0, binding = 1) uniform Material {
+      vec4 factors[64];
+    };
+    void main() {
+      vec3 normal = normalize(in_normal);
+      vec4 color = vec4(0.0);
+    "##,
+  );
+  for i in 0..FEATURE_COUNT {
+    source.push_str(&format!(
+      r##"
+    #ifdef FEATURE_{i}
+      {{
+        vec4 texel = texture(textures[{texture}], in_uv * float(FEATURE_{i}));
+        float light = max(dot(normal, normalize(factors[{i}].xyz)), 0.0);
+        color += texel * pow(light, factors[{i}].w) * FEATURE_SCALE;
+      }}
+    #endif
+    "##,
+      i = i,
+      texture = i % 16,
+    ));
+  }
+  source.push_str("  out_color = color;\n}\n");
+
+  let preamble = (0..FEATURE_COUNT)
+    .filter(|i| i % 4 != 3)
+    .fold(Preamble::new().define("FEATURE_SCALE", Some("0.25")), |preamble, i| preamble.define(&format!("FEATURE_{}", i), Some(&(i + 1).to_string())));
+  CompileInput::new().source(&source).stage(ShaderStage::Fragment).preamble(preamble)
+}
+
+fn bench_compile(c: &mut Criterion) {
+  let small_vertex = CompileInput::new().source(SMALL_VERTEX).stage(ShaderStage::Vertex);
+  let ubershader = generate_ubershader();
+
+  // Each compile initializes glslang's built-in symbol tables, unless a guard keeps the process initialized.
+  let mut group = c.benchmark_group("process");
+  group.bench_function("per_compile", |b| {
+    b.iter(|| compile_input(&small_vertex, CompileOptionFlags::empty()).unwrap())
+  });
+  group.bench_function("reused", |b| {
+    let _process = GlslangProcess::new();
+    b.iter(|| compile_input(&small_vertex, CompileOptionFlags::empty()).unwrap())
+  });
+  group.finish();
+
+  // The other benchmarks measure the compile alone.
+  let _process = GlslangProcess::new();
+
+  let mut group = c.benchmark_group("shader");
+  group.bench_function("small_vertex", |b| {
+    b.iter(|| compile_input(&small_vertex, CompileOptionFlags::empty()).unwrap())
+  });
+  group.bench_function("ubershader", |b| {
+    b.iter(|| compile_input(&ubershader, CompileOptionFlags::empty()).unwrap())
+  });
+  group.finish();
+
+  let mut group = c.benchmark_group("debug_info");
+  for (name, option_flags) in [("without", CompileOptionFlags::empty()), ("with", CompileOptionFlags::GenerateDebugInfo)] {
+    group.bench_function(name, |b| {
+      b.iter(|| compile_input(&ubershader, option_flags).unwrap())
+    });
+  }
+  group.finish();
+
+  let mut group = c.benchmark_group("validation");
+  for validate in [false, true] {
+    let input = generate_ubershader().spv_options(SpvOptions { validate, ..Default::default() });
+    group.bench_function(if validate { "with" } else { "without" }, |b| {
+      b.iter(|| compile_input(&input, CompileOptionFlags::empty()).unwrap())
+    });
+  }
+  group.finish();
+
+  // A new `Vec` per compile, or one buffer whose capacity is reused.
+  let mut group = c.benchmark_group("spirv_output");
+  group.bench_function("new_vec", |b| {
+    b.iter(|| compile_input(&ubershader, CompileOptionFlags::empty()).unwrap().spirv)
+  });
+  group.bench_function("reused_vec", |b| {
+    let mut spirv = Vec::new();
+    b.iter(|| {
+      compile_input_into(&ubershader, CompileOptionFlags::empty(), &mut spirv).unwrap();
+      spirv.len()
+    })
+  });
+  // Copying the words of a compile, e.g. for an API taking ownership, which borrowing `Spirv::words` avoids.
+  let output = compile_input(&ubershader, CompileOptionFlags::empty()).unwrap();
+  group.bench_function("copy_words", |b| {
+    b.iter(|| output.spirv.words().to_vec())
+  });
+  group.finish();
+}
+
+criterion_group!(benches, bench_compile);
+criterion_main!(benches);
@@ -0,0 +1,50 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+use glslang_sys_2022 as glslang_sys;
+use glslang_sys::*;
+
+const JOB_COUNT: usize = 64;
+
+/// Material permutations of one fragment shader, selected with defines.
+fn generate_jobs() -> Vec<CompileJob> {
+  let source = r##"
+    #version 450
+    layout(location = 0) in vec2 in_uv;
+    layout(location = 0) out vec4 out_color;
+    void main() {
+      vec4 color = vec4(in_uv, 0.0, 1.0);
+    #if (PERMUTATION & 1) != 0
+      color.rgb = color.bgr;
+    #endif
+    #if (PERMUTATION & 2) != 0
+      color *= 0.5;
+    #endif
+      out_color = color + vec4(float(PERMUTATION) / 64.0);
+    }
+  "##;
+  (0..JOB_COUNT)
+    .map(|i| CompileJob::new(source, ShaderStage::Fragment).define("PERMUTATION", Some(&i.to_string())))
+    .collect()
+}
+
+fn bench_compile_batch(c: &mut Criterion) {
+  let jobs = generate_jobs();
+  // Keeps the process initialized across iterations, as in a long-running asset pipeline.
+  let _process = GlslangProcess::new();
+
+  let mut group = c.benchmark_group("compile_batch");
+  group.throughput(Throughput::Elements(jobs.len() as u64));
+
+  let single_thread = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+  group.bench_function("one_thread", |b| {
+    b.iter(|| single_thread.install(|| compile_batch(&jobs)))
+  });
+  group.bench_function(format!("{}_threads", rayon::current_num_threads()), |b| {
+    b.iter(|| compile_batch(&jobs))
+  });
+
+  group.finish();
+}
+
+criterion_group!(benches, bench_compile_batch);
+criterion_main!(benches);
@@ -0,0 +1,69 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+use glslang_sys_2022 as glslang_sys;
+use glslang_sys::*;
+
+const PERMUTATION_COUNT: usize = 64;
+
+/// Small fragment shader permutations, as produced by a material system.
+fn generate_corpus() -> Vec<String> {
+  (0..PERMUTATION_COUNT)
+    .map(|i| {
+      format!(
+        r##"
+        #version 450
+        #define PERMUTATION {}
+        layout(location = 0) in vec2 in_uv;
+        layout(location = 0) out vec4 out_color;
+        void main() {{
+          vec4 color = vec4(in_uv, 0.0, 1.0);
+        #if (PERMUTATION & 1) != 0
+          color.rgb = color.bgr;
+        #endif
+        #if (PERMUTATION & 2) != 0
+          color *= 0.5;
+        #endif
+          out_color = color + vec4(float(PERMUTATION) / {}.0);
+        }}
+        "##,
+        i, PERMUTATION_COUNT,
+      )
+    })
+    .collect()
+}
+
+fn make_template() -> CompileInput {
+  CompileInput::new().stage(ShaderStage::Fragment).target_env(TargetEnv::vulkan_1_1())
+}
+
+fn bench_compile_pool(c: &mut Criterion) {
+  let corpus = generate_corpus();
+
+  // Both arms share the process initialization, so that they only differ in the pool.
+  let _process = GlslangProcess::new();
+
+  let mut group = c.benchmark_group("compile_corpus");
+  group.throughput(Throughput::Elements(corpus.len() as u64));
+
+  group.bench_function("unpooled", |b| {
+    b.iter(|| {
+      for source in &corpus {
+        compile_input(&make_template().source(source), CompileOptionFlags::empty()).unwrap();
+      }
+    })
+  });
+
+  let pool = CompilerPool::new(make_template(), CompileOptionFlags::empty(), 1).unwrap();
+  group.bench_function("pooled", |b| {
+    b.iter(|| {
+      for source in &corpus {
+        pool.compile(source).unwrap();
+      }
+    })
+  });
+
+  group.finish();
+}
+
+criterion_group!(benches, bench_compile_pool);
+criterion_main!(benches);
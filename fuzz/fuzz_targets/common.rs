@@ -0,0 +1,34 @@
+//! Shared by the fuzz targets: the test shaders and the files they include, served from memory so that no target reads or
+//! writes files while fuzzing.
+
+use std::collections::HashMap;
+
+use glslang_sys_2022::*;
+
+/// The test shaders, with the stage of their file extension.
+pub const SHADERS: &[(&str, ShaderStage)] = &[
+  (include_str!("../../tests/fixtures/triangle.vert"), ShaderStage::Vertex),
+  (include_str!("../../tests/fixtures/blur.comp"), ShaderStage::Compute),
+  (include_str!("../../tests/fixtures/tinted.frag"), ShaderStage::Fragment),
+];
+
+/// Resolves the includes of [`SHADERS`].
+pub fn includer() -> VirtualIncluder {
+  VirtualIncluder::new(HashMap::from([
+    ("include/kernel.glsl".to_owned(), include_str!("../../tests/fixtures/include/kernel.glsl").to_owned()),
+    ("tint.glsl".to_owned(), include_str!("../../tests/fixtures/include/tint.glsl").to_owned()),
+  ]))
+}
+
+/// A stage `source` plausibly is, so that the test shaders used as seeds compile as they do in the tests.
+pub fn guess_stage(source: &str) -> ShaderStage {
+  if source.contains("local_size_x") {
+    ShaderStage::Compute
+  }
+  else if source.contains("gl_Position") {
+    ShaderStage::Vertex
+  }
+  else {
+    ShaderStage::Fragment
+  }
+}
@@ -0,0 +1,25 @@
+//! Arbitrary bytes as the GLSL source of a compile. Errors are fine; only crashes count.
+//!
+//! Seed the corpus with the test shaders: `cargo fuzz run compile_source fuzz/corpus/compile_source tests/fixtures`
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use glslang_sys_2022::*;
+
+mod common;
+
+fuzz_target!(
+  init: GlslangProcess::ensure_initialized(),
+  |data: &[u8]| {
+    // Invalid UTF-8 is replaced rather than skipped, so that such inputs still reach glslang. Interior NUL bytes are
+    // rejected by `compile_input` before that.
+    let source = String::from_utf8_lossy(data);
+    let input = CompileInput::new()
+      .source(&source)
+      .stage(common::guess_stage(&source))
+      .include_handler(common::includer());
+    let _ = compile_input(&input, CompileOptionFlags::empty());
+  }
+);
@@ -0,0 +1,53 @@
+//! The test shaders with arbitrary defines, preamble fragments and options. Errors are fine; only crashes count.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+use glslang_sys_2022::*;
+
+mod common;
+
+/// Macros the test shaders use, which a fuzzer would hardly guess.
+const KNOWN_NAMES: &[&str] = &["SCALE", "TINT", "KERNEL_SIZE", "main", "gl_Position"];
+
+#[derive(Debug, Arbitrary)]
+enum DefineName {
+  Known(u8),
+  Other(String),
+}
+
+#[derive(Debug, Arbitrary)]
+struct Mutation {
+  shader: u8,
+  defines: Vec<(DefineName, Option<String>)>,
+  fragments: Vec<String>,
+  option_flags: u32,
+  shader_options: u32,
+  debug_info: bool,
+}
+
+fuzz_target!(
+  init: GlslangProcess::ensure_initialized(),
+  |mutation: Mutation| {
+    let (source, stage) = common::SHADERS[usize::from(mutation.shader) % common::SHADERS.len()];
+    let preamble = mutation.defines.iter().fold(Preamble::new(), |preamble, (name, value)| {
+      let name = match name {
+        DefineName::Known(index) => KNOWN_NAMES[usize::from(*index) % KNOWN_NAMES.len()],
+        DefineName::Other(name) => name,
+      };
+      preamble.define(name, value.as_deref())
+    });
+    let preamble = mutation.fragments.iter().fold(preamble, |preamble, fragment| preamble.fragment(fragment));
+
+    let input = CompileInput::new()
+      .source(source)
+      .stage(stage)
+      .preamble(preamble)
+      .include_handler(common::includer())
+      .shader_options(ShaderOptions::from_bits_truncate(mutation.shader_options))
+      .spv_options(SpvOptions { generate_debug_info: mutation.debug_info, ..Default::default() });
+    let _ = compile_input(&input, CompileOptionFlags::from_bits_truncate(mutation.option_flags));
+  }
+);
@@ -0,0 +1,241 @@
+//! [`include_glsl!`], compiling a shader to SPIR-V with `glslang-sys-2022` when the Rust code using it is compiled.
+//!
+//! The macro runs glslang on the host, so the features of this crate choose how glslang is obtained for the host, like
+//! those of `glslang-sys-2022`: `prebuilt` (default) or `build-from-source`.
+//!
+//! `glslang-sys-2022` does not re-export the macro behind a feature: this crate depends on it, and Cargo rejects the
+//! dependency cycle. Depend on this crate instead; the expansion is a plain `&'static [u32]` and uses nothing from
+//! `glslang-sys-2022`.
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use proc_macro2::{Literal, Span, TokenStream};
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{Ident, LitStr, Token};
+
+use glslang_sys_2022 as glslang_sys;
+use glslang_sys::*;
+
+/// Compiles a shader file to SPIR-V at compile time, expanding to its words as a `&'static [u32]`.
+///
+/// ```ignore
+/// use glslang_sys_2022_macros::include_glsl;
+///
+/// let spirv: &[u32] = include_glsl!("shaders/tri.vert", target = "vulkan1.2", define FOO = "1");
+/// ```
+///
+/// Like `include_bytes!`, a relative path is resolved relative to the file containing the invocation (or the directory
+/// of `Cargo.toml`, if the invocation has no source file). The file is compiled like [`CompileInput::from_file`]: the stage
+/// is inferred from the extension, `.hlsl` files are HLSL, and `#include`s are looked up in the directory of the file.
+///
+/// Options, separated by commas:
+/// - `target = "<target>"`: `vulkan1.0` to `vulkan1.3`, `opengl`, or `spirv1.0` to `spirv1.6`, like `--target-env` of
+///   glslangValidator. Defaults to [`TargetEnv::default`].
+/// - `stage = "<stage>"`: e.g. `frag`, for files whose extension names no stage.
+/// - `define NAME = "value"`, `define NAME = 16` or `define NAME`: like `-DNAME=value` and `-DNAME`.
+/// - `include_dir = "<directory>"`: searched for `#include`s after the directory of the file, relative like the path.
+///
+/// glslang's errors are reported as compile errors at the invocation. Warnings are not reported, as procedural macros
+/// cannot emit warnings on stable Rust.
+///
+/// The shader and its includes are tracked with `include_bytes!`, so that editing any of them rebuilds the invoking
+/// crate.
+#[proc_macro]
+pub fn include_glsl(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+  let args = syn::parse_macro_input!(input as IncludeGlsl);
+  let base_dir = proc_macro::Span::call_site()
+    .local_file()
+    .and_then(|file| file.parent().map(Path::to_owned))
+    .or_else(|| std::env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from))
+    .unwrap_or_default();
+  expand(&args, &base_dir).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// The arguments of [`include_glsl!`].
+struct IncludeGlsl {
+  path: LitStr,
+  options: Vec<MacroOption>,
+}
+impl Parse for IncludeGlsl {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let path = input.parse()?;
+    let mut options = Vec::new();
+    while !input.is_empty() {
+      input.parse::<Token![,]>()?;
+      if input.is_empty() {
+        break;
+      }
+      options.push(input.parse()?);
+    }
+    Ok(IncludeGlsl { path, options })
+  }
+}
+
+enum MacroOption {
+  Target(LitStr),
+  Stage(LitStr),
+  Define { name: Ident, value: Option<String> },
+  IncludeDir(LitStr),
+}
+impl Parse for MacroOption {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let key: Ident = input.parse()?;
+    if key == "define" {
+      let name = input.parse()?;
+      let value = if input.parse::<Option<Token![=]>>()?.is_some() {
+        match input.parse::<syn::Lit>()? {
+          syn::Lit::Str(value) => Some(value.value()),
+          syn::Lit::Int(value) => Some(value.base10_digits().to_owned()),
+          value => return Err(syn::Error::new(value.span(), "Expected a string or an integer")),
+        }
+      }
+      else {
+        None
+      };
+      return Ok(MacroOption::Define { name, value });
+    }
+
+    let option: fn(LitStr) -> MacroOption = match key.to_string().as_str() {
+      "target" => MacroOption::Target,
+      "stage" => MacroOption::Stage,
+      "include_dir" => MacroOption::IncludeDir,
+      _ => return Err(syn::Error::new(key.span(), format!("Unknown option `{}`: expected `target`, `stage`, `define` or `include_dir`", key))),
+    };
+    input.parse::<Token![=]>()?;
+    Ok(option(input.parse()?))
+  }
+}
+
+/// Compiles the shader of `args`, with relative paths resolved in `base_dir`.
+fn expand(args: &IncludeGlsl, base_dir: &Path) -> syn::Result<TokenStream> {
+  // Canonical like the resolved names of includes, so that both are shown relative to it in errors.
+  let base_dir = base_dir.canonicalize().unwrap_or_else(|_| base_dir.to_owned());
+  let path = base_dir.join(args.path.value());
+  let mut input = CompileInput::from_file(&path).map_err(|error| syn::Error::new(args.path.span(), error_chain(&error)))?;
+
+  let mut search_paths = vec![path.parent().map(Path::to_owned).unwrap_or_default()];
+  let mut preamble = Preamble::new();
+  for option in &args.options {
+    match option {
+      MacroOption::Target(target) => input = input.target_env(parse_target(target)?),
+      MacroOption::Stage(stage) => {
+        let stage = ShaderStage::from_extension(&stage.value()).ok_or_else(|| {
+          let stages = ShaderStage::ALL.iter().map(|stage| stage.extension()).collect::<Vec<_>>().join(", ");
+          syn::Error::new(stage.span(), format!("Unknown stage {:?}: expected one of {}", stage.value(), stages))
+        })?;
+        input = input.stage(stage);
+      },
+      MacroOption::Define { name, value } => preamble = preamble.define(&name.to_string(), value.as_deref()),
+      MacroOption::IncludeDir(include_dir) => search_paths.push(base_dir.join(include_dir.value())),
+    }
+  }
+  let input = input.include_handler(FileIncluder::new(search_paths)).preamble(preamble);
+
+  let output = compile_input(&input, CompileOptionFlags::empty()).map_err(|error| compile_error(&error, &base_dir))?;
+
+  // `include_bytes!` makes Cargo rebuild the invoking crate when the shader or one of its includes changes.
+  let tracked_files = std::iter::once(path.to_string_lossy().into_owned())
+    .chain(output.includes.iter().map(|include| include.resolved.resolved_name.clone()));
+  let words = output.spirv.iter().map(|&word| Literal::u32_suffixed(word));
+  Ok(quote! {
+    {
+      #(const _: &[u8] = ::core::include_bytes!(#tracked_files);)*
+      const SPIRV: &[u32] = &[#(#words),*];
+      SPIRV
+    }
+  })
+}
+
+/// Parses `target` like `--target-env` of glslangValidator.
+fn parse_target(target: &LitStr) -> syn::Result<TargetEnv> {
+  let value = target.value();
+  match CompileOptions::from_validator_args(&["--target-env", &value]) {
+    Ok((spec, _)) => Ok(TargetEnv { client: spec.client, client_version: spec.client_version, spirv_version: spec.target_language_version }),
+    Err(_) => Err(syn::Error::new(target.span(), format!("Unknown target {:?}: expected vulkan1.0 to vulkan1.3, opengl, or spirv1.0 to spirv1.6", value))),
+  }
+}
+
+/// One error per glslang error, at the invocation, with the names of files in `base_dir` relative to it as in the macro.
+fn compile_error(error: &CompileError, base_dir: &Path) -> syn::Error {
+  let error_log = match error {
+    CompileError::Preprocess(error_log) | CompileError::Parse(error_log) | CompileError::Link(error_log) | CompileError::MapIo(error_log) => error_log,
+    _ => return syn::Error::new(Span::call_site(), error_chain(error)),
+  };
+  error_log
+    .diagnostics()
+    .into_iter()
+    .filter(|diagnostic| !matches!(diagnostic.severity, Severity::Note | Severity::Warning))
+    .map(|mut diagnostic| {
+      if let Some(SourceLocation { source: DiagnosticSource::Name(name), .. }) = &mut diagnostic.location {
+        if let Ok(relative) = Path::new(name.as_str()).strip_prefix(base_dir).map(|relative| relative.to_string_lossy().into_owned()) {
+          *name = relative;
+        }
+      }
+      let message = match (diagnostic.severity, &diagnostic.location) {
+        (Severity::Error, Some(location)) => format!("{} {}", location, diagnostic.message),
+        (Severity::Error, None) => diagnostic.message,
+        _ => diagnostic.to_string(),
+      };
+      syn::Error::new(Span::call_site(), message)
+    })
+    .reduce(|mut errors, error| {
+      errors.combine(error);
+      errors
+    })
+    .unwrap_or_else(|| syn::Error::new(Span::call_site(), format!("{}: {}", error, error_log.info_log.trim())))
+}
+
+/// `error` followed by its sources, e.g. the I/O error of [`CompileError::ReadSource`].
+fn error_chain(error: &dyn std::error::Error) -> String {
+  let mut message = error.to_string();
+  let mut source = error.source();
+  while let Some(error) = source {
+    write!(message, ": {}", error).unwrap();
+    source = error.source();
+  }
+  message
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn expand_str(args: TokenStream, base_dir: &Path) -> Result<String, String> {
+    let args: IncludeGlsl = syn::parse2(args).map_err(|error| error.to_string())?;
+    expand(&args, base_dir).map(|tokens| tokens.to_string()).map_err(|error| error.to_string())
+  }
+
+  #[test]
+  fn invalid_options() {
+    let base_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+    let error = expand_str(quote!("shaders/triangle.vert", optimize = "size"), &base_dir).unwrap_err();
+    assert_eq!(error, "Unknown option `optimize`: expected `target`, `stage`, `define` or `include_dir`");
+    let error = expand_str(quote!("shaders/triangle.vert", target = "vulkan9.9"), &base_dir).unwrap_err();
+    assert!(error.starts_with("Unknown target \"vulkan9.9\""), "{}", error);
+    let error = expand_str(quote!("shaders/triangle.vert", stage = "pixel"), &base_dir).unwrap_err();
+    assert!(error.starts_with("Unknown stage \"pixel\""), "{}", error);
+    let error = expand_str(quote!("shaders/missing.vert"), &base_dir).unwrap_err();
+    assert!(error.starts_with("Failed to read shader source"), "{}", error);
+  }
+
+  #[test]
+  fn include_edit_is_tracked() {
+    let dir = std::env::temp_dir().join(format!("glslang-sys-macros-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let include_path = dir.join("color.glsl");
+    std::fs::write(dir.join("shader.frag"), "#version 450\n#extension GL_GOOGLE_include_directive : require\n#include \"color.glsl\"\nlayout(location = 0) out vec4 color;\nvoid main() { color = COLOR; }\n").unwrap();
+
+    std::fs::write(&include_path, "#define COLOR vec4(1.0)\n").unwrap();
+    let before = expand_str(quote!("shader.frag"), &dir).unwrap();
+    // The canonical path of the include, as `include_bytes!` gets it.
+    let tracked = Literal::string(&include_path.canonicalize().unwrap().to_string_lossy()).to_string();
+    assert!(before.contains(&tracked), "{}", before);
+
+    std::fs::write(&include_path, "#define COLOR vec4(0.5)\n").unwrap();
+    let after = expand_str(quote!("shader.frag"), &dir).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_ne!(before, after);
+  }
+}
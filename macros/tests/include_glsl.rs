@@ -0,0 +1,27 @@
+use glslang_sys_2022_macros::include_glsl;
+
+use glslang_sys_2022 as glslang_sys;
+use glslang_sys::*;
+
+static TRIANGLE: &[u32] = include_glsl!("shaders/triangle.vert", target = "vulkan1.2");
+
+#[test]
+fn compiles_at_build_time() {
+  assert_eq!(TRIANGLE[0], Spirv::MAGIC_NUMBER);
+  // SPIR-V 1.5, the version of Vulkan 1.2.
+  assert_eq!(TRIANGLE[1], 0x0001_0500);
+  assert_eq!(TRIANGLE.as_ptr() as usize % std::mem::align_of::<u32>(), 0);
+
+  let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/shaders/triangle.vert");
+  let input = CompileInput::from_file(path).unwrap().target_env(TargetEnv::vulkan_1_2());
+  assert_eq!(TRIANGLE, &*compile_input(&input, CompileOptionFlags::empty()).unwrap().spirv);
+}
+
+#[test]
+fn defines_and_include_dirs() {
+  let spirv: &[u32] = include_glsl!("shaders/tinted.frag", define SCALE = "0.5", include_dir = "shaders/include",);
+  assert_eq!(spirv[0], Spirv::MAGIC_NUMBER);
+
+  let scaled_twice: &[u32] = include_glsl!("shaders/tinted.frag", define SCALE = 2, include_dir = "shaders/include");
+  assert_ne!(spirv, scaled_twice);
+}
@@ -0,0 +1,15 @@
+#version 450
+#extension GL_GOOGLE_include_directive : require
+
+#include <tint.glsl>
+
+#ifndef SCALE
+#error SCALE is not defined
+#endif
+
+layout(location = 0) in vec2 in_uv;
+layout(location = 0) out vec4 out_color;
+
+void main() {
+  out_color = vec4(in_uv, 0.0, 1.0) * TINT * SCALE;
+}
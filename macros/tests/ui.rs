@@ -0,0 +1,10 @@
+//! Compile errors of `include_glsl!`. Regenerate the expected output with:
+//!
+//! ```text
+//! TRYBUILD=overwrite cargo test --test ui
+//! ```
+
+#[test]
+fn ui() {
+  trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,5 @@
+use glslang_sys_2022_macros::include_glsl;
+
+fn main() {
+  let _spirv: &[u32] = include_glsl!("undeclared_identifier.frag");
+}
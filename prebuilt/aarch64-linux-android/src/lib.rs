@@ -0,0 +1,4 @@
+//! Prebuilt glslang static libraries for `aarch64-linux-android`.
+//!
+//! This crate contains no Rust code; `glslang-sys-2022` locates the binaries through its `links` metadata.
+#![no_std]
@@ -0,0 +1,18 @@
+//
+// Exports the location of the prebuilt glslang binaries to the dependent `glslang-sys-2022` build script
+// through `links` metadata (`DEP_GLSLANG_PREBUILT_<TARGET>_*`).
+//
+
+use std::env;
+use std::path::PathBuf;
+
+fn main() {
+  let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+  let glslang_commit = std::fs::read_to_string(manifest_dir.join("version.txt")).unwrap();
+
+  println!("cargo:root={}", manifest_dir.to_str().unwrap());
+  println!("cargo:lib_dir={}", manifest_dir.join("lib").to_str().unwrap());
+  println!("cargo:include_dir={}", manifest_dir.join("include").to_str().unwrap());
+  println!("cargo:glslang_commit={}", glslang_commit.trim());
+  println!("cargo:rerun-if-changed=version.txt");
+}
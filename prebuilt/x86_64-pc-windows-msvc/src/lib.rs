@@ -0,0 +1,4 @@
+//! Prebuilt glslang static libraries for `x86_64-pc-windows-msvc`.
+//!
+//! This crate contains no Rust code; `glslang-sys-2022` locates the binaries through its `links` metadata.
+#![no_std]
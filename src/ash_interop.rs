@@ -0,0 +1,92 @@
+//! Helpers for creating Vulkan shader modules with [`ash`] from compiled SPIR-V.
+
+use ash::vk;
+
+use crate::*;
+
+/// Reinterprets SPIR-V bytes (e.g. read from a `.spv` file) as words, without copying.
+///
+/// The bytes are assumed to be in host byte order.
+///
+/// # Panics
+/// - If `bytes` is not 4-byte aligned, or its length is not a multiple of 4.
+///   `Vec<u32>` is always suitably aligned; arbitrary byte buffers are not.
+#[must_use]
+pub fn words_from_bytes(bytes: &[u8]) -> &[u32] {
+  assert_eq!(bytes.as_ptr() as usize % std::mem::align_of::<u32>(), 0, "SPIR-V bytes must be 4-byte aligned");
+  assert_eq!(bytes.len() % std::mem::size_of::<u32>(), 0, "SPIR-V byte length must be a multiple of 4");
+
+  // SAFETY: Alignment and length are checked above, and any bit pattern is a valid `u32`.
+  let (prefix, words, suffix) = unsafe { bytes.align_to::<u32>() };
+  debug_assert!(prefix.is_empty() && suffix.is_empty());
+  words
+}
+
+/// Makes a `vk::ShaderModuleCreateInfo` referring to `code` without copying it.
+///
+/// The returned builder borrows `code`, so the words cannot be dropped while the create-info is in use.
+/// `code_size` is in bytes, as Vulkan requires.
+#[must_use]
+pub fn shader_module_create_info(code: &[u32]) -> vk::ShaderModuleCreateInfoBuilder<'_> {
+  vk::ShaderModuleCreateInfo::builder().code(code)
+}
+
+/// Maps a shader stage to the corresponding `vk::ShaderStageFlags` bit.
+#[must_use]
+pub fn shader_stage_flags(stage: ShaderStage) -> vk::ShaderStageFlags {
+  match stage {
+    ShaderStage::Vertex         => vk::ShaderStageFlags::VERTEX,
+    ShaderStage::TessControl    => vk::ShaderStageFlags::TESSELLATION_CONTROL,
+    ShaderStage::TessEvaluation => vk::ShaderStageFlags::TESSELLATION_EVALUATION,
+    ShaderStage::Geometry       => vk::ShaderStageFlags::GEOMETRY,
+    ShaderStage::Fragment       => vk::ShaderStageFlags::FRAGMENT,
+    ShaderStage::Compute        => vk::ShaderStageFlags::COMPUTE,
+    ShaderStage::RayGen         => vk::ShaderStageFlags::RAYGEN_KHR,
+    ShaderStage::Intersect      => vk::ShaderStageFlags::INTERSECTION_KHR,
+    ShaderStage::AnyHit         => vk::ShaderStageFlags::ANY_HIT_KHR,
+    ShaderStage::ClosestHit     => vk::ShaderStageFlags::CLOSEST_HIT_KHR,
+    ShaderStage::Miss           => vk::ShaderStageFlags::MISS_KHR,
+    ShaderStage::Callable       => vk::ShaderStageFlags::CALLABLE_KHR,
+    ShaderStage::Task           => vk::ShaderStageFlags::TASK_NV,
+    ShaderStage::Mesh           => vk::ShaderStageFlags::MESH_NV,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn create_info_refers_to_words() {
+    let words: Vec<u32> = vec![0x07230203, 0x00010000, 0, 1, 0];
+    let create_info = shader_module_create_info(&words);
+
+    assert_eq!(create_info.code_size, words.len() * 4);
+    assert_eq!(create_info.p_code, words.as_ptr());
+    assert_eq!(create_info.s_type, vk::StructureType::SHADER_MODULE_CREATE_INFO);
+  }
+
+  #[test]
+  fn words_from_aligned_bytes() {
+    let words: Vec<u32> = vec![0x07230203, 0x00010000];
+    let bytes = unsafe { std::slice::from_raw_parts(words.as_ptr() as *const u8, words.len() * 4) };
+
+    let reinterpreted = words_from_bytes(bytes);
+    assert_eq!(reinterpreted, &words[..]);
+    assert_eq!(reinterpreted.as_ptr(), words.as_ptr());
+  }
+
+  #[test]
+  #[should_panic(expected = "4-byte aligned")]
+  fn words_from_misaligned_bytes() {
+    let words: Vec<u32> = vec![0; 3];
+    let bytes = unsafe { std::slice::from_raw_parts((words.as_ptr() as *const u8).add(1), 8) };
+    let _ = words_from_bytes(bytes);
+  }
+
+  #[test]
+  fn stage_flags() {
+    assert_eq!(shader_stage_flags(ShaderStage::Vertex), vk::ShaderStageFlags::VERTEX);
+    assert_eq!(shader_stage_flags(ShaderStage::ClosestHit), vk::ShaderStageFlags::CLOSEST_HIT_KHR);
+  }
+}
@@ -0,0 +1,180 @@
+use std::ffi::CString;
+
+use crate::*;
+
+/// One shader of [`compile_batch`]: a source and stage, with settings added to or replacing the defaults of the batch.
+///
+/// ```no_run
+/// # use glslang_sys_2022::*;
+/// let jobs = [
+///   CompileJob::new("#version 450\nvoid main() { gl_Position = vec4(SCALE); }\n", ShaderStage::Vertex).define("SCALE", Some("2.0")),
+///   CompileJob::new("#version 450\nvoid main() {\n", ShaderStage::Fragment),
+/// ];
+/// let results = compile_batch(&jobs);
+/// assert!(results[0].is_ok());
+/// assert!(results[1].is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompileJob {
+  source: String,
+  stage: ShaderStage,
+  /// `[name, value]` pairs in order, e.g. `[["SCALE", "2.0"], ["USE_FOG", null]]`.
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+  defines: Vec<(String, Option<String>)>,
+  #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+  option_flags: Option<CompileOptionFlags>,
+}
+impl CompileJob {
+  #[must_use]
+  pub fn new(source: impl Into<String>, stage: ShaderStage) -> Self {
+    CompileJob {
+      source: source.into(),
+      stage,
+      defines: Vec::new(),
+      option_flags: None,
+    }
+  }
+
+  /// Defines the macro `name` as `value` for this job, after the defines of the defaults. See [`Preamble::define`].
+  #[must_use]
+  pub fn define(mut self, name: &str, value: Option<&str>) -> Self {
+    self.defines.push((name.to_owned(), value.map(str::to_owned)));
+    self
+  }
+
+  /// Compiles this job with `option_flags` instead of those of the batch.
+  #[must_use]
+  pub fn option_flags(mut self, option_flags: CompileOptionFlags) -> Self {
+    self.option_flags = Some(option_flags);
+    self
+  }
+
+  #[must_use]
+  pub fn source(&self) -> &str {
+    &self.source
+  }
+
+  #[must_use]
+  pub fn stage(&self) -> ShaderStage {
+    self.stage
+  }
+}
+
+/// Compiles every job with the defaults of [`CompileInput::new`], see [`compile_batch_with_defaults`].
+pub fn compile_batch(jobs: &[CompileJob]) -> Vec<Result<CompileOutput, CompileError>> {
+  compile_batch_with_defaults(&CompileInput::new(), CompileOptionFlags::empty(), jobs)
+}
+
+/// Compiles every job in order, with the settings of `defaults` and `option_flags` unless the job overrides them. A failed
+/// job does not stop the batch; the results are in the order of `jobs`.
+///
+/// The glslang process is initialized once for the whole batch, and all jobs share the resource limits of `defaults`. The
+/// source and stage of `defaults` are not used, and a stage of a job raises the default target environment like
+/// [`CompileInput::stage`]. An invalid setting of `defaults`, e.g. its preamble, fails every job.
+///
+/// With the `rayon` feature, the jobs are compiled in parallel on the current rayon thread pool, each with its own glslang
+/// objects. The results are still in the order of `jobs`.
+pub fn compile_batch_with_defaults(defaults: &CompileInput, option_flags: CompileOptionFlags, jobs: &[CompileJob]) -> Vec<Result<CompileOutput, CompileError>> {
+  let _process = GlslangProcess::new();
+  #[cfg(feature = "rayon")]
+  {
+    use rayon::prelude::*;
+    jobs.par_iter().map(|job| compile_job(defaults, option_flags, job)).collect()
+  }
+  #[cfg(not(feature = "rayon"))]
+  {
+    jobs.iter().map(|job| compile_job(defaults, option_flags, job)).collect()
+  }
+}
+
+fn compile_job(defaults: &CompileInput, option_flags: CompileOptionFlags, job: &CompileJob) -> Result<CompileOutput, CompileError> {
+  let source = CString::new(job.source.as_str()).map_err(|error| CompileError::nul_in(InputKind::Source, &error))?;
+  let input = glslang_input_t {
+    code: source.as_ptr(),
+    ..defaults.raw_for_stage(job.stage)?
+  };
+  let preamble = job.defines.iter().fold(defaults.current_preamble().clone(), |preamble, (name, value)| preamble.define(name, value.as_deref()));
+  let settings = ShaderSettings {
+    preamble: preamble.as_c_str()?,
+    ..defaults.shader_settings()
+  };
+  // SAFETY: `input` points into `source` and `defaults`, and the preamble into `preamble`, which all outlive the call.
+  unsafe { compile_shader(&input, job.option_flags.unwrap_or(option_flags), &settings) }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const COMPUTE_SOURCE: &str = "#version 450\nlayout(local_size_x = 64) in;\nlayout(std430, binding = 0) buffer Data { float values[]; };\nvoid main() {\n  values[gl_GlobalInvocationID.x] *= SCALE * BIAS;\n}\n";
+  const FRAGMENT_SOURCE: &str = "#version 450\nlayout(location = 0) out vec4 out_color;\nvoid main() {\n  out_color = vec4(SCALE);\n}\n";
+
+  #[test]
+  fn independent_results() {
+    let defaults = CompileInput::new().add_define("SCALE", Some("2.0"));
+    let jobs = [
+      CompileJob::new(COMPUTE_SOURCE, ShaderStage::Compute).define("BIAS", Some("0.5")),
+      CompileJob::new(COMPUTE_SOURCE, ShaderStage::Compute),
+      CompileJob::new(FRAGMENT_SOURCE, ShaderStage::Fragment).option_flags(CompileOptionFlags::AddOpSource),
+      CompileJob::new("#version 450\n\0", ShaderStage::Fragment),
+      CompileJob::new(FRAGMENT_SOURCE, ShaderStage::Fragment),
+    ];
+    let results = compile_batch_with_defaults(&defaults, CompileOptionFlags::empty(), &jobs);
+    assert_eq!(results.len(), jobs.len());
+
+    let expected = compile_input(&CompileInput::new().source(COMPUTE_SOURCE).stage(ShaderStage::Compute).add_define("SCALE", Some("2.0")).add_define("BIAS", Some("0.5")), CompileOptionFlags::empty()).unwrap();
+    assert_eq!(results[0].as_ref().unwrap().spirv, expected.spirv);
+    // The define of the first job does not leak into the second.
+    match &results[1] {
+      Err(CompileError::Parse(error_log)) => assert!(error_log.info_log.contains("BIAS"), "{}", error_log.info_log),
+      result => panic!("Unexpected result: {:?}", result),
+    }
+    assert!(matches!(results[3], Err(CompileError::InvalidInput { input: InputKind::Source, offset: 13 })));
+
+    // Only the third job adds its source to `OpSource`.
+    let fragment = CompileInput::new().source(FRAGMENT_SOURCE).stage(ShaderStage::Fragment).add_define("SCALE", Some("2.0"));
+    assert_eq!(results[2].as_ref().unwrap().spirv, compile_input(&fragment, CompileOptionFlags::AddOpSource).unwrap().spirv);
+    assert_eq!(results[4].as_ref().unwrap().spirv, compile_input(&fragment, CompileOptionFlags::empty()).unwrap().spirv);
+    assert_ne!(results[2].as_ref().unwrap().spirv, results[4].as_ref().unwrap().spirv);
+  }
+
+  #[test]
+  fn batch_matches_sequential() {
+    let jobs: Vec<_> = (0..32)
+      .map(|i| {
+        let source = format!("#version 450\nlayout(location = 0) out vec4 out_color;\nvoid main() {{\n  out_color = vec4({}.0 * SCALE);\n}}\n", i);
+        // Every fourth job fails, so errors are interleaved with outputs.
+        let job = CompileJob::new(source, ShaderStage::Fragment);
+        if i % 4 == 3 { job } else { job.define("SCALE", Some("0.5")) }
+      })
+      .collect();
+    let defaults = CompileInput::new();
+    let sequential: Vec<_> = jobs.iter().map(|job| compile_job(&defaults, CompileOptionFlags::empty(), job)).collect();
+
+    let results = compile_batch(&jobs);
+    assert_eq!(results.len(), sequential.len());
+    for (i, (result, expected)) in results.iter().zip(&sequential).enumerate() {
+      match (result, expected) {
+        (Ok(output), Ok(expected)) => assert_eq!(output, expected, "job {}", i),
+        (Err(CompileError::Parse(error_log)), Err(CompileError::Parse(expected))) => assert_eq!(error_log.info_log, expected.info_log, "job {}", i),
+        (result, expected) => panic!("Job {}: {:?} instead of {:?}", i, result, expected),
+      }
+      assert_eq!(result.is_err(), i % 4 == 3);
+    }
+  }
+
+  #[test]
+  fn stages_of_jobs() {
+    // Ray tracing raises the default target environment for that job only.
+    let ray_gen = "#version 460\n#extension GL_EXT_ray_tracing : require\nlayout(location = 0) rayPayloadEXT vec4 payload;\nvoid main() {}\n";
+    let results = compile_batch(&[CompileJob::new(ray_gen, ShaderStage::RayGen), CompileJob::new("#version 450\nvoid main() {}\n", ShaderStage::Vertex)]);
+    assert_eq!(results[0].as_ref().unwrap().spirv[1], 0x0001_0500);
+    assert_eq!(results[1].as_ref().unwrap().spirv[1], 0x0001_0000);
+
+    // The stage of the defaults does not matter, but an explicitly too old target environment fails the job.
+    let defaults = CompileInput::new().stage(ShaderStage::Compute).target_env(TargetEnv::vulkan_1_1());
+    let results = compile_batch_with_defaults(&defaults, CompileOptionFlags::empty(), &[CompileJob::new(ray_gen, ShaderStage::RayGen)]);
+    assert!(matches!(results[0], Err(CompileError::InvalidTargetEnv(TargetEnvError::SpirvVersionTooOld { stage: ShaderStage::RayGen, .. }))));
+  }
+}
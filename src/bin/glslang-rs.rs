@@ -0,0 +1,105 @@
+//! A glslangValidator-like command line compiler, for exercising the crate end to end:
+//!
+//! ```text
+//! cargo run --features cli -- -V -o out.spv shader.vert -DFOO=1 -Iinclude/
+//! ```
+//!
+//! See [`CompileOptions::from_validator_args`] for the supported flags. Without `-o`, the SPIR-V is written to
+//! `<stage>.spv`, e.g. `frag.spv`, like glslangValidator does.
+//!
+//! Exits with 1 if the shader fails to compile, and 2 for invalid arguments.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use glslang_sys_2022 as glslang_sys;
+use glslang_sys::*;
+
+const USAGE: &str = "Usage: glslang-rs [-V | -G] [--target-env <env>] [-S <stage>] [-D<name>[=<value>]] [-U<name>] [-I<dir>] [-e <name>] [--source-entrypoint <name>] [-g] [-Od | -Os] [--keep-uncalled | --ku] [--enhanced-msgs] [-o <file>] <file>";
+
+fn main() -> ExitCode {
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  if args.is_empty() || args.iter().any(|arg| arg == "-h" || arg == "--help") {
+    eprintln!("{}", USAGE);
+    return if args.is_empty() { ExitCode::from(2) } else { ExitCode::SUCCESS };
+  }
+
+  let (spec, options) = match CompileOptions::from_validator_args(&args.iter().map(String::as_str).collect::<Vec<_>>()) {
+    Ok(parsed) => parsed,
+    Err(error) => {
+      eprintln!("error: {}\n{}", error, USAGE);
+      return ExitCode::from(2);
+    },
+  };
+  let Some(source_path) = spec.source_path.clone() else {
+    eprintln!("error: No input file\n{}", USAGE);
+    return ExitCode::from(2);
+  };
+
+  let input = match build_input(&spec, &options) {
+    Ok(input) => input,
+    Err(error) => return report_error(&error, &source_path),
+  };
+  let output = match compile_input(&input, options.flags) {
+    Ok(output) => output,
+    Err(error) => return report_error(&error, &source_path),
+  };
+  for warning in output.warnings.iter().chain(&output.spirv_messages) {
+    eprintln!("{}", warning);
+  }
+
+  let output_path = spec.output_path.clone().unwrap_or_else(|| PathBuf::from(format!("{}.spv", spec.stage.expect("the stage of an input file is inferred").extension())));
+  if let Err(error) = output.spirv.save(&output_path) {
+    eprintln!("error: Failed to write {}: {}", output_path.display(), error);
+    return ExitCode::from(1);
+  }
+  ExitCode::SUCCESS
+}
+
+/// The input of the file of `spec`, with the settings of `spec` and `options`.
+fn build_input(spec: &CompileInputSpec, options: &CompileOptions) -> Result<CompileInput, CompileError> {
+  let source_path = spec.source_path.as_ref().unwrap();
+  let mut input = CompileInput::from_file(source_path)?.language(spec.language);
+  if let Some(stage) = spec.stage {
+    input = input.stage(stage);
+  }
+  // Without `-G` or `--target-env`, the input keeps its default, which is raised for ray tracing stages.
+  let default_spec = CompileInputSpec::default();
+  if (spec.client, spec.client_version, spec.target_language_version) != (default_spec.client, default_spec.client_version, default_spec.target_language_version) {
+    input = input.target_env(TargetEnv { client: spec.client, client_version: spec.client_version, spirv_version: spec.target_language_version });
+  }
+
+  // glslangValidator searches the directory of the source before the `-I` directories.
+  let mut search_paths = vec![source_path.parent().map(PathBuf::from).unwrap_or_default()];
+  search_paths.extend(options.include_dirs.iter().cloned());
+  input = input.include_handler(FileIncluder::new(search_paths));
+
+  let preamble = options.defines.iter().fold(Preamble::new(), |preamble, (name, value)| preamble.define(name, value.as_deref()));
+  let preamble = options.undefines.iter().fold(preamble, |preamble, name| preamble.fragment(&format!("#undef {}", name)));
+  input = input.preamble(preamble).spv_options(options.spv_options()).keep_uncalled(options.keep_uncalled);
+  // Unlike `HlslOptions`, these apply to GLSL too.
+  if let Some(entry_point) = &options.entry_point {
+    input = input.entry_point(entry_point);
+  }
+  if let Some(source_entry_point) = &options.source_entry_point {
+    input = input.source_entry_point(source_entry_point);
+  }
+  Ok(input)
+}
+
+/// Prints `error`, rendering glslang's diagnostics with the lines of the source they point at.
+fn report_error(error: &CompileError, source_path: &std::path::Path) -> ExitCode {
+  let error_log = match error {
+    CompileError::Preprocess(error_log) | CompileError::Parse(error_log) | CompileError::Link(error_log) | CompileError::MapIo(error_log) => error_log,
+    _ => {
+      eprintln!("error: {}", error);
+      return ExitCode::from(1);
+    },
+  };
+  let source = std::fs::read_to_string(source_path).unwrap_or_default();
+  let source_name = source_path.to_string_lossy();
+  let sources = DiagnosticSources::new(&source).file(&source_name, &source);
+  eprintln!("{}", render_diagnostics(&error_log.diagnostics(), &sources));
+  eprintln!("error: {}", error);
+  ExitCode::from(1)
+}
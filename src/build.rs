@@ -1,8 +1,8 @@
 extern crate bindgen;
 
 //
-// Host: Windows, Target: x86_64-pc-windows-msvc
-//  cmake .. -DCMAKE_INSTALL_PREFIX="install" -DENABLE_OPT=OFF -DENABLE_SPVREMAPPER=OFF -DSPIRV_SKIP_TESTS=ON -DSPIRV_SKIP_EXECUTABLES=ON
+// Host: Windows, Target: x86_64-pc-windows-msvc (i686-pc-windows-msvc: `-A Win32`)
+//  cmake .. -A x64 -DCMAKE_INSTALL_PREFIX="install" -DENABLE_OPT=OFF -DENABLE_SPVREMAPPER=OFF -DSPIRV_SKIP_TESTS=ON -DSPIRV_SKIP_EXECUTABLES=ON
 //  cmake --build . --config Release --target install
 //
 // Host: Windows, Target: aarch64-linux-android
@@ -19,6 +19,9 @@ use log::info;
 use thiserror::Error;
 use scopeguard::defer;
 
+mod build_paths;
+use build_paths::{cmake_define, cmake_path, clang_path_arg, utf8_path, parse_extra_cmake_args};
+
 fn make_package_version_string() -> String {
   format!(
     "{}.{}",
@@ -47,28 +50,52 @@ fn get_win32_unused_drive_letters() -> Vec<char> {
 enum BuilderError {
   #[error("No unused drive letter found for working around MAX_PATH limitation on Windows")]
   NoAvailableDriveLetter,
+  #[error("Invalid GLSLANG_SYS_CMAKE_ARGS: {0}")]
+  InvalidExtraCmakeArgs(String),
   #[error("Failed to configure project with cmake")]
   ConfigureFailed { output: process::Output },
   #[error("Failed to build project with cmake")]
   BuildFailed { output: process::Output },
 }
 
+/// A CMake toolchain file for cross targets. Replaces the build script's own per-target configuration.
+const CMAKE_TOOLCHAIN_FILE_ENV: &str = "GLSLANG_SYS_CMAKE_TOOLCHAIN_FILE";
+/// Whitespace-separated `-D` arguments appended to the cmake configure command.
+const CMAKE_ARGS_ENV: &str = "GLSLANG_SYS_CMAKE_ARGS";
+/// Overrides the `--target` passed to clang by bindgen, for targets whose Rust and clang triples differ.
+const BINDGEN_TARGET_ENV: &str = "GLSLANG_SYS_BINDGEN_TARGET";
+/// `--sysroot` passed to clang by bindgen.
+const BINDGEN_SYSROOT_ENV: &str = "GLSLANG_SYS_BINDGEN_SYSROOT";
+
 struct Builder {
   glslang_clone_dst_dir_path: PathBuf,
+  cmake_toolchain_file: Option<PathBuf>,
 }
 impl Builder {
-  fn new() -> Self {
+  fn new(cmake_toolchain_file: Option<PathBuf>) -> Self {
     let glslang_clone_dst_dir = format!("glslang-{}", make_package_version_string());
     let glslang_clone_dst_dir_path = Self::get_raw_out_dir().join(glslang_clone_dst_dir);
 
     Builder {
       glslang_clone_dst_dir_path,
+      cmake_toolchain_file,
     }
   }
 
   fn get_raw_out_dir() -> PathBuf {
     PathBuf::from(env::var("OUT_DIR").unwrap())
   }
+
+  /// The commit of the fetched glslang clone, which follows `master`. `None` if git cannot tell.
+  fn glslang_commit(&self) -> Option<String> {
+    let output = Command::new("git")
+      .arg("-C").arg(&self.glslang_clone_dst_dir_path)
+      .arg("rev-parse").arg("HEAD")
+      .output()
+      .ok()?;
+    let commit = String::from_utf8(output.stdout).ok()?;
+    (output.status.success() && !commit.trim().is_empty()).then(|| commit.trim().to_owned())
+  }
 }
 impl Builder {
   fn fetch_glslang(&self) -> io::Result<()> {
@@ -102,7 +129,9 @@ impl Builder {
       .output()?;
     io::stdout().write_all(&output.stdout).unwrap();
   
-    Command::new("python").arg("update_glslang_sources.py").status().unwrap();
+    // Linux and macOS may only have `python3`.
+    let python = if cfg!(target_os = "windows") { "python" } else { "python3" };
+    Command::new(python).arg("update_glslang_sources.py").status().unwrap();
     
     if output.status.success() {
       Ok(())
@@ -113,8 +142,16 @@ impl Builder {
   }
 
   fn build_glslang(&self, target_os: &str, target_arch: &str) -> Result<PathBuf, BuilderError> {
-    // Building is only supported for these platforms now:
-    assert!(cfg!(target_os = "windows"), "Building only supported on Windows.");
+    // Building is only supported on these platforms now, unless a toolchain file takes care of the target:
+    assert!(
+      cfg!(any(target_os = "windows", target_os = "linux", target_os = "macos")) || self.cmake_toolchain_file.is_some(),
+      "Building only supported on Windows, Linux and macOS.",
+    );
+
+    let extra_cmake_args = match env::var(CMAKE_ARGS_ENV) {
+      Ok(value) => parse_extra_cmake_args(&value).map_err(BuilderError::InvalidExtraCmakeArgs)?,
+      Err(_) => Vec::new(),
+    };
 
     let original_current_dir = env::current_dir().unwrap();
     defer! {
@@ -155,67 +192,83 @@ impl Builder {
 
     let install_dir = "install";
     let install_dir_path = build_dir_path.join(install_dir);
+    // Absolute, so that it doesn't depend on how cmake resolves relative install prefixes.
     let mapped_install_dir_path = mapped_build_dir_path.join(install_dir);
     std::fs::create_dir_all(&mapped_install_dir_path).unwrap();
 
     // Configure.
-    match target_os {
-      "windows" => {
-        let output = Command::new("cmake")
-          .arg("..")
-          .arg(format!(r#"-DCMAKE_INSTALL_PREFIX={}"#, install_dir))
-          // glslang options
-          .arg(r#"-DENABLE_OPT=OFF"#)
-          .arg(r#"-DENABLE_SPVREMAPPER=OFF"#)
-          // SPIRV-Tools options
-          .arg(r#"-DSPIRV_SKIP_TESTS=ON"#)
-          .arg(r#"-DSPIRV_SKIP_EXECUTABLES=ON"#)
-          .output()
-          .unwrap();
-        if !output.status.success() {
-          return Err(BuilderError::ConfigureFailed { output });
-        }
-      },
-      "android" => {
-        assert!(cfg!(target_os = "windows"), "TODO: CMAKE_MAKE_PROGRAM for other platforms.");
-
-        let android_ndk_home = env::var("ANDROID_NDK_HOME").expect("Environment variable ANDROID_NDK_HOME not set !");
-        let android_abi_name = match target_arch {
-          "aarch64" => "arm64-v8a",
-          "arm"     => "armeabi-v7a",
-          _ => panic!("Unexpected CARGO_CFG_TARGET_ARCH: {:?}", target_arch),
-        };
-
-        let output = Command::new("cmake")
-          .arg("..")
-          .arg("-G").arg("Unix Makefiles")
-          .arg(format!(r#"-DCMAKE_INSTALL_PREFIX={}"#, install_dir))
-          // glslang options
-          .arg(r#"-DENABLE_OPT=OFF"#)
-          .arg(r#"-DENABLE_SPVREMAPPER=OFF"#)
-          // SPIRV-Tools options
-          .arg(r#"-DSPIRV_SKIP_TESTS=ON"#)
-          .arg(r#"-DSPIRV_SKIP_EXECUTABLES=ON"#)
-          // Android
-          .arg(format!(r#"-DANDROID_ABI={}"#, android_abi_name))
-          .arg(r#"-DCMAKE_BUILD_TYPE=Release"#)
-          .arg(r#"-DANDROID_STL=c++_shared"#)
-          .arg(r#"-DANDROID_PLATFORM=android-24"#)
-          .arg(r#"-DCMAKE_SYSTEM_NAME=Android"#)
-          .arg(r#"-DANDROID_TOOLCHAIN=clang"#)
-          .arg(r#"-DANDROID_ARM_MODE=arm"#)
-          .arg(format!(r#"-DCMAKE_MAKE_PROGRAM={}/prebuilt/windows-x86_64/bin/make.exe"#, android_ndk_home))
-          .arg(format!(r#"-DCMAKE_TOOLCHAIN_FILE={}/build/cmake/android.toolchain.cmake"#, android_ndk_home))
-          .output()
-          .unwrap();
-        io::stdout().write_all(&output.stdout).unwrap();
-        if !output.status.success() {
-          io::stdout().write_all(&output.stderr).unwrap();
-          return Err(BuilderError::ConfigureFailed { output });
-        }
-      },
-      _ => panic!("Unexpected target_os:{:?}", target_os)
-    };
+    let mut configure_command = Command::new("cmake");
+    configure_command
+      .arg("..")
+      .arg(cmake_define("CMAKE_INSTALL_PREFIX", cmake_path(&mapped_install_dir_path)))
+      // glslang options
+      .arg(format!(r#"-DENABLE_OPT={}"#, if cfg!(feature = "spirv-tools") { "ON" } else { "OFF" }))
+      .arg(format!(r#"-DENABLE_SPVREMAPPER={}"#, if cfg!(feature = "remapper") { "ON" } else { "OFF" }))
+      .arg(format!(r#"-DENABLE_HLSL={}"#, if cfg!(feature = "hlsl") { "ON" } else { "OFF" }))
+      // SPIRV-Tools options
+      .arg(r#"-DSPIRV_SKIP_TESTS=ON"#)
+      .arg(r#"-DSPIRV_SKIP_EXECUTABLES=ON"#);
+
+    if let Some(cmake_toolchain_file) = &self.cmake_toolchain_file {
+      // Trust the toolchain file for everything target-specific. The generator can be chosen with the `CMAKE_GENERATOR` environment variable.
+      configure_command
+        .arg(cmake_define("CMAKE_TOOLCHAIN_FILE", cmake_path(cmake_toolchain_file)))
+        .arg(r#"-DCMAKE_BUILD_TYPE=Release"#);
+    }
+    else {
+      match target_os {
+        "windows" => {
+          // Visual Studio generator platform.
+          let platform = match target_arch {
+            "x86_64" => "x64",
+            "x86"    => "Win32",
+            _ => panic!("Unexpected CARGO_CFG_TARGET_ARCH: {:?}", target_arch),
+          };
+
+          configure_command.arg("-A").arg(platform);
+        },
+        "android" => {
+          assert!(cfg!(target_os = "windows"), "TODO: CMAKE_MAKE_PROGRAM for other platforms.");
+
+          let android_ndk_home = PathBuf::from(env::var_os("ANDROID_NDK_HOME").expect("Environment variable ANDROID_NDK_HOME not set !"));
+          let android_abi_name = match target_arch {
+            "aarch64" => "arm64-v8a",
+            "arm"     => "armeabi-v7a",
+            _ => panic!("Unexpected CARGO_CFG_TARGET_ARCH: {:?}", target_arch),
+          };
+
+          configure_command
+            .arg("-G").arg("Unix Makefiles")
+            .arg(format!(r#"-DANDROID_ABI={}"#, android_abi_name))
+            .arg(r#"-DCMAKE_BUILD_TYPE=Release"#)
+            .arg(r#"-DANDROID_STL=c++_shared"#)
+            .arg(r#"-DANDROID_PLATFORM=android-24"#)
+            .arg(r#"-DCMAKE_SYSTEM_NAME=Android"#)
+            .arg(r#"-DANDROID_TOOLCHAIN=clang"#)
+            .arg(r#"-DANDROID_ARM_MODE=arm"#)
+            .arg(cmake_define("CMAKE_MAKE_PROGRAM", cmake_path(&android_ndk_home.join("prebuilt/windows-x86_64/bin/make.exe"))))
+            .arg(cmake_define("CMAKE_TOOLCHAIN_FILE", cmake_path(&android_ndk_home.join("build/cmake/android.toolchain.cmake"))));
+        },
+        "linux" | "macos" => {
+          assert_eq!(target_os, env::consts::OS, "Building for {} is only supported on {} hosts, unless {} is set !", target_os, target_os, CMAKE_TOOLCHAIN_FILE_ENV);
+
+          configure_command
+            .arg(r#"-DCMAKE_BUILD_TYPE=Release"#)
+            // Rust links position-independent executables by default.
+            .arg(r#"-DCMAKE_POSITION_INDEPENDENT_CODE=ON"#);
+        },
+        _ => panic!("Unexpected target_os:{:?}", target_os)
+      };
+    }
+
+    // Last, so that they can override any of the above.
+    configure_command.args(&extra_cmake_args);
+
+    let output = configure_command.output().unwrap();
+    io::stdout().write_all(&output.stdout).unwrap();
+    if !output.status.success() {
+      return Err(BuilderError::ConfigureFailed { output });
+    }
 
     // Build.
     // cmake --build . --config Release --target install
@@ -223,7 +276,7 @@ impl Builder {
       .arg("--build")
       .arg(".")
       .arg("--config").arg("Release")
-      .arg("--target").arg(install_dir)
+      .arg("--target").arg("install")
       .arg("--parallel").arg("8")
       .output()
       .unwrap();
@@ -237,14 +290,22 @@ impl Builder {
   }
 }
 
-fn get_prebuilt_glslang_install_dir() -> PathBuf {
+/// The glslang commit the bindings and the Rust API in this crate are written against.
+const GLSLANG_COMMIT: &str = "adbf0d3106b26daa237b10b9bf72b1af7c31092d";
+
+/// Locates the prebuilt glslang install directory exported by the `glslang-sys-2022-prebuilt-<target>` companion crate,
+/// returning it with the glslang commit the binaries are built from.
+fn get_prebuilt_glslang_install_dir() -> (PathBuf, String) {
   let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap();  
 
   let target_arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap();
   let target_dir = match target_os.as_str() {
     "windows" => {
-      assert_eq!(target_arch, "x86_64");
-      "x86_64-pc-windows-msvc"
+      match target_arch.as_str() {
+        "x86_64" => "x86_64-pc-windows-msvc",
+        "x86"    => panic!("No prebuilt glslang binaries for i686-pc-windows-msvc ! Enable the `build-from-source` feature."),
+        _ => panic!("Unexpected CARGO_CFG_TARGET_ARCH: {:?}", target_arch),
+      }
     },
     "android" => {
       assert_eq!(target_arch, "aarch64");
@@ -253,39 +314,89 @@ fn get_prebuilt_glslang_install_dir() -> PathBuf {
     _ => panic!("Unexpected CARGO_CFG_TARGET_OS:{:?}", target_os)
   };
 
-  let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+  // `links = "glslang-prebuilt-<target>"` in the companion crate.
+  let dep_prefix = format!("DEP_GLSLANG_PREBUILT_{}", target_dir.to_uppercase().replace('-', "_"));
+
+  let root = env::var(format!("{}_ROOT", dep_prefix))
+    .unwrap_or_else(|_| panic!("Prebuilt glslang binaries for {} not found ! Is the `prebuilt` feature enabled ?", target_dir));
 
-  PathBuf::from(manifest_dir).join(format!("prebuilt/{}", target_dir))
+  let glslang_commit = env::var(format!("{}_GLSLANG_COMMIT", dep_prefix)).unwrap();
+  info!("Prebuilt glslang commit: {}", glslang_commit);
+  if glslang_commit != GLSLANG_COMMIT {
+    println!(
+      "cargo:warning=Prebuilt glslang binaries are built from commit {}, but glslang-sys expects {}.",
+      glslang_commit, GLSLANG_COMMIT,
+    );
+  }
+
+  (PathBuf::from(root), glslang_commit)
 }
 
+/// Locates `libstdc++.a` through the C++ compiler `shim_build` uses, returning its directory.
+fn find_static_libstdcpp_dir(shim_build: &cc::Build) -> Result<PathBuf, String> {
+  let compiler = shim_build.get_compiler();
+  let output = compiler.to_command()
+    .arg("-print-file-name=libstdc++.a")
+    .output()
+    .map_err(|error| format!("Failed to run {:?}: {}", compiler.path(), error))?;
+
+  // GCC and Clang print the name unchanged when the file is not found.
+  let path = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+  if !output.status.success() || !path.is_absolute() || !path.exists() {
+    return Err(format!(
+      "{} is set, but {:?} cannot find libstdc++.a. Install the static libstdc++ (e.g. `libstdc++-<version>-dev`), or unset {}.",
+      STATIC_STDCPP_ENV, compiler.path(), STATIC_STDCPP_ENV,
+    ));
+  }
+  Ok(path.parent().unwrap().to_owned())
+}
+
+/// Set to `1` to link libstdc++ statically on Linux targets.
+const STATIC_STDCPP_ENV: &str = "GLSLANG_SYS_STATIC_STDCPP";
+
 fn main() {
   const WRAPPER_HEADER: &str = "src/wrapper.h";
-  const LIBS: [&str; 8] = [
+  const SHIM_HEADER: &str = "src/shim.h";
+  const SHIM_SOURCE: &str = "src/shim.cpp";
+  const LIBS: [&str; 7] = [
     "GenericCodeGen",
     "glslang",
     "glslang-default-resource-limits",
-    "HLSL",
     "MachineIndependent",
     "OGLCompiler",
     "OSDependent",
     "SPIRV",
   ];
+  const HLSL_LIB: &str = "HLSL";
+  const SPVREMAPPER_LIB: &str = "SPVRemapper";
+  // Built from glslang's `External/spirv-tools` with `ENABLE_OPT=ON`. `SPIRV` depends on the optimizer, which depends on the core library.
+  const SPIRV_TOOLS_LIBS: [&str; 2] = ["SPIRV-Tools-opt", "SPIRV-Tools"];
 
   env_logger::init();
 
   let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap();
   let target_arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap();
 
-  let install_dir_path: PathBuf =
+  for env_var in [CMAKE_TOOLCHAIN_FILE_ENV, CMAKE_ARGS_ENV, BINDGEN_TARGET_ENV, BINDGEN_SYSROOT_ENV] {
+    println!("cargo:rerun-if-env-changed={}", env_var);
+  }
+  let cmake_toolchain_file = env::var_os(CMAKE_TOOLCHAIN_FILE_ENV).map(PathBuf::from);
+
+  let (install_dir_path, glslang_commit): (PathBuf, String) =
     if cfg!(feature = "build-from-source") {
-      let builder = Builder::new();
+      let builder = Builder::new(cmake_toolchain_file.clone());
       builder.fetch_glslang().unwrap();
+      // Caches of compiled shaders are keyed on the commit, so a placeholder shared by every unknown build would be wrong.
+      let glslang_commit = builder.glslang_commit().unwrap_or_else(|| {
+        panic!("Cannot determine the commit of the glslang clone in {} ! `git rev-parse HEAD` failed.", builder.glslang_clone_dst_dir_path.display())
+      });
 
       match builder.build_glslang(&target_os, &target_arch) {
-        Ok(path) => path,
+        Ok(path) => (path, glslang_commit),
         Err(error) => {
-          match error {
+          match &error {
             BuilderError::NoAvailableDriveLetter => (),
+            BuilderError::InvalidExtraCmakeArgs(_) => (),
             BuilderError::ConfigureFailed { output } => {
               io::stderr().write_all(&output.stdout).unwrap();
               io::stderr().write_all(&output.stderr).unwrap();
@@ -295,40 +406,128 @@ fn main() {
               io::stderr().write_all(&output.stderr).unwrap();
             },
           }
-          panic!("Failed to build glslang from source !");
+          panic!("Failed to build glslang from source ! {}", error);
         },
       }
     }
-    else {
+    else if cfg!(feature = "prebuilt") {
+      // The prebuilt binaries are always built with HLSL support; the feature then only affects the Rust API.
+      if !cfg!(feature = "hlsl") {
+        println!("cargo:warning=Feature `hlsl` is disabled, but the prebuilt glslang binaries include HLSL support. Only the Rust API will reject HLSL input.");
+      }
       get_prebuilt_glslang_install_dir()
+    }
+    else {
+      panic!("No source for the glslang binaries ! Enable either the `prebuilt` or the `build-from-source` feature.");
     };
+  let link_hlsl = cfg!(feature = "hlsl") || !cfg!(feature = "build-from-source");
+
+  let glslang_include_dir = install_dir_path.join("include");
+  // `GLSLANG_COMMIT` of the crate, e.g. for invalidating caches of compiled shaders.
+  println!("cargo:rustc-env=GLSLANG_SYS_GLSLANG_COMMIT={}", glslang_commit);
+
+  println!("cargo:rerun-if-env-changed={}", STATIC_STDCPP_ENV);
+  println!("cargo:rustc-check-cfg=cfg(glslang_sys_static_stdcpp)");
+  let static_stdcpp = env::var(STATIC_STDCPP_ENV).is_ok_and(|value| value == "1");
+  if static_stdcpp && target_os != "linux" {
+    println!("cargo:warning={} is only supported for Linux targets, ignoring.", STATIC_STDCPP_ENV);
+  }
+  let static_stdcpp = static_stdcpp && target_os == "linux";
+
+  let mut shim_build = cc::Build::new();
+  shim_build
+    .cpp(true)
+    .file(SHIM_SOURCE)
+    .include(&glslang_include_dir)
+    .flag_if_supported("-std=c++17");
+  // glslang's public headers declare the HLSL setters only with `ENABLE_HLSL`, as glslang itself is built.
+  if link_hlsl {
+    shim_build.define("ENABLE_HLSL", None);
+  }
+  if cfg!(feature = "remapper") {
+    shim_build.define("GLSLANG_SYS_SPVREMAPPER", None);
+  }
+
+  let static_libstdcpp_dir = if static_stdcpp {
+    let dir = find_static_libstdcpp_dir(&shim_build).unwrap_or_else(|message| panic!("{}", message));
+    // Linked explicitly below instead.
+    shim_build.cpp_link_stdlib(None);
+    Some(dir)
+  }
+  else {
+    None
+  };
+
+  // The shim links against glslang, so it must come before the glslang libraries.
+  shim_build.compile("glslang-sys-shim");
 
   let link_search_path = install_dir_path.join("lib");
-  println!("cargo:rustc-link-search=native={}", link_search_path.to_str().unwrap());
+  println!("cargo:rustc-link-search=native={}", utf8_path(&link_search_path));
   for lib in LIBS {
     println!("cargo:rustc-link-lib=static={}", lib);
   }
+  if link_hlsl {
+    println!("cargo:rustc-link-lib=static={}", HLSL_LIB);
+  }
+  if cfg!(feature = "remapper") {
+    println!("cargo:rustc-link-lib=static={}", SPVREMAPPER_LIB);
+  }
+  if cfg!(feature = "spirv-tools") {
+    for lib in SPIRV_TOOLS_LIBS {
+      println!("cargo:rustc-link-lib=static={}", lib);
+    }
+  }
+
+  // `-static-libstdc++` via `cargo:rustc-link-arg` would only apply to this package's own binaries and tests,
+  // so link `libstdc++.a` like any other static library, which propagates to dependents. It must come after glslang.
+  if let Some(static_libstdcpp_dir) = static_libstdcpp_dir {
+    println!("cargo:rustc-link-search=native={}", utf8_path(&static_libstdcpp_dir));
+    println!("cargo:rustc-link-lib=static=stdc++");
+    println!("cargo:rustc-cfg=glslang_sys_static_stdcpp");
+  }
   println!("cargo:rerun-if-changed={}", WRAPPER_HEADER);
+  println!("cargo:rerun-if-changed={}", SHIM_HEADER);
+  println!("cargo:rerun-if-changed={}", SHIM_SOURCE);
 
   // For Android, link to `c++_shared`.
   if target_os == "android" {
     println!("cargo:rustc-link-lib=c++_shared");
   }
 
-  let glslang_include_dir = install_dir_path.join("include");
+  let bindgen_target = env::var(BINDGEN_TARGET_ENV).unwrap_or_else(|_| env::var("TARGET").unwrap());
 
+  // Pointer width (and thus struct layout) depends on the target, e.g. i686 vs x86_64 on the same host.
   let mut bindings_builder = bindgen::Builder::default()
     .header(WRAPPER_HEADER)
     .allowlist_file(".*glslang_c_shader_types.h")
     .allowlist_file(".*glslang_c_interface.h")
+    .allowlist_file(".*shim.h")
+    // `GLSLANG_VERSION_MAJOR` etc. of the headers, e.g. for gating features at compile time.
+    .allowlist_var("GLSLANG_VERSION_.*")
     .parse_callbacks(Box::new(bindgen::CargoCallbacks))
-    .clang_arg(format!("-I{}", glslang_include_dir.to_str().unwrap()));
+    .clang_arg(format!("--target={}", bindgen_target))
+    .clang_arg(clang_path_arg("-I", &glslang_include_dir));
+
+  if cfg!(feature = "remapper") {
+    bindings_builder = bindings_builder.clang_arg("-DGLSLANG_SYS_SPVREMAPPER");
+  }
+
+  // SPIRV-Tools installs its headers next to glslang's.
+  if cfg!(feature = "spirv-tools") {
+    bindings_builder = bindings_builder
+      .allowlist_file(".*libspirv.h")
+      .clang_arg("-DGLSLANG_SYS_SPIRV_TOOLS");
+  }
 
-  // For Android, add header search paths:
+  if let Some(bindgen_sysroot) = env::var_os(BINDGEN_SYSROOT_ENV) {
+    bindings_builder = bindings_builder.clang_arg(clang_path_arg("--sysroot=", &PathBuf::from(bindgen_sysroot)));
+  }
+
+  // For Android, add header search paths, unless a toolchain file is used:
   //  %ANDROID_NDK_HOME%/sysroot/usr/include
   //  %ANDROID_NDK_HOME%/sysroot/usr/include/(aarch64-linux-android|arm-linux-androideabi)
-  if target_os == "android" {
-    let android_ndk_home = env::var("ANDROID_NDK_HOME").expect("Environment variable ANDROID_NDK_HOME not set !");
+  if target_os == "android" && cmake_toolchain_file.is_none() {
+    let android_ndk_home = PathBuf::from(env::var_os("ANDROID_NDK_HOME").expect("Environment variable ANDROID_NDK_HOME not set !"));
     info!("ANDROID_NDK_HOME: {:?}", android_ndk_home);
     
     let android_arch_name = match target_arch.as_str() {
@@ -337,14 +536,14 @@ fn main() {
       _ => panic!("Unexpected CARGO_CFG_TARGET_ARCH: {:?}", target_arch),
     };
 
-    let android_ndk_include_dir: PathBuf = [ android_ndk_home.as_str(), r#"sysroot/usr/include"# ].iter().collect();
+    let android_ndk_include_dir: PathBuf = android_ndk_home.join("sysroot/usr/include");
     let android_ndk_arch_include_dir: PathBuf = android_ndk_include_dir.join(android_arch_name);
     info!("Android NDK include directory: {:?}", android_ndk_include_dir);
     info!("Android NDK architecture-dependent include directory: {:?}", android_ndk_arch_include_dir);
 
     bindings_builder = bindings_builder
-      .clang_arg(format!("-isystem{}", android_ndk_arch_include_dir.to_str().unwrap()))
-      .clang_arg(format!("-isystem{}", android_ndk_include_dir.to_str().unwrap()));
+      .clang_arg(clang_path_arg("-isystem", &android_ndk_arch_include_dir))
+      .clang_arg(clang_path_arg("-isystem", &android_ndk_include_dir));
   }
 
   let bindings = bindings_builder.generate().expect("Unable to generate bindings !");
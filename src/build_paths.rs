@@ -0,0 +1,104 @@
+//! Arguments, mostly paths, passed to external tools by the build script.
+//!
+//! Arguments are passed to `Command` one by one (never through a shell), so paths containing spaces or non-ASCII
+//! characters need no quoting as long as each path stays within a single argument.
+
+use std::ffi::{OsStr, OsString};
+use std::path::Path;
+
+/// `-D<name>=<value>` as a single cmake argument.
+pub fn cmake_define(name: &str, value: impl AsRef<OsStr>) -> OsString {
+  let mut define = OsString::from(format!("-D{}=", name));
+  define.push(value);
+  define
+}
+
+/// `path` with forward slashes, since CMake treats backslashes in strings as escape characters.
+/// Paths that are not valid Unicode are returned unchanged.
+pub fn cmake_path(path: &Path) -> OsString {
+  match path.to_str() {
+    Some(path) => OsString::from(path.replace('\\', "/")),
+    None => path.as_os_str().to_owned(),
+  }
+}
+
+/// `<flag><path>`, e.g. `-I<path>`, as a single clang argument for bindgen, which only takes `String`s.
+///
+/// # Panics
+/// - If `path` is not valid Unicode.
+pub fn clang_path_arg(flag: &str, path: &Path) -> String {
+  format!("{}{}", flag, utf8_path(path))
+}
+
+/// For cargo directives, which are UTF-8 text.
+///
+/// # Panics
+/// - If `path` is not valid Unicode.
+pub fn utf8_path(path: &Path) -> &str {
+  path.to_str().unwrap_or_else(|| panic!("Path is not valid Unicode: {:?}", path))
+}
+
+/// Splits whitespace-separated cmake arguments, e.g. from an environment variable.
+///
+/// Only `-D<name>=<value>` (cache variables) is accepted.
+pub fn parse_extra_cmake_args(value: &str) -> Result<Vec<String>, String> {
+  value
+    .split_whitespace()
+    .map(|arg| {
+      match arg.strip_prefix("-D") {
+        Some(define) if define.contains('=') && !define.starts_with('=') => Ok(arg.to_owned()),
+        _ => Err(format!("expected -D<name>=<value>, got {:?}", arg)),
+      }
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::PathBuf;
+  use super::*;
+
+  #[test]
+  fn cmake_define_keeps_spaces_and_non_ascii() {
+    let install_dir = Path::new(r"C:\Users\José García\target\out dir\install");
+    assert_eq!(cmake_define("CMAKE_INSTALL_PREFIX", cmake_path(install_dir)), OsString::from("-DCMAKE_INSTALL_PREFIX=C:/Users/José García/target/out dir/install"));
+    assert_eq!(cmake_define("ENABLE_HLSL", "ON"), OsString::from("-DENABLE_HLSL=ON"));
+  }
+
+  #[test]
+  fn cmake_path_from_joined_components() {
+    let ndk_home = PathBuf::from("/opt/Android SDK/ndk/25.1 (ñ)");
+    assert_eq!(
+      cmake_path(&ndk_home.join("build").join("cmake").join("android.toolchain.cmake")),
+      OsString::from("/opt/Android SDK/ndk/25.1 (ñ)/build/cmake/android.toolchain.cmake"),
+    );
+  }
+
+  #[test]
+  fn clang_path_arg_is_a_single_argument() {
+    assert_eq!(clang_path_arg("-I", Path::new("/home/José García/out/include")), "-I/home/José García/out/include");
+    assert_eq!(clang_path_arg("-isystem", Path::new("C:/ndk dir/sysroot/usr/include")), "-isystemC:/ndk dir/sysroot/usr/include");
+  }
+
+  #[test]
+  fn extra_cmake_args() {
+    assert_eq!(parse_extra_cmake_args(""), Ok(vec![]));
+    assert_eq!(
+      parse_extra_cmake_args("  -DCMAKE_SYSROOT=/opt/poky/sysroots/cortexa53 \t-DENABLE_OPT=ON\n"),
+      Ok(vec!["-DCMAKE_SYSROOT=/opt/poky/sysroots/cortexa53".to_owned(), "-DENABLE_OPT=ON".to_owned()]),
+    );
+    assert!(parse_extra_cmake_args("-DENABLE_OPT=ON -G Ninja").is_err());
+    assert!(parse_extra_cmake_args("-DENABLE_OPT").is_err());
+    assert!(parse_extra_cmake_args("-D=ON").is_err());
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn non_unicode_paths() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = Path::new(OsStr::from_bytes(b"/tmp/\xff/install"));
+    assert_eq!(cmake_path(path), path.as_os_str());
+    assert!(std::panic::catch_unwind(|| utf8_path(path)).is_err());
+  }
+}
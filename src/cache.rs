@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::*;
+
+struct CacheEntry {
+  spirv: Spirv,
+  includes: Vec<IncludeRecord>,
+  /// The `clock` of the cache when the entry was last returned or inserted.
+  last_used: u64,
+}
+
+/// Caches the SPIR-V of compiles in memory, e.g. for hot reloading, keyed by a hash of the whole [`CompileInput`] and the
+/// option flags.
+///
+/// The key cannot cover the content of `#include`s, which is only known while compiling. Instead, the includes of a cached
+/// compile are resolved again with the input's include handler on every lookup, and any change is a miss.
+///
+/// Errors are not cached. When full, inserting evicts the least recently used entry.
+pub struct CompileCache {
+  capacity: usize,
+  entries: HashMap<u64, CacheEntry>,
+  /// Incremented on every lookup.
+  clock: u64,
+  hits: u64,
+  misses: u64,
+}
+impl CompileCache {
+  /// A cache of at most `capacity` entries. A capacity of 0 caches nothing.
+  #[must_use]
+  pub fn new(capacity: usize) -> Self {
+    CompileCache {
+      capacity,
+      entries: HashMap::new(),
+      clock: 0,
+      hits: 0,
+      misses: 0,
+    }
+  }
+
+  /// The SPIR-V of a previous compile with the same input, option flags and include contents, or else that of
+  /// [`compile_input`].
+  pub fn compile_or_get(&mut self, input: &CompileInput, option_flags: CompileOptionFlags) -> Result<Spirv, CompileError> {
+    let key = input_key(input, option_flags);
+    self.clock += 1;
+
+    if let Some(entry) = self.entries.get_mut(&key) {
+      if includes_unchanged(&entry.includes, input) {
+        entry.last_used = self.clock;
+        self.hits += 1;
+        return Ok(entry.spirv.clone());
+      }
+    }
+    self.misses += 1;
+
+    let output = compile_input(input, option_flags)?;
+    if self.capacity == 0 {
+      return Ok(output.spirv);
+    }
+    if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+      let (&oldest, _) = self.entries.iter().min_by_key(|(_, entry)| entry.last_used).unwrap();
+      self.entries.remove(&oldest);
+    }
+    self.entries.insert(key, CacheEntry { spirv: output.spirv.clone(), includes: output.includes, last_used: self.clock });
+    Ok(output.spirv)
+  }
+
+  #[must_use]
+  pub fn capacity(&self) -> usize {
+    self.capacity
+  }
+
+  #[must_use]
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  #[must_use]
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  /// Lookups answered from the cache.
+  #[must_use]
+  pub fn hits(&self) -> u64 {
+    self.hits
+  }
+
+  /// Lookups that compiled, including failed compiles.
+  #[must_use]
+  pub fn misses(&self) -> u64 {
+    self.misses
+  }
+
+  /// Removes every entry, keeping the hit and miss counts.
+  pub fn clear(&mut self) {
+    self.entries.clear();
+  }
+}
+
+/// The key of a compile of `input` with `option_flags` in [`CompileCache`] and [`DiskCache`].
+pub(crate) fn input_key(input: &CompileInput, option_flags: CompileOptionFlags) -> u64 {
+  let mut hasher = DefaultHasher::new();
+  input.hash_settings(&mut hasher);
+  option_flags.hash(&mut hasher);
+  hasher.finish()
+}
+
+/// Whether every include of a cached compile still resolves to the same name and content with the handler of `input`.
+fn includes_unchanged(includes: &[IncludeRecord], input: &CompileInput) -> bool {
+  let Some(include_handler) = input.shader_settings().include_handler else {
+    return includes.is_empty();
+  };
+  includes.iter().all(|include| include.resolve_again(include_handler).as_ref() == Ok(&include.resolved))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SOURCE: &str = "#version 450\n#extension GL_GOOGLE_include_directive : require\n#include \"tint.glsl\"\nlayout(location = 0) out vec4 out_color;\nvoid main() {\n  out_color = TINT;\n}\n";
+
+  #[test]
+  fn hit_and_include_change() {
+    let root = std::env::temp_dir().join(format!("glslang-sys-compile-cache-{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+    scopeguard::defer! {
+      let _ = std::fs::remove_dir_all(&root);
+    }
+    let tint_path = root.join("tint.glsl");
+    std::fs::write(&tint_path, "#define TINT vec4(1.0)\n").unwrap();
+
+    let input = CompileInput::new()
+      .source(SOURCE)
+      .stage(ShaderStage::Fragment)
+      .include_handler(FileIncluder::new(vec![root.clone()]));
+    let mut cache = CompileCache::new(4);
+    let first = cache.compile_or_get(&input, CompileOptionFlags::empty()).unwrap();
+    assert_eq!(first, compile_input(&input, CompileOptionFlags::empty()).unwrap().spirv);
+    assert_eq!((cache.hits(), cache.misses()), (0, 1));
+
+    assert_eq!(cache.compile_or_get(&input, CompileOptionFlags::empty()).unwrap(), first);
+    assert_eq!((cache.hits(), cache.misses()), (1, 1));
+
+    // Other option flags are another entry.
+    assert!(cache.compile_or_get(&input, CompileOptionFlags::GenerateDebugInfo).is_ok());
+    assert_eq!((cache.hits(), cache.misses(), cache.len()), (1, 2, 2));
+
+    // The key is unchanged, but the include is not.
+    std::fs::write(&tint_path, "#define TINT vec4(0.5)\n").unwrap();
+    let changed = cache.compile_or_get(&input, CompileOptionFlags::empty()).unwrap();
+    assert_ne!(changed, first);
+    assert_eq!((cache.hits(), cache.misses(), cache.len()), (1, 3, 2));
+    assert_eq!(cache.compile_or_get(&input, CompileOptionFlags::empty()).unwrap(), changed);
+    assert_eq!(cache.hits(), 2);
+  }
+
+  #[test]
+  fn least_recently_used_is_evicted() {
+    let inputs: Vec<_> = (0..3)
+      .map(|i| CompileInput::new().source(&format!("#version 450\nlayout(location = 0) out vec4 out_color;\nvoid main() {{\n  out_color = vec4({}.0);\n}}\n", i)).stage(ShaderStage::Fragment))
+      .collect();
+    let mut cache = CompileCache::new(2);
+    cache.compile_or_get(&inputs[0], CompileOptionFlags::empty()).unwrap();
+    cache.compile_or_get(&inputs[1], CompileOptionFlags::empty()).unwrap();
+    // Using the first entry makes the second the least recently used.
+    cache.compile_or_get(&inputs[0], CompileOptionFlags::empty()).unwrap();
+    cache.compile_or_get(&inputs[2], CompileOptionFlags::empty()).unwrap();
+    assert_eq!((cache.hits(), cache.misses(), cache.len()), (1, 3, 2));
+
+    cache.compile_or_get(&inputs[0], CompileOptionFlags::empty()).unwrap();
+    assert_eq!(cache.hits(), 2);
+    cache.compile_or_get(&inputs[1], CompileOptionFlags::empty()).unwrap();
+    assert_eq!(cache.misses(), 4);
+
+    // Errors are not cached.
+    let invalid = CompileInput::new().source("#version 450\nvoid main() {\n").stage(ShaderStage::Fragment);
+    assert!(cache.compile_or_get(&invalid, CompileOptionFlags::empty()).is_err());
+    assert!(cache.compile_or_get(&invalid, CompileOptionFlags::empty()).is_err());
+    assert_eq!((cache.misses(), cache.len()), (6, 2));
+  }
+}
@@ -0,0 +1,158 @@
+use std::os::raw::c_int;
+
+use thiserror::Error;
+
+use crate::*;
+
+/// The GLSL version and profile of sources without a `#version` directive (`default_version` and `default_profile` of
+/// `glslang_input_t`).
+///
+/// With `force`, they also override the `#version` directive of sources that have one (`force_default_version_and_profile`).
+///
+/// # GLSL ES
+/// [`DefaultVersion::es_310`] and [`DefaultVersion::es_320`] compile mobile shaders. glslang only generates SPIR-V from
+/// ES 310 and up, so `#version 300 es` and `100` sources need a forced ES preset. Forcing also accepts sources whose
+/// directive lacks the `es` suffix, e.g. `#version 310`.
+///
+/// Unlike desktop GLSL, ES fragment shaders need a default float precision, e.g. `precision mediump float;`. `mediump` and
+/// `lowp` values are decorated `RelaxedPrecision` in the SPIR-V, allowing drivers to compute them at 16 bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DefaultVersion {
+  pub version: u32,
+  /// [`Profile::None`] lets glslang pick the profile from the version, e.g. ES for 100.
+  pub profile: Profile,
+  pub force: bool,
+}
+impl Default for DefaultVersion {
+  /// glslang's own default: GLSL ES 1.00, as if the source started with `#version 100`.
+  fn default() -> Self {
+    Self::new(100, Profile::None)
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum DefaultVersionError {
+  #[error("Unknown GLSL version: {0}")]
+  UnknownVersion(u32),
+  #[error("GLSL {version} does not support the {profile:?} profile")]
+  ProfileMismatch { version: u32, profile: Profile },
+}
+
+impl DefaultVersion {
+  #[must_use]
+  pub const fn new(version: u32, profile: Profile) -> Self {
+    DefaultVersion {
+      version,
+      profile,
+      force: false,
+    }
+  }
+
+  /// `#version 450 core`.
+  #[must_use]
+  pub const fn desktop_450_core() -> Self {
+    Self::new(450, Profile::Core)
+  }
+  /// `#version 460 core`.
+  #[must_use]
+  pub const fn desktop_460_core() -> Self {
+    Self::new(460, Profile::Core)
+  }
+  /// `#version 310 es`.
+  #[must_use]
+  pub const fn es_310() -> Self {
+    Self::new(310, Profile::Es)
+  }
+  /// `#version 320 es`.
+  #[must_use]
+  pub const fn es_320() -> Self {
+    Self::new(320, Profile::Es)
+  }
+
+  /// Same version and profile, also overriding `#version` directives.
+  #[must_use]
+  pub const fn forced(self) -> Self {
+    DefaultVersion {
+      force: true,
+      ..self
+    }
+  }
+
+  /// Rejects unknown versions, and profiles the version does not have, e.g. ES with 450.
+  ///
+  /// Desktop GLSL has profiles from 150 on; [`Profile::None`] is accepted with any desktop version and with 100.
+  pub fn validate(&self) -> Result<(), DefaultVersionError> {
+    let is_es = matches!(self.version, 100 | 300 | 310 | 320);
+    let is_desktop = matches!(self.version, 110 | 120 | 130 | 140 | 150 | 330 | 400 | 410 | 420 | 430 | 440 | 450 | 460);
+    if !is_es && !is_desktop {
+      return Err(DefaultVersionError::UnknownVersion(self.version));
+    }
+
+    let profile_matches = match self.profile {
+      // glslang requires `es` for 300 and up.
+      Profile::None => is_desktop || self.version == 100,
+      Profile::Core | Profile::Compatibility => is_desktop && self.version >= 150,
+      Profile::Es => is_es,
+    };
+    if !profile_matches {
+      return Err(DefaultVersionError::ProfileMismatch { version: self.version, profile: self.profile });
+    }
+    Ok(())
+  }
+
+  /// Sets the corresponding fields of `input`.
+  pub(crate) fn apply(&self, input: &mut glslang_input_t) {
+    input.default_version = self.version as c_int;
+    input.default_profile = self.profile.into();
+    input.force_default_version_and_profile = self.force as c_int;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn presets_are_valid() {
+    for default_version in [DefaultVersion::default(), DefaultVersion::desktop_450_core(), DefaultVersion::desktop_460_core(), DefaultVersion::es_310(), DefaultVersion::es_320()] {
+      assert_eq!(default_version.validate(), Ok(()), "{:?}", default_version);
+    }
+    assert_eq!(DefaultVersion::new(450, Profile::Compatibility).forced().validate(), Ok(()));
+  }
+
+  #[test]
+  fn es_shaders() {
+    let compute = "#version 310 es\nlayout(local_size_x = 64) in;\nlayout(std430, binding = 0) buffer Data { mediump float values[]; };\nvoid main() {\n  values[gl_GlobalInvocationID.x] *= 2.0;\n}\n";
+    let input = CompileInput::new().source(compute).stage(ShaderStage::Compute);
+    assert!(compile_input(&input, CompileOptionFlags::empty()).is_ok());
+
+    let fragment = "#version 300 es\nprecision mediump float;\nlayout(location = 0) out vec4 out_color;\nvoid main() {\n  out_color = vec4(0.5);\n}\n";
+    let input = CompileInput::new().source(fragment).stage(ShaderStage::Fragment);
+    match compile_input(&input, CompileOptionFlags::empty()) {
+      Err(CompileError::Preprocess(error_log) | CompileError::Parse(error_log)) => assert!(error_log.info_log.contains("310"), "{}", error_log.info_log),
+      result => panic!("Unexpected result: {:?}", result),
+    }
+    let spirv = compile_input(&input.default_version(DefaultVersion::es_310().forced()), CompileOptionFlags::empty()).unwrap().spirv;
+    const DECORATION_RELAXED_PRECISION: u32 = 0;
+    assert!(spirv.windows(3).any(|words| words[0] == (3 << 16 | 71) && words[2] == DECORATION_RELAXED_PRECISION));
+
+    // Without the `es` suffix, 310 is not a desktop version.
+    let compute = compute.replace("310 es", "310");
+    let input = CompileInput::new().source(&compute).stage(ShaderStage::Compute);
+    assert!(compile_input(&input, CompileOptionFlags::empty()).is_err());
+    let input = input.default_version(DefaultVersion::es_310().forced());
+    assert!(compile_input(&input, CompileOptionFlags::empty()).is_ok());
+  }
+
+  #[test]
+  fn invalid_combinations() {
+    let error = DefaultVersion::new(450, Profile::Es).validate().unwrap_err();
+    assert_eq!(error, DefaultVersionError::ProfileMismatch { version: 450, profile: Profile::Es });
+    assert_eq!(error.to_string(), "GLSL 450 does not support the Es profile");
+
+    assert!(DefaultVersion::new(310, Profile::Core).validate().is_err());
+    assert!(DefaultVersion::new(310, Profile::None).validate().is_err());
+    assert!(DefaultVersion::new(140, Profile::Core).validate().is_err());
+    assert_eq!(DefaultVersion::new(451, Profile::Core).validate(), Err(DefaultVersionError::UnknownVersion(451)));
+  }
+}
@@ -0,0 +1,587 @@
+//! Parsing of glslang's info logs, e.g. `ERROR: 0:12: 'foo' : undeclared identifier`.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Write;
+
+/// The prefix glslang puts before a message (`TPrefixType`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+  Note,
+  Warning,
+  Error,
+  /// A bug in glslang rather than in the shader.
+  InternalError,
+  /// Functionality glslang does not implement.
+  Unimplemented,
+}
+impl Severity {
+  /// Ordered so that `ERROR: ` is not mistaken for the end of `INTERNAL ERROR: `.
+  const PREFIXES: [(&'static str, Severity); 5] = [
+    ("INTERNAL ERROR: ", Severity::InternalError),
+    ("UNIMPLEMENTED: ", Severity::Unimplemented),
+    ("ERROR: ", Severity::Error),
+    ("WARNING: ", Severity::Warning),
+    ("NOTE: ", Severity::Note),
+  ];
+
+  /// The prefix in the log, e.g. `ERROR: `.
+  #[must_use]
+  pub fn prefix(self) -> &'static str {
+    Self::PREFIXES.iter().find(|(_, severity)| *severity == self).unwrap().0
+  }
+
+  /// The label of [`render_diagnostics`], e.g. `error`.
+  fn label(self) -> &'static str {
+    match self {
+      Severity::Note => "note",
+      Severity::Warning => "warning",
+      Severity::Error => "error",
+      Severity::InternalError => "internal error",
+      Severity::Unimplemented => "unimplemented",
+    }
+  }
+
+  fn strip_prefix(line: &str) -> Option<(Severity, &str)> {
+    Self::PREFIXES.iter().find_map(|&(prefix, severity)| line.strip_prefix(prefix).map(|rest| (severity, rest)))
+  }
+}
+
+/// The source string a diagnostic refers to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DiagnosticSource {
+  /// The index of an unnamed source string, e.g. `0` for the source of a [`CompileInput`](crate::CompileInput).
+  /// Preambles come before it, so glslang numbers them negatively: `-1` is the
+  /// [`CompileInput::preamble`](crate::CompileInput::preamble).
+  Index(i32),
+  /// The name of the source, e.g. [`CompileInput::source_file_name`](crate::CompileInput::source_file_name), or the resolved
+  /// name of an include.
+  Name(String),
+}
+impl fmt::Display for DiagnosticSource {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      DiagnosticSource::Index(index) => write!(f, "{}", index),
+      DiagnosticSource::Name(name) => f.write_str(name),
+    }
+  }
+}
+
+/// Where a diagnostic points, e.g. `0:12:` or `shader.frag:12:5:`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SourceLocation {
+  pub source: DiagnosticSource,
+  pub line: u32,
+  /// Only reported with [`Messages::Enhanced`](crate::Messages::Enhanced).
+  pub column: Option<u32>,
+}
+impl fmt::Display for SourceLocation {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}:{}:", self.source, self.line)?;
+    if let Some(column) = self.column {
+      write!(f, "{}:", column)?;
+    }
+    Ok(())
+  }
+}
+
+/// One message of a glslang info log.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+  pub severity: Severity,
+  /// `None` for messages about no particular line, e.g. link errors.
+  pub location: Option<SourceLocation>,
+  /// The text after the location, e.g. `'foo' : undeclared identifier`, with continuation lines joined by `\n`.
+  pub message: String,
+}
+impl fmt::Display for Diagnostic {
+  /// The line as glslang logs it.
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.severity.prefix())?;
+    if let Some(location) = &self.location {
+      write!(f, "{} ", location)?;
+    }
+    f.write_str(&self.message)
+  }
+}
+
+/// Parses a glslang info log, e.g. [`GlslangErrorLog::info_log`](crate::GlslangErrorLog::info_log) or the lines of
+/// [`CompileOutput::warnings`](crate::CompileOutput::warnings) joined by `\n`.
+///
+/// Lines without a severity prefix continue the message before them; ones before the first message are skipped. The
+/// summary glslang ends failed compiles with, e.g. `ERROR: 1 compilation errors.  No code generated.`, is skipped too.
+#[must_use]
+pub fn parse_diagnostics(log: &str) -> Vec<Diagnostic> {
+  let mut diagnostics: Vec<Diagnostic> = Vec::new();
+  for line in log.lines().map(str::trim_end).filter(|line| !line.is_empty()) {
+    match Severity::strip_prefix(line) {
+      Some((_, rest)) if is_summary(rest) => {},
+      Some((severity, rest)) => {
+        let (location, message) = match split_location(rest) {
+          Some((location, message)) => (Some(location), message),
+          None => (None, rest),
+        };
+        diagnostics.push(Diagnostic { severity, location, message: message.to_owned() });
+      },
+      None => {
+        if let Some(diagnostic) = diagnostics.last_mut() {
+          diagnostic.message.push('\n');
+          diagnostic.message.push_str(line);
+        }
+      },
+    }
+  }
+  diagnostics
+}
+
+/// `1 compilation errors.  No code generated.`
+fn is_summary(message: &str) -> bool {
+  let count_length = message.bytes().take_while(u8::is_ascii_digit).count();
+  count_length > 0 && message[count_length..].starts_with(" compilation errors.")
+}
+
+/// Splits `source:line:[column:] message` at the first `:` followed by a line number, so that sources may contain `:`,
+/// e.g. `C:\shaders\blur.comp:3: ...`.
+fn split_location(text: &str) -> Option<(SourceLocation, &str)> {
+  for (colon, _) in text.match_indices(':').filter(|&(colon, _)| colon > 0) {
+    let source = &text[..colon];
+    let Some((line, rest)) = parse_number(&text[colon + 1..]) else {
+      continue;
+    };
+    let (column, message) = match parse_number(rest) {
+      Some((column, message)) => (Some(column), message),
+      None => (None, rest),
+    };
+    let source = match source.parse() {
+      Ok(index) => DiagnosticSource::Index(index),
+      Err(_) => DiagnosticSource::Name(source.to_owned()),
+    };
+    return Some((SourceLocation { source, line, column }, message.trim_start()));
+  }
+  None
+}
+
+/// Splits `123:rest` into `123` and `rest`.
+fn parse_number(text: &str) -> Option<(u32, &str)> {
+  let (number, rest) = text.split_once(':')?;
+  if number.is_empty() || !number.bytes().all(|byte| byte.is_ascii_digit()) {
+    return None;
+  }
+  Some((number.parse().ok()?, rest))
+}
+
+/// The texts diagnostics point into, for [`render_diagnostics`].
+#[derive(Debug, Clone, Default)]
+pub struct DiagnosticSources<'a> {
+  source: Option<&'a str>,
+  preamble: Option<&'a str>,
+  files: HashMap<String, &'a str>,
+}
+impl<'a> DiagnosticSources<'a> {
+  /// `source` is the unnamed source, [`DiagnosticSource::Index`] `0`.
+  #[must_use]
+  pub fn new(source: &'a str) -> Self {
+    DiagnosticSources {
+      source: Some(source),
+      ..Default::default()
+    }
+  }
+
+  /// The [`CompileInput::preamble`](crate::CompileInput::preamble), [`DiagnosticSource::Index`] `-1`.
+  #[must_use]
+  pub fn preamble(mut self, preamble: &'a str) -> Self {
+    self.preamble = Some(preamble);
+    self
+  }
+
+  /// A named source, e.g. the [`CompileInput::source_file_name`](crate::CompileInput::source_file_name) or the resolved name
+  /// of an include.
+  #[must_use]
+  pub fn file(mut self, name: &str, text: &'a str) -> Self {
+    self.files.insert(name.to_owned(), text);
+    self
+  }
+
+  fn line(&self, location: &SourceLocation) -> Option<&'a str> {
+    let text = match &location.source {
+      DiagnosticSource::Index(0) => self.source?,
+      DiagnosticSource::Index(-1) => self.preamble?,
+      DiagnosticSource::Index(_) => return None,
+      DiagnosticSource::Name(name) => *self.files.get(name)?,
+    };
+    text.lines().nth((location.line as usize).checked_sub(1)?)
+  }
+}
+
+/// Renders `diagnostics` like rustc, showing the line each one points at with the offending token underlined:
+///
+/// ```text
+/// error: 'undefined_value' : undeclared identifier
+///  --> 0:5
+///   |
+/// 5 |   out_color = undefined_value;
+///   |               ^^^^^^^^^^^^^^^
+/// ```
+///
+/// Lines missing from `sources`, e.g. of an include that was not added, are left out.
+#[must_use]
+pub fn render_diagnostics(diagnostics: &[Diagnostic], sources: &DiagnosticSources) -> String {
+  let mut rendered = String::new();
+  for diagnostic in diagnostics {
+    if !rendered.is_empty() {
+      rendered.push('\n');
+    }
+    render_diagnostic(&mut rendered, diagnostic, sources).unwrap();
+  }
+  rendered
+}
+
+fn render_diagnostic(out: &mut String, diagnostic: &Diagnostic, sources: &DiagnosticSources) -> fmt::Result {
+  let mut message_lines = diagnostic.message.lines();
+  writeln!(out, "{}: {}", diagnostic.severity.label(), message_lines.next().unwrap_or_default())?;
+
+  let line_number = diagnostic.location.as_ref().map_or(String::new(), |location| location.line.to_string());
+  let gutter = " ".repeat(line_number.len());
+  if let Some(location) = &diagnostic.location {
+    write!(out, "{}--> {}:{}", gutter, location.source, location.line)?;
+    if let Some(column) = location.column {
+      write!(out, ":{}", column)?;
+    }
+    writeln!(out)?;
+
+    if let Some(line) = sources.line(location) {
+      let (start, length) = underline(line, location.column, quoted_token(&diagnostic.message));
+      // Tabs before the underline are kept, so that it lines up however they are displayed.
+      let indent = line[..start].chars().map(|c| if c == '\t' { '\t' } else { ' ' }).collect::<String>();
+      writeln!(out, "{} |", gutter)?;
+      writeln!(out, "{} | {}", line_number, line)?;
+      writeln!(out, "{} | {}{}", gutter, indent, "^".repeat(length))?;
+    }
+  }
+  for message_line in message_lines {
+    writeln!(out, "{} = {}", gutter, message_line.trim_start())?;
+  }
+  Ok(())
+}
+
+/// The token glslang quotes at the start of most messages, e.g. `foo` of `'foo' : undeclared identifier`.
+fn quoted_token(message: &str) -> Option<&str> {
+  let (token, _) = message.strip_prefix('\'')?.split_once("' :")?;
+  (!token.is_empty()).then_some(token)
+}
+
+/// The byte offset and length in characters of the part of `line` to underline: `token`, preferably at or after `column`,
+/// or else the character at `column`, or else the whole line without indentation.
+fn underline(line: &str, column: Option<u32>, token: Option<&str>) -> (usize, usize) {
+  let column_offset = column.and_then(|column| line.char_indices().nth((column as usize).checked_sub(1)?)).map(|(offset, _)| offset);
+  if let Some(token) = token {
+    let token_offset = column_offset
+      .and_then(|column_offset| line[column_offset..].find(token).map(|offset| column_offset + offset))
+      .or_else(|| line.find(token));
+    if let Some(token_offset) = token_offset {
+      return (token_offset, token.chars().count());
+    }
+  }
+  if let Some(column_offset) = column_offset {
+    return (column_offset, 1);
+  }
+  let trimmed = line.trim_start();
+  (line.len() - trimmed.len(), trimmed.trim_end().chars().count().max(1))
+}
+
+/// Receives the diagnostics of a compile as they are discovered, in addition to them being returned in the result, e.g. to
+/// show them in an editor panel or an engine log. See [`CompileInput::diagnostic_sink`](crate::CompileInput::diagnostic_sink).
+///
+/// Closures `Fn(&Diagnostic)` implement this trait. Sinks are `Send + Sync` like [`IncludeHandler`](crate::IncludeHandler)s,
+/// so collecting sinks need e.g. a `Mutex`.
+pub trait DiagnosticSink: Send + Sync {
+  fn diagnostic(&self, diagnostic: &Diagnostic);
+}
+impl<F> DiagnosticSink for F
+where
+  F: Fn(&Diagnostic) + Send + Sync,
+{
+  fn diagnostic(&self, diagnostic: &Diagnostic) {
+    self(diagnostic)
+  }
+}
+impl fmt::Debug for dyn DiagnosticSink {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("DiagnosticSink")
+  }
+}
+
+/// Delivers the diagnostics of one compile to its sink, if any.
+///
+/// glslang's info logs grow over the steps of a compile, so only the part after what was already delivered is.
+/// A panic of the sink is caught, and the sink receives nothing more of the compile.
+pub(crate) struct DiagnosticDelivery<'a> {
+  sink: Option<&'a dyn DiagnosticSink>,
+  shader_log_length: usize,
+  program_log_length: usize,
+}
+impl<'a> DiagnosticDelivery<'a> {
+  pub(crate) fn new(sink: Option<&'a dyn DiagnosticSink>) -> Self {
+    DiagnosticDelivery { sink, shader_log_length: 0, program_log_length: 0 }
+  }
+
+  /// Delivers the new part of the shader info log `log`.
+  pub(crate) fn shader_log(&mut self, log: &str) {
+    let new_part = log.get(self.shader_log_length..).unwrap_or_default();
+    self.shader_log_length = log.len();
+    self.deliver_log(new_part);
+  }
+
+  /// Delivers the new part of the program info log `log`.
+  pub(crate) fn program_log(&mut self, log: &str) {
+    let new_part = log.get(self.program_log_length..).unwrap_or_default();
+    self.program_log_length = log.len();
+    self.deliver_log(new_part);
+  }
+
+  /// Delivers lines of the SPIR-V generator, e.g. `warning: ...`, see [`spirv_message_diagnostic`].
+  pub(crate) fn spirv_messages<'m>(&mut self, messages: impl IntoIterator<Item = &'m str>) {
+    for message in messages {
+      self.deliver(&spirv_message_diagnostic(message));
+    }
+  }
+
+  pub(crate) fn deliver_log(&mut self, log: &str) {
+    for diagnostic in parse_diagnostics(log) {
+      self.deliver(&diagnostic);
+    }
+  }
+
+  pub(crate) fn deliver(&mut self, diagnostic: &Diagnostic) {
+    let Some(sink) = self.sink else {
+      return;
+    };
+    // Nothing is borrowed mutably across the call, so a panic cannot leave broken state behind.
+    if std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| sink.diagnostic(diagnostic))).is_err() {
+      self.sink = None;
+    }
+  }
+}
+
+/// A line of the SPIR-V generator's messages, which are prefixed in lowercase, e.g. `warning: ...`, as a diagnostic.
+/// Lines without a prefix, e.g. `TBD functionality: ...`, are notes.
+pub(crate) fn spirv_message_diagnostic(line: &str) -> Diagnostic {
+  let (severity, message) = match line.split_once(": ") {
+    Some(("error", message)) => (Severity::Error, message),
+    Some(("warning", message)) => (Severity::Warning, message),
+    _ => (Severity::Note, line),
+  };
+  Diagnostic { severity, location: None, message: message.to_owned() }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::*;
+  use super::spirv_message_diagnostic;
+
+  fn at(source: DiagnosticSource, line: u32) -> Option<SourceLocation> {
+    Some(SourceLocation { source, line, column: None })
+  }
+
+  #[test]
+  fn glsl_log() {
+    let log = "\
+WARNING: 0:2: '#extension' : extension not supported: GL_EXT_unknown
+ERROR: 0:12: 'foo' : undeclared identifier
+ERROR: 0:12: '' : compilation terminated
+ERROR: 2 compilation errors.  No code generated.
+
+
+";
+    let diagnostics = parse_diagnostics(log);
+    assert_eq!(diagnostics, [
+      Diagnostic { severity: Severity::Warning, location: at(DiagnosticSource::Index(0), 2), message: "'#extension' : extension not supported: GL_EXT_unknown".to_owned() },
+      Diagnostic { severity: Severity::Error, location: at(DiagnosticSource::Index(0), 12), message: "'foo' : undeclared identifier".to_owned() },
+      Diagnostic { severity: Severity::Error, location: at(DiagnosticSource::Index(0), 12), message: "'' : compilation terminated".to_owned() },
+    ]);
+    assert_eq!(diagnostics[1].to_string(), "ERROR: 0:12: 'foo' : undeclared identifier");
+  }
+
+  #[test]
+  fn hlsl_and_file_logs() {
+    let log = "\
+ERROR: C:\\shaders\\lighting.hlsl:7: 'undefined_value' : unknown variable
+ERROR: shaders/blur.comp:3:14: 'kernel' : undeclared identifier
+INTERNAL ERROR: Unable to parse built-ins
+UNIMPLEMENTED: some feature
+ERROR: Linking fragment stage: Missing entry point: Each stage requires one entry point
+  with a continuation line
+";
+    let diagnostics = parse_diagnostics(log);
+    assert_eq!(diagnostics.len(), 5);
+    assert_eq!(diagnostics[0].location, at(DiagnosticSource::Name("C:\\shaders\\lighting.hlsl".to_owned()), 7));
+    assert_eq!(diagnostics[0].message, "'undefined_value' : unknown variable");
+    assert_eq!(diagnostics[1].location, Some(SourceLocation { source: DiagnosticSource::Name("shaders/blur.comp".to_owned()), line: 3, column: Some(14) }));
+    assert_eq!(diagnostics[1].to_string(), "ERROR: shaders/blur.comp:3:14: 'kernel' : undeclared identifier");
+    assert_eq!((diagnostics[2].severity, diagnostics[2].location.as_ref()), (Severity::InternalError, None));
+    assert_eq!(diagnostics[3].severity, Severity::Unimplemented);
+    assert_eq!((diagnostics[4].severity, diagnostics[4].location.as_ref()), (Severity::Error, None));
+    assert_eq!(diagnostics[4].message, "Linking fragment stage: Missing entry point: Each stage requires one entry point\n  with a continuation line");
+  }
+
+  #[test]
+  fn warnings_and_errors_of_one_compile() {
+    let source = "#version 450\n#extension GL_EXT_unknown_extension : warn\nlayout(location = 0) out vec4 out_color;\nvoid main() {\n  out_color = undefined_value;\n}\n";
+    let input = CompileInput::new().source(source).stage(ShaderStage::Fragment);
+    let error_log = match compile_input(&input, CompileOptionFlags::empty()) {
+      Err(CompileError::Parse(error_log)) => error_log,
+      result => panic!("Unexpected result: {:?}", result),
+    };
+
+    let diagnostics = error_log.diagnostics();
+    let warning = diagnostics.iter().find(|diagnostic| diagnostic.severity == Severity::Warning).unwrap();
+    assert_eq!(warning.location, at(DiagnosticSource::Index(0), 2));
+    let error = diagnostics.iter().find(|diagnostic| diagnostic.severity == Severity::Error).unwrap();
+    assert_eq!(error.location, at(DiagnosticSource::Index(0), 5));
+    assert!(error.message.contains("undefined_value"), "{}", error.message);
+    assert!(diagnostics.iter().all(|diagnostic| !diagnostic.message.contains("compilation errors")), "{:?}", diagnostics);
+    // The raw text stays available.
+    assert!(error_log.info_log.contains("compilation errors"), "{}", error_log.info_log);
+  }
+
+  #[cfg(feature = "hlsl")]
+  #[test]
+  fn hlsl_compile_errors() {
+    let source = "float4 main() : SV_Target {\n  return undefined_value;\n}\n";
+    let input = CompileInput::new().source(source).language(SourceLanguage::Hlsl).stage(ShaderStage::Fragment);
+    let error_log = match compile_input(&input, CompileOptionFlags::empty()) {
+      Err(CompileError::Parse(error_log)) => error_log,
+      result => panic!("Unexpected result: {:?}", result),
+    };
+
+    let diagnostics = error_log.diagnostics();
+    let error = diagnostics.iter().find(|diagnostic| diagnostic.severity == Severity::Error).unwrap();
+    assert_eq!(error.location, at(DiagnosticSource::Index(0), 2));
+    assert!(error.message.contains("undefined_value"), "{}", error.message);
+  }
+
+  #[test]
+  fn render_broken_shader() {
+    let source = "#version 450\nlayout(location = 0) out vec4 out_color;\nvoid main() {\n  out_color = undefined_value;\n\tout_color.w = 1.0 +;\n}\n";
+    let log = "ERROR: 0:4: 'undefined_value' : undeclared identifier\nERROR: 0:5: ';' : syntax error, unexpected SEMICOLON\nERROR: 2 compilation errors.  No code generated.\n";
+    let rendered = render_diagnostics(&parse_diagnostics(log), &DiagnosticSources::new(source));
+    assert_eq!(rendered, "\
+error: 'undefined_value' : undeclared identifier
+ --> 0:4
+  |
+4 |   out_color = undefined_value;
+  |               ^^^^^^^^^^^^^^^
+
+error: ';' : syntax error, unexpected SEMICOLON
+ --> 0:5
+  |
+5 | 	out_color.w = 1.0 +;
+  | 	                   ^
+");
+  }
+
+  #[test]
+  fn render_preamble_includes_and_missing_lines() {
+    let log = "\
+WARNING: -1:1: 'FOO' : macro redefined
+ERROR: shaders/common.glsl:3:7: 'x' : undeclared identifier
+ERROR: 0:40: '' : missing #endif
+ERROR: Linking fragment stage: Missing entry point: Each stage requires one entry point
+  second line
+";
+    let sources = DiagnosticSources::new("void main() {}\n")
+      .preamble("#define FOO 1\n")
+      .file("shaders/common.glsl", "float f() {\n  float y = 1.0;\n  return x + y;\n}\n");
+    assert_eq!(render_diagnostics(&parse_diagnostics(log), &sources), "\
+warning: 'FOO' : macro redefined
+ --> -1:1
+  |
+1 | #define FOO 1
+  |         ^^^
+
+error: 'x' : undeclared identifier
+ --> shaders/common.glsl:3:7
+  |
+3 |   return x + y;
+  |          ^
+
+error: '' : missing #endif
+  --> 0:40
+
+error: Linking fragment stage: Missing entry point: Each stage requires one entry point
+ = second line
+");
+
+    // Without the texts, only the locations are rendered.
+    let rendered = render_diagnostics(&parse_diagnostics(log), &DiagnosticSources::default());
+    assert!(!rendered.contains(" | "), "{}", rendered);
+    assert!(rendered.contains(" --> shaders/common.glsl:3:7\n"), "{}", rendered);
+  }
+
+  #[test]
+  fn render_compile_errors() {
+    let source = "#version 450\nlayout(location = 0) out vec4 out_color;\nvoid main() {\n  out_color = undefined_value;\n}\n";
+    let input = CompileInput::new().source(source).stage(ShaderStage::Fragment);
+    let error_log = match compile_input(&input, CompileOptionFlags::empty()) {
+      Err(CompileError::Parse(error_log)) => error_log,
+      result => panic!("Unexpected result: {:?}", result),
+    };
+    let rendered = render_diagnostics(&error_log.diagnostics(), &DiagnosticSources::new(source));
+    assert!(rendered.starts_with("error: 'undefined_value' : undeclared identifier\n --> 0:4"), "{}", rendered);
+    assert!(rendered.contains("4 |   out_color = undefined_value;\n  |               ^^^^^^^^^^^^^^^\n"), "{}", rendered);
+  }
+
+  #[test]
+  fn sink_collects_diagnostics() {
+    let collected = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let sink = {
+      let collected = collected.clone();
+      move |diagnostic: &Diagnostic| collected.lock().unwrap().push(diagnostic.clone())
+    };
+    let warning_source = "#version 450\n#extension GL_EXT_no_such_extension : enable\nvoid main() {}\n";
+    let input = CompileInput::new().source(warning_source).stage(ShaderStage::Compute).diagnostic_sink(sink.clone());
+    let output = compile_input(&input, CompileOptionFlags::empty()).unwrap();
+    assert_eq!(*collected.lock().unwrap(), parse_diagnostics(&output.warnings.join("\n")));
+    assert_eq!(collected.lock().unwrap()[0].severity, Severity::Warning);
+
+    collected.lock().unwrap().clear();
+    let error_source = "#version 450\n#extension GL_EXT_no_such_extension : enable\nvoid main() {\n  undefined_value = 1;\n}\n";
+    let input = CompileInput::new().source(error_source).stage(ShaderStage::Compute).diagnostic_sink(sink);
+    let error_log = match compile_input(&input, CompileOptionFlags::empty()) {
+      Err(CompileError::Parse(error_log)) => error_log,
+      result => panic!("Unexpected result: {:?}", result),
+    };
+    let collected = collected.lock().unwrap();
+    assert_eq!(*collected, error_log.diagnostics());
+    assert_eq!(collected.iter().map(|diagnostic| diagnostic.severity).collect::<Vec<_>>(), [Severity::Warning, Severity::Error]);
+  }
+
+  #[test]
+  fn panicking_sink() {
+    let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let sink = {
+      let calls = calls.clone();
+      move |_: &Diagnostic| {
+        calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        panic!("sink failure");
+      }
+    };
+    let source = "#version 450\n#extension GL_EXT_no_such_extension : enable\n#extension GL_EXT_no_other_extension : enable\nvoid main() {}\n";
+    let input = CompileInput::new().source(source).stage(ShaderStage::Compute);
+    let expected = compile_input(&input, CompileOptionFlags::empty()).unwrap();
+    assert_eq!(expected.warnings.len(), 2, "{:?}", expected.warnings);
+
+    let output = compile_input(&input.diagnostic_sink(sink), CompileOptionFlags::empty()).unwrap();
+    assert_eq!(output, expected);
+    // The sink is not called again after it panicked.
+    assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+  }
+
+  #[test]
+  fn spirv_messages() {
+    assert_eq!(spirv_message_diagnostic("warning: unsupported decoration").severity, Severity::Warning);
+    assert_eq!(spirv_message_diagnostic("error: bad id").message, "bad id");
+    let note = spirv_message_diagnostic("TBD functionality: missing: 1");
+    assert_eq!((note.severity, note.message.as_str(), note.location), (Severity::Note, "TBD functionality: missing: 1", None));
+  }
+}
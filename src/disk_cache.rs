@@ -0,0 +1,268 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::*;
+
+/// Stores compiled SPIR-V in a directory, e.g. for CI and build machines, keyed by the same hash of the input as
+/// [`CompileCache`].
+///
+/// Each entry is a `<key>.spv` file, written like [`Spirv::save`], and a `<key>.meta` text file with the crate version, the
+/// [`GLSLANG_COMMIT`], the size of the SPIR-V and the hashes of the resolved `#include`s. Entries of another crate version or
+/// glslang commit, entries whose includes changed, and corrupt or truncated entries are misses.
+///
+/// The key uses the standard library's hasher, so a new Rust toolchain may also turn all entries into misses. Stale entries
+/// are never removed.
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+  dir: PathBuf,
+}
+
+/// The metadata of an entry, as written to its `.meta` file.
+#[derive(Debug, PartialEq, Eq)]
+struct EntryMetadata {
+  crate_version: String,
+  glslang_commit: String,
+  spirv_words: usize,
+  includes: Vec<IncludeMetadata>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct IncludeMetadata {
+  system: bool,
+  include_depth: usize,
+  content_hash: u64,
+  header_name: String,
+  includer_name: String,
+}
+
+impl DiskCache {
+  /// Creates `dir` if it does not exist.
+  pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+    let dir = dir.into();
+    std::fs::create_dir_all(&dir)?;
+    Ok(DiskCache { dir })
+  }
+
+  #[must_use]
+  pub fn dir(&self) -> &Path {
+    &self.dir
+  }
+
+  /// The SPIR-V of a previous compile stored with [`DiskCache::put`], if its entry is valid and its includes still resolve
+  /// to the same content with the handler of `input`.
+  #[must_use]
+  pub fn get(&self, input: &CompileInput, option_flags: CompileOptionFlags) -> Option<Spirv> {
+    let key = cache::input_key(input, option_flags);
+    let metadata = EntryMetadata::parse(&std::fs::read_to_string(self.metadata_path(key)).ok()?)?;
+    if metadata.crate_version != env!("CARGO_PKG_VERSION") || metadata.glslang_commit != GLSLANG_COMMIT {
+      return None;
+    }
+    // `load` checks the magic number and that the length is a multiple of 4.
+    let spirv = Spirv::load(self.spirv_path(key)).ok()?;
+    if spirv.len() != metadata.spirv_words {
+      return None;
+    }
+
+    let include_handler = input.shader_settings().include_handler;
+    let includes_unchanged = metadata.includes.iter().all(|include| {
+      let Some(include_handler) = include_handler else {
+        return false;
+      };
+      let record = IncludeRecord {
+        system: include.system,
+        header_name: include.header_name.clone(),
+        includer_name: include.includer_name.clone(),
+        include_depth: include.include_depth,
+        resolved: ResolvedInclude { resolved_name: String::new(), content: String::new() },
+      };
+      record.resolve_again(include_handler).is_ok_and(|resolved| content_hash(&resolved) == include.content_hash)
+    });
+    includes_unchanged.then_some(spirv)
+  }
+
+  /// Stores the SPIR-V of `output`, the result of compiling `input` with `option_flags`, replacing any previous entry.
+  ///
+  /// Fails with [`io::ErrorKind::InvalidInput`] if the name of an include contains a tab or line break, which the metadata
+  /// cannot hold.
+  pub fn put(&self, input: &CompileInput, option_flags: CompileOptionFlags, output: &CompileOutput) -> io::Result<()> {
+    let key = cache::input_key(input, option_flags);
+    let metadata = EntryMetadata {
+      crate_version: env!("CARGO_PKG_VERSION").to_owned(),
+      glslang_commit: GLSLANG_COMMIT.to_owned(),
+      spirv_words: output.spirv.len(),
+      includes: output.includes.iter().map(IncludeMetadata::new).collect(),
+    };
+    let invalid_name = |name: &str| name.contains(['\t', '\n', '\r']);
+    if metadata.includes.iter().any(|include| invalid_name(&include.header_name) || invalid_name(&include.includer_name)) {
+      return Err(io::Error::new(io::ErrorKind::InvalidInput, "Include names containing tabs or line breaks cannot be cached"));
+    }
+
+    // Each file is renamed into place, so a concurrent `get` sees either the old or the new file, never a partial one.
+    // Until both are replaced, the size recorded in the metadata rejects a mismatched pair.
+    let spirv_path = self.spirv_path(key);
+    let temp_spirv_path = spirv_path.with_extension("spv.tmp");
+    output.spirv.save(&temp_spirv_path)?;
+    std::fs::rename(&temp_spirv_path, &spirv_path)?;
+    let metadata_path = self.metadata_path(key);
+    let temp_metadata_path = metadata_path.with_extension("meta.tmp");
+    std::fs::write(&temp_metadata_path, metadata.to_string())?;
+    std::fs::rename(&temp_metadata_path, &metadata_path)
+  }
+
+  /// [`DiskCache::get`], or else [`compile_input`] and [`DiskCache::put`]. Failing to store the result does not fail the
+  /// compile.
+  pub fn compile_or_get(&self, input: &CompileInput, option_flags: CompileOptionFlags) -> Result<Spirv, CompileError> {
+    if let Some(spirv) = self.get(input, option_flags) {
+      return Ok(spirv);
+    }
+    let output = compile_input(input, option_flags)?;
+    let _ = self.put(input, option_flags, &output);
+    Ok(output.spirv)
+  }
+
+  fn spirv_path(&self, key: u64) -> PathBuf {
+    self.dir.join(format!("{:016x}.spv", key))
+  }
+
+  fn metadata_path(&self, key: u64) -> PathBuf {
+    self.dir.join(format!("{:016x}.meta", key))
+  }
+}
+
+fn content_hash(resolved: &ResolvedInclude) -> u64 {
+  let mut hasher = DefaultHasher::new();
+  resolved.resolved_name.hash(&mut hasher);
+  resolved.content.hash(&mut hasher);
+  hasher.finish()
+}
+
+impl IncludeMetadata {
+  fn new(record: &IncludeRecord) -> Self {
+    IncludeMetadata {
+      system: record.system,
+      include_depth: record.include_depth,
+      content_hash: content_hash(&record.resolved),
+      header_name: record.header_name.clone(),
+      includer_name: record.includer_name.clone(),
+    }
+  }
+}
+
+/// One `key value` line per field, and an `include <system> <depth> <content hash> <header name>\t<includer name>` line per
+/// include.
+impl std::fmt::Display for EntryMetadata {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    writeln!(f, "crate_version {}", self.crate_version)?;
+    writeln!(f, "glslang_commit {}", self.glslang_commit)?;
+    writeln!(f, "spirv_words {}", self.spirv_words)?;
+    for include in &self.includes {
+      writeln!(f, "include {} {} {:016x} {}\t{}", include.system as u8, include.include_depth, include.content_hash, include.header_name, include.includer_name)?;
+    }
+    Ok(())
+  }
+}
+impl EntryMetadata {
+  /// `None` if `text` is not complete metadata.
+  fn parse(text: &str) -> Option<Self> {
+    let mut crate_version = None;
+    let mut glslang_commit = None;
+    let mut spirv_words = None;
+    let mut includes = Vec::new();
+    for line in text.lines() {
+      let (key, value) = line.split_once(' ')?;
+      match key {
+        "crate_version" => crate_version = Some(value.to_owned()),
+        "glslang_commit" => glslang_commit = Some(value.to_owned()),
+        "spirv_words" => spirv_words = Some(value.parse().ok()?),
+        "include" => {
+          let mut fields = value.splitn(4, ' ');
+          let system = match fields.next()? {
+            "0" => false,
+            "1" => true,
+            _ => return None,
+          };
+          let include_depth = fields.next()?.parse().ok()?;
+          let content_hash = u64::from_str_radix(fields.next()?, 16).ok()?;
+          let (header_name, includer_name) = fields.next()?.split_once('\t')?;
+          includes.push(IncludeMetadata { system, include_depth, content_hash, header_name: header_name.to_owned(), includer_name: includer_name.to_owned() });
+        },
+        _ => return None,
+      }
+    }
+    Some(EntryMetadata { crate_version: crate_version?, glslang_commit: glslang_commit?, spirv_words: spirv_words?, includes })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SOURCE: &str = "#version 450\n#extension GL_GOOGLE_include_directive : require\n#include \"tint.glsl\"\nlayout(location = 0) out vec4 out_color;\nvoid main() {\n  out_color = TINT;\n}\n";
+
+  #[test]
+  fn metadata_round_trips() {
+    let metadata = EntryMetadata {
+      crate_version: "0.1.0".to_owned(),
+      glslang_commit: GLSLANG_COMMIT.to_owned(),
+      spirv_words: 123,
+      includes: vec![IncludeMetadata { system: true, include_depth: 2, content_hash: 0xfeed, header_name: "a b.glsl".to_owned(), includer_name: String::new() }],
+    };
+    assert_eq!(EntryMetadata::parse(&metadata.to_string()), Some(metadata));
+    assert_eq!(EntryMetadata::parse("crate_version 0.1.0\nglslang_commit abc\n"), None);
+  }
+
+  #[test]
+  fn cold_warm_and_corrupt_entries() {
+    let root = std::env::temp_dir().join(format!("glslang-sys-disk-cache-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    scopeguard::defer! {
+      let _ = std::fs::remove_dir_all(&root);
+    }
+    let include_dir = root.join("include");
+    std::fs::create_dir_all(&include_dir).unwrap();
+    std::fs::write(include_dir.join("tint.glsl"), "#define TINT vec4(1.0)\n").unwrap();
+    let input = CompileInput::new()
+      .source(SOURCE)
+      .stage(ShaderStage::Fragment)
+      .include_handler(FileIncluder::new(vec![include_dir.clone()]));
+    let expected = compile_input(&input, CompileOptionFlags::empty()).unwrap();
+
+    // Cold: nothing is stored yet.
+    let cache = DiskCache::new(root.join("cache")).unwrap();
+    assert_eq!(cache.get(&input, CompileOptionFlags::empty()), None);
+    assert_eq!(cache.compile_or_get(&input, CompileOptionFlags::empty()).unwrap(), expected.spirv);
+
+    // Warm: another cache over the same directory, as in the next build.
+    let cache = DiskCache::new(cache.dir()).unwrap();
+    assert_eq!(cache.get(&input, CompileOptionFlags::empty()), Some(expected.spirv.clone()));
+    assert_eq!(cache.get(&input, CompileOptionFlags::GenerateDebugInfo), None);
+
+    // Corrupt: truncated SPIR-V, a wrong magic number, and garbage metadata are misses, and recompiling repairs them.
+    let key = cache::input_key(&input, CompileOptionFlags::empty());
+    let spirv_bytes = std::fs::read(cache.spirv_path(key)).unwrap();
+    let mut wrong_magic = spirv_bytes.clone();
+    wrong_magic[0] ^= 0xff;
+    for (path, content) in [
+      (cache.spirv_path(key), &spirv_bytes[..spirv_bytes.len() - 4]),
+      (cache.spirv_path(key), &spirv_bytes[..spirv_bytes.len() - 1]),
+      (cache.spirv_path(key), &wrong_magic[..]),
+      (cache.metadata_path(key), b"spirv_words".as_slice()),
+    ] {
+      std::fs::write(&path, content).unwrap();
+      assert_eq!(cache.get(&input, CompileOptionFlags::empty()), None);
+      assert_eq!(cache.compile_or_get(&input, CompileOptionFlags::empty()).unwrap(), expected.spirv);
+      assert_eq!(cache.get(&input, CompileOptionFlags::empty()), Some(expected.spirv.clone()));
+    }
+
+    // A changed include is a miss, as is another glslang commit.
+    std::fs::write(include_dir.join("tint.glsl"), "#define TINT vec4(0.5)\n").unwrap();
+    assert_eq!(cache.get(&input, CompileOptionFlags::empty()), None);
+    let changed = cache.compile_or_get(&input, CompileOptionFlags::empty()).unwrap();
+    assert_ne!(changed, expected.spirv);
+    let metadata = std::fs::read_to_string(cache.metadata_path(key)).unwrap();
+    std::fs::write(cache.metadata_path(key), metadata.replace(GLSLANG_COMMIT, "0000000")).unwrap();
+    assert_eq!(cache.get(&input, CompileOptionFlags::empty()), None);
+  }
+}
@@ -0,0 +1,391 @@
+use std::ffi::CString;
+use std::os::raw::{c_char, c_uint};
+
+use crate::*;
+
+/// HLSL-specific options: entry point selection, and fixups mostly useful when porting D3D shaders to Vulkan.
+///
+/// Ignored for GLSL input. [`CompileInput::entry_point`] and [`CompileInput::source_entry_point`] override the entry point
+/// names, also for GLSL.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct HlslOptions {
+  /// The function to compile, e.g. `PSMain` in a file that also contains `VSMain`.
+  /// `None` compiles the function named [`HlslOptions::entry_point`].
+  pub source_entry_point: Option<String>,
+  /// The entry point name in the generated SPIR-V. `None` means `main`, which is what most Vulkan code expects.
+  pub entry_point: Option<String>,
+  /// Negate the Y component of the vertex position output (`TShader::setInvertY`), compensating for Vulkan's downward Y axis.
+  pub invert_y: bool,
+  /// Use D3D semantics for the W component of the fragment position input, i.e. `1/w` instead of `w` (`TShader::setDxPositionW`).
+  pub dx_position_w: bool,
+  /// Lay out `cbuffer` members following HLSL packing rules instead of std140, e.g. a `float3` right after a `float`
+  /// instead of at the next 16 bytes (`GLSLANG_MSG_HLSL_OFFSETS_BIT`, see [`HlslOptions::messages`]).
+  pub hlsl_offsets: bool,
+  /// Emit the `SPV_GOOGLE_hlsl_functionality1` decorations, e.g. the semantic names of inputs and outputs
+  /// (`TShader::setEnvTargetHlslFunctionality1`).
+  pub functionality1: bool,
+  /// Assign bindings from `register(...)` when mapping IO, e.g. with [`ShaderOptions::AutoMapBindings`] (`TShader::setHlslIoMapping`).
+  pub io_mapping: bool,
+  /// Added to the bindings of `register(...)`s per register class, e.g. so that `t0` and `s0` do not both become binding 0.
+  /// Non-zero shifts make the compile map IO, and replace the base of [`CompileInput::auto_bind_uniforms`] for their class.
+  pub binding_shifts: BindingShifts,
+  /// The descriptor sets, and bindings, of `register(...)`s.
+  pub resource_set_binding: Option<ResourceSetBinding>,
+}
+impl HlslOptions {
+  const DEFAULT_ENTRY_POINT: &'static str = "main";
+
+  /// # Safety
+  /// - `shader` MUST be a valid shader that has not been parsed yet.
+  pub(crate) unsafe fn apply(&self, shader: *mut glslang_shader_t) -> Result<(), CompileError> {
+    let entry_point = CString::new(self.entry_point.as_deref().unwrap_or(Self::DEFAULT_ENTRY_POINT)).map_err(|error| CompileError::nul_in(InputKind::EntryPoint, &error))?;
+    let source_entry_point = self.source_entry_point.as_deref().map(CString::new).transpose().map_err(|error| CompileError::nul_in(InputKind::EntryPoint, &error))?;
+
+    // Both names are copied by glslang.
+    glslang_sys_shader_set_entry_point(shader, entry_point.as_ptr());
+    if let Some(source_entry_point) = source_entry_point {
+      glslang_sys_shader_set_source_entry_point(shader, source_entry_point.as_ptr());
+    }
+
+    glslang_sys_shader_set_invert_y(shader, self.invert_y);
+    glslang_sys_shader_set_dx_position_w(shader, self.dx_position_w);
+    glslang_sys_shader_set_hlsl_functionality1(shader, self.functionality1);
+    glslang_sys_shader_set_hlsl_io_mapping(shader, self.io_mapping);
+    self.binding_shifts.apply(shader);
+    if let Some(resource_set_binding) = &self.resource_set_binding {
+      resource_set_binding.apply(shader)?;
+    }
+    Ok(())
+  }
+
+  /// Whether the options only take effect when mapping IO, see [`Program::map_io`].
+  pub(crate) fn needs_io_mapping(&self) -> bool {
+    self.binding_shifts != BindingShifts::default() || matches!(self.resource_set_binding, Some(ResourceSetBinding::Global(_)))
+  }
+
+  /// The options that glslang takes as message bits rather than shader settings, to be added to `glslang_input_t::messages`.
+  ///
+  /// [`CompileInput::hlsl_options`] and [`compile_with_hlsl_options`] add them.
+  #[must_use]
+  pub fn messages(&self) -> Messages {
+    let mut messages = Messages::empty();
+    messages.set(Messages::HlslOffsets, self.hlsl_offsets);
+    messages
+  }
+}
+
+/// The shifts of [`HlslOptions::binding_shifts`] (`TShader::setShiftBinding`), e.g. the classic `b` +0, `t` +1000, `u` +2000
+/// and `s` +3000 for translating register spaces to Vulkan bindings.
+///
+/// glslang classifies resources by their type, so `u` registers are shifted by `uav` with [`HlslOptions::io_mapping`], and
+/// by `image` or `ssbo` without.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct BindingShifts {
+  /// `t` registers: textures, and structured buffers with [`HlslOptions::io_mapping`].
+  pub texture: u32,
+  /// `s` registers.
+  pub sampler: u32,
+  /// `b` registers: constant buffers.
+  pub ubo: u32,
+  /// Structured and byte address buffers, without [`HlslOptions::io_mapping`].
+  pub ssbo: u32,
+  /// Read-write textures, without [`HlslOptions::io_mapping`].
+  pub image: u32,
+  /// `u` registers, with [`HlslOptions::io_mapping`].
+  pub uav: u32,
+}
+impl BindingShifts {
+  /// # Safety
+  /// - `shader` MUST be a valid shader that has not been parsed yet.
+  pub(crate) unsafe fn apply(&self, shader: *mut glslang_shader_t) {
+    // Zero shifts keep the base of `CompileInput::auto_bind_uniforms`.
+    let shifts: [(u32, unsafe extern "C" fn(*mut glslang_shader_t, c_uint)); 6] = [
+      (self.texture, glslang_sys_shader_set_shift_texture_binding),
+      (self.sampler, glslang_sys_shader_set_shift_sampler_binding),
+      (self.ubo, glslang_sys_shader_set_shift_ubo_binding),
+      (self.ssbo, glslang_sys_shader_set_shift_ssbo_binding),
+      (self.image, glslang_sys_shader_set_shift_image_binding),
+      (self.uav, glslang_sys_shader_set_shift_uav_binding),
+    ];
+    for (shift, set_shift) in shifts {
+      if shift != 0 {
+        set_shift(shader, shift);
+      }
+    }
+  }
+
+  /// The same shift for every class.
+  pub(crate) fn all(shift: u32) -> Self {
+    BindingShifts { texture: shift, sampler: shift, ubo: shift, ssbo: shift, image: shift, uav: shift }
+  }
+}
+
+/// The descriptor sets of HLSL resources (`TShader::setResourceSetBinding`), like the `--resource-set-binding` option of
+/// glslangValidator.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ResourceSetBinding {
+  /// The set of all resources not declared in a `space`, e.g. `--resource-set-binding 1`. Makes the compile map IO.
+  Global(u32),
+  /// The set and binding of the resources of each register, e.g. `("t0", 1, 5)` for `--resource-set-binding t0 1 5`.
+  PerRegister(Vec<(String, u32, u32)>),
+}
+impl ResourceSetBinding {
+  /// Fails with [`CompileError::InvalidResourceSetBinding`] for registers that are not a `b`, `t`, `s` or `u` followed by a
+  /// number, e.g. `t0`, or that are listed twice. [`CompileInput::as_raw`] checks it for HLSL input.
+  pub fn validate(&self) -> Result<(), CompileError> {
+    let ResourceSetBinding::PerRegister(bindings) = self else {
+      return Ok(());
+    };
+    for (index, (register, _, _)) in bindings.iter().enumerate() {
+      let mut chars = register.chars();
+      let is_register = chars.next().is_some_and(|class| "btsuBTSU".contains(class)) && !chars.as_str().is_empty() && chars.all(|c| c.is_ascii_digit());
+      if !is_register {
+        return Err(CompileError::InvalidResourceSetBinding(format!("{:?} is not a register like \"t0\"", register)));
+      }
+      if bindings[..index].iter().any(|(other, _, _)| other == register) {
+        return Err(CompileError::InvalidResourceSetBinding(format!("register {:?} is listed twice", register)));
+      }
+    }
+    Ok(())
+  }
+
+  /// # Safety
+  /// - `shader` MUST be a valid shader that has not been parsed yet.
+  unsafe fn apply(&self, shader: *mut glslang_shader_t) -> Result<(), CompileError> {
+    self.validate()?;
+    let strings: Vec<CString> = match self {
+      ResourceSetBinding::Global(set) => vec![CString::new(set.to_string()).unwrap()],
+      ResourceSetBinding::PerRegister(bindings) => bindings
+        .iter()
+        .flat_map(|(register, set, binding)| [register.clone(), set.to_string(), binding.to_string()])
+        // `validate` rejects registers with NUL bytes.
+        .map(|string| CString::new(string).unwrap())
+        .collect(),
+    };
+    let pointers: Vec<*const c_char> = strings.iter().map(|string| string.as_ptr()).collect();
+    // The strings are copied by glslang.
+    glslang_sys_shader_set_resource_set_binding(shader, pointers.as_ptr(), pointers.len() as size_t);
+    Ok(())
+  }
+}
+
+#[cfg(all(test, feature = "hlsl"))]
+mod tests {
+  use super::*;
+
+  unsafe fn compile_hlsl(source: &str, stage: glslang_stage_t, hlsl_options: &HlslOptions) -> Spirv {
+    let source_c_string = CString::new(source).unwrap();
+
+    let input = glslang_input_t {
+      language: glslang_source_t_GLSLANG_SOURCE_HLSL,
+      stage,
+      client: glslang_client_t_GLSLANG_CLIENT_VULKAN,
+      client_version: glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_1,
+      target_language: glslang_target_language_t_GLSLANG_TARGET_SPV,
+      target_language_version: glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_0,
+      code: source_c_string.as_ptr(),
+      default_version: 100,
+      default_profile: glslang_profile_t_GLSLANG_NO_PROFILE,
+      force_default_version_and_profile: 0,
+      forward_compatible: 0,
+      messages: (Messages::DEFAULT_VULKAN | Messages::ReadHlsl).into(),
+      resource: &DEFAULT_RESOURCE_LIMITS as *const glslang_resource_t,
+    };
+
+    compile_with_hlsl_options(&input, CompileOptionFlags::empty(), hlsl_options).unwrap().spirv
+  }
+
+  #[test]
+  fn invert_y() {
+    unsafe {
+      glslang_initialize_process();
+      scopeguard::defer! {
+        glslang_finalize_process();
+      }
+
+      let source = "float4 main(float4 position : POSITION) : SV_Position { return position; }";
+
+      let default = compile_hlsl(source, glslang_stage_t_GLSLANG_STAGE_VERTEX, &HlslOptions::default());
+      let inverted = compile_hlsl(source, glslang_stage_t_GLSLANG_STAGE_VERTEX, &HlslOptions { invert_y: true, ..Default::default() });
+      assert_ne!(default, inverted);
+      // The flip is an extra negation of the position's Y component.
+      assert!(inverted.len() > default.len());
+    }
+  }
+
+  #[test]
+  fn dx_position_w() {
+    unsafe {
+      glslang_initialize_process();
+      scopeguard::defer! {
+        glslang_finalize_process();
+      }
+
+      let source = "float4 main(float4 position : SV_Position) : SV_Target { return position; }";
+
+      let default = compile_hlsl(source, glslang_stage_t_GLSLANG_STAGE_FRAGMENT, &HlslOptions::default());
+      let dx_position_w = compile_hlsl(source, glslang_stage_t_GLSLANG_STAGE_FRAGMENT, &HlslOptions { dx_position_w: true, ..Default::default() });
+      assert_ne!(default, dx_position_w);
+    }
+  }
+
+  #[test]
+  fn source_entry_point() {
+    unsafe {
+      glslang_initialize_process();
+      scopeguard::defer! {
+        glslang_finalize_process();
+      }
+
+      let source =
+        r##"
+        float4 VSMain(float4 position : POSITION) : SV_Position { return position; }
+        float4 PSMain() : SV_Target { return float4(1.0, 0.0, 0.0, 1.0); }
+        "##;
+
+      let vs = compile_hlsl(source, glslang_stage_t_GLSLANG_STAGE_VERTEX, &HlslOptions { source_entry_point: Some("VSMain".to_owned()), ..Default::default() });
+      let ps = compile_hlsl(source, glslang_stage_t_GLSLANG_STAGE_FRAGMENT, &HlslOptions { source_entry_point: Some("PSMain".to_owned()), ..Default::default() });
+      assert_ne!(vs, ps);
+      assert!(crate::tests::contains_literal_string(&vs, "main"));
+      assert!(crate::tests::contains_literal_string(&ps, "main"));
+
+      let renamed = compile_hlsl(
+        source,
+        glslang_stage_t_GLSLANG_STAGE_FRAGMENT,
+        &HlslOptions { source_entry_point: Some("PSMain".to_owned()), entry_point: Some("ps_main".to_owned()), ..Default::default() },
+      );
+      assert!(crate::tests::contains_literal_string(&renamed, "ps_main"));
+    }
+  }
+
+  const CBUFFER_SOURCE: &str = r##"
+    cbuffer Constants {
+      float scale;
+      float3 tint;
+    };
+    float4 main(float4 color : COLOR) : SV_Target { return float4(color.rgb * tint, scale); }
+  "##;
+
+  /// The `Offset` decorations of struct members in `spirv`, as (member, offset) pairs.
+  fn member_offsets(spirv: &[u32]) -> Vec<(u32, u32)> {
+    const OP_MEMBER_DECORATE: u32 = 72;
+    const DECORATION_OFFSET: u32 = 35;
+
+    let mut offsets = Vec::new();
+    let mut words = &spirv[5..];
+    while let Some(&first) = words.first() {
+      let (word_count, opcode) = ((first >> 16) as usize, first & 0xffff);
+      if opcode == OP_MEMBER_DECORATE && word_count == 5 && words[3] == DECORATION_OFFSET {
+        offsets.push((words[2], words[4]));
+      }
+      words = &words[word_count.max(1)..];
+    }
+    offsets
+  }
+
+  #[test]
+  fn hlsl_offsets() {
+    unsafe {
+      glslang_initialize_process();
+      scopeguard::defer! {
+        glslang_finalize_process();
+      }
+
+      // std140 starts a `float3` at a multiple of 16 bytes; HLSL only keeps it from straddling one.
+      let std140 = compile_hlsl(CBUFFER_SOURCE, glslang_stage_t_GLSLANG_STAGE_FRAGMENT, &HlslOptions::default());
+      assert_eq!(member_offsets(&std140), [(0, 0), (1, 16)]);
+      let hlsl = compile_hlsl(CBUFFER_SOURCE, glslang_stage_t_GLSLANG_STAGE_FRAGMENT, &HlslOptions { hlsl_offsets: true, ..Default::default() });
+      assert_eq!(member_offsets(&hlsl), [(0, 0), (1, 4)]);
+    }
+  }
+
+  #[test]
+  fn hlsl_options_with_auto_map_bindings() {
+    let hlsl_options = HlslOptions { hlsl_offsets: true, functionality1: true, io_mapping: true, ..Default::default() };
+    let input = CompileInput::new()
+      .source(CBUFFER_SOURCE)
+      .language(SourceLanguage::Hlsl)
+      .stage(ShaderStage::Fragment)
+      .shader_options(ShaderOptions::AutoMapBindings)
+      .hlsl_options(hlsl_options);
+
+    let spirv = compile_input(&input, CompileOptionFlags::empty()).unwrap().spirv;
+    assert_eq!(member_offsets(&spirv), [(0, 0), (1, 4)]);
+    assert_eq!(crate::tests::binding_decorations(&spirv).len(), 1);
+    assert!(crate::tests::contains_literal_string(&spirv, "SPV_GOOGLE_hlsl_functionality1"));
+
+    // Only for HLSL.
+    let input = input.language(SourceLanguage::Glsl);
+    assert!(!Messages::from(input.as_raw().unwrap().messages).contains(Messages::HlslOffsets));
+  }
+
+  #[test]
+  fn binding_shifts() {
+    let source = r##"
+      Texture2D albedo : register(t0);
+      SamplerState linear_sampler : register(s0);
+      cbuffer Constants : register(b1) { float4 tint; };
+      float4 main(float2 uv : TEXCOORD0) : SV_Target { return albedo.Sample(linear_sampler, uv) * tint; }
+    "##;
+    let input = CompileInput::new().source(source).language(SourceLanguage::Hlsl).stage(ShaderStage::Fragment);
+    let bindings = |input: &CompileInput| {
+      let mut bindings: Vec<u32> = crate::tests::binding_decorations(&compile_input(input, CompileOptionFlags::empty()).unwrap().spirv)
+        .into_iter()
+        .map(|(_, binding)| binding)
+        .collect();
+      bindings.sort_unstable();
+      bindings
+    };
+    assert_eq!(bindings(&input), [0, 0, 1]);
+
+    let binding_shifts = BindingShifts { texture: 1000, sampler: 3000, ubo: 0, uav: 2000, ..Default::default() };
+    let input = input.hlsl_options(HlslOptions { binding_shifts, ..Default::default() });
+    assert_eq!(bindings(&input), [1, 1000, 3000]);
+  }
+
+  #[test]
+  fn resource_set_binding() {
+    let source = r##"
+      Texture2D albedo : register(t0, space1);
+      Texture2D normal_map : register(t1);
+      SamplerState linear_sampler : register(s0);
+      float4 main(float2 uv : TEXCOORD0) : SV_Target { return albedo.Sample(linear_sampler, uv) + normal_map.Sample(linear_sampler, uv); }
+    "##;
+    let compile = |resource_set_binding: Option<ResourceSetBinding>| {
+      let input = CompileInput::new()
+        .source(source)
+        .language(SourceLanguage::Hlsl)
+        .stage(ShaderStage::Fragment)
+        .hlsl_options(HlslOptions { resource_set_binding, ..Default::default() });
+      compile_input(&input, CompileOptionFlags::empty()).map(|output| crate::tests::sets_and_bindings(&output.spirv))
+    };
+    assert_eq!(compile(None).unwrap(), [(0, 0), (0, 1), (1, 0)]);
+
+    // `space1` keeps its set.
+    assert_eq!(compile(Some(ResourceSetBinding::Global(3))).unwrap(), [(1, 0), (3, 0), (3, 1)]);
+
+    let per_register = vec![("t0".to_owned(), 1, 5), ("s0".to_owned(), 2, 3)];
+    assert_eq!(compile(Some(ResourceSetBinding::PerRegister(per_register))).unwrap(), [(0, 1), (1, 5), (2, 3)]);
+
+    for register in ["x0", "t", "t0x", ""] {
+      let result = compile(Some(ResourceSetBinding::PerRegister(vec![(register.to_owned(), 1, 0)])));
+      assert!(matches!(result, Err(CompileError::InvalidResourceSetBinding(_))), "{:?}: {:?}", register, result);
+    }
+    let duplicate = vec![("t0".to_owned(), 1, 0), ("t0".to_owned(), 2, 0)];
+    assert!(matches!(compile(Some(ResourceSetBinding::PerRegister(duplicate))), Err(CompileError::InvalidResourceSetBinding(_))));
+  }
+
+  #[test]
+  fn nul_in_entry_point() {
+    let input = CompileInput::new()
+      .source("float4 PSMain() : SV_Target { return 0; }\n")
+      .language(SourceLanguage::Hlsl)
+      .stage(ShaderStage::Fragment)
+      .entry_point("PS\0Main");
+    let error = compile_input(&input, CompileOptionFlags::empty()).unwrap_err();
+    assert!(matches!(error, CompileError::InvalidInput { input: InputKind::EntryPoint, offset: 2 }), "{:?}", error);
+  }
+}
@@ -0,0 +1,507 @@
+use std::any::Any;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int, c_void};
+use std::panic::AssertUnwindSafe;
+
+use crate::*;
+
+/// The result of resolving an `#include`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedInclude {
+  /// The name glslang reports in diagnostics, and passes as `includer_name` for includes nested in this one, e.g. a canonical path.
+  /// MUST NOT be empty.
+  pub resolved_name: String,
+  pub content: String,
+}
+
+/// An `#include` resolved by an [`IncludeHandler`] during preprocessing, see [`CompileOutput::includes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncludeRecord {
+  /// `#include <header_name>` rather than `#include "header_name"`.
+  pub system: bool,
+  pub header_name: String,
+  pub includer_name: String,
+  pub include_depth: usize,
+  pub resolved: ResolvedInclude,
+}
+impl IncludeRecord {
+  /// Resolves the include again with `include_handler`, e.g. to check whether its content changed.
+  pub fn resolve_again(&self, include_handler: &dyn IncludeHandler) -> Result<ResolvedInclude, String> {
+    if self.system {
+      include_handler.resolve_system(&self.header_name, &self.includer_name, self.include_depth)
+    }
+    else {
+      include_handler.resolve_local(&self.header_name, &self.includer_name, self.include_depth)
+    }
+  }
+}
+
+/// Resolves `#include` directives during preprocessing.
+///
+/// GLSL sources need `#extension GL_GOOGLE_include_directive : require` to use `#include`.
+/// Closures `Fn(header_name, includer_name, include_depth)` implement this trait, resolving both kinds of includes the same way.
+///
+/// Following C, handlers usually look up local includes relative to the including file before any search paths,
+/// and system includes in the search paths only. See [`FileIncluder`].
+///
+/// Handlers are `Send + Sync`, as a [`CompileInput`] can be shared by threads compiling it at the same time.
+pub trait IncludeHandler: Send + Sync {
+  /// `#include "header_name"`. `includer_name` is the resolved name of the including file, empty for the main source.
+  ///
+  /// An error message is reported by glslang as a preprocessing error.
+  fn resolve_local(&self, header_name: &str, includer_name: &str, include_depth: usize) -> Result<ResolvedInclude, String>;
+
+  /// `#include <header_name>`. Resolved like [`IncludeHandler::resolve_local`] unless overridden.
+  fn resolve_system(&self, header_name: &str, includer_name: &str, include_depth: usize) -> Result<ResolvedInclude, String> {
+    self.resolve_local(header_name, includer_name, include_depth)
+  }
+}
+impl<F> IncludeHandler for F
+where
+  F: Fn(&str, &str, usize) -> Result<ResolvedInclude, String> + Send + Sync,
+{
+  fn resolve_local(&self, header_name: &str, includer_name: &str, include_depth: usize) -> Result<ResolvedInclude, String> {
+    self(header_name, includer_name, include_depth)
+  }
+}
+impl std::fmt::Debug for dyn IncludeHandler {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str("IncludeHandler")
+  }
+}
+
+/// A `glsl_include_result_t` together with the storage it points to, for implementing `glsl_include_callbacks_t` by hand.
+///
+/// `include_local` and `include_system` callbacks return [`OwnedIncludeResult::into_raw`], and `free_include_result` passes the
+/// pointer to [`OwnedIncludeResult::free`]. In debug builds, freeing a pointer that did not come from `into_raw`, or freeing it
+/// twice, panics.
+#[repr(C)]
+pub struct OwnedIncludeResult {
+  /// MUST stay the first field, so that a pointer to it is a pointer to the whole struct.
+  raw: glsl_include_result_t,
+  header_name: CString,
+  header_data: Vec<u8>,
+}
+impl std::fmt::Debug for OwnedIncludeResult {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("OwnedIncludeResult")
+      .field("header_name", &self.header_name)
+      .field("header_data", &String::from_utf8_lossy(&self.header_data))
+      .finish()
+  }
+}
+impl OwnedIncludeResult {
+  /// A failure is reported to glslang as a result with an empty `header_name`, with the error message in `header_data`.
+  #[must_use]
+  pub fn new(result: Result<ResolvedInclude, String>) -> Self {
+    let (header_name, header_data) = match result {
+      Ok(ResolvedInclude { resolved_name, content }) => match CString::new(resolved_name) {
+        Ok(header_name) if !header_name.as_bytes().is_empty() => (header_name, content.into_bytes()),
+        Ok(_) => (CString::default(), b"Include handler returned an empty resolved name".to_vec()),
+        Err(_) => (CString::default(), b"Include handler returned a resolved name containing a NUL byte".to_vec()),
+      },
+      Err(message) => (CString::default(), message.into_bytes()),
+    };
+
+    // The heap allocations of the `CString` and `Vec` don't move with `self`.
+    OwnedIncludeResult {
+      raw: glsl_include_result_t {
+        header_name: header_name.as_ptr(),
+        header_data: header_data.as_ptr() as *const c_char,
+        header_length: header_data.len() as size_t,
+      },
+      header_name,
+      header_data,
+    }
+  }
+
+  #[must_use]
+  pub fn as_raw(&self) -> &glsl_include_result_t {
+    &self.raw
+  }
+
+  /// Moves `self` to the heap, returning a pointer that stays valid until passed to [`OwnedIncludeResult::free`].
+  #[must_use]
+  pub fn into_raw(self) -> *mut glsl_include_result_t {
+    let raw = Box::into_raw(Box::new(self)) as *mut glsl_include_result_t;
+    #[cfg(debug_assertions)]
+    live_results::insert(raw);
+    raw
+  }
+
+  /// Takes back ownership of a result returned by [`OwnedIncludeResult::into_raw`].
+  ///
+  /// # Safety
+  /// - `raw` MUST have been returned by [`OwnedIncludeResult::into_raw`], and not been passed to `from_raw` or `free` before.
+  #[must_use]
+  pub unsafe fn from_raw(raw: *mut glsl_include_result_t) -> Self {
+    #[cfg(debug_assertions)]
+    live_results::remove(raw);
+    *Box::from_raw(raw as *mut OwnedIncludeResult)
+  }
+
+  /// Frees a result returned by [`OwnedIncludeResult::into_raw`].
+  ///
+  /// # Safety
+  /// - Same as [`OwnedIncludeResult::from_raw`].
+  pub unsafe fn free(raw: *mut glsl_include_result_t) {
+    drop(Self::from_raw(raw));
+  }
+}
+
+/// Pointers returned by `OwnedIncludeResult::into_raw` and not freed yet, to catch double frees in debug builds.
+#[cfg(debug_assertions)]
+mod live_results {
+  use std::cell::Cell;
+  use std::collections::BTreeSet;
+  use std::sync::Mutex;
+
+  use super::glsl_include_result_t;
+
+  static LIVE: Mutex<BTreeSet<usize>> = Mutex::new(BTreeSet::new());
+  thread_local! {
+    /// Results created minus results freed on this thread, so tests running in parallel don't interfere.
+    static THREAD_BALANCE: Cell<isize> = const { Cell::new(0) };
+  }
+
+  pub(super) fn insert(raw: *mut glsl_include_result_t) {
+    LIVE.lock().unwrap().insert(raw as usize);
+    THREAD_BALANCE.with(|balance| balance.set(balance.get() + 1));
+  }
+
+  pub(super) fn remove(raw: *mut glsl_include_result_t) {
+    assert!(LIVE.lock().unwrap().remove(&(raw as usize)), "glsl_include_result_t {:p} was not created by OwnedIncludeResult::into_raw, or was already freed", raw);
+    THREAD_BALANCE.with(|balance| balance.set(balance.get() - 1));
+  }
+
+  #[cfg(test)]
+  pub(super) fn thread_balance() -> isize {
+    THREAD_BALANCE.with(Cell::get)
+  }
+}
+
+/// Default for [`CompileInput::max_include_depth`].
+pub const DEFAULT_MAX_INCLUDE_DEPTH: usize = 64;
+
+/// What `callbacks_ctx` points to during [`preprocess_with_include_handler`].
+struct IncludeContext<'a> {
+  include_handler: &'a dyn IncludeHandler,
+  max_depth: usize,
+  /// Resolved names of the includes being preprocessed, outermost first.
+  chain: Vec<String>,
+  /// An include error glslang can only report as text, returned instead of the preprocessing error.
+  error: Option<CompileError>,
+  /// A panic in the include handler, resumed once glslang returns. Unwinding through C++ is undefined behavior.
+  panic: Option<Box<dyn Any + Send>>,
+  /// The includes resolved so far, in the order glslang requested them.
+  includes: Vec<IncludeRecord>,
+}
+impl<'a> IncludeContext<'a> {
+  fn new(include_handler: &'a dyn IncludeHandler, max_depth: usize) -> Self {
+    IncludeContext {
+      include_handler,
+      max_depth,
+      chain: Vec::new(),
+      error: None,
+      panic: None,
+      includes: Vec::new(),
+    }
+  }
+
+  /// Checks a resolved include against the active chain, making it the innermost include if it is accepted.
+  fn enter(&mut self, result: Result<ResolvedInclude, String>) -> Result<ResolvedInclude, String> {
+    let resolved = result?;
+    if let Some(offset) = resolved.resolved_name.find('\0') {
+      return Err(self.fail(CompileError::InvalidInput { input: InputKind::IncludeName, offset }));
+    }
+    if let Some(offset) = resolved.content.find('\0') {
+      return Err(self.fail(CompileError::InvalidInput { input: InputKind::IncludeContent, offset }));
+    }
+    if self.chain.contains(&resolved.resolved_name) {
+      let mut chain = self.chain.clone();
+      chain.push(resolved.resolved_name);
+      return Err(self.fail(CompileError::IncludeCycle { chain }));
+    }
+    self.chain.push(resolved.resolved_name.clone());
+    Ok(resolved)
+  }
+
+  /// Records `error`, returning the message for glslang.
+  fn fail(&mut self, error: CompileError) -> String {
+    let message = error.to_string();
+    self.error.get_or_insert(error);
+    message
+  }
+}
+
+unsafe fn include(
+  ctx: *mut c_void,
+  header_name: *const c_char,
+  includer_name: *const c_char,
+  include_depth: size_t,
+  system: bool,
+) -> *mut glsl_include_result_t {
+  let context = &mut *(ctx as *mut IncludeContext);
+  if context.panic.is_some() {
+    return std::ptr::null_mut();
+  }
+
+  let header_name = CStr::from_ptr(header_name).to_string_lossy();
+  // glslang passes the depth of the requested include, 1 for includes in the main source. Includes of a lower depth have ended.
+  let include_depth = include_depth as usize;
+  context.chain.truncate(include_depth.saturating_sub(1));
+  if include_depth > context.max_depth {
+    let mut chain = context.chain.clone();
+    chain.push(header_name.into_owned());
+    let message = context.fail(CompileError::IncludeDepthExceeded { max_depth: context.max_depth, chain });
+    return OwnedIncludeResult::new(Err(message)).into_raw();
+  }
+
+  let includer_name = if includer_name.is_null() { "".into() } else { CStr::from_ptr(includer_name).to_string_lossy() };
+  let mut record = IncludeRecord {
+    system,
+    header_name: header_name.into_owned(),
+    includer_name: includer_name.into_owned(),
+    include_depth,
+    resolved: ResolvedInclude { resolved_name: String::new(), content: String::new() },
+  };
+  let include_handler = context.include_handler;
+  let result = std::panic::catch_unwind(AssertUnwindSafe(|| record.resolve_again(include_handler)));
+
+  match result {
+    Ok(result) => {
+      let result = context.enter(result);
+      if let Ok(resolved) = &result {
+        record.resolved = resolved.clone();
+        context.includes.push(record);
+      }
+      OwnedIncludeResult::new(result).into_raw()
+    },
+    Err(panic) => {
+      context.panic = Some(panic);
+      std::ptr::null_mut()
+    },
+  }
+}
+
+unsafe extern "C" fn include_system(ctx: *mut c_void, header_name: *const c_char, includer_name: *const c_char, include_depth: size_t) -> *mut glsl_include_result_t {
+  include(ctx, header_name, includer_name, include_depth, true)
+}
+
+unsafe extern "C" fn include_local(ctx: *mut c_void, header_name: *const c_char, includer_name: *const c_char, include_depth: size_t) -> *mut glsl_include_result_t {
+  include(ctx, header_name, includer_name, include_depth, false)
+}
+
+unsafe extern "C" fn free_include_result(_ctx: *mut c_void, result: *mut glsl_include_result_t) -> c_int {
+  OwnedIncludeResult::free(result);
+  0
+}
+
+/// Same as `glslang_shader_preprocess`, resolving `#include`s with `include_handler`, returning whether it succeeded.
+/// The resolved includes are appended to `includes`.
+///
+/// Include cycles and includes nested deeper than `max_depth` are returned as errors.
+///
+/// # Safety
+/// - `shader` and `input` MUST be valid, as for `glslang_shader_preprocess`.
+pub(crate) unsafe fn preprocess_with_include_handler(
+  shader: *mut glslang_shader_t,
+  input: &glslang_input_t,
+  include_handler: &dyn IncludeHandler,
+  max_depth: usize,
+  includes: &mut Vec<IncludeRecord>,
+) -> Result<bool, CompileError> {
+  let callbacks = glsl_include_callbacks_t {
+    include_system: Some(include_system),
+    include_local: Some(include_local),
+    free_include_result: Some(free_include_result),
+  };
+  let mut context = IncludeContext::new(include_handler, max_depth);
+
+  // Every result is freed by glslang before this returns.
+  let result = glslang_sys_shader_preprocess_with_callbacks(shader, input, &callbacks, &mut context as *mut IncludeContext as *mut c_void);
+  if let Some(panic) = context.panic {
+    std::panic::resume_unwind(panic);
+  }
+  includes.append(&mut context.includes);
+  match context.error {
+    Some(error) => Err(error),
+    None => Ok(result != 0),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+  use super::*;
+
+  const SOURCE: &str = r##"
+    #version 450
+    #extension GL_GOOGLE_include_directive : require
+    #include "common.glsl"
+    layout(location = 0) out vec4 out_color;
+    void main() {
+      out_color = tint(vec4(1.0));
+    }
+  "##;
+
+  fn include_common(header_name: &str, _includer_name: &str, _include_depth: usize) -> Result<ResolvedInclude, String> {
+    match header_name {
+      "common.glsl" => Ok(ResolvedInclude {
+        resolved_name: "common.glsl".to_owned(),
+        content: "vec4 tint(vec4 color) { return color * 0.5; }\n".to_owned(),
+      }),
+      _ => Err(format!("No such file: {}", header_name)),
+    }
+  }
+
+  #[test]
+  fn include_from_closure() {
+    let input = CompileInput::new()
+      .source(SOURCE)
+      .stage(ShaderStage::Fragment)
+      .include_handler(include_common);
+
+    let preprocessed = preprocess(&input).unwrap();
+    assert!(preprocessed.contains("vec4 tint(vec4 color)"), "{}", preprocessed);
+    assert!(compile_input(&input, CompileOptionFlags::empty()).is_ok());
+  }
+
+  #[test]
+  fn include_error_is_reported() {
+    let input = CompileInput::new()
+      .source(&SOURCE.replace("common.glsl", "missing.glsl"))
+      .stage(ShaderStage::Fragment)
+      .include_handler(include_common);
+
+    match preprocess(&input) {
+      Err(CompileError::Preprocess(error_log)) => assert!(error_log.info_log.contains("No such file: missing.glsl"), "{}", error_log.info_log),
+      result => panic!("Unexpected result: {:?}", result),
+    }
+  }
+
+  #[test]
+  fn nul_in_include() {
+    let include_nul = |header_name: &str, _: &str, _: usize| -> Result<ResolvedInclude, String> {
+      match header_name {
+        "common.glsl" => Ok(ResolvedInclude { resolved_name: "common.glsl".to_owned(), content: "vec4 tint(vec4 color) {\0}\n".to_owned() }),
+        _ => Ok(ResolvedInclude { resolved_name: "common\0.glsl".to_owned(), content: String::new() }),
+      }
+    };
+    let input = CompileInput::new()
+      .source(SOURCE)
+      .stage(ShaderStage::Fragment)
+      .include_handler(include_nul);
+    assert!(matches!(preprocess(&input), Err(CompileError::InvalidInput { input: InputKind::IncludeContent, offset: 23 })));
+
+    let input = input.source(&SOURCE.replace("common.glsl", "other.glsl"));
+    let error = compile_input(&input, CompileOptionFlags::empty()).unwrap_err();
+    assert!(matches!(error, CompileError::InvalidInput { input: InputKind::IncludeName, offset: 6 }), "{:?}", error);
+  }
+
+  #[test]
+  fn include_cycle() {
+    let files: HashMap<String, String> = [
+      ("common.glsl", "#include \"a.glsl\"\n"),
+      ("a.glsl", "#include \"b.glsl\"\n"),
+      ("b.glsl", "#include \"a.glsl\"\n"),
+    ]
+    .into_iter()
+    .map(|(name, content)| (name.to_owned(), content.to_owned()))
+    .collect();
+    let input = CompileInput::new()
+      .source(SOURCE)
+      .stage(ShaderStage::Fragment)
+      .include_handler(VirtualIncluder::new(files));
+
+    let error = preprocess(&input).unwrap_err();
+    assert!(matches!(&error, CompileError::IncludeCycle { chain } if chain == &["common.glsl", "a.glsl", "b.glsl", "a.glsl"]), "{:?}", error);
+    assert_eq!(error.to_string(), "Include cycle: common.glsl -> a.glsl -> b.glsl -> a.glsl");
+  }
+
+  #[test]
+  fn include_depth_exceeded() {
+    // `common.glsl` includes `0.glsl`, which includes `1.glsl`, and so on.
+    let files: HashMap<String, String> = std::iter::once(("common.glsl".to_owned(), "#include \"0.glsl\"\n".to_owned()))
+      .chain((0..16).map(|i| (format!("{}.glsl", i), format!("#include \"{}.glsl\"\n", i + 1))))
+      .collect();
+    let input = CompileInput::new()
+      .source(SOURCE)
+      .stage(ShaderStage::Fragment)
+      .include_handler(VirtualIncluder::new(files))
+      .max_include_depth(4);
+
+    let error = preprocess(&input).unwrap_err();
+    assert!(matches!(&error, CompileError::IncludeDepthExceeded { max_depth: 4, chain } if chain.len() == 5), "{:?}", error);
+    assert_eq!(error.to_string(), "Maximum include depth 4 exceeded: common.glsl -> 0.glsl -> 1.glsl -> 2.glsl -> 3.glsl");
+  }
+
+  #[cfg(debug_assertions)]
+  #[test]
+  fn results_balance_over_many_compiles() {
+    let input = CompileInput::new()
+      .source(&SOURCE.replace("#include \"common.glsl\"", "#include \"common.glsl\"\n#include <common.glsl>\n#include \"missing.glsl\""))
+      .stage(ShaderStage::Fragment)
+      .include_handler(include_common);
+
+    let balance = live_results::thread_balance();
+    for _ in 0..32 {
+      assert!(matches!(compile_input(&input, CompileOptionFlags::empty()), Err(CompileError::Preprocess(_))));
+    }
+    let input = CompileInput::new().source(SOURCE).stage(ShaderStage::Fragment).include_handler(include_common);
+    for _ in 0..32 {
+      compile_input(&input, CompileOptionFlags::empty()).unwrap();
+    }
+    assert_eq!(live_results::thread_balance(), balance);
+  }
+
+  #[cfg(debug_assertions)]
+  #[test]
+  #[should_panic(expected = "already freed")]
+  fn double_free_panics() {
+    let raw = OwnedIncludeResult::new(Err("error".to_owned())).into_raw();
+    unsafe {
+      OwnedIncludeResult::free(raw);
+      // The registry check fails before the memory is touched.
+      OwnedIncludeResult::free(raw);
+    }
+  }
+
+  #[test]
+  fn results_are_freed_once() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    static LIVE: AtomicUsize = AtomicUsize::new(0);
+
+    // Counts the results handed to glslang by wrapping the trampolines.
+    unsafe extern "C" fn counting_include_local(ctx: *mut c_void, header_name: *const c_char, includer_name: *const c_char, include_depth: size_t) -> *mut glsl_include_result_t {
+      CALLS.fetch_add(1, Ordering::SeqCst);
+      LIVE.fetch_add(1, Ordering::SeqCst);
+      include_local(ctx, header_name, includer_name, include_depth)
+    }
+    unsafe extern "C" fn counting_free_include_result(ctx: *mut c_void, result: *mut glsl_include_result_t) -> c_int {
+      LIVE.fetch_sub(1, Ordering::SeqCst);
+      free_include_result(ctx, result)
+    }
+
+    let source = SOURCE.replace("#include \"common.glsl\"", "#include \"common.glsl\"\n#include \"missing.glsl\"");
+    let input = CompileInput::new().source(&source).stage(ShaderStage::Fragment);
+    let raw_input = input.as_raw().unwrap();
+    unsafe {
+      glslang_initialize_process();
+      let shader = glslang_shader_create(raw_input);
+      let callbacks = glsl_include_callbacks_t {
+        include_system: None,
+        include_local: Some(counting_include_local),
+        free_include_result: Some(counting_free_include_result),
+      };
+      let include_handler: &dyn IncludeHandler = &include_common;
+      let mut context = IncludeContext::new(include_handler, DEFAULT_MAX_INCLUDE_DEPTH);
+      glslang_sys_shader_preprocess_with_callbacks(shader, raw_input, &callbacks, &mut context as *mut IncludeContext as *mut c_void);
+      glslang_shader_delete(shader);
+      glslang_finalize_process();
+    }
+    assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+    assert_eq!(LIVE.load(Ordering::SeqCst), 0);
+  }
+}
@@ -0,0 +1,340 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::*;
+
+/// Resolves `#include`s from the filesystem.
+///
+/// - `#include "name"` is looked up relative to the directory of the including file, then in the search paths.
+/// - `#include <name>` is looked up in the search paths only.
+///
+/// Includes from the main source, which has no path, are looked up in the search paths only.
+/// Resolved names are canonical paths, so diagnostics point at the actual files.
+#[derive(Debug, Clone, Default)]
+pub struct FileIncluder {
+  search_paths: Vec<PathBuf>,
+}
+impl FileIncluder {
+  #[must_use]
+  pub fn new(search_paths: Vec<PathBuf>) -> Self {
+    FileIncluder { search_paths }
+  }
+
+  #[must_use]
+  pub fn search_paths(&self) -> &[PathBuf] {
+    &self.search_paths
+  }
+
+  fn resolve<'a>(&'a self, header_name: &str, directories: impl IntoIterator<Item = &'a Path>) -> Result<ResolvedInclude, String> {
+    let mut tried = Vec::new();
+    for directory in directories {
+      let path = directory.join(header_name);
+      if path.is_file() {
+        let path = path.canonicalize().map_err(|error| format!("Failed to resolve {}: {}", path.display(), error))?;
+        let content = std::fs::read_to_string(&path).map_err(|error| format!("Failed to read {}: {}", path.display(), error))?;
+        return Ok(ResolvedInclude {
+          resolved_name: path.to_string_lossy().into_owned(),
+          content,
+        });
+      }
+      tried.push(directory.display().to_string());
+    }
+
+    if tried.is_empty() {
+      Err(format!("Cannot find \"{}\": no directories to search", header_name))
+    }
+    else {
+      Err(format!("Cannot find \"{}\" in: {}", header_name, tried.join(", ")))
+    }
+  }
+}
+impl IncludeHandler for FileIncluder {
+  fn resolve_local(&self, header_name: &str, includer_name: &str, _include_depth: usize) -> Result<ResolvedInclude, String> {
+    let includer_directory = if includer_name.is_empty() { None } else { Path::new(includer_name).parent() };
+    self.resolve(header_name, includer_directory.into_iter().chain(self.search_paths.iter().map(PathBuf::as_path)))
+  }
+
+  fn resolve_system(&self, header_name: &str, _includer_name: &str, _include_depth: usize) -> Result<ResolvedInclude, String> {
+    self.resolve(header_name, self.search_paths.iter().map(PathBuf::as_path))
+  }
+}
+
+/// Virtual files by name, for [`VirtualIncluder`].
+pub trait VirtualFiles {
+  fn get(&self, name: &str) -> Option<&str>;
+  /// All names, for suggesting near misses when a file is not found.
+  fn names(&self) -> Vec<&str>;
+}
+impl VirtualFiles for HashMap<String, String> {
+  fn get(&self, name: &str) -> Option<&str> {
+    HashMap::get(self, name).map(String::as_str)
+  }
+  fn names(&self) -> Vec<&str> {
+    self.keys().map(String::as_str).collect()
+  }
+}
+impl VirtualFiles for BTreeMap<String, String> {
+  fn get(&self, name: &str) -> Option<&str> {
+    BTreeMap::get(self, name).map(String::as_str)
+  }
+  fn names(&self) -> Vec<&str> {
+    self.keys().map(String::as_str).collect()
+  }
+}
+
+/// Resolves `#include`s from in-memory files, e.g. sources kept in an asset database.
+///
+/// Names are `/`-separated paths. `#include "name"` is looked up relative to the including file first, then as is;
+/// `#include <name>` is looked up as is. `.` and `..` components are normalized.
+#[derive(Debug, Clone, Default)]
+pub struct VirtualIncluder<F = HashMap<String, String>> {
+  files: F,
+}
+impl<F: VirtualFiles> VirtualIncluder<F> {
+  #[must_use]
+  pub fn new(files: F) -> Self {
+    VirtualIncluder { files }
+  }
+
+  #[must_use]
+  pub fn files(&self) -> &F {
+    &self.files
+  }
+
+  fn resolve(&self, candidates: &[String], header_name: &str) -> Result<ResolvedInclude, String> {
+    for candidate in candidates {
+      if let Some(content) = self.files.get(candidate) {
+        return Ok(ResolvedInclude {
+          resolved_name: candidate.clone(),
+          content: content.to_owned(),
+        });
+      }
+    }
+
+    let mut near_misses: Vec<&str> = self.files.names().into_iter().filter(|name| candidates.iter().any(|candidate| is_near_miss(name, candidate))).collect();
+    near_misses.sort_unstable();
+    if near_misses.is_empty() {
+      Err(format!("Virtual file \"{}\" is not registered", header_name))
+    }
+    else {
+      Err(format!("Virtual file \"{}\" is not registered; did you mean {}?", header_name, near_misses.iter().map(|name| format!("\"{}\"", name)).collect::<Vec<_>>().join(", ")))
+    }
+  }
+}
+impl<F: VirtualFiles + Send + Sync> IncludeHandler for VirtualIncluder<F> {
+  fn resolve_local(&self, header_name: &str, includer_name: &str, _include_depth: usize) -> Result<ResolvedInclude, String> {
+    let mut candidates = Vec::new();
+    if let Some((includer_directory, _)) = includer_name.rsplit_once('/') {
+      candidates.push(normalize_virtual_path(&format!("{}/{}", includer_directory, header_name)));
+    }
+    candidates.push(normalize_virtual_path(header_name));
+    candidates.dedup();
+    self.resolve(&candidates, header_name)
+  }
+
+  fn resolve_system(&self, header_name: &str, _includer_name: &str, _include_depth: usize) -> Result<ResolvedInclude, String> {
+    self.resolve(&[normalize_virtual_path(header_name)], header_name)
+  }
+}
+
+/// Removes `.` components, and `..` components along with the preceding component.
+fn normalize_virtual_path(path: &str) -> String {
+  let mut components: Vec<&str> = Vec::new();
+  for component in path.split('/') {
+    match component {
+      "" | "." => {},
+      ".." if components.last().is_some_and(|last| *last != "..") => {
+        components.pop();
+      },
+      _ => components.push(component),
+    }
+  }
+  components.join("/")
+}
+
+/// Whether `name` differs from `wanted` only by case, by directory, or by at most two edits.
+fn is_near_miss(name: &str, wanted: &str) -> bool {
+  let file_name = |path: &'_ str| path.rsplit('/').next().unwrap_or_default().to_ascii_lowercase();
+  name.eq_ignore_ascii_case(wanted) || file_name(name) == file_name(wanted) || edit_distance(name, wanted) <= 2
+}
+
+/// Levenshtein distance.
+fn edit_distance(a: &str, b: &str) -> usize {
+  let b: Vec<char> = b.chars().collect();
+  let mut previous: Vec<usize> = (0..=b.len()).collect();
+  for (i, a_char) in a.chars().enumerate() {
+    let mut current = vec![i + 1; b.len() + 1];
+    for (j, b_char) in b.iter().enumerate() {
+      let substitution = previous[j] + usize::from(a_char != *b_char);
+      current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+    }
+    previous = current;
+  }
+  previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// A directory tree under the temporary directory, removed on drop.
+  struct TempTree {
+    root: PathBuf,
+  }
+  impl TempTree {
+    fn new(name: &str, files: &[(&str, &str)]) -> Self {
+      let root = std::env::temp_dir().join(format!("glslang-sys-{}-{}", name, std::process::id()));
+      for (path, content) in files {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+      }
+      TempTree { root }
+    }
+  }
+  impl Drop for TempTree {
+    fn drop(&mut self) {
+      let _ = std::fs::remove_dir_all(&self.root);
+    }
+  }
+
+  const MAIN_SOURCE: &str = r##"
+    #version 450
+    #extension GL_GOOGLE_include_directive : require
+    #include "lighting.glsl"
+    layout(location = 0) out vec4 out_color;
+    void main() {
+      out_color = vec4(brdf(0.5));
+    }
+  "##;
+
+  #[test]
+  fn nested_includes_across_directories() {
+    let tree = TempTree::new("nested-includes", &[
+      ("shaders/lighting.glsl", "#include \"detail/brdf.glsl\"\n"),
+      ("shaders/detail/brdf.glsl", "#include <constants.glsl>\nfloat brdf(float x) { return x / PI; }\n"),
+      ("lib/constants.glsl", "const float PI = 3.14159265;\n"),
+    ]);
+    let includer = FileIncluder::new(vec![tree.root.join("shaders"), tree.root.join("lib")]);
+
+    let input = CompileInput::new()
+      .source(MAIN_SOURCE)
+      .stage(ShaderStage::Fragment)
+      .include_handler(includer);
+    let preprocessed = preprocess(&input).unwrap();
+    assert!(preprocessed.contains("const float PI = 3.14159265;"), "{}", preprocessed);
+    assert!(preprocessed.contains("float brdf(float x)"), "{}", preprocessed);
+    // `#line` directives name the canonical paths.
+    let constants_path = tree.root.join("lib/constants.glsl").canonicalize().unwrap();
+    assert!(preprocessed.contains(&*constants_path.to_string_lossy()), "{}", preprocessed);
+
+    assert!(compile_input(&input, CompileOptionFlags::empty()).is_ok());
+  }
+
+  fn virtual_files() -> HashMap<String, String> {
+    [
+      ("lighting.glsl", "#include \"detail/brdf.glsl\"\n"),
+      ("detail/brdf.glsl", "#include \"../common/constants.glsl\"\nfloat brdf(float x) { return x / PI * SCALE; }\n"),
+      ("common/constants.glsl", "const float PI = 3.14159265;\n"),
+    ]
+    .into_iter()
+    .map(|(name, content)| (name.to_owned(), content.to_owned()))
+    .collect()
+  }
+
+  #[test]
+  fn virtual_includes() {
+    let input = CompileInput::new()
+      .source(MAIN_SOURCE)
+      .stage(ShaderStage::Fragment)
+      .add_define("SCALE", Some("2.0"))
+      .include_handler(VirtualIncluder::new(virtual_files()));
+
+    let spirv = compile_input(&input, CompileOptionFlags::empty()).unwrap().spirv;
+    assert_eq!(spirv[0], 0x07230203);
+  }
+
+  #[test]
+  fn virtual_include_near_misses() {
+    let includer = VirtualIncluder::new(virtual_files());
+    assert_eq!(includer.resolve_system("common/constants.glsl", "", 1).unwrap().resolved_name, "common/constants.glsl");
+    assert_eq!(includer.resolve_local("./constants.glsl", "common/lighting.glsl", 1).unwrap().resolved_name, "common/constants.glsl");
+
+    let error = includer.resolve_local("common/constant.glsl", "", 1).unwrap_err();
+    assert_eq!(error, "Virtual file \"common/constant.glsl\" is not registered; did you mean \"common/constants.glsl\"?");
+    let error = includer.resolve_system("brdf.glsl", "", 1).unwrap_err();
+    assert_eq!(error, "Virtual file \"brdf.glsl\" is not registered; did you mean \"detail/brdf.glsl\"?");
+    let error = includer.resolve_system("unrelated/path.glsl", "", 1).unwrap_err();
+    assert_eq!(error, "Virtual file \"unrelated/path.glsl\" is not registered");
+  }
+
+  #[test]
+  fn local_and_system_includes_pick_different_copies() {
+    let tree = TempTree::new("shadowed-includes", &[
+      ("shaders/lighting.glsl", "#include \"constants.glsl\"\n#include <constants.glsl>\n"),
+      ("shaders/constants.glsl", "const float LOCAL_PI = 3.14159265;\n"),
+      ("lib/constants.glsl", "const float SYSTEM_PI = 3.14159265;\n"),
+    ]);
+    let includer = FileIncluder::new(vec![tree.root.join("lib"), tree.root.join("shaders")]);
+    let includer_name = tree.root.join("shaders/lighting.glsl").canonicalize().unwrap();
+    let includer_name = includer_name.to_string_lossy();
+
+    assert!(includer.resolve_local("constants.glsl", &includer_name, 1).unwrap().content.contains("LOCAL_PI"));
+    assert!(includer.resolve_system("constants.glsl", &includer_name, 1).unwrap().content.contains("SYSTEM_PI"));
+    // Without an includer, local includes fall back to the search paths in order.
+    assert!(includer.resolve_local("constants.glsl", "", 1).unwrap().content.contains("SYSTEM_PI"));
+
+    let input = CompileInput::new()
+      .source("#version 450\n#extension GL_GOOGLE_include_directive : require\n#include <lighting.glsl>\nvoid main() {}\n")
+      .stage(ShaderStage::Fragment)
+      .include_handler(includer);
+    let preprocessed = preprocess(&input).unwrap();
+    assert!(preprocessed.contains("LOCAL_PI") && preprocessed.contains("SYSTEM_PI"), "{}", preprocessed);
+
+    let files: HashMap<String, String> = [
+      ("shaders/constants.glsl", "const float LOCAL_PI = 3.14159265;\n"),
+      ("constants.glsl", "const float SYSTEM_PI = 3.14159265;\n"),
+    ]
+    .into_iter()
+    .map(|(name, content)| (name.to_owned(), content.to_owned()))
+    .collect();
+    let includer = VirtualIncluder::new(files);
+    assert!(includer.resolve_local("constants.glsl", "shaders/lighting.glsl", 1).unwrap().content.contains("LOCAL_PI"));
+    assert!(includer.resolve_system("constants.glsl", "shaders/lighting.glsl", 1).unwrap().content.contains("SYSTEM_PI"));
+  }
+
+  #[test]
+  fn closures_resolve_both_forms() {
+    let include = |header_name: &str, _includer_name: &str, _include_depth: usize| Ok(ResolvedInclude {
+      resolved_name: header_name.to_owned(),
+      content: String::new(),
+    });
+    assert_eq!(include.resolve_system("a.glsl", "", 1), include.resolve_local("a.glsl", "", 1));
+  }
+
+  #[test]
+  fn system_includes_ignore_the_includer_directory() {
+    let tree = TempTree::new("system-includes", &[
+      ("shaders/lighting.glsl", "#include <detail/brdf.glsl>\n"),
+      ("shaders/detail/brdf.glsl", "#include <sibling.glsl>\n"),
+      ("shaders/detail/sibling.glsl", "float brdf(float x) { return x; }\n"),
+    ]);
+    let includer = FileIncluder::new(vec![tree.root.join("shaders"), tree.root.join("lib")]);
+
+    let resolved = includer.resolve_local("sibling.glsl", &tree.root.join("shaders/detail/brdf.glsl").to_string_lossy(), 2).unwrap();
+    assert!(resolved.content.contains("float brdf"));
+
+    let error = includer.resolve_system("sibling.glsl", &tree.root.join("shaders/detail/brdf.glsl").to_string_lossy(), 2).unwrap_err();
+    assert!(error.contains(&tree.root.join("shaders").display().to_string()), "{}", error);
+    assert!(error.contains(&tree.root.join("lib").display().to_string()), "{}", error);
+
+    let input = CompileInput::new()
+      .source(MAIN_SOURCE)
+      .stage(ShaderStage::Fragment)
+      .include_handler(includer);
+    match preprocess(&input) {
+      Err(CompileError::Preprocess(error_log)) => assert!(error_log.info_log.contains("Cannot find \"sibling.glsl\""), "{}", error_log.info_log),
+      result => panic!("Unexpected result: {:?}", result),
+    }
+  }
+}
@@ -0,0 +1,1050 @@
+use std::ffi::{CStr, CString, NulError};
+use std::hash::{Hash, Hasher};
+use std::os::raw::c_int;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::*;
+
+/// Builds a `glslang_input_t`, owning the source string and resource limits it points to.
+///
+/// Inputs are `Send + Sync`: threads can compile a shared input at the same time, e.g. with [`compile_input`].
+///
+/// ```no_run
+/// # use glslang_sys_2022::*;
+/// let input = CompileInput::new()
+///   .source("#version 450\nvoid main() {}\n")
+///   .stage(ShaderStage::Compute);
+/// let spirv = compile_input(&input, CompileOptionFlags::empty()).unwrap().spirv;
+/// ```
+#[derive(Debug)]
+pub struct CompileInput {
+  /// `code` and `resource` point into `source` and `resource`, whose heap allocations don't move with `self`.
+  raw: glslang_input_t,
+  source: CString,
+  /// An interior NUL byte in the last `source()`, reported by `as_raw()`.
+  source_error: Option<NulError>,
+  /// An interior NUL byte in the last `source_file_name()` is reported by `as_raw()`.
+  source_file_name: Result<Option<CString>, NulError>,
+  /// Interior NUL bytes in the last `entry_point()` and `source_entry_point()` are reported by `as_raw()`.
+  entry_point: Result<Option<CString>, NulError>,
+  source_entry_point: Result<Option<CString>, NulError>,
+  /// The file whose stage `from_file()` could not infer, reported by `as_raw()` until `stage()` is called.
+  unknown_stage: Option<PathBuf>,
+  resource: Box<glslang_resource_t>,
+  preamble: Preamble,
+  include_handler: Option<Box<dyn IncludeHandler>>,
+  max_include_depth: usize,
+  diagnostic_sink: Option<Box<dyn DiagnosticSink>>,
+  shader_options: ShaderOptions,
+  auto_map_bases: AutoMapBases,
+  glsl_version: Option<u32>,
+  spv_options: SpvOptions,
+  hlsl_options: HlslOptions,
+  /// Mirrors `client`, `client_version` and `target_language_version` in `raw`, validated by `as_raw()`.
+  target_env: TargetEnv,
+  /// Whether `target_env` was chosen by the caller rather than defaulted, see `stage()`.
+  explicit_target_env: bool,
+  /// Mirrors `default_version`, `default_profile` and `force_default_version_and_profile` in `raw`, validated by `as_raw()`.
+  default_version: DefaultVersion,
+}
+// SAFETY: The only non-`Send`/`Sync` members are the raw pointers in `raw`, which point into `source` and `resource`.
+// They are only written through `&mut self`, and glslang only reads through them; the include handler and diagnostic sink
+// are `Send + Sync`.
+unsafe impl Send for CompileInput {}
+unsafe impl Sync for CompileInput {}
+
+impl Default for CompileInput {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+impl CompileInput {
+  /// GLSL vertex shader with an empty source, targeting [`TargetEnv::vulkan_1_0`] with [`DEFAULT_RESOURCE_LIMITS`].
+  /// Sources without `#version` are compiled as [`DefaultVersion::default`].
+  #[must_use]
+  pub fn new() -> Self {
+    let source = CString::default();
+    let resource = Box::new(DEFAULT_RESOURCE_LIMITS);
+    let target_env = TargetEnv::vulkan_1_0();
+    let default_version = DefaultVersion::default();
+    let mut raw = glslang_input_t {
+      language: glslang_source_t_GLSLANG_SOURCE_GLSL,
+      stage: glslang_stage_t_GLSLANG_STAGE_VERTEX,
+      client: target_env.client.into(),
+      client_version: target_env.client_version,
+      target_language: glslang_target_language_t_GLSLANG_TARGET_SPV,
+      target_language_version: target_env.spirv_version,
+      code: source.as_ptr(),
+      default_version: 100,
+      default_profile: glslang_profile_t_GLSLANG_NO_PROFILE,
+      force_default_version_and_profile: 0,
+      forward_compatible: 0,
+      messages: Messages::DEFAULT_VULKAN.into(),
+      resource: &*resource as *const glslang_resource_t,
+    };
+    default_version.apply(&mut raw);
+    CompileInput {
+      raw,
+      source,
+      source_error: None,
+      source_file_name: Ok(None),
+      entry_point: Ok(None),
+      source_entry_point: Ok(None),
+      unknown_stage: None,
+      resource,
+      preamble: Preamble::new(),
+      include_handler: None,
+      max_include_depth: DEFAULT_MAX_INCLUDE_DEPTH,
+      diagnostic_sink: None,
+      shader_options: ShaderOptions::empty(),
+      auto_map_bases: AutoMapBases::default(),
+      glsl_version: None,
+      spv_options: SpvOptions::default(),
+      hlsl_options: HlslOptions::default(),
+      target_env,
+      explicit_target_env: false,
+      default_version,
+    }
+  }
+
+  /// An interior NUL byte in `source` is reported when the input is used, see [`CompileInput::as_raw`].
+  #[must_use]
+  pub fn source(mut self, source: &str) -> Self {
+    match CString::new(source) {
+      Ok(source) => {
+        self.source = source;
+        self.source_error = None;
+      },
+      Err(error) => {
+        self.source = CString::default();
+        self.source_error = Some(error);
+      },
+    }
+    self.raw.code = self.source.as_ptr();
+    self
+  }
+
+  /// Reads the source of `path`, e.g. `shaders/blur.comp`, and sets the input up for compiling a file:
+  /// - The stage is inferred with [`ShaderStage::from_path`]. For other files, e.g. `common.glsl`, set it with
+  ///   [`CompileInput::stage`]; compiling without fails with [`CompileError::UnknownStage`].
+  /// - `.hlsl` files are HLSL.
+  /// - `path` is the [`CompileInput::source_file_name`].
+  /// - `#include`s are resolved by a [`FileIncluder`] searching the directory of `path`, unless replaced with
+  ///   [`CompileInput::include_handler`].
+  ///
+  /// Fails with [`CompileError::ReadSource`] if the file cannot be read, or is not valid UTF-8.
+  pub fn from_file(path: impl AsRef<Path>) -> Result<Self, CompileError> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path).map_err(|error| CompileError::ReadSource { path: path.to_owned(), source: Arc::new(error) })?;
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut input = CompileInput::new()
+      .source(&source)
+      .source_file_name(&path.to_string_lossy())
+      .include_handler(FileIncluder::new(vec![directory.to_owned()]));
+    if path.extension().is_some_and(|extension| extension == "hlsl") {
+      input = input.language(SourceLanguage::Hlsl);
+    }
+    match ShaderStage::from_path(path) {
+      Some(stage) => input = input.stage(stage),
+      None => input.unknown_stage = Some(path.to_owned()),
+    }
+    Ok(input)
+  }
+
+  /// The name of the source in diagnostics, `OpSource` and `OpLine` (with [`CompileOptionFlags::GenerateDebugInfo`]), e.g. a
+  /// path. Also the includer name of the `#include`s of the source, see [`IncludeHandler::resolve_local`].
+  ///
+  /// An interior NUL byte is reported when the input is used, see [`CompileInput::as_raw`].
+  #[must_use]
+  pub fn source_file_name(mut self, source_file_name: &str) -> Self {
+    self.source_file_name = CString::new(source_file_name).map(Some);
+    self
+  }
+
+  /// For ray tracing stages, which need SPIR-V 1.4, also changes the default target environment to
+  /// [`TargetEnv::vulkan_1_2`]. An explicitly chosen one that is too old is reported when the input is used, see
+  /// [`CompileInput::as_raw`].
+  #[must_use]
+  pub fn stage(mut self, stage: ShaderStage) -> Self {
+    self.raw.stage = stage.into();
+    self.unknown_stage = None;
+    let target_env = self.target_env_for_stage(stage);
+    if target_env != self.target_env {
+      self.set_target_env(target_env);
+    }
+    self
+  }
+
+  /// The target environment after `stage()`: the current one, or [`TargetEnv::vulkan_1_2`] if that is the default and too old.
+  fn target_env_for_stage(&self, stage: ShaderStage) -> TargetEnv {
+    if !self.explicit_target_env && self.target_env.validate_for_stage(stage).is_err() && TargetEnv::vulkan_1_2().validate_for_stage(stage).is_ok() {
+      TargetEnv::vulkan_1_2()
+    }
+    else {
+      self.target_env
+    }
+  }
+
+  /// Also sets [`Messages::ReadHlsl`] and the [`HlslOptions::messages`] for HLSL.
+  #[must_use]
+  pub fn language(mut self, language: SourceLanguage) -> Self {
+    self.raw.language = language.into();
+    self.set_messages(Messages::ReadHlsl, language == SourceLanguage::Hlsl);
+    self.set_hlsl_messages();
+    self
+  }
+
+  /// Sets the client, client version and SPIR-V version together. Also sets or clears [`Messages::VulkanRules`], see
+  /// [`TargetEnv::messages`].
+  ///
+  /// The combination is validated when the input is used, see [`CompileInput::as_raw`].
+  #[must_use]
+  pub fn target_env(mut self, target_env: TargetEnv) -> Self {
+    self.set_target_env(target_env);
+    self.explicit_target_env = true;
+    self
+  }
+
+  fn set_target_env(&mut self, target_env: TargetEnv) {
+    self.target_env = target_env;
+    apply_target_env(&mut self.raw, target_env);
+  }
+
+  /// Changes the client, with its canonical SPIR-V version (see [`TargetEnv::for_client_version`]). Call
+  /// [`CompileInput::target_spirv`] afterwards to choose another one.
+  #[must_use]
+  pub fn client(self, client: Client, client_version: glslang_target_client_version_t) -> Self {
+    self.target_env(TargetEnv::for_client_version(client, client_version))
+  }
+
+  /// Changes the SPIR-V version, keeping the client. See [`CompileInput::target_env`].
+  #[must_use]
+  pub fn target_spirv(self, target_language_version: glslang_target_language_version_t) -> Self {
+    let target_env = self.target_env.with_spirv_version(target_language_version);
+    self.target_env(target_env)
+  }
+
+  /// Adds `preamble` to the code preprocessed before the source. Defines always come before fragments, see [`Preamble`].
+  ///
+  /// An invalid preamble is reported when the input is used, see [`CompileInput::as_raw`].
+  #[must_use]
+  pub fn preamble(mut self, preamble: impl Into<Preamble>) -> Self {
+    self.preamble.extend(&preamble.into());
+    self
+  }
+
+  /// Defines the macro `name` as `value`. See [`Preamble::define`].
+  #[must_use]
+  pub fn add_define(mut self, name: &str, value: Option<&str>) -> Self {
+    self.preamble = self.preamble.define(name, value);
+    self
+  }
+
+  /// Resolves `#include`s with `include_handler`. Without one, glslang resolves them relative to the working directory.
+  #[must_use]
+  pub fn include_handler(mut self, include_handler: impl IncludeHandler + 'static) -> Self {
+    self.include_handler = Some(Box::new(include_handler));
+    self
+  }
+
+  /// Nesting depth beyond which includes fail with [`CompileError::IncludeDepthExceeded`]. Defaults to [`DEFAULT_MAX_INCLUDE_DEPTH`].
+  ///
+  /// Only applies with an [`CompileInput::include_handler`].
+  #[must_use]
+  pub fn max_include_depth(mut self, max_include_depth: usize) -> Self {
+    self.max_include_depth = max_include_depth;
+    self
+  }
+
+  /// Delivers the warnings, errors and SPIR-V messages of each compile to `diagnostic_sink` as glslang reports them, in
+  /// addition to returning them.
+  ///
+  /// A panic of the sink is caught; it receives nothing more of that compile, which goes on unaffected. Results returned by
+  /// a [`CompileCache`] or [`DiskCache`] deliver nothing.
+  ///
+  /// ```no_run
+  /// # use glslang_sys_2022::*;
+  /// let input = CompileInput::new()
+  ///   .source("#version 450\nvoid main() {}\n")
+  ///   .stage(ShaderStage::Compute)
+  ///   .diagnostic_sink(|diagnostic: &Diagnostic| eprintln!("{}", diagnostic));
+  /// ```
+  #[must_use]
+  pub fn diagnostic_sink(mut self, diagnostic_sink: impl DiagnosticSink + 'static) -> Self {
+    self.diagnostic_sink = Some(Box::new(diagnostic_sink));
+    self
+  }
+
+  /// Options set on the glslang shader object, e.g. [`ShaderOptions::AutoMapBindings`].
+  #[must_use]
+  pub fn shader_options(mut self, shader_options: ShaderOptions) -> Self {
+    self.shader_options = shader_options;
+    self
+  }
+
+  /// Assigns bindings to uniforms and buffers without `layout(binding = ...)` from `base` on in each descriptor set, e.g.
+  /// to leave room for bindings the engine reserves. Adds [`ShaderOptions::AutoMapBindings`].
+  ///
+  /// glslang adds `base` to the explicit bindings as well, so that they keep their place relative to the auto-mapped ones.
+  #[must_use]
+  pub fn auto_bind_uniforms(mut self, base: u32) -> Self {
+    self.shader_options |= ShaderOptions::AutoMapBindings;
+    self.auto_map_bases.binding = Some(base);
+    self
+  }
+
+  /// Same as [`CompileInput::auto_bind_uniforms`] for descriptor set `set` only, overriding the base of all sets for it.
+  #[must_use]
+  pub fn auto_bind_uniforms_in_set(mut self, set: u32, base: u32) -> Self {
+    self.shader_options |= ShaderOptions::AutoMapBindings;
+    self.auto_map_bases.set_bindings.retain(|&(other_set, _)| other_set != set);
+    self.auto_map_bases.set_bindings.push((set, base));
+    self
+  }
+
+  /// Assigns locations to inputs and outputs without `layout(location = ...)`, and to uniforms outside of blocks from
+  /// `uniform_base` on (OpenGL). Adds [`ShaderOptions::AutoMapLocations`].
+  ///
+  /// Inputs and outputs are numbered from 0: glslang 11.10 has no base for them.
+  #[must_use]
+  pub fn auto_map_locations(mut self, uniform_base: u32) -> Self {
+    self.shader_options |= ShaderOptions::AutoMapLocations;
+    self.auto_map_bases.uniform_location = Some(uniform_base);
+    self
+  }
+
+  /// Parses the source as GLSL `version` (e.g. `450`) regardless of its `#version` directive, for legacy shaders.
+  ///
+  /// `0` removes the override.
+  #[must_use]
+  pub fn override_glsl_version(mut self, version: u32) -> Self {
+    self.glsl_version = (version != 0).then_some(version);
+    self
+  }
+
+  /// Options of SPIR-V generation. [`CompileOptionFlags::GenerateDebugInfo`] and [`CompileOptionFlags::AddOpSource`] still
+  /// enable `generate_debug_info`.
+  #[must_use]
+  pub fn spv_options(mut self, spv_options: SpvOptions) -> Self {
+    self.spv_options = spv_options;
+    self
+  }
+
+  /// Entry point selection and fixups for HLSL input. Ignored for GLSL.
+  ///
+  /// Also sets the [`HlslOptions::messages`] for HLSL.
+  #[must_use]
+  pub fn hlsl_options(mut self, hlsl_options: HlslOptions) -> Self {
+    self.hlsl_options = hlsl_options;
+    self.set_hlsl_messages();
+    self
+  }
+
+  /// The name of the entry point in the SPIR-V (`TShader::setEntryPoint`), overriding [`HlslOptions::entry_point`].
+  ///
+  /// Without a [`CompileInput::source_entry_point`], HLSL compiles the function of that name, e.g. `PSMain`, and GLSL
+  /// renames `main`.
+  ///
+  /// An interior NUL byte is reported when the input is used, see [`CompileInput::as_raw`].
+  #[must_use]
+  pub fn entry_point(mut self, entry_point: &str) -> Self {
+    self.entry_point = CString::new(entry_point).map(Some);
+    self
+  }
+
+  /// The function to compile as the entry point, e.g. `PSMain` in an HLSL file that also contains `VSMain`, named
+  /// [`CompileInput::entry_point`] in the SPIR-V, or `main` if not set (`TShader::setSourceEntryPoint`). Overrides
+  /// [`HlslOptions::source_entry_point`].
+  ///
+  /// glslang only accepts `main` for GLSL.
+  ///
+  /// An interior NUL byte is reported when the input is used, see [`CompileInput::as_raw`].
+  #[must_use]
+  pub fn source_entry_point(mut self, source_entry_point: &str) -> Self {
+    self.source_entry_point = CString::new(source_entry_point).map(Some);
+    self
+  }
+
+  /// Copies `resource`.
+  #[must_use]
+  pub fn resource(mut self, resource: &glslang_resource_t) -> Self {
+    *self.resource = *resource;
+    self
+  }
+
+  /// The version and profile of sources without `#version`, e.g. [`DefaultVersion::desktop_450_core`].
+  ///
+  /// Invalid combinations, e.g. ES with 450, are reported when the input is used, see [`CompileInput::as_raw`].
+  #[must_use]
+  pub fn default_version(mut self, default_version: DefaultVersion) -> Self {
+    self.default_version = default_version;
+    default_version.apply(&mut self.raw);
+    self
+  }
+
+  /// Replaces the message options, [`Messages::DEFAULT_VULKAN`] by default.
+  ///
+  /// [`CompileInput::language`] and [`CompileInput::target_env`] set or clear `ReadHlsl` and `VulkanRules` afterwards.
+  #[must_use]
+  pub fn messages(mut self, messages: Messages) -> Self {
+    self.raw.messages = messages.into();
+    self
+  }
+
+  /// Reports some errors as warnings instead, e.g. `#define defined`, for best-effort compiles of legacy shaders
+  /// ([`Messages::RelaxedErrors`]). Keeps the other message options.
+  #[must_use]
+  pub fn relaxed_errors(mut self, enabled: bool) -> Self {
+    self.set_messages(Messages::RelaxedErrors, enabled);
+    self
+  }
+
+  /// Keeps functions that are never called in the SPIR-V, like `--keep-uncalled` of glslangValidator
+  /// ([`Messages::KeepUncalled`]). Keeps the other message options.
+  #[must_use]
+  pub fn keep_uncalled(mut self, enabled: bool) -> Self {
+    self.set_messages(Messages::KeepUncalled, enabled);
+    self
+  }
+
+  /// Drops all warnings, leaving [`CompileOutput::warnings`] empty ([`Messages::SuppressWarnings`]). Keeps the other message options.
+  #[must_use]
+  pub fn suppress_warnings(mut self, enabled: bool) -> Self {
+    self.set_messages(Messages::SuppressWarnings, enabled);
+    self
+  }
+
+  /// `GLSLANG_MSG_HLSL_OFFSETS_BIT` would also change the layout of GLSL blocks.
+  fn set_hlsl_messages(&mut self) {
+    let is_hlsl = self.raw.language == glslang_source_t_GLSLANG_SOURCE_HLSL;
+    self.set_messages(Messages::HlslOffsets, is_hlsl && self.hlsl_options.hlsl_offsets);
+  }
+
+  fn set_messages(&mut self, messages: Messages, enabled: bool) {
+    let mut raw_messages = Messages::from(self.raw.messages);
+    raw_messages.set(messages, enabled);
+    self.raw.messages = raw_messages.into();
+  }
+
+  /// The `glslang_input_t`, valid for as long as `self` is borrowed.
+  ///
+  /// Fails if the source or source file name contains an interior NUL byte, the stage of [`CompileInput::from_file`] is
+  /// unknown, the preamble is invalid (see [`Preamble::as_c_str`]), or the target environment or default version is invalid
+  /// (see [`TargetEnv::validate_for_stage`] and [`DefaultVersion::validate`]).
+  ///
+  /// The preamble is not part of `glslang_input_t`; see [`CompileInput::preamble_c_str`].
+  pub fn as_raw(&self) -> Result<&glslang_input_t, CompileError> {
+    if let Some(error) = &self.source_error {
+      return Err(CompileError::nul_in(InputKind::Source, error));
+    }
+    if let Some(path) = &self.unknown_stage {
+      return Err(CompileError::UnknownStage(path.clone()));
+    }
+    self.validate_settings(&self.target_env, self.shader_stage())?;
+    Ok(&self.raw)
+  }
+
+  /// A copy of the `glslang_input_t` for `stage`, as if set with [`CompileInput::stage`], without a source. `resource` still
+  /// points into `self`.
+  ///
+  /// Fails like [`CompileInput::as_raw`], except that the source and stage of `self` are not used.
+  pub(crate) fn raw_for_stage(&self, stage: ShaderStage) -> Result<glslang_input_t, CompileError> {
+    let target_env = self.target_env_for_stage(stage);
+    self.validate_settings(&target_env, stage)?;
+    let mut raw = glslang_input_t {
+      stage: stage.into(),
+      code: std::ptr::null(),
+      ..self.raw
+    };
+    if target_env != self.target_env {
+      apply_target_env(&mut raw, target_env);
+    }
+    Ok(raw)
+  }
+
+  /// The checks of `as_raw()` that do not depend on the source.
+  fn validate_settings(&self, target_env: &TargetEnv, stage: ShaderStage) -> Result<(), CompileError> {
+    if let Err(error) = &self.source_file_name {
+      return Err(CompileError::nul_in(InputKind::SourceFileName, error));
+    }
+    if let Err(error) = self.entry_point.as_ref().and(self.source_entry_point.as_ref()) {
+      return Err(CompileError::nul_in(InputKind::EntryPoint, error));
+    }
+    if let Some(resource_set_binding) = self.hlsl_options.resource_set_binding.as_ref().filter(|_| self.raw.language == glslang_source_t_GLSLANG_SOURCE_HLSL) {
+      resource_set_binding.validate()?;
+    }
+    self.preamble.as_c_str()?;
+    target_env.validate_for_stage(stage)?;
+    self.default_version.validate()?;
+    Ok(())
+  }
+
+  /// The stage set with [`CompileInput::stage`].
+  pub(crate) fn shader_stage(&self) -> ShaderStage {
+    // Only `stage()` sets it, from a `ShaderStage`.
+    ShaderStage::try_from(self.raw.stage).unwrap()
+  }
+
+  /// Whether compiling the input needs [`Program::map_io`]: for [`ShaderOptions::AutoMapBindings`] and
+  /// [`ShaderOptions::AutoMapLocations`], or the [`HlslOptions::binding_shifts`] of HLSL.
+  pub(crate) fn needs_io_mapping(&self) -> bool {
+    let auto_map = ShaderOptions::AutoMapBindings | ShaderOptions::AutoMapLocations;
+    let is_hlsl = self.raw.language == glslang_source_t_GLSLANG_SOURCE_HLSL;
+    self.shader_options.intersects(auto_map) || (is_hlsl && self.hlsl_options.needs_io_mapping())
+  }
+
+  /// The settings that are applied to the glslang shader object, borrowing from `self`.
+  pub(crate) fn shader_settings(&self) -> ShaderSettings<'_> {
+    ShaderSettings {
+      preamble: self.preamble_c_str(),
+      source_file_name: self.source_file_name.as_ref().ok().and_then(Option::as_deref),
+      entry_point: self.entry_point.as_ref().ok().and_then(Option::as_deref),
+      source_entry_point: self.source_entry_point.as_ref().ok().and_then(Option::as_deref),
+      include_handler: self.include_handler.as_deref(),
+      max_include_depth: self.max_include_depth,
+      shader_options: self.shader_options,
+      auto_map_bases: Some(&self.auto_map_bases),
+      glsl_version: self.glsl_version,
+      spv_options: self.spv_options,
+      hlsl_options: Some(&self.hlsl_options),
+      diagnostic_sink: self.diagnostic_sink.as_deref(),
+    }
+  }
+
+  /// Hashes everything that affects the output of a compile, except the content of `#include`s, which the include handler
+  /// decides. See [`CompileCache`].
+  pub(crate) fn hash_settings<H: Hasher>(&self, state: &mut H) {
+    let raw = &self.raw;
+    (raw.language, raw.stage, raw.client, raw.client_version, raw.target_language, raw.target_language_version).hash(state);
+    (raw.default_version, raw.default_profile, raw.force_default_version_and_profile, raw.forward_compatible, raw.messages).hash(state);
+    self.source.hash(state);
+    self.source_file_name.as_ref().ok().hash(state);
+    (self.entry_point.as_ref().ok(), self.source_entry_point.as_ref().ok()).hash(state);
+    self.preamble_c_str().hash(state);
+    ResourceLimits(*self.resource).hash(state);
+    self.include_handler.is_some().hash(state);
+    self.max_include_depth.hash(state);
+    self.shader_options.hash(state);
+    self.auto_map_bases.hash(state);
+    self.glsl_version.hash(state);
+    self.spv_options.hash(state);
+    self.hlsl_options.hash(state);
+  }
+
+  /// The preamble set with [`CompileInput::preamble`] and [`CompileInput::add_define`], e.g. for extending it.
+  pub(crate) fn current_preamble(&self) -> &Preamble {
+    &self.preamble
+  }
+
+  /// The preamble, if any, valid for as long as `self` is borrowed. `None` if the preamble is empty or invalid.
+  #[must_use]
+  pub fn preamble_c_str(&self) -> Option<&CStr> {
+    self.preamble.as_c_str().ok().flatten()
+  }
+}
+
+/// Sets the client, client version and SPIR-V version of `raw`, and [`Messages::VulkanRules`] for `target_env`.
+fn apply_target_env(raw: &mut glslang_input_t, target_env: TargetEnv) {
+  raw.client = target_env.client.into();
+  raw.client_version = target_env.client_version;
+  raw.target_language = glslang_target_language_t_GLSLANG_TARGET_SPV;
+  raw.target_language_version = target_env.spirv_version;
+  let mut messages = Messages::from(raw.messages);
+  messages.set(Messages::VulkanRules, target_env.messages().contains(Messages::VulkanRules));
+  raw.messages = messages.into();
+}
+
+/// Same as [`compile`], without having to keep the pointers in `glslang_input_t` valid by hand.
+///
+/// The glslang process is initialized for the duration of the call. Since glslang reference-counts initialization,
+/// holding a [`GlslangProcess`] avoids setting up its built-in symbol tables on every call.
+pub fn compile_input(input: &CompileInput, option_flags: CompileOptionFlags) -> Result<CompileOutput, CompileError> {
+  let raw = input.as_raw()?;
+  unsafe {
+    glslang_initialize_process();
+    // SAFETY: `raw` and the preamble point into `input`, which outlives the call.
+    let result = compile_shader(raw, option_flags, &input.shader_settings());
+    glslang_finalize_process();
+    result.map(|output| add_target_env_warning(input, output))
+  }
+}
+
+/// Same as [`compile_input`], writing the SPIR-V to `spirv` instead of [`CompileOutput::spirv`], which is left empty.
+///
+/// `spirv` is cleared first, also on error, and its capacity is reused, e.g. when compiling many permutations.
+pub fn compile_input_into(input: &CompileInput, option_flags: CompileOptionFlags, spirv: &mut Vec<u32>) -> Result<CompileOutput, CompileError> {
+  spirv.clear();
+  let raw = input.as_raw()?;
+  unsafe {
+    glslang_initialize_process();
+    // SAFETY: `raw` and the preamble point into `input`, which outlives the call.
+    let result = compile_shader_into(raw, option_flags, &input.shader_settings(), spirv);
+    glslang_finalize_process();
+    result.map(|output| add_target_env_warning(input, output))
+  }
+}
+
+/// Adds the [`TargetEnv::spirv_version_warning`] of `input` as the first warning of `output`.
+pub(crate) fn add_target_env_warning(input: &CompileInput, mut output: CompileOutput) -> CompileOutput {
+  if let Some(warning) = input.target_env.spirv_version_warning() {
+    #[cfg(feature = "tracing")]
+    trace::warning_event(&warning);
+    diagnostic::DiagnosticDelivery::new(input.diagnostic_sink.as_deref()).deliver_log(&warning);
+    output.warnings.insert(0, warning);
+  }
+  output
+}
+
+/// Compiles a GLSL shader to SPIR-V for `target_env`, with [`DEFAULT_RESOURCE_LIMITS`].
+///
+/// An interior NUL byte in `source` is reported as [`CompileError::InvalidInput`].
+/// Use [`compile_input`] to also get the warnings.
+pub fn compile_glsl_to_spirv(source: &str, stage: ShaderStage, target_env: &TargetEnv) -> Result<Spirv, CompileError> {
+  compile_glsl_to_spirv_with_options(source, stage, target_env, CompileOptionFlags::empty(), ShaderOptions::empty())
+}
+
+/// The bases of auto-mapped bindings and locations, see [`CompileInput::auto_bind_uniforms`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub(crate) struct AutoMapBases {
+  /// The base of every descriptor set.
+  pub binding: Option<u32>,
+  /// (set, base), overriding `binding`.
+  pub set_bindings: Vec<(u32, u32)>,
+  pub uniform_location: Option<u32>,
+}
+impl AutoMapBases {
+  /// # Safety
+  /// - `shader` MUST be a valid shader that has not been parsed yet.
+  pub(crate) unsafe fn apply(&self, shader: *mut glslang_shader_t) {
+    // glslang's base is the shift of each resource class.
+    if let Some(binding) = self.binding {
+      BindingShifts::all(binding).apply(shader);
+    }
+    for &(set, base) in &self.set_bindings {
+      glslang_sys_shader_set_binding_base_for_set(shader, base, set);
+    }
+    if let Some(uniform_location) = self.uniform_location {
+      glslang_sys_shader_set_uniform_location_base(shader, uniform_location as c_int);
+    }
+  }
+}
+
+/// Same as [`compile_glsl_to_spirv`], with `option_flags` and `shader_options`.
+pub fn compile_glsl_to_spirv_with_options(
+  source: &str,
+  stage: ShaderStage,
+  target_env: &TargetEnv,
+  option_flags: CompileOptionFlags,
+  shader_options: ShaderOptions,
+) -> Result<Spirv, CompileError> {
+  let input = CompileInput::new()
+    .source(source)
+    .stage(stage)
+    .target_env(*target_env)
+    .shader_options(shader_options);
+  compile_input(&input, option_flags).map(|output| output.spirv)
+}
+
+/// Compiles the HLSL function `entry_point` of `source`, e.g. `PSMain`, to SPIR-V with the same entry point name.
+///
+/// Fails with [`CompileError::HlslSupportDisabled`] without the `hlsl` feature.
+pub fn compile_hlsl_to_spirv(source: &str, stage: ShaderStage, entry_point: &str, target_env: &TargetEnv) -> Result<Spirv, CompileError> {
+  let input = CompileInput::new()
+    .source(source)
+    .language(SourceLanguage::Hlsl)
+    .stage(stage)
+    .target_env(*target_env)
+    .entry_point(entry_point);
+  compile_input(&input, CompileOptionFlags::empty()).map(|output| output.spirv)
+}
+
+/// Compiles the file at `path`, see [`CompileInput::from_file`]. Use that to override e.g. the stage or the include handler.
+pub fn compile_file(path: impl AsRef<Path>, option_flags: CompileOptionFlags) -> Result<CompileOutput, CompileError> {
+  compile_input(&CompileInput::from_file(path)?, option_flags)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+  use super::*;
+
+  fn fixture_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+  }
+
+  #[test]
+  fn compile_fixture_files() {
+    let path = fixture_path("triangle.vert");
+    let output = compile_file(&path, CompileOptionFlags::GenerateDebugInfo).unwrap();
+    // The path is recorded for `OpSource` and `OpLine`.
+    let path_bytes = path.to_string_lossy().into_owned().into_bytes();
+    assert!(output.spirv.as_bytes().windows(path_bytes.len()).any(|bytes| bytes == path_bytes));
+
+    // `blur.comp` includes `include/kernel.glsl` relative to its own directory.
+    let spirv = compile_file(fixture_path("blur.comp"), CompileOptionFlags::empty()).unwrap().spirv;
+    assert_eq!(spirv[0], Spirv::MAGIC_NUMBER);
+    let input = CompileInput::from_file(fixture_path("blur.comp")).unwrap().include_handler(VirtualIncluder::new(HashMap::<String, String>::new()));
+    assert!(matches!(compile_input(&input, CompileOptionFlags::empty()), Err(CompileError::Preprocess(_))));
+  }
+
+  #[test]
+  fn compile_file_errors() {
+    match compile_file(fixture_path("missing.frag"), CompileOptionFlags::empty()) {
+      Err(CompileError::ReadSource { path, source }) => {
+        assert_eq!(path, fixture_path("missing.frag"));
+        assert_eq!(source.kind(), std::io::ErrorKind::NotFound);
+      },
+      result => panic!("Unexpected result: {:?}", result),
+    }
+
+    let path = fixture_path("include/kernel.glsl");
+    let error = compile_file(&path, CompileOptionFlags::empty()).unwrap_err();
+    assert!(matches!(&error, CompileError::UnknownStage(unknown) if *unknown == path));
+    assert!(error.to_string().contains(".vert, .tesc"), "{}", error);
+    // Naming the stage clears the error; the kernel has no `main`, though.
+    let input = CompileInput::from_file(&path).unwrap().stage(ShaderStage::Compute);
+    assert!(input.as_raw().is_ok());
+
+    let input = CompileInput::new().source_file_name("shader\0.frag");
+    assert!(matches!(input.as_raw(), Err(CompileError::InvalidInput { input: InputKind::SourceFileName, offset: 6 })));
+  }
+
+  #[test]
+  fn compile_without_cstring() {
+    let input = CompileInput::new()
+      .source(
+        r##"
+        #version 450
+        layout(location = 0) out vec4 out_color;
+        void main() {
+          out_color = vec4(1.0);
+        }
+        "##,
+      )
+      .stage(ShaderStage::Fragment)
+      .client(Client::Vulkan, glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_1)
+      .target_spirv(glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_3)
+      .resource(&DEFAULT_RESOURCE_LIMITS);
+
+    let spirv = compile_input(&input, CompileOptionFlags::empty()).unwrap().spirv;
+    assert_eq!(spirv[0], 0x07230203);
+  }
+
+  #[cfg(feature = "hlsl")]
+  #[test]
+  fn compile_hlsl_entry_point() {
+    let source = "float4 PSMain(float4 color : COLOR) : SV_Target { return color; }\n";
+    let spirv = compile_hlsl_to_spirv(source, ShaderStage::Fragment, "PSMain", &TargetEnv::vulkan_1_1()).unwrap();
+    assert_eq!(crate::tests::entry_point_names(&spirv), ["PSMain"]);
+
+    // There is no `main` to fall back to.
+    let input = CompileInput::new().source(source).language(SourceLanguage::Hlsl).stage(ShaderStage::Fragment);
+    assert!(compile_input(&input, CompileOptionFlags::empty()).is_err());
+    let spirv = compile_input(&input.entry_point("PSMain"), CompileOptionFlags::empty()).unwrap().spirv;
+    assert_eq!(crate::tests::entry_point_names(&spirv), ["PSMain"]);
+  }
+
+  #[cfg(feature = "hlsl")]
+  #[test]
+  fn hlsl_source_entry_point() {
+    let source = "float4 VSMain(float4 position : POSITION) : SV_Position { return position; }\nfloat4 PSMain() : SV_Target { return float4(1.0, 0.0, 0.0, 1.0); }\n";
+    let input = CompileInput::new().source(source).language(SourceLanguage::Hlsl).stage(ShaderStage::Fragment).source_entry_point("PSMain");
+    let spirv = compile_input(&input, CompileOptionFlags::empty()).unwrap().spirv;
+    assert_eq!(crate::tests::entry_point_names(&spirv), ["main"]);
+
+    let input = input.entry_point("ps_main");
+    let spirv = compile_input(&input, CompileOptionFlags::empty()).unwrap().spirv;
+    assert_eq!(crate::tests::entry_point_names(&spirv), ["ps_main"]);
+
+    // Overrides the `HlslOptions`.
+    let input = input.hlsl_options(HlslOptions { source_entry_point: Some("VSMain".to_owned()), entry_point: Some("vs_main".to_owned()), ..Default::default() });
+    let spirv = compile_input(&input, CompileOptionFlags::empty()).unwrap().spirv;
+    assert_eq!(crate::tests::entry_point_names(&spirv), ["ps_main"]);
+  }
+
+  #[test]
+  fn auto_bind_uniforms_from_base() {
+    let source = r##"
+      #version 450
+      layout(set = 0) uniform sampler2D albedo;
+      layout(set = 0) uniform Params { vec4 tint; };
+      layout(set = 1) uniform sampler2D shadow_map;
+      layout(location = 0) out vec4 out_color;
+      void main() {
+        out_color = texture(albedo, vec2(0.5)) * tint + texture(shadow_map, vec2(0.5));
+      }
+    "##;
+    let input = CompileInput::new().source(source).stage(ShaderStage::Fragment);
+    let sets_and_bindings = |input: &CompileInput| crate::tests::sets_and_bindings(&compile_input(input, CompileOptionFlags::empty()).unwrap().spirv);
+
+    let input = input.auto_bind_uniforms(10);
+    assert!(input.shader_settings().shader_options.contains(ShaderOptions::AutoMapBindings));
+    assert_eq!(sets_and_bindings(&input), [(0, 10), (0, 11), (1, 10)]);
+
+    let input = input.auto_bind_uniforms_in_set(1, 20);
+    assert_eq!(sets_and_bindings(&input), [(0, 10), (0, 11), (1, 20)]);
+    // `link_program` maps IO for them as well.
+    let outputs = link_program(&[&input], CompileOptionFlags::empty()).unwrap();
+    assert_eq!(crate::tests::sets_and_bindings(&outputs[&ShaderStage::Fragment].spirv), [(0, 10), (0, 11), (1, 20)]);
+  }
+
+  #[test]
+  fn glsl_entry_point() {
+    let input = CompileInput::new().source("#version 450\nlayout(location = 0) out vec4 color;\nvoid main() { color = vec4(1.0); }\n").stage(ShaderStage::Fragment);
+    let spirv = compile_input(&input, CompileOptionFlags::empty()).unwrap().spirv;
+    assert_eq!(crate::tests::entry_point_names(&spirv), ["main"]);
+
+    let renamed = input.entry_point("fs_main");
+    let spirv = compile_input(&renamed, CompileOptionFlags::empty()).unwrap().spirv;
+    assert_eq!(crate::tests::entry_point_names(&spirv), ["fs_main"]);
+
+    // The shader also links as part of a program.
+    let outputs = link_program(&[&renamed], CompileOptionFlags::empty()).unwrap();
+    assert_eq!(crate::tests::entry_point_names(&outputs[&ShaderStage::Fragment].spirv), ["fs_main"]);
+
+    // GLSL entry points can only be renamed from `main`.
+    let spirv = compile_input(&renamed.source_entry_point("main"), CompileOptionFlags::empty()).unwrap().spirv;
+    assert_eq!(crate::tests::entry_point_names(&spirv), ["fs_main"]);
+    let input = CompileInput::new().source("#version 450\nvoid other() {}\nvoid main() {}\n").stage(ShaderStage::Fragment).source_entry_point("other");
+    assert!(compile_input(&input, CompileOptionFlags::empty()).is_err());
+
+    assert!(matches!(
+      CompileInput::new().source_entry_point("ma\0in").as_raw(),
+      Err(CompileError::InvalidInput { input: InputKind::EntryPoint, offset: 2 }),
+    ));
+  }
+
+  #[test]
+  fn pointers_survive_moves() {
+    let input = CompileInput::new().source("void main() {}");
+    let moved = Box::new(input);
+
+    let raw = moved.as_raw().unwrap();
+    assert_eq!(unsafe { std::ffi::CStr::from_ptr(raw.code) }.to_str(), Ok("void main() {}"));
+    assert_eq!(unsafe { (*raw.resource).max_draw_buffers }, DEFAULT_RESOURCE_LIMITS.max_draw_buffers);
+  }
+
+  #[test]
+  fn nul_in_source() {
+    let input = CompileInput::new().source("void main() {}\0");
+    assert!(matches!(input.as_raw(), Err(CompileError::InvalidInput { input: InputKind::Source, offset: 14 })));
+    let error = compile_input(&input, CompileOptionFlags::empty()).unwrap_err();
+    assert_eq!(error.to_string(), "Shader source contains an interior NUL byte at byte offset 14");
+
+    let input = input.source("void main() {}");
+    assert!(input.as_raw().is_ok());
+  }
+
+  #[cfg(feature = "hlsl")]
+  #[test]
+  fn compile_hlsl_for_vulkan() {
+    let input = CompileInput::new()
+      .source("float4 main(float4 position : POSITION) : SV_Position { return position; }")
+      .language(SourceLanguage::Hlsl)
+      .stage(ShaderStage::Vertex)
+      .client(Client::Vulkan, glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_1);
+
+    let spirv = compile_input(&input, CompileOptionFlags::empty()).unwrap().spirv;
+    assert_eq!(spirv[0], 0x07230203);
+  }
+
+  #[test]
+  fn warnings_on_success() {
+    let input = CompileInput::new()
+      .source("#version 450\n#extension GL_EXT_no_such_extension : enable\nvoid main() {}\n")
+      .stage(ShaderStage::Compute);
+
+    let output = compile_input(&input, CompileOptionFlags::empty()).unwrap();
+    assert_eq!(output.spirv[0], 0x07230203);
+    assert_eq!(output.warnings.len(), 1, "{:?}", output.warnings);
+    assert!(output.warnings[0].starts_with("WARNING: 0:2:"), "{:?}", output.warnings);
+    assert!(output.warnings[0].contains("GL_EXT_no_such_extension"), "{:?}", output.warnings);
+  }
+
+  #[test]
+  fn defines() {
+    let source = "#version 450\nlayout(location = 0) out vec4 out_color;\nvoid main() {\n#ifdef USE_RED\n  out_color = vec4(RED, 0.0, 0.0, 1.0);\n#else\n  out_color = vec4(0.0);\n#endif\n}\n";
+    let input = CompileInput::new().source(source).stage(ShaderStage::Fragment);
+    let plain = compile_input(&input, CompileOptionFlags::empty()).unwrap().spirv;
+
+    // Defines come before the explicit preamble, so the preamble can use them.
+    let input = input
+      .add_define("USE_RED", None)
+      .add_define("RED_BASE", Some("0.25 +\n 0.5"))
+      .preamble("#define RED RED_BASE\n");
+    assert_eq!(input.preamble_c_str().unwrap().to_str(), Ok("#define USE_RED\n#define RED_BASE 0.25 +\\\n 0.5\n#define RED RED_BASE\n"));
+    let red = compile_input(&input, CompileOptionFlags::empty()).unwrap().spirv;
+    assert_ne!(plain, red);
+
+    let input = input.add_define("NOT A NAME", Some("1"));
+    assert!(matches!(input.as_raw(), Err(CompileError::InvalidDefineName(name)) if name == "NOT A NAME"));
+    let input = CompileInput::new().add_define("NUL\0", None);
+    assert!(matches!(input.as_raw(), Err(CompileError::InvalidDefineName(_))));
+  }
+
+  #[test]
+  fn compile_glsl_to_spirv_vertex() {
+    let source = "#version 450\nlayout(location = 0) out vec2 out_uv;\nvoid main() {\n  out_uv = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);\n  gl_Position = vec4(out_uv * 2.0 + -1.0, 0.0, 1.0);\n}\n";
+    let spirv = compile_glsl_to_spirv(source, ShaderStage::Vertex, &TargetEnv::vulkan_1_1()).unwrap();
+    assert_eq!(spirv[0], 0x07230203);
+
+    assert!(matches!(compile_glsl_to_spirv("#version 450\0", ShaderStage::Vertex, &TargetEnv::default()), Err(CompileError::InvalidInput { input: InputKind::Source, offset: 12 })));
+  }
+
+  #[test]
+  fn auto_map_bindings() {
+    let source = "#version 450
+layout(set = 0) uniform sampler2D tex;
+layout(set = 0) uniform Params { vec4 tint; };
+layout(location = 0) out vec4 out_color;
+void main() {
+  out_color = texture(tex, vec2(0.5)) * tint;
+}
+";
+
+    let spirv = compile_glsl_to_spirv_with_options(source, ShaderStage::Fragment, &TargetEnv::default(), CompileOptionFlags::empty(), ShaderOptions::AutoMapBindings).unwrap();
+    let mut bindings = crate::tests::binding_decorations(&spirv).into_iter().map(|(_, binding)| binding).collect::<Vec<_>>();
+    bindings.sort_unstable();
+    assert_eq!(bindings, vec![0, 1]);
+
+    // Without auto-mapping, glslang either rejects the resources or leaves them undecorated.
+    if let Ok(spirv) = compile_glsl_to_spirv(source, ShaderStage::Fragment, &TargetEnv::default()) {
+      assert_eq!(crate::tests::binding_decorations(&spirv), vec![]);
+    }
+  }
+
+  #[test]
+  fn override_glsl_version() {
+    // Compute shaders require GLSL 420 or later.
+    let input = CompileInput::new()
+      .source("#version 330\nlayout(local_size_x = 1) in;\nvoid main() {}\n")
+      .stage(ShaderStage::Compute);
+    assert!(matches!(compile_input(&input, CompileOptionFlags::empty()), Err(CompileError::Preprocess(_) | CompileError::Parse(_))));
+
+    let input = input.override_glsl_version(450);
+    assert_eq!(compile_input(&input, CompileOptionFlags::empty()).unwrap().spirv[0], 0x07230203);
+
+    let input = input.override_glsl_version(0);
+    assert!(compile_input(&input, CompileOptionFlags::empty()).is_err());
+  }
+
+  #[test]
+  fn compile_into_reuses_buffer() {
+    let source = |scale: &str| format!("#version 450\nlayout(location = 0) out vec4 out_color;\nvoid main() {{\n  out_color = vec4({});\n}}\n", scale);
+    let mut spirv = Vec::new();
+
+    let input = CompileInput::new().source(&source("0.5")).stage(ShaderStage::Fragment);
+    let output = compile_input_into(&input, CompileOptionFlags::empty(), &mut spirv).unwrap();
+    assert!(output.spirv.is_empty());
+    assert_eq!(spirv, compile_input(&input, CompileOptionFlags::empty()).unwrap().spirv.words());
+    let (ptr, capacity) = (spirv.as_ptr(), spirv.capacity());
+
+    // Shaders differing only in a constant produce the same amount of SPIR-V.
+    for scale in ["0.25", "0.75", "2.0", "4.0"] {
+      let input = CompileInput::new().source(&source(scale)).stage(ShaderStage::Fragment);
+      compile_input_into(&input, CompileOptionFlags::StripDebugInfo, &mut spirv).unwrap();
+      assert_eq!(spirv[0], 0x07230203);
+      assert_eq!((spirv.as_ptr(), spirv.capacity()), (ptr, capacity));
+    }
+
+    let input = CompileInput::new().source("#version 450\nvoid main() { undefined(); }\n").stage(ShaderStage::Fragment);
+    assert!(compile_input_into(&input, CompileOptionFlags::empty(), &mut spirv).is_err());
+    assert!(spirv.is_empty());
+  }
+
+  #[test]
+  fn invalid_target_env() {
+    let input = CompileInput::new()
+      .source("#version 450\nvoid main() {}\n")
+      .stage(ShaderStage::Compute)
+      .target_env(TargetEnv::vulkan_1_1().with_spirv_version(glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_6));
+    assert!(matches!(compile_input(&input, CompileOptionFlags::empty()), Err(CompileError::InvalidTargetEnv(TargetEnvError::SpirvVersionTooNew { .. }))));
+
+    let input = input.target_env(TargetEnv::vulkan_1_3());
+    let raw = input.as_raw().unwrap();
+    assert_eq!(raw.client_version, glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_3);
+    assert_eq!(raw.target_language_version, glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_6);
+  }
+
+  #[test]
+  fn message_bits_follow_language_and_client() {
+    let input = CompileInput::new().language(SourceLanguage::Hlsl).client(Client::OpenGL, glslang_target_client_version_t_GLSLANG_TARGET_OPENGL_450);
+    let messages = Messages::from(input.as_raw().unwrap().messages);
+    assert!(messages.contains(Messages::SpvRules | Messages::ReadHlsl));
+    assert!(!messages.contains(Messages::VulkanRules));
+
+    let input = input.messages(Messages::SpvRules | Messages::KeepUncalled).language(SourceLanguage::Glsl);
+    assert_eq!(Messages::from(input.as_raw().unwrap().messages), Messages::SpvRules | Messages::KeepUncalled);
+  }
+
+  #[test]
+  fn default_version_without_version_directive() {
+    // Doubles are desktop-only.
+    let source = "void main() {\n  double x = 1.0;\n  gl_Position = vec4(float(x));\n}\n";
+    let input = CompileInput::new().source(source).stage(ShaderStage::Vertex);
+
+    let input = input.default_version(DefaultVersion::desktop_450_core());
+    assert!(compile_input(&input, CompileOptionFlags::empty()).is_ok());
+    let input = input.default_version(DefaultVersion::es_310());
+    assert!(matches!(compile_input(&input, CompileOptionFlags::empty()), Err(CompileError::Parse(_))));
+
+    // Without `force`, the directive wins.
+    let input = input.source(&format!("#version 450\n{}", source));
+    assert!(compile_input(&input, CompileOptionFlags::empty()).is_ok());
+    let input = input.default_version(DefaultVersion::es_310().forced());
+    assert!(matches!(compile_input(&input, CompileOptionFlags::empty()), Err(CompileError::Parse(_))));
+
+    let input = input.default_version(DefaultVersion::new(450, Profile::Es));
+    assert!(matches!(input.as_raw(), Err(CompileError::InvalidDefaultVersion(DefaultVersionError::ProfileMismatch { version: 450, profile: Profile::Es }))));
+  }
+
+  #[test]
+  fn relaxed_errors_and_suppressed_warnings() {
+    let input = CompileInput::new().source("#version 450\n#define defined 1\nvoid main() {}\n").stage(ShaderStage::Compute);
+    assert!(compile_input(&input, CompileOptionFlags::empty()).is_err());
+    let input = input.relaxed_errors(true);
+    assert_eq!(Messages::from(input.as_raw().unwrap().messages), Messages::DEFAULT_VULKAN | Messages::RelaxedErrors);
+    let output = compile_input(&input, CompileOptionFlags::empty()).unwrap();
+    assert!(output.warnings.iter().any(|warning| warning.contains("defined")), "{:?}", output.warnings);
+
+    let output = compile_input(&input.suppress_warnings(true), CompileOptionFlags::empty()).unwrap();
+    assert_eq!(output.warnings, Vec::<String>::new());
+  }
+
+  #[test]
+  fn mesh_and_task_shaders() {
+    let task = r##"
+      #version 450
+      #extension GL_NV_mesh_shader : require
+      layout(local_size_x = 1) in;
+      void main() {
+        gl_TaskCountNV = 1;
+      }
+    "##;
+    let mesh = r##"
+      #version 450
+      #extension GL_NV_mesh_shader : require
+      layout(local_size_x = 1) in;
+      layout(triangles, max_vertices = 3, max_primitives = 1) out;
+      void main() {
+        gl_MeshVerticesNV[0].gl_Position = vec4(-1.0, -1.0, 0.0, 1.0);
+        gl_MeshVerticesNV[1].gl_Position = vec4(3.0, -1.0, 0.0, 1.0);
+        gl_MeshVerticesNV[2].gl_Position = vec4(-1.0, 3.0, 0.0, 1.0);
+        gl_PrimitiveIndicesNV[0] = 0;
+        gl_PrimitiveIndicesNV[1] = 1;
+        gl_PrimitiveIndicesNV[2] = 2;
+        gl_PrimitiveCountNV = 1;
+      }
+    "##;
+
+    const OP_CAPABILITY: u32 = 17;
+    const CAPABILITY_MESH_SHADING_NV: u32 = 5266;
+    // Unlike `GL_EXT_mesh_shader`, the NV extension does not need SPIR-V 1.4.
+    for (source, stage) in [(task, ShaderStage::Task), (mesh, ShaderStage::Mesh)] {
+      let spirv = compile_glsl_to_spirv(source, stage, &TargetEnv::vulkan_1_1()).unwrap();
+      assert!(spirv.windows(2).any(|words| words == [2 << 16 | OP_CAPABILITY, CAPABILITY_MESH_SHADING_NV]));
+    }
+  }
+}
@@ -16,12 +16,127 @@ impl Default for glslang_spv_options_t {
   }
 }
 
-use std::ffi::CStr;
-use std::os::raw::c_char;
+use std::ffi::{CStr, NulError};
+use std::os::raw::{c_char, c_int};
+use std::path::PathBuf;
+use std::sync::Arc;
 
 use thiserror::Error;
 use bitflags::bitflags;
 
+mod pool;
+mod batch;
+mod cache;
+mod disk_cache;
+mod process;
+mod options;
+mod resource_limits;
+mod hlsl;
+mod input;
+mod types;
+mod target_env;
+mod default_version;
+mod shader;
+mod program;
+mod reflection;
+mod spv_options;
+mod spirv;
+mod preamble;
+mod include;
+mod includers;
+mod messages;
+mod diagnostic;
+#[cfg(feature = "remapper")]
+mod remap;
+#[cfg(feature = "spirv-tools")]
+mod spirv_tools;
+#[cfg(feature = "tracing")]
+mod trace;
+#[cfg(feature = "serde")]
+mod serde_impls;
+#[cfg(feature = "ash")]
+pub mod ash_interop;
+#[cfg(feature = "naga")]
+pub mod naga_interop;
+#[cfg(feature = "rspirv")]
+pub mod rspirv_interop;
+// Cargo does not run tests of build scripts, so the build script's path helpers are tested along with the library.
+#[cfg(test)]
+mod build_paths;
+
+pub use pool::CompilerPool;
+pub use batch::{CompileJob, compile_batch, compile_batch_with_defaults};
+pub use cache::CompileCache;
+pub use disk_cache::DiskCache;
+pub use process::GlslangProcess;
+pub use options::{CompileInputSpec, CompileOptions, ArgParseError};
+pub use resource_limits::{ResourceLimits, LimitConstraint, LimitViolation, ResourceConfigError, default_resource_string};
+pub use hlsl::{HlslOptions, BindingShifts, ResourceSetBinding};
+pub use input::{CompileInput, compile_input, compile_input_into, compile_glsl_to_spirv, compile_glsl_to_spirv_with_options, compile_hlsl_to_spirv, compile_file};
+pub use types::{ShaderStage, SourceLanguage, Client, Profile, UnknownEnumValue};
+pub use target_env::{TargetEnv, TargetEnvError};
+pub use default_version::{DefaultVersion, DefaultVersionError};
+pub use shader::{Shader, preprocess};
+pub use program::{Program, link_program};
+pub use reflection::{Reflection, ReflectedUniform, ReflectedUniformBlock, DescriptorBinding, DescriptorType, StageInput, ScalarType, SpecConstant, StageReflection};
+pub use spv_options::{SpvOptions, DebugInfo};
+pub use spirv::Spirv;
+#[cfg(feature = "remapper")]
+pub use remap::RemapOptions;
+#[cfg(feature = "spirv-tools")]
+pub use spirv_tools::{disassemble_spirv, DisassembleOptions, validate_spirv, optimize, optimize_with_options, OptimizationLevel, OptimizeOptions, OptimizeError};
+pub use messages::Messages;
+pub use diagnostic::{Diagnostic, DiagnosticSource, Severity, SourceLocation, DiagnosticSources, DiagnosticSink, parse_diagnostics, render_diagnostics};
+pub use preamble::Preamble;
+pub use include::{IncludeHandler, ResolvedInclude, IncludeRecord, OwnedIncludeResult, DEFAULT_MAX_INCLUDE_DEPTH};
+pub use includers::{FileIncluder, VirtualIncluder, VirtualFiles};
+
+/// The glslang commit the linked binaries are built from. With `build-from-source`, the build fails if git cannot tell
+/// the commit of the clone.
+pub const GLSLANG_COMMIT: &str = env!("GLSLANG_SYS_GLSLANG_COMMIT");
+
+/// The version of the linked glslang, see [`glslang_version`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GlslangVersion {
+  pub major: u32,
+  pub minor: u32,
+  pub patch: u32,
+  /// E.g. `dev` for builds between releases. Empty for releases.
+  pub flavor: String,
+  /// [`GLSLANG_COMMIT`].
+  pub commit: &'static str,
+}
+impl std::fmt::Display for GlslangVersion {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+    if !self.flavor.is_empty() {
+      write!(f, "-{}", self.flavor)?;
+    }
+    write!(f, " ({})", self.commit)
+  }
+}
+
+/// The version of the linked glslang (`glslang::GetVersion`) and the commit it is built from, e.g. for bug reports about
+/// differences between builds.
+///
+/// The version of the headers the crate is built against is [`GLSLANG_VERSION_MAJOR`], [`GLSLANG_VERSION_MINOR`],
+/// [`GLSLANG_VERSION_PATCH`] and [`GLSLANG_VERSION_FLAVOR`] (`glslang/build_info.h`), e.g. for gating features at compile
+/// time.
+#[must_use]
+pub fn glslang_version() -> GlslangVersion {
+  let (mut major, mut minor, mut patch, mut flavor): (c_int, c_int, c_int, *const c_char) = (0, 0, 0, std::ptr::null());
+  unsafe {
+    glslang_sys_get_version(&mut major, &mut minor, &mut patch, &mut flavor);
+    GlslangVersion {
+      major: major as u32,
+      minor: minor as u32,
+      patch: patch as u32,
+      flavor: log_text(flavor).into_owned(),
+      commit: GLSLANG_COMMIT,
+    }
+  }
+}
+
 #[derive(Debug, Clone, Error)]
 pub struct GlslangErrorLog {
   pub context: String,
@@ -45,81 +160,579 @@ impl GlslangErrorLog {
     Self::new(context, glslang_program_get_info_log(program), glslang_program_get_info_debug_log(program))
   }
 
+  /// The messages of `info_log`, see [`parse_diagnostics`].
+  #[must_use]
+  pub fn diagnostics(&self) -> Vec<Diagnostic> {
+    parse_diagnostics(&self.info_log)
+  }
+
+  /// Invalid UTF-8, e.g. echoed from the source, is replaced with `U+FFFD`. Null logs are empty.
+  ///
   /// # Safety
-  /// - `info_log` and `debug_log` MUST point to a valid, null-terminated C string.
+  /// - `info_log` and `debug_log` MUST be null or point to a valid, null-terminated C string.
   unsafe fn new(context: String, info_log: *const c_char, debug_log: *const c_char) -> Self {
-    let info_log = CStr::from_ptr(info_log);
-    let debug_log = CStr::from_ptr(debug_log);
+    let to_string = |log: *const c_char| if log.is_null() { String::new() } else { CStr::from_ptr(log).to_string_lossy().into_owned() };
     GlslangErrorLog {
       context,
-      info_log: info_log.to_str().unwrap().to_owned(),
-      debug_log: debug_log.to_str().unwrap().to_owned(),
+      info_log: to_string(info_log),
+      debug_log: to_string(debug_log),
     }
   }
 }
 
+/// The result of a successful compile.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CompileOutput {
+  pub spirv: Spirv,
+  /// Non-empty lines of the shader and program info logs, e.g. `WARNING: 0:2: ...`, after the resource limit violations
+  /// that did not fail the compile (see [`ResourceLimits::validate`]). [`compile_input`] adds
+  /// [`TargetEnv::spirv_version_warning`] first.
+  pub warnings: Vec<String>,
+  /// Non-empty lines of the SPIR-V generator's messages, e.g. `warning: ...` or `TBD functionality: ...`.
+  pub spirv_messages: Vec<String>,
+  /// The SPIR-V in textual form, if requested with [`SpvOptions::disassemble`].
+  pub disassembly: Option<String>,
+  /// The `#include`s resolved by the [`CompileInput::include_handler`], in the order glslang requested them.
+  pub includes: Vec<IncludeRecord>,
+}
+
+/// Invalid UTF-8 is replaced with `U+FFFD`. Null logs are empty.
+///
+/// # Safety
+/// - `log` MUST be null or point to a valid, null-terminated C string.
+unsafe fn log_text<'a>(log: *const c_char) -> std::borrow::Cow<'a, str> {
+  if log.is_null() {
+    return std::borrow::Cow::Borrowed("");
+  }
+  CStr::from_ptr(log).to_string_lossy()
+}
+
+/// # Safety
+/// - `log` MUST be null or point to a valid, null-terminated C string.
+unsafe fn push_log_lines(lines: &mut Vec<String>, log: *const c_char) {
+  if log.is_null() {
+    return;
+  }
+  let log = CStr::from_ptr(log).to_string_lossy();
+  lines.extend(log.lines().map(str::trim_end).filter(|line| !line.is_empty()).map(str::to_owned));
+}
+
+#[derive(Debug, Clone, Error)]
+#[non_exhaustive]
+pub enum CompileError {
+  #[error("Preprocessing failed")]
+  Preprocess(#[source] GlslangErrorLog),
+  #[error("Parsing failed")]
+  Parse(#[source] GlslangErrorLog),
+  #[error("Linking failed")]
+  Link(#[source] GlslangErrorLog),
+  /// Assigning bindings or locations failed, see [`Program::map_io`].
+  #[error("IO mapping failed")]
+  MapIo(#[source] GlslangErrorLog),
+  /// Contains the messages of the SPIR-V generator, after `no SPIR-V was generated` if the output is empty.
+  #[error("SPIR-V generation failed: {0}")]
+  SpirvGeneration(String),
+  /// [`Program::generate_spirv`] was called for a stage none of the program's shaders has.
+  #[error("The program has no {0:?} shader")]
+  StageNotInProgram(ShaderStage),
+  /// The generated SPIR-V failed validation (see [`SpvOptions::validate`]). Contains the validator's messages.
+  #[error("SPIR-V validation failed: {0}")]
+  Validation(String),
+  /// An internal error: the module glslang returned does not start with the magic number, the requested SPIR-V version and a
+  /// nonzero bound. Contains what is wrong.
+  #[error("glslang returned invalid SPIR-V: {0}")]
+  InvalidSpirvHeader(String),
+  /// naga rejected the SPIR-V, see [`SpvOptions::validate_with_naga`].
+  #[cfg(feature = "naga")]
+  #[error(transparent)]
+  Naga(#[from] naga_interop::NagaError),
+  /// rspirv failed to parse the SPIR-V, see [`Spirv::to_rspirv`]. Contains rspirv's error message.
+  #[cfg(feature = "rspirv")]
+  #[error("rspirv failed to parse the SPIR-V: {0}")]
+  RspirvParse(String),
+  /// The input requests HLSL, but this crate was built without the `hlsl` feature.
+  #[error("HLSL support is disabled; enable the `hlsl` feature of glslang-sys")]
+  HlslSupportDisabled,
+  /// An input glslang takes as a C string contains a NUL byte, at `offset` bytes.
+  #[error("{input} contains an interior NUL byte at byte offset {offset}")]
+  InvalidInput { input: InputKind, offset: usize },
+  /// See [`CompileInput::from_file`].
+  #[error("Failed to read shader source {}", .path.display())]
+  ReadSource { path: PathBuf, source: Arc<std::io::Error> },
+  /// The stage of a file could not be inferred from its extension, see [`CompileInput::from_file`].
+  #[error("Cannot infer the shader stage of {}: use one of the extensions {}, optionally followed by .glsl or .hlsl, or set the stage explicitly", .0.display(), ShaderStage::supported_extensions())]
+  UnknownStage(PathBuf),
+  #[error("Invalid macro name {0:?}: must be non-empty and contain no whitespace or NUL bytes")]
+  InvalidDefineName(String),
+  /// See [`ResourceSetBinding::validate`].
+  #[error("Invalid resource set binding: {0}")]
+  InvalidResourceSetBinding(String),
+  /// `chain` lists the resolved names of the active includes, outermost first, ending with the repeated one.
+  #[error("Include cycle: {}", .chain.join(" -> "))]
+  IncludeCycle { chain: Vec<String> },
+  /// `chain` lists the resolved names of the active includes, outermost first, ending with the requested header name.
+  #[error("Maximum include depth {max_depth} exceeded: {}", .chain.join(" -> "))]
+  IncludeDepthExceeded { max_depth: usize, chain: Vec<String> },
+  /// See [`ResourceLimits::validate`]. Contains all violations, including warnings.
+  #[error("Invalid resource limits: {}", .0.iter().filter(|violation| violation.is_error()).map(|violation| violation.to_string()).collect::<Vec<_>>().join(", "))]
+  InvalidResourceLimits(Vec<LimitViolation>),
+  #[error(transparent)]
+  InvalidTargetEnv(#[from] TargetEnvError),
+  #[error(transparent)]
+  InvalidDefaultVersion(#[from] DefaultVersionError),
+}
+
+impl CompileError {
+  /// [`CompileError::InvalidInput`] for a failed conversion of `input` to a C string.
+  pub(crate) fn nul_in(input: InputKind, error: &NulError) -> Self {
+    CompileError::InvalidInput { input, offset: error.nul_position() }
+  }
+}
+
+/// The input of [`CompileError::InvalidInput`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputKind {
+  Source,
+  Preamble,
+  /// See [`CompileInput::source_file_name`].
+  SourceFileName,
+  /// The entry point or source entry point name, see [`HlslOptions`].
+  EntryPoint,
+  /// The resolved name of an include, see [`ResolvedInclude::resolved_name`].
+  IncludeName,
+  /// The content of an include, see [`ResolvedInclude::content`].
+  IncludeContent,
+}
+impl std::fmt::Display for InputKind {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(match self {
+      InputKind::Source => "Shader source",
+      InputKind::Preamble => "Preamble",
+      InputKind::SourceFileName => "Source file name",
+      InputKind::EntryPoint => "Entry point name",
+      InputKind::IncludeName => "Include name",
+      InputKind::IncludeContent => "Include content",
+    })
+  }
+}
+
 bitflags! {
   pub struct CompileOptionFlags: u32 {
     const GenerateDebugInfo = 0b0001;
     /// Implies `GenerateDebugInfo`.
     const AddOpSource = 0b0010;
+    /// Do not fail the compile on resource limit errors (see [`ResourceLimits::validate`]).
+    const ResourceLimitErrorsAsWarnings = 0b0100;
+    /// More readable diagnostics, e.g. GLSL type names and column numbers (`GLSLANG_MSG_ENHANCED`).
+    const EnhancedMessages = 0b1000;
+    /// Removes debug instructions, e.g. `OpName` and `OpSource`, from the generated SPIR-V (`strip_debug_info`).
+    /// Takes precedence over `GenerateDebugInfo` and `AddOpSource`.
+    const StripDebugInfo = 0b1_0000;
+  }
+}
+
+bitflags! {
+  /// Options of the glslang shader object (`glslang_shader_options_t`), see [`glslang_shader_set_options`].
+  // The casts are needed where `glslang_shader_options_t` (being an enum) is `i32`.
+  pub struct ShaderOptions: u32 {
+    /// Assigns bindings to uniforms and buffers without `layout(binding = ...)` when linking.
+    #[allow(clippy::unnecessary_cast)]
+    const AutoMapBindings = glslang_shader_options_t_GLSLANG_SHADER_AUTO_MAP_BINDINGS as u32;
+    /// Assigns locations to inputs and outputs without `layout(location = ...)` when linking.
+    #[allow(clippy::unnecessary_cast)]
+    const AutoMapLocations = glslang_shader_options_t_GLSLANG_SHADER_AUTO_MAP_LOCATIONS as u32;
+    /// Accepts OpenGL-style GLSL when targeting Vulkan, e.g. uniforms outside of blocks.
+    #[allow(clippy::unnecessary_cast)]
+    const VulkanRulesRelaxed = glslang_shader_options_t_GLSLANG_SHADER_VULKAN_RULES_RELAXED as u32;
   }
 }
 
 /// # Safety
 /// - It is the caller's responsibility to ensure the validity of `input`.
-pub unsafe fn compile(input: &glslang_input_t, option_flags: CompileOptionFlags) -> Result<Vec<u32>, GlslangErrorLog> {
-  let shader = glslang_shader_create(input);
+pub unsafe fn compile(input: &glslang_input_t, option_flags: CompileOptionFlags) -> Result<CompileOutput, CompileError> {
+  compile_with_hlsl_options(input, option_flags, &HlslOptions::default())
+}
+
+/// Same as [`compile`], applying `hlsl_options` if `input.language` is HLSL.
+///
+/// # Safety
+/// - It is the caller's responsibility to ensure the validity of `input`.
+pub unsafe fn compile_with_hlsl_options(input: &glslang_input_t, option_flags: CompileOptionFlags, hlsl_options: &HlslOptions) -> Result<CompileOutput, CompileError> {
+  compile_shader(input, option_flags, &ShaderSettings { hlsl_options: Some(hlsl_options), ..Default::default() })
+}
 
-  if glslang_shader_preprocess(shader, input) == 0 {
-    return Err(GlslangErrorLog::from_shader("glslang_shader_preprocess".to_string(), shader));
+/// Same as [`compile`], preprocessing `preamble` before the source.
+///
+/// # Safety
+/// - It is the caller's responsibility to ensure the validity of `input`.
+pub unsafe fn compile_with_preamble(input: &glslang_input_t, option_flags: CompileOptionFlags, preamble: &Preamble) -> Result<CompileOutput, CompileError> {
+  compile_shader(input, option_flags, &ShaderSettings { preamble: preamble.as_c_str()?, ..Default::default() })
+}
+
+/// Same as [`compile`], setting `shader_options` on the shader object.
+///
+/// # Safety
+/// - It is the caller's responsibility to ensure the validity of `input`.
+pub unsafe fn compile_with_shader_options(input: &glslang_input_t, option_flags: CompileOptionFlags, shader_options: ShaderOptions) -> Result<CompileOutput, CompileError> {
+  compile_shader(input, option_flags, &ShaderSettings { shader_options, ..Default::default() })
+}
+
+/// Same as [`compile`], generating SPIR-V with `spv_options`. [`CompileOptionFlags::GenerateDebugInfo`] and
+/// [`CompileOptionFlags::AddOpSource`] still enable `generate_debug_info`.
+///
+/// # Safety
+/// - It is the caller's responsibility to ensure the validity of `input`.
+pub unsafe fn compile_with_spv_options(input: &glslang_input_t, option_flags: CompileOptionFlags, spv_options: &SpvOptions) -> Result<CompileOutput, CompileError> {
+  compile_shader(input, option_flags, &ShaderSettings { spv_options: *spv_options, ..Default::default() })
+}
+
+/// Settings of a compile that are applied to the glslang shader object rather than passed in `glslang_input_t`.
+#[derive(Clone, Copy)]
+pub(crate) struct ShaderSettings<'a> {
+  /// Applied if the input is HLSL. `None` applies the defaults.
+  pub hlsl_options: Option<&'a HlslOptions>,
+  pub preamble: Option<&'a CStr>,
+  /// The name of the source in diagnostics, `OpSource` and `OpLine`, and the includer name of its `#include`s.
+  pub source_file_name: Option<&'a CStr>,
+  /// See [`CompileInput::entry_point`].
+  pub entry_point: Option<&'a CStr>,
+  /// See [`CompileInput::source_entry_point`].
+  pub source_entry_point: Option<&'a CStr>,
+  /// `None` resolves includes relative to the working directory, as `glslang_shader_preprocess` does.
+  pub include_handler: Option<&'a dyn IncludeHandler>,
+  pub max_include_depth: usize,
+  pub shader_options: ShaderOptions,
+  /// Applied before the [`HlslOptions`], whose binding shifts replace the bases.
+  pub auto_map_bases: Option<&'a input::AutoMapBases>,
+  /// Replaces the `#version` of the source, see [`CompileInput::override_glsl_version`].
+  pub glsl_version: Option<u32>,
+  pub spv_options: SpvOptions,
+  pub diagnostic_sink: Option<&'a dyn DiagnosticSink>,
+}
+impl Default for ShaderSettings<'_> {
+  fn default() -> Self {
+    ShaderSettings {
+      hlsl_options: None,
+      preamble: None,
+      source_file_name: None,
+      entry_point: None,
+      source_entry_point: None,
+      include_handler: None,
+      max_include_depth: DEFAULT_MAX_INCLUDE_DEPTH,
+      shader_options: ShaderOptions::empty(),
+      auto_map_bases: None,
+      glsl_version: None,
+      spv_options: SpvOptions::default(),
+      diagnostic_sink: None,
+    }
   }
-  if glslang_shader_parse(shader, input) == 0 {
-    return Err(GlslangErrorLog::from_shader("glslang_shader_parse".to_string(), shader));
+}
+
+impl ShaderSettings<'_> {
+  /// Applies the entry point names, after the [`HlslOptions`] of HLSL, which they override.
+  ///
+  /// # Safety
+  /// - `shader` MUST be a valid shader that has not been parsed yet.
+  pub(crate) unsafe fn apply_entry_point(&self, shader: *mut glslang_shader_t, language: glslang_source_t) {
+    const MAIN: &[u8] = b"main\0";
+    const EMPTY: &[u8] = b"\0";
+
+    // Both names are copied by glslang.
+    if language == glslang_source_t_GLSLANG_SOURCE_HLSL {
+      if let Some(entry_point) = self.entry_point {
+        glslang_sys_shader_set_entry_point(shader, entry_point.as_ptr());
+      }
+      // An empty source entry point compiles the function named after the entry point.
+      match (self.source_entry_point, self.entry_point) {
+        (Some(source_entry_point), _) => glslang_sys_shader_set_source_entry_point(shader, source_entry_point.as_ptr()),
+        (None, Some(_)) => glslang_sys_shader_set_source_entry_point(shader, EMPTY.as_ptr() as *const c_char),
+        (None, None) => {},
+      }
+    }
+    // glslang only renames the GLSL entry point if a source entry point is set, and names it `main` otherwise.
+    else if self.entry_point.is_some() || self.source_entry_point.is_some() {
+      let main = MAIN.as_ptr() as *const c_char;
+      glslang_sys_shader_set_entry_point(shader, self.entry_point.map_or(main, CStr::as_ptr));
+      glslang_sys_shader_set_source_entry_point(shader, self.source_entry_point.map_or(main, CStr::as_ptr));
+    }
   }
+}
 
-  let program = glslang_program_create();
-  glslang_program_add_shader(program, shader);
+/// Same as [`compile`], writing the SPIR-V to `spirv` instead of [`CompileOutput::spirv`], which is left empty.
+///
+/// `spirv` is cleared first, also on error, and its capacity is reused, e.g. when compiling many permutations.
+///
+/// # Safety
+/// - It is the caller's responsibility to ensure the validity of `input`.
+pub unsafe fn compile_into(input: &glslang_input_t, option_flags: CompileOptionFlags, spirv: &mut Vec<u32>) -> Result<CompileOutput, CompileError> {
+  compile_shader_into(input, option_flags, &ShaderSettings::default(), spirv)
+}
+
+/// # Safety
+/// - It is the caller's responsibility to ensure the validity of `input`.
+pub(crate) unsafe fn compile_shader(input: &glslang_input_t, option_flags: CompileOptionFlags, settings: &ShaderSettings) -> Result<CompileOutput, CompileError> {
+  let mut spirv = Vec::new();
+  let output = compile_shader_into(input, option_flags, settings, &mut spirv)?;
+  Ok(CompileOutput { spirv: spirv.into(), ..output })
+}
 
-  // `glslang_program_link` takes `c_int` but `messages` (`glslang_messages_t` being an enum) can be `i32` or `u32` depending on build target.
-  #[allow(clippy::useless_conversion)]
-  if glslang_program_link(program, input.messages.try_into().unwrap()) == 0 {
-    return Err(GlslangErrorLog::from_program("glslang_program_link".to_string(), program));
+/// Same as [`compile_shader`], writing the SPIR-V to `spirv`, which is cleared first.
+///
+/// # Safety
+/// - It is the caller's responsibility to ensure the validity of `input`.
+pub(crate) unsafe fn compile_shader_into(
+  input: &glslang_input_t,
+  option_flags: CompileOptionFlags,
+  settings: &ShaderSettings,
+  spirv: &mut Vec<u32>,
+) -> Result<CompileOutput, CompileError> {
+  #[cfg(feature = "tracing")]
+  let span = trace::CompileSpan::enter(input.stage, settings.source_file_name);
+  let result = compile_shader_untraced(input, option_flags, settings, spirv);
+  #[cfg(feature = "tracing")]
+  span.finish(&result);
+  result
+}
+
+/// Same as [`compile_shader_into`], without the `tracing` span and events.
+///
+/// # Safety
+/// - It is the caller's responsibility to ensure the validity of `input`.
+unsafe fn compile_shader_untraced(
+  input: &glslang_input_t,
+  option_flags: CompileOptionFlags,
+  settings: &ShaderSettings,
+  spirv: &mut Vec<u32>,
+) -> Result<CompileOutput, CompileError> {
+  spirv.clear();
+  if !cfg!(feature = "hlsl") && input.language == glslang_source_t_GLSLANG_SOURCE_HLSL {
+    return Err(CompileError::HlslSupportDisabled);
   }
 
-  if option_flags.contains(CompileOptionFlags::AddOpSource) {
-    let code_c_str = CStr::from_ptr(input.code);
-    glslang_program_add_source_text(program, input.stage, code_c_str.as_ptr(), code_c_str.to_str().unwrap().len() as size_t);
+  // Violations that do not fail the compile are reported as its first warnings.
+  let mut limit_warnings = Vec::new();
+  if !input.resource.is_null() {
+    let violations = ResourceLimits(*input.resource).validate();
+    if violations.iter().any(LimitViolation::is_error) && !option_flags.contains(CompileOptionFlags::ResourceLimitErrorsAsWarnings) {
+      return Err(CompileError::InvalidResourceLimits(violations));
+    }
+    limit_warnings.extend(violations.iter().map(|violation| format!("WARNING: Resource limit {}", violation)));
+  }
 
-    // let filename_c_string = std::ffi::CString::new("test").unwrap();
-    // glslang_program_set_source_file(program, input.stage, filename_c_string.as_ptr());
+  let mut messages = Messages::from(input.messages);
+  if option_flags.contains(CompileOptionFlags::EnhancedMessages) {
+    messages |= Messages::Enhanced;
+  }
+  if input.language == glslang_source_t_GLSLANG_SOURCE_HLSL {
+    messages |= settings.hlsl_options.unwrap_or(&HlslOptions::default()).messages();
+  }
+  let extended_input;
+  let input = if messages != Messages::from(input.messages) {
+    extended_input = glslang_input_t {
+      messages: messages.into(),
+      ..*input
+    };
+    &extended_input
   }
+  else {
+    input
+  };
+
+  // glslang keeps a pointer to the names, which live until `shader` is deleted.
+  let source_names = settings.source_file_name.map(|source_file_name| [source_file_name.as_ptr()]);
 
-  let mut spv_options = glslang_spv_options_t {
-    generate_debug_info: option_flags.intersects(CompileOptionFlags::GenerateDebugInfo | CompileOptionFlags::AddOpSource),
-    validate: true,
-    ..Default::default()
+  let shader = glslang_shader_create(input);
+  if let Some(source_names) = &source_names {
+    glslang_sys_shader_set_source_name(shader, input, source_names.as_ptr());
+  }
+  if !settings.shader_options.is_empty() {
+    glslang_shader_set_options(shader, settings.shader_options.bits() as c_int);
+  }
+  if let Some(glsl_version) = settings.glsl_version {
+    glslang_shader_set_glsl_version(shader, glsl_version as c_int);
+  }
+  if let Some(preamble) = settings.preamble {
+    // `preamble` outlives `shader`.
+    glslang_sys_shader_set_preamble(shader, preamble.as_ptr());
+  }
+  if let Some(auto_map_bases) = settings.auto_map_bases {
+    auto_map_bases.apply(shader);
+  }
+  if input.language == glslang_source_t_GLSLANG_SOURCE_HLSL {
+    if let Err(error) = settings.hlsl_options.unwrap_or(&HlslOptions::default()).apply(shader) {
+      glslang_shader_delete(shader);
+      return Err(error);
+    }
+  }
+  settings.apply_entry_point(shader, input.language);
+
+  let mut delivery = diagnostic::DiagnosticDelivery::new(settings.diagnostic_sink);
+  for warning in &limit_warnings {
+    delivery.deliver_log(warning);
+  }
+  let mut includes = Vec::new();
+  let preprocessed = match settings.include_handler {
+    Some(include_handler) => match include::preprocess_with_include_handler(shader, input, include_handler, settings.max_include_depth, &mut includes) {
+      Ok(preprocessed) => preprocessed,
+      Err(error) => {
+        glslang_shader_delete(shader);
+        return Err(error);
+      },
+    },
+    None => glslang_shader_preprocess(shader, input) != 0,
   };
+  if !preprocessed {
+    let error_log = GlslangErrorLog::from_shader("glslang_shader_preprocess".to_string(), shader);
+    delivery.shader_log(&error_log.info_log);
+    glslang_shader_delete(shader);
+    return Err(CompileError::Preprocess(error_log));
+  }
+  if glslang_shader_parse(shader, input) == 0 {
+    let error_log = GlslangErrorLog::from_shader("glslang_shader_parse".to_string(), shader);
+    delivery.shader_log(&error_log.info_log);
+    glslang_shader_delete(shader);
+    return Err(CompileError::Parse(error_log));
+  }
+  delivery.shader_log(&log_text(glslang_shader_get_info_log(shader)));
 
-  glslang_program_SPIRV_generate_with_options(program, input.stage, &mut spv_options);
+  let program = glslang_program_create();
+  glslang_program_add_shader(program, shader);
 
-  if !glslang_program_SPIRV_get_messages(program).is_null() {
-    let messages_c_str = CStr::from_ptr(glslang_program_SPIRV_get_messages(program));
-    println!("{:?}", messages_c_str);
+  if glslang_program_link(program, Messages::from(input.messages).to_c_int()) == 0 {
+    let error_log = GlslangErrorLog::from_program("glslang_program_link".to_string(), program);
+    delivery.program_log(&error_log.info_log);
+    glslang_program_delete(program);
+    glslang_shader_delete(shader);
+    return Err(CompileError::Link(error_log));
+  }
+  delivery.program_log(&log_text(glslang_program_get_info_log(program)));
+  // Auto-mapping only marks the shader; bindings and locations are assigned by the IO mapper, which also shifts them.
+  let auto_map = settings.shader_options.intersects(ShaderOptions::AutoMapBindings | ShaderOptions::AutoMapLocations);
+  let hlsl_io_mapping = input.language == glslang_source_t_GLSLANG_SOURCE_HLSL && settings.hlsl_options.is_some_and(HlslOptions::needs_io_mapping);
+  if (auto_map || hlsl_io_mapping) && glslang_program_map_io(program) == 0 {
+    let error_log = GlslangErrorLog::from_program("glslang_program_map_io".to_string(), program);
+    delivery.program_log(&error_log.info_log);
+    glslang_program_delete(program);
+    glslang_shader_delete(shader);
+    return Err(CompileError::MapIo(error_log));
   }
 
-  let spirv_size = glslang_program_SPIRV_get_size(program) as usize;
-  let spirv_ptr = glslang_program_SPIRV_get_ptr(program) as *mut u32;
-  let spirv = std::slice::from_raw_parts(spirv_ptr, spirv_size).to_vec();
+  let mut source = program::StageSource::new(input.stage);
+  source.add(CStr::from_ptr(input.code), settings.source_file_name);
+  source.apply(program, option_flags);
+
+  let mut output = match generate_spirv(program, input.stage, input.target_language_version, option_flags, settings.spv_options, spirv) {
+    Ok(output) => {
+      delivery.spirv_messages(output.spirv_messages.iter().map(String::as_str));
+      output
+    },
+    Err(error) => {
+      match &error {
+        CompileError::Validation(errors) => {
+          for line in errors.lines() {
+            delivery.deliver(&Diagnostic { severity: Severity::Error, location: None, message: line.to_owned() });
+          }
+        },
+        CompileError::SpirvGeneration(messages) => delivery.spirv_messages(messages.lines()),
+        _ => {},
+      }
+      glslang_program_delete(program);
+      glslang_shader_delete(shader);
+      return Err(error);
+    },
+  };
+
+  output.warnings = limit_warnings;
+  push_log_lines(&mut output.warnings, glslang_shader_get_info_log(shader));
+  push_log_lines(&mut output.warnings, glslang_program_get_info_log(program));
+  output.includes = includes;
 
   glslang_program_delete(program);
   glslang_shader_delete(shader);
 
-  Ok(spirv)
+  Ok(output)
 }
 
+/// Generates SPIR-V for `stage` of the linked `program` into `spirv`, which is cleared first.
+/// The SPIR-V and warnings of the returned output are left empty.
+///
+/// Fails with [`CompileError::InvalidSpirvHeader`] if the module does not have the header of `target_language_version`.
+///
+/// # Safety
+/// - `program` MUST be linked and contain a shader of `stage`.
+pub(crate) unsafe fn generate_spirv(
+  program: *mut glslang_program_t,
+  stage: glslang_stage_t,
+  target_language_version: glslang_target_language_version_t,
+  option_flags: CompileOptionFlags,
+  spv_options: SpvOptions,
+  spirv: &mut Vec<u32>,
+) -> Result<CompileOutput, CompileError> {
+  spirv.clear();
+  let mut raw_spv_options = spv_options.to_raw(option_flags);
+  // glslang would print the disassembly to stdout, if built with SPIRV-Tools.
+  raw_spv_options.disassemble = false;
+
+  glslang_program_SPIRV_generate_with_options(program, stage, &mut raw_spv_options);
+
+  let mut spirv_messages = Vec::new();
+  push_log_lines(&mut spirv_messages, glslang_program_SPIRV_get_messages(program));
+
+  if raw_spv_options.validate {
+    if let Some(validation_errors) = spirv_validation_errors(&spirv_messages) {
+      return Err(CompileError::Validation(validation_errors));
+    }
+  }
+  let spirv_size = glslang_program_SPIRV_get_size(program) as usize;
+  if spirv_size == 0 {
+    // E.g. for a stage the program has no intermediate of; glslang does not always say why.
+    let mut message = "no SPIR-V was generated".to_string();
+    if !spirv_messages.is_empty() {
+      message.push('\n');
+      message.push_str(&spirv_messages.join("\n"));
+    }
+    return Err(CompileError::SpirvGeneration(message));
+  }
+  if has_spirv_generation_errors(&spirv_messages) {
+    return Err(CompileError::SpirvGeneration(spirv_messages.join("\n")));
+  }
+  let spirv_ptr = glslang_program_SPIRV_get_ptr(program);
+  spirv.extend_from_slice(std::slice::from_raw_parts(spirv_ptr, spirv_size));
+  spirv::check_header(spirv, target_language_version).map_err(CompileError::InvalidSpirvHeader)?;
+  if raw_spv_options.strip_debug_info {
+    spv_options::strip_debug_info(spirv);
+  }
+  #[cfg(feature = "naga")]
+  if spv_options.validate_with_naga {
+    naga_interop::validate(spirv, naga::valid::Capabilities::default())?;
+  }
+  let disassembly = spv_options.disassemble.then(|| spv_options::disassemble(spirv));
+  Ok(CompileOutput { spirv: Spirv::default(), warnings: Vec::new(), spirv_messages, disassembly, includes: Vec::new() })
+}
+
+/// Whether the messages of glslang's SPIR-V generator (`spv::SpvBuildLogger`) contain an error.
+fn has_spirv_generation_errors(messages: &[String]) -> bool {
+  messages.iter().any(|line| line.starts_with("error:"))
+}
+
+/// The messages of the SPIR-V validator, which glslang logs after this line (see `SpirvToolsValidate`).
+const SPIRV_VALIDATION_ERRORS_HEADER: &str = "error: SPIRV-Tools Validation Errors";
+
+/// The validator's messages among the messages of glslang's SPIR-V generator, if any.
+fn spirv_validation_errors(messages: &[String]) -> Option<String> {
+  let header_index = messages.iter().position(|line| line == SPIRV_VALIDATION_ERRORS_HEADER)?;
+  let errors = messages[header_index + 1..]
+    .iter()
+    .map(|line| line.strip_prefix("error: ").unwrap_or(line))
+    .collect::<Vec<_>>();
+  Some(errors.join("\n"))
+}
+
+// The tests stay next to the compile functions they cover, ahead of the resource limit table.
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
+  use std::collections::HashMap;
   use std::ffi::CString;
   use super::*;
 
@@ -132,7 +745,108 @@ mod tests {
   }
 
   #[test]
-  fn compile_vertex_shader() -> Result<(), GlslangErrorLog> {
+  fn struct_layout_matches_pointer_width() {
+    // Catches bindings generated for the wrong target, e.g. x86_64 bindings used on i686.
+    #[cfg(target_pointer_width = "64")]
+    assert_eq!(std::mem::size_of::<glslang_input_t>(), 64);
+    #[cfg(target_pointer_width = "32")]
+    assert_eq!(std::mem::size_of::<glslang_input_t>(), 52);
+
+    assert_eq!(std::mem::size_of::<glslang_spv_options_t>(), 6);
+  }
+
+  /// The instructions of `spirv` after the header, each with its opcode and word count in the first word.
+  pub(crate) fn instructions(spirv: &[u32]) -> impl Iterator<Item = &[u32]> {
+    let mut words = &spirv[5..];
+    std::iter::from_fn(move || {
+      let word_count = (*words.first()? >> 16) as usize;
+      let (instruction, rest) = words.split_at(word_count.clamp(1, words.len()));
+      words = rest;
+      Some(instruction)
+    })
+  }
+
+  const OP_ENTRY_POINT: u32 = 15;
+  const OP_DECORATE: u32 = 71;
+  const DECORATION_BINDING: u32 = 33;
+  const DECORATION_DESCRIPTOR_SET: u32 = 34;
+
+  /// The `Binding` decorations in `spirv`, as (target id, binding) pairs.
+  pub(crate) fn binding_decorations(spirv: &[u32]) -> Vec<(u32, u32)> {
+    instructions(spirv)
+      .filter(|instruction| instruction.len() == 4 && instruction[0] & 0xffff == OP_DECORATE && instruction[2] == DECORATION_BINDING)
+      .map(|instruction| (instruction[1], instruction[3]))
+      .collect()
+  }
+
+  /// The (descriptor set, binding) of each resource in `spirv`, sorted.
+  pub(crate) fn sets_and_bindings(spirv: &[u32]) -> Vec<(u32, u32)> {
+    let mut decorations = HashMap::<u32, (u32, u32)>::new();
+    for instruction in instructions(spirv).filter(|instruction| instruction.len() == 4 && instruction[0] & 0xffff == OP_DECORATE) {
+      let entry = decorations.entry(instruction[1]).or_default();
+      match instruction[2] {
+        DECORATION_DESCRIPTOR_SET => entry.0 = instruction[3],
+        DECORATION_BINDING => entry.1 = instruction[3],
+        _ => {},
+      }
+    }
+    let mut sets_and_bindings: Vec<(u32, u32)> = decorations.into_values().collect();
+    sets_and_bindings.sort_unstable();
+    sets_and_bindings
+  }
+
+  /// The names of the `OpEntryPoint`s in `spirv`.
+  pub(crate) fn entry_point_names(spirv: &[u32]) -> Vec<String> {
+    instructions(spirv)
+      .filter(|instruction| instruction[0] & 0xffff == OP_ENTRY_POINT)
+      .map(|instruction| {
+        // The name is a null-terminated literal string after the execution model and function id.
+        let bytes = instruction[3..].iter().flat_map(|word| word.to_le_bytes()).take_while(|&byte| byte != 0).collect::<Vec<_>>();
+        String::from_utf8(bytes).unwrap()
+      })
+      .collect()
+  }
+
+  /// Whether an instruction of `spirv` has `literal` as a literal string operand, e.g. an entry point name.
+  #[cfg(feature = "hlsl")]
+  pub(crate) fn contains_literal_string(spirv: &[u32], literal: &str) -> bool {
+    // SPIR-V literal strings are null-terminated and padded to a word boundary.
+    let mut bytes = literal.as_bytes().to_vec();
+    bytes.resize((literal.len() / 4 + 1) * 4, 0);
+    let literal_words: Vec<u32> = bytes.chunks(4).map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap())).collect();
+    instructions(spirv).any(|instruction| instruction[1..].windows(literal_words.len()).any(|words| words == literal_words.as_slice()))
+  }
+
+  #[test]
+  fn linked_glslang_version() {
+    let version = glslang_version();
+    assert!((version.major, version.minor, version.patch) > (0, 0, 0), "{}", version);
+    assert!(version.commit.len() == 40 && version.commit.chars().all(|c| c.is_ascii_hexdigit()), "{}", version);
+    assert!(version.to_string().ends_with(&format!(" ({})", GLSLANG_COMMIT)));
+  }
+
+  /// Catches headers of one glslang used with the libraries of another, e.g. a custom install.
+  #[test]
+  fn header_version_matches_linked_version() {
+    let version = glslang_version();
+    let header_flavor = std::ffi::CStr::from_bytes_with_nul(GLSLANG_VERSION_FLAVOR).unwrap().to_str().unwrap();
+    assert_eq!(
+      (GLSLANG_VERSION_MAJOR, GLSLANG_VERSION_MINOR, GLSLANG_VERSION_PATCH, header_flavor),
+      (version.major, version.minor, version.patch, version.flavor.as_str()),
+      "The glslang headers don't match the linked glslang {}", version,
+    );
+  }
+
+  #[cfg(all(target_os = "linux", glslang_sys_static_stdcpp))]
+  #[test]
+  fn no_dynamic_libstdcpp() {
+    // Every shared object the test binary depends on is mapped by the time tests run.
+    let maps = std::fs::read_to_string("/proc/self/maps").unwrap();
+    assert!(!maps.contains("libstdc++.so"), "libstdc++ is linked dynamically:\n{}", maps);
+  }
+
+  #[test]
+  fn compile_vertex_shader() -> Result<(), CompileError> {
     unsafe {
       glslang_initialize_process();
       scopeguard::defer! {
@@ -163,16 +877,267 @@ mod tests {
         default_profile: glslang_profile_t_GLSLANG_NO_PROFILE,
         force_default_version_and_profile: 0,
         forward_compatible: 0,
-        messages: glslang_messages_t_GLSLANG_MSG_DEFAULT_BIT | glslang_messages_t_GLSLANG_MSG_SPV_RULES_BIT | glslang_messages_t_GLSLANG_MSG_VULKAN_RULES_BIT,
+        messages: Messages::DEFAULT_VULKAN.into(),
         resource: &DEFAULT_RESOURCE_LIMITS as *const glslang_resource_t,
       };
 
-      let spirv = compile(&input, CompileOptionFlags::empty())?;
-      println!("SPIR-V word count: {}", spirv.len());
+      let output = compile(&input, CompileOptionFlags::empty())?;
+      assert!(!output.spirv.is_empty());
+      assert_eq!(output.warnings, Vec::<String>::new());
+      assert_eq!(output.spirv_messages, Vec::<String>::new());
 
       Ok(())
     }
   }
+
+  #[test]
+  fn null_error_logs() {
+    let error_log = unsafe { GlslangErrorLog::new("glslang_shader_parse".to_owned(), std::ptr::null(), std::ptr::null()) };
+    assert_eq!(error_log.context, "glslang_shader_parse");
+    assert_eq!((error_log.info_log.as_str(), error_log.debug_log.as_str()), ("", ""));
+
+    let info_log = CString::new("ERROR: 0:1: '' : syntax error").unwrap();
+    let error_log = unsafe { GlslangErrorLog::new(String::new(), info_log.as_ptr(), std::ptr::null()) };
+    assert_eq!((error_log.info_log.as_str(), error_log.debug_log.as_str()), ("ERROR: 0:1: '' : syntax error", ""));
+
+    let mut lines = vec!["kept".to_owned()];
+    unsafe { push_log_lines(&mut lines, std::ptr::null()) };
+    assert_eq!(lines, ["kept"]);
+  }
+
+  #[test]
+  fn invalid_utf8_in_source() {
+    unsafe {
+      glslang_initialize_process();
+      scopeguard::defer! {
+        glslang_finalize_process();
+      }
+
+      // Owns the resource limits `input` points to.
+      let template = CompileInput::new().stage(ShaderStage::Compute);
+      let source_c_string = CString::new(b"#version 450\n// caf\xe9\n#error bad \xff\xfe bytes\nvoid main() {}\n".to_vec()).unwrap();
+      let input = glslang_input_t {
+        code: source_c_string.as_ptr(),
+        ..*template.as_raw().unwrap()
+      };
+      // `#error` echoes the bytes to the info log.
+      match compile(&input, CompileOptionFlags::empty()) {
+        Err(CompileError::Preprocess(error_log) | CompileError::Parse(error_log)) => assert!(error_log.info_log.contains("bad"), "{}", error_log.info_log),
+        result => panic!("Unexpected result: {:?}", result),
+      }
+
+      // The source text is added to `OpSource` as is.
+      let source_c_string = CString::new(b"#version 450\n// caf\xe9\nvoid main() {}\n".to_vec()).unwrap();
+      let input = glslang_input_t {
+        code: source_c_string.as_ptr(),
+        ..input
+      };
+      assert!(compile(&input, CompileOptionFlags::AddOpSource).is_ok());
+    }
+  }
+
+  #[test]
+  fn compile_with_invalid_resource_limits() {
+    unsafe {
+      glslang_initialize_process();
+      scopeguard::defer! {
+        glslang_finalize_process();
+      }
+
+      let source_c_string = CString::new("#version 450\nvoid main() {}\n").unwrap();
+
+      let limits = glslang_resource_t {
+        max_draw_buffers: 0,
+        ..DEFAULT_RESOURCE_LIMITS
+      };
+
+      let input = glslang_input_t {
+        language: glslang_source_t_GLSLANG_SOURCE_GLSL,
+        stage: glslang_stage_t_GLSLANG_STAGE_COMPUTE,
+        client: glslang_client_t_GLSLANG_CLIENT_VULKAN,
+        client_version: glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_1,
+        target_language: glslang_target_language_t_GLSLANG_TARGET_SPV,
+        target_language_version: glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_0,
+        code: source_c_string.as_ptr(),
+        default_version: 100,
+        default_profile: glslang_profile_t_GLSLANG_NO_PROFILE,
+        force_default_version_and_profile: 0,
+        forward_compatible: 0,
+        messages: Messages::DEFAULT_VULKAN.into(),
+        resource: &limits as *const glslang_resource_t,
+      };
+
+      match compile(&input, CompileOptionFlags::empty()) {
+        Err(CompileError::InvalidResourceLimits(violations)) => assert_eq!(violations[0].field_name, "max_draw_buffers"),
+        result => panic!("Unexpected result: {:?}", result),
+      }
+      let output = compile(&input, CompileOptionFlags::ResourceLimitErrorsAsWarnings).unwrap();
+      assert_eq!(output.warnings[..2], [
+        "WARNING: Resource limit max_draw_buffers = 0 must be greater than zero",
+        "WARNING: Resource limit max_draw_buffers = 0 should be at least the minimum-spec value 4",
+      ]);
+
+      // Warning-level violations never fail the compile.
+      let limits = glslang_resource_t {
+        max_draw_buffers: 2,
+        ..DEFAULT_RESOURCE_LIMITS
+      };
+      let input = glslang_input_t {
+        resource: &limits as *const glslang_resource_t,
+        ..input
+      };
+      let output = compile(&input, CompileOptionFlags::empty()).unwrap();
+      assert_eq!(output.warnings, ["WARNING: Resource limit max_draw_buffers = 2 should be at least the minimum-spec value 4"]);
+    }
+  }
+
+  #[test]
+  fn compile_with_enhanced_messages() {
+    unsafe {
+      glslang_initialize_process();
+      scopeguard::defer! {
+        glslang_finalize_process();
+      }
+
+      let source_c_string = CString::new("#version 450\nvoid main() {\n  vec3 v = vec2(0.0);\n}\n").unwrap();
+
+      let input = glslang_input_t {
+        language: glslang_source_t_GLSLANG_SOURCE_GLSL,
+        stage: glslang_stage_t_GLSLANG_STAGE_COMPUTE,
+        client: glslang_client_t_GLSLANG_CLIENT_VULKAN,
+        client_version: glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_1,
+        target_language: glslang_target_language_t_GLSLANG_TARGET_SPV,
+        target_language_version: glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_0,
+        code: source_c_string.as_ptr(),
+        default_version: 100,
+        default_profile: glslang_profile_t_GLSLANG_NO_PROFILE,
+        force_default_version_and_profile: 0,
+        forward_compatible: 0,
+        messages: Messages::DEFAULT_VULKAN.into(),
+        resource: &DEFAULT_RESOURCE_LIMITS as *const glslang_resource_t,
+      };
+
+      let info_log = |option_flags| match compile(&input, option_flags) {
+        Err(CompileError::Parse(error_log)) => error_log.info_log,
+        result => panic!("Unexpected result: {:?}", result),
+      };
+
+      let plain = info_log(CompileOptionFlags::empty());
+      let enhanced = info_log(CompileOptionFlags::EnhancedMessages);
+      assert_ne!(plain, enhanced);
+      // Enhanced mode spells out types the way they are written in GLSL.
+      assert!(!plain.contains("vec3"));
+      assert!(enhanced.contains("vec3"));
+    }
+  }
+
+  #[test]
+  fn error_phases() {
+    let compile_compute = |source| compile_glsl_to_spirv(source, ShaderStage::Compute, &TargetEnv::default());
+
+    match compile_compute("#version 450\n#error unsupported configuration\nvoid main() {}\n") {
+      Err(CompileError::Preprocess(error_log)) => assert!(error_log.info_log.contains("unsupported configuration")),
+      result => panic!("Unexpected result: {:?}", result),
+    }
+    match compile_compute("#version 450\nvoid main() { undeclared = 1; }\n") {
+      Err(CompileError::Parse(error_log)) => assert!(error_log.info_log.contains("undeclared")),
+      result => panic!("Unexpected result: {:?}", result),
+    }
+    match compile_compute("#version 450\nlayout(local_size_x = 1) in;\nvoid not_main() {}\n") {
+      Err(error @ CompileError::Link(_)) => {
+        let source = std::error::Error::source(&error).unwrap().to_string();
+        assert!(source.contains("Missing entry point"), "{}", source);
+      },
+      result => panic!("Unexpected result: {:?}", result),
+    }
+  }
+
+  #[test]
+  fn spirv_generation_messages() {
+    // With `ENABLE_OPT=OFF` there is no SPIR-V validator, and no shader that links is known to fail generation,
+    // so only the classification of the generator's messages is tested.
+    let lines = |messages: &str| {
+      let messages = CString::new(messages).unwrap();
+      let mut lines = Vec::new();
+      unsafe { push_log_lines(&mut lines, messages.as_ptr()) };
+      lines
+    };
+    // Empty but non-null messages must not produce an entry.
+    assert_eq!(lines(""), Vec::<String>::new());
+    assert!(!has_spirv_generation_errors(&lines("warning: unsupported feature\n")));
+    assert!(has_spirv_generation_errors(&lines("warning: unsupported feature\nerror: invalid operand\n")));
+
+    assert_eq!(spirv_validation_errors(&lines("warning: unsupported feature\nerror: invalid operand\n")), None);
+    let messages = lines("warning: unsupported feature\nerror: SPIRV-Tools Validation Errors\nerror: ID 5 has not been defined\n  %7 = OpLoad %6 %5\n");
+    assert_eq!(spirv_validation_errors(&messages).as_deref(), Some("ID 5 has not been defined\n%7 = OpLoad %6 %5"));
+  }
+
+  #[cfg(not(feature = "hlsl"))]
+  #[test]
+  fn compile_hlsl_without_hlsl_feature() {
+    unsafe {
+      glslang_initialize_process();
+      scopeguard::defer! {
+        glslang_finalize_process();
+      }
+
+      let source_c_string = CString::new("float4 main() : SV_Target { return 0; }").unwrap();
+
+      let input = glslang_input_t {
+        language: glslang_source_t_GLSLANG_SOURCE_HLSL,
+        stage: glslang_stage_t_GLSLANG_STAGE_FRAGMENT,
+        client: glslang_client_t_GLSLANG_CLIENT_VULKAN,
+        client_version: glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_1,
+        target_language: glslang_target_language_t_GLSLANG_TARGET_SPV,
+        target_language_version: glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_0,
+        code: source_c_string.as_ptr(),
+        default_version: 100,
+        default_profile: glslang_profile_t_GLSLANG_NO_PROFILE,
+        force_default_version_and_profile: 0,
+        forward_compatible: 0,
+        messages: Messages::DEFAULT_VULKAN.into(),
+        resource: &DEFAULT_RESOURCE_LIMITS as *const glslang_resource_t,
+      };
+
+      assert!(matches!(compile(&input, CompileOptionFlags::empty()), Err(CompileError::HlslSupportDisabled)));
+    }
+  }
+
+  #[test]
+  fn compile_from_multiple_threads() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<CompileInput>();
+    assert_send_sync::<CompileOutput>();
+    assert_send_sync::<CompileError>();
+    assert_send_sync::<GlslangProcess>();
+
+    let files = HashMap::from([("scale.glsl".to_owned(), "const float SCALE = 2.0;\n".to_owned())]);
+    let compute = CompileInput::new()
+      .source("#version 450\n#extension GL_GOOGLE_include_directive : require\n#include \"scale.glsl\"\nlayout(local_size_x = 64) in;\nlayout(std430, binding = 0) buffer Data { float values[]; };\nvoid main() {\n  values[gl_GlobalInvocationID.x] *= SCALE;\n}\n")
+      .stage(ShaderStage::Compute)
+      .include_handler(VirtualIncluder::new(files));
+    let vertex = CompileInput::new().source("#version 450\nlayout(location = 0) out vec4 color;\nvoid main() {\n  color = vec4(1.0);\n  gl_Position = vec4(0.0);\n}\n").stage(ShaderStage::Vertex);
+    let fragment = CompileInput::new().source("#version 450\nlayout(location = 0) in vec4 color;\nlayout(location = 0) out vec4 out_color;\nvoid main() {\n  out_color = color;\n}\n").stage(ShaderStage::Fragment);
+    let expected = compile_input(&compute, CompileOptionFlags::empty()).unwrap().spirv;
+
+    // The inputs are shared; every thread creates its own shader and program objects.
+    let _process = GlslangProcess::new();
+    std::thread::scope(|scope| {
+      let threads: Vec<_> = (0..8)
+        .map(|_| {
+          scope.spawn(|| {
+            for _ in 0..4 {
+              assert_eq!(compile_input(&compute, CompileOptionFlags::empty()).unwrap().spirv, expected);
+              assert_eq!(link_program(&[&vertex, &fragment], CompileOptionFlags::empty()).unwrap().len(), 2);
+            }
+          })
+        })
+        .collect();
+      for thread in threads {
+        thread.join().unwrap();
+      }
+    });
+  }
 }
 
 /// Values copied from ` glslang/StandAlone/ResourceLimits.cpp `.
@@ -0,0 +1,113 @@
+use std::os::raw::c_int;
+
+use bitflags::bitflags;
+
+use crate::*;
+
+bitflags! {
+  /// The message options of a compile (`glslang_messages_t`), e.g. `glslang_input_t::messages`.
+  ///
+  /// `glslang_messages_t` (being an enum) is `i32` or `u32` depending on the target, and `glslang_program_link` takes
+  /// `c_int`. Convert with `From` and [`Messages::to_c_int`], which keep the bit pattern, instead of casting by hand.
+  // The casts are needed where `glslang_messages_t` is `i32`.
+  pub struct Messages: u32 {
+    /// Keeps going after some errors, e.g. for tools.
+    #[allow(clippy::unnecessary_cast)]
+    const RelaxedErrors = glslang_messages_t_GLSLANG_MSG_RELAXED_ERRORS_BIT as u32;
+    #[allow(clippy::unnecessary_cast)]
+    const SuppressWarnings = glslang_messages_t_GLSLANG_MSG_SUPPRESS_WARNINGS_BIT as u32;
+    /// Prints the AST to the info log.
+    #[allow(clippy::unnecessary_cast)]
+    const Ast = glslang_messages_t_GLSLANG_MSG_AST_BIT as u32;
+    /// The semantics of SPIR-V generation, e.g. requiring explicit locations.
+    #[allow(clippy::unnecessary_cast)]
+    const SpvRules = glslang_messages_t_GLSLANG_MSG_SPV_RULES_BIT as u32;
+    /// The semantics of GLSL for Vulkan (`GL_KHR_vulkan_glsl`).
+    #[allow(clippy::unnecessary_cast)]
+    const VulkanRules = glslang_messages_t_GLSLANG_MSG_VULKAN_RULES_BIT as u32;
+    #[allow(clippy::unnecessary_cast)]
+    const OnlyPreprocessor = glslang_messages_t_GLSLANG_MSG_ONLY_PREPROCESSOR_BIT as u32;
+    /// The source is HLSL.
+    #[allow(clippy::unnecessary_cast)]
+    const ReadHlsl = glslang_messages_t_GLSLANG_MSG_READ_HLSL_BIT as u32;
+    /// Reports errors that follow from earlier ones.
+    #[allow(clippy::unnecessary_cast)]
+    const CascadingErrors = glslang_messages_t_GLSLANG_MSG_CASCADING_ERRORS_BIT as u32;
+    /// Keeps functions that are never called.
+    #[allow(clippy::unnecessary_cast)]
+    const KeepUncalled = glslang_messages_t_GLSLANG_MSG_KEEP_UNCALLED_BIT as u32;
+    /// HLSL-style `cbuffer` member offsets.
+    #[allow(clippy::unnecessary_cast)]
+    const HlslOffsets = glslang_messages_t_GLSLANG_MSG_HLSL_OFFSETS_BIT as u32;
+    /// Keeps the information needed for debug info, e.g. for `OpLine`.
+    #[allow(clippy::unnecessary_cast)]
+    const DebugInfo = glslang_messages_t_GLSLANG_MSG_DEBUG_INFO_BIT as u32;
+    #[allow(clippy::unnecessary_cast)]
+    const HlslEnable16BitTypes = glslang_messages_t_GLSLANG_MSG_HLSL_ENABLE_16BIT_TYPES_BIT as u32;
+    #[allow(clippy::unnecessary_cast)]
+    const HlslLegalization = glslang_messages_t_GLSLANG_MSG_HLSL_LEGALIZATION_BIT as u32;
+    #[allow(clippy::unnecessary_cast)]
+    const HlslDx9Compatible = glslang_messages_t_GLSLANG_MSG_HLSL_DX9_COMPATIBLE_BIT as u32;
+    #[allow(clippy::unnecessary_cast)]
+    const BuiltinSymbolTable = glslang_messages_t_GLSLANG_MSG_BUILTIN_SYMBOL_TABLE_BIT as u32;
+    /// More readable diagnostics, see [`CompileOptionFlags::EnhancedMessages`].
+    #[allow(clippy::unnecessary_cast)]
+    const Enhanced = glslang_messages_t_GLSLANG_MSG_ENHANCED as u32;
+  }
+}
+
+impl Messages {
+  /// The messages of [`CompileInput::new`].
+  pub const DEFAULT_VULKAN: Messages = Messages::SpvRules.union(Messages::VulkanRules);
+
+  /// The bits as `glslang_program_link` takes them.
+  #[must_use]
+  pub fn to_c_int(self) -> c_int {
+    self.bits() as c_int
+  }
+}
+
+/// Keeps unknown bits, e.g. of a newer glslang.
+impl From<glslang_messages_t> for Messages {
+  #[allow(clippy::unnecessary_cast)]
+  fn from(raw: glslang_messages_t) -> Self {
+    // SAFETY: glslang ignores bits it does not know, and so does `Messages`.
+    unsafe { Messages::from_bits_unchecked(raw as u32) }
+  }
+}
+impl From<Messages> for glslang_messages_t {
+  #[allow(clippy::unnecessary_cast)]
+  fn from(messages: Messages) -> Self {
+    messages.bits() as glslang_messages_t
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn bits_match_raw_constants() {
+    assert_eq!(glslang_messages_t::from(Messages::empty()), glslang_messages_t_GLSLANG_MSG_DEFAULT_BIT);
+    assert_eq!(
+      glslang_messages_t::from(Messages::DEFAULT_VULKAN | Messages::ReadHlsl),
+      glslang_messages_t_GLSLANG_MSG_SPV_RULES_BIT | glslang_messages_t_GLSLANG_MSG_VULKAN_RULES_BIT | glslang_messages_t_GLSLANG_MSG_READ_HLSL_BIT,
+    );
+    assert_eq!(Messages::from(glslang_messages_t_GLSLANG_MSG_ENHANCED), Messages::Enhanced);
+  }
+
+  #[test]
+  #[allow(clippy::unnecessary_cast)]
+  fn high_bit_round_trips() {
+    // The same bit pattern, from a signed and an unsigned representation.
+    let signed = i32::MIN | Messages::Enhanced.bits() as i32;
+    let unsigned = 0x8000_0000 | Messages::Enhanced.bits();
+    for raw in [signed as glslang_messages_t, unsigned as glslang_messages_t] {
+      let messages = Messages::from(raw);
+      assert!(messages.contains(Messages::Enhanced));
+      assert_eq!(messages.bits(), unsigned);
+      assert_eq!(messages.to_c_int(), signed);
+      assert_eq!(glslang_messages_t::from(messages), raw);
+    }
+  }
+}
@@ -0,0 +1,134 @@
+//! Validation of compiled SPIR-V with [`naga`], which wgpu runs every SPIR-V module through, so that modules naga rejects
+//! fail when shaders are compiled rather than at pipeline creation.
+
+use naga::valid::{Capabilities, ValidationFlags, Validator};
+use thiserror::Error;
+
+use crate::*;
+
+/// A rejection of a module by naga. Contains naga's message, followed by the errors it was caused by.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum NagaError {
+  /// naga's SPIR-V frontend cannot translate the module, e.g. for an execution model or instruction it does not support.
+  #[error("naga failed to parse the SPIR-V: {0}")]
+  Parse(String),
+  #[error("naga validation failed: {0}")]
+  Validation(String),
+}
+
+impl Spirv {
+  /// Parses and validates the module with naga, with the capabilities every wgpu device has, see
+  /// [`Capabilities::default`].
+  pub fn validate_with_naga(&self) -> Result<(), NagaError> {
+    validate(self.words(), Capabilities::default())
+  }
+
+  /// [`Spirv::validate_with_naga`] with the capabilities of a device, e.g. [`Capabilities::FLOAT64`].
+  pub fn validate_with_naga_capabilities(&self, capabilities: Capabilities) -> Result<(), NagaError> {
+    validate(self.words(), capabilities)
+  }
+}
+
+/// Also used for [`SpvOptions::validate_with_naga`].
+pub(crate) fn validate(words: &[u32], capabilities: Capabilities) -> Result<(), NagaError> {
+  let module = naga::front::spv::Frontend::new(words.iter().copied(), &naga::front::spv::Options::default())
+    .parse()
+    .map_err(|error| NagaError::Parse(error_chain(&error)))?;
+  Validator::new(ValidationFlags::all(), capabilities)
+    .validate(&module)
+    .map_err(|error| NagaError::Validation(error_chain(error.as_inner())))?;
+  Ok(())
+}
+
+/// `error` and its sources, separated by `: `. naga's errors name the function or type at fault and leave the details to
+/// their sources.
+fn error_chain(error: &dyn std::error::Error) -> String {
+  let mut message = error.to_string();
+  let mut source = error.source();
+  while let Some(error) = source {
+    message.push_str(": ");
+    message.push_str(&error.to_string());
+    source = error.source();
+  }
+  message
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn compile_compute(source: &str, validate_with_naga: bool) -> Result<CompileOutput, CompileError> {
+    let input = CompileInput::new()
+      .source(source)
+      .stage(ShaderStage::Compute)
+      .spv_options(SpvOptions { validate_with_naga, ..Default::default() });
+    compile_input(&input, CompileOptionFlags::empty())
+  }
+
+  #[test]
+  fn accepted_control_flow() {
+    // A return from inside a loop, and a switch and a break inside another, which naga restructures.
+    let source = r##"
+      #version 450
+      layout(local_size_x = 64) in;
+      layout(std430, binding = 0) buffer Data { uint values[]; };
+      uint classify(uint value) {
+        for (uint shift = 0; shift < 32; shift += 8) {
+          if (((value >> shift) & 0xff) == 0) {
+            return shift;
+          }
+        }
+        return 32;
+      }
+      void main() {
+        uint sum = 0;
+        for (uint i = 0; i < 16; i++) {
+          switch (values[i] % 4) {
+            case 0:
+              sum += 1;
+              break;
+            case 1:
+              sum += classify(values[i]);
+              break;
+            default:
+              sum *= 2;
+              break;
+          }
+          if (sum > 1000) {
+            break;
+          }
+        }
+        values[gl_GlobalInvocationID.x] = sum;
+      }
+    "##;
+    let output = compile_compute(source, true).unwrap();
+    assert_eq!(output.spirv.validate_with_naga(), Ok(()));
+  }
+
+  #[test]
+  fn rejected_float64() {
+    // glslang accepts doubles, but they need a capability that wgpu devices don't have by default.
+    let source = r##"
+      #version 450
+      layout(local_size_x = 1) in;
+      layout(std430, binding = 0) buffer Data { double values[]; };
+      void main() {
+        values[0] = values[0] * 2.0lf;
+      }
+    "##;
+    let spirv = compile_compute(source, false).unwrap().spirv;
+    let error = spirv.validate_with_naga().unwrap_err();
+    assert!(matches!(&error, NagaError::Validation(message) if message.contains("FLOAT64")), "{}", error);
+    assert_eq!(spirv.validate_with_naga_capabilities(Capabilities::FLOAT64), Ok(()));
+
+    let error = compile_compute(source, true).unwrap_err();
+    assert!(matches!(error, CompileError::Naga(NagaError::Validation(_))), "{:?}", error);
+
+    // naga has no geometry shaders.
+    let geometry = CompileInput::new()
+      .source("#version 450\nlayout(points) in;\nlayout(points, max_vertices = 1) out;\nvoid main() {\n  EmitVertex();\n}\n")
+      .stage(ShaderStage::Geometry);
+    let spirv = compile_input(&geometry, CompileOptionFlags::empty()).unwrap().spirv;
+    assert!(matches!(spirv.validate_with_naga(), Err(NagaError::Parse(_))));
+  }
+}
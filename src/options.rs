@@ -0,0 +1,356 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+use crate::*;
+
+/// What to compile, apart from the options and the source text itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileInputSpec {
+  pub language: SourceLanguage,
+  /// `-S`, or inferred from the input file. `None` without either.
+  pub stage: Option<ShaderStage>,
+  pub client: Client,
+  pub client_version: glslang_target_client_version_t,
+  pub target_language_version: glslang_target_language_version_t,
+  /// The positional input file, if any.
+  pub source_path: Option<PathBuf>,
+  /// `-o`
+  pub output_path: Option<PathBuf>,
+}
+impl Default for CompileInputSpec {
+  fn default() -> Self {
+    CompileInputSpec {
+      language: SourceLanguage::Glsl,
+      stage: None,
+      client: Client::Vulkan,
+      client_version: glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_0,
+      target_language_version: glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_0,
+      source_path: None,
+      output_path: None,
+    }
+  }
+}
+
+/// Options controlling how a shader is compiled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileOptions {
+  pub flags: CompileOptionFlags,
+  /// `#define`s as name and optional value, in order.
+  pub defines: Vec<(String, Option<String>)>,
+  /// `#undef`s, applied after `defines`.
+  pub undefines: Vec<String>,
+  pub include_dirs: Vec<PathBuf>,
+  /// The SPIR-V entry point name.
+  pub entry_point: Option<String>,
+  /// The source function to compile as the entry point, if different from `entry_point`.
+  pub source_entry_point: Option<String>,
+  pub disable_optimizer: bool,
+  pub optimize_size: bool,
+  /// Keep functions that are never called, see [`CompileInput::keep_uncalled`].
+  pub keep_uncalled: bool,
+}
+impl Default for CompileOptions {
+  fn default() -> Self {
+    let spv_options = glslang_spv_options_t::default();
+    CompileOptions {
+      flags: CompileOptionFlags::empty(),
+      defines: Vec::new(),
+      undefines: Vec::new(),
+      include_dirs: Vec::new(),
+      entry_point: None,
+      source_entry_point: None,
+      disable_optimizer: spv_options.disable_optimizer,
+      optimize_size: spv_options.optimize_size,
+      keep_uncalled: false,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ArgParseError {
+  #[error("Unknown flag: {0}")]
+  UnknownFlag(String),
+  #[error("Flag {0} requires a value")]
+  MissingValue(String),
+  #[error("Invalid value for flag {flag}: {value}")]
+  InvalidValue { flag: String, value: String },
+  #[error("More than one input file: {0}")]
+  MultipleInputFiles(String),
+  #[error("Cannot infer the shader stage of {}: use -S, or one of the extensions {}, optionally followed by .glsl or .hlsl", .0.display(), ShaderStage::supported_extensions())]
+  UnknownStage(PathBuf),
+}
+
+impl CompileOptions {
+  /// Enables or disables [`CompileOptionFlags::EnhancedMessages`].
+  #[must_use]
+  pub fn enhanced_messages(mut self, enabled: bool) -> Self {
+    self.flags.set(CompileOptionFlags::EnhancedMessages, enabled);
+    self
+  }
+
+  /// The SPIR-V generation options for [`CompileInput::spv_options`], i.e. `disable_optimizer` and `optimize_size`.
+  /// The debug info flags are part of [`CompileOptions::flags`].
+  #[must_use]
+  pub fn spv_options(&self) -> SpvOptions {
+    SpvOptions {
+      disable_optimizer: self.disable_optimizer,
+      optimize_size: self.optimize_size,
+      ..Default::default()
+    }
+  }
+
+  /// Parses a glslangValidator-style argument list, e.g. `-V -S frag --target-env vulkan1.2 -DUSE_FOG=1 -I./inc -e mainPS shader.frag`.
+  ///
+  /// Supported flags:
+  /// - `-V`, `-G`: Vulkan or OpenGL semantics, unless `--target-env` chooses the client. Vulkan is assumed if neither is given.
+  /// - `--target-env vulkan1.0|vulkan1.1|vulkan1.2|vulkan1.3|opengl|spirv1.0..spirv1.6`
+  /// - `-S <stage>`: `vert`, `tesc`, `tese`, `geom`, `frag`, `comp`, `rgen`, `rint`, `rahit`, `rchit`, `rmiss`, `rcall`, `task`, `mesh`
+  /// - `-D<name>[=<value>]`, `-U<name>`, and `-D` alone for HLSL input
+  /// - `-I<dir>`
+  /// - `-e <name>`, `--source-entrypoint <name>`
+  /// - `-Od`, `-Os`, `-g`, `--keep-uncalled`/`--ku`, `--enhanced-msgs`
+  /// - `-o <file>` and one positional input file; a `.hlsl` input file implies HLSL
+  ///
+  /// Without `-S`, the stage is inferred from the input file with [`ShaderStage::from_path`], e.g. `shader.frag` or
+  /// `shader.frag.hlsl`; other input files fail with [`ArgParseError::UnknownStage`].
+  pub fn from_validator_args(args: &[&str]) -> Result<(CompileInputSpec, CompileOptions), ArgParseError> {
+    let mut spec = CompileInputSpec::default();
+    let mut options = CompileOptions::default();
+    // Like glslangValidator, `-V` and `-G` only choose the client if `--target-env` does not, whatever their order.
+    let mut semantics: Option<(Client, glslang_target_client_version_t)> = None;
+    let mut target_env: Option<(Client, glslang_target_client_version_t, glslang_target_language_version_t)> = None;
+    let mut explicit_target_language_version: Option<glslang_target_language_version_t> = None;
+
+    let mut args = args.iter().copied();
+    while let Some(arg) = args.next() {
+      let mut next_value = |flag: &str| args.next().ok_or_else(|| ArgParseError::MissingValue(flag.to_owned()));
+
+      match arg {
+        "-V" => semantics = Some((Client::Vulkan, glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_0)),
+        "-G" => semantics = Some((Client::OpenGL, glslang_target_client_version_t_GLSLANG_TARGET_OPENGL_450)),
+        "--target-env" => {
+          let value = next_value(arg)?;
+          target_env = Some(match value {
+            "vulkan1.0" => (Client::Vulkan, glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_0, glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_0),
+            "vulkan1.1" => (Client::Vulkan, glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_1, glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_3),
+            "vulkan1.2" => (Client::Vulkan, glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_2, glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_5),
+            "vulkan1.3" => (Client::Vulkan, glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_3, glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_6),
+            "opengl"    => (Client::OpenGL, glslang_target_client_version_t_GLSLANG_TARGET_OPENGL_450, glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_0),
+            _ => {
+              explicit_target_language_version = Some(parse_spirv_version(value).ok_or_else(|| ArgParseError::InvalidValue { flag: arg.to_owned(), value: value.to_owned() })?);
+              continue;
+            },
+          });
+        },
+        "-S" => {
+          let value = next_value(arg)?;
+          spec.stage = Some(ShaderStage::from_extension(value).ok_or_else(|| ArgParseError::InvalidValue { flag: arg.to_owned(), value: value.to_owned() })?);
+        },
+        "-D" => spec.language = SourceLanguage::Hlsl,
+        "-e" => options.entry_point = Some(next_value(arg)?.to_owned()),
+        "--source-entrypoint" => options.source_entry_point = Some(next_value(arg)?.to_owned()),
+        "-Od" => {
+          options.disable_optimizer = true;
+          options.optimize_size = false;
+        },
+        "-Os" => {
+          options.disable_optimizer = false;
+          options.optimize_size = true;
+        },
+        "-g" => options.flags |= CompileOptionFlags::GenerateDebugInfo,
+        "--keep-uncalled" | "--ku" => options.keep_uncalled = true,
+        "--enhanced-msgs" => options.flags |= CompileOptionFlags::EnhancedMessages,
+        "-o" => spec.output_path = Some(PathBuf::from(next_value(arg)?)),
+        _ => {
+          if let Some(define) = arg.strip_prefix("-D") {
+            let (name, value) = match define.split_once('=') {
+              Some((name, value)) => (name, Some(value.to_owned())),
+              None => (define, None),
+            };
+            options.defines.push((name.to_owned(), value));
+          }
+          else if let Some(name) = arg.strip_prefix("-U") {
+            if name.is_empty() {
+              return Err(ArgParseError::MissingValue(arg.to_owned()));
+            }
+            options.undefines.push(name.to_owned());
+          }
+          else if let Some(dir) = arg.strip_prefix("-I") {
+            if dir.is_empty() {
+              return Err(ArgParseError::MissingValue(arg.to_owned()));
+            }
+            options.include_dirs.push(PathBuf::from(dir));
+          }
+          else if arg.starts_with('-') {
+            return Err(ArgParseError::UnknownFlag(arg.to_owned()));
+          }
+          else {
+            if spec.source_path.is_some() {
+              return Err(ArgParseError::MultipleInputFiles(arg.to_owned()));
+            }
+            if arg.ends_with(".hlsl") {
+              spec.language = SourceLanguage::Hlsl;
+            }
+            spec.source_path = Some(PathBuf::from(arg));
+          }
+        },
+      }
+    }
+
+    match (target_env, semantics) {
+      (Some((client, client_version, target_language_version)), _) => {
+        spec.client = client;
+        spec.client_version = client_version;
+        spec.target_language_version = target_language_version;
+      },
+      (None, Some((client, client_version))) => {
+        spec.client = client;
+        spec.client_version = client_version;
+      },
+      (None, None) => (),
+    }
+    if let Some(target_language_version) = explicit_target_language_version {
+      spec.target_language_version = target_language_version;
+    }
+    if let (None, Some(source_path)) = (spec.stage, &spec.source_path) {
+      spec.stage = Some(ShaderStage::from_path(source_path).ok_or_else(|| ArgParseError::UnknownStage(source_path.clone()))?);
+    }
+
+    Ok((spec, options))
+  }
+}
+
+fn parse_spirv_version(value: &str) -> Option<glslang_target_language_version_t> {
+  let version = match value {
+    "spirv1.0" => glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_0,
+    "spirv1.1" => glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_1,
+    "spirv1.2" => glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_2,
+    "spirv1.3" => glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_3,
+    "spirv1.4" => glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_4,
+    "spirv1.5" => glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_5,
+    "spirv1.6" => glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_6,
+    _ => return None,
+  };
+  Some(version)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse_validator_args() {
+    let (spec, options) = CompileOptions::from_validator_args(
+      &["-V", "-S", "frag", "--target-env", "vulkan1.2", "-DUSE_FOG=1", "-DHAS_SHADOWS", "-UDEBUG", "-I./inc", "-e", "mainPS", "-Os", "-g", "-o", "out.spv", "shader.glsl"],
+    ).unwrap();
+
+    assert_eq!(spec.language, SourceLanguage::Glsl);
+    assert_eq!(spec.stage, Some(ShaderStage::Fragment));
+    assert_eq!(spec.client, Client::Vulkan);
+    assert_eq!(spec.client_version, glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_2);
+    assert_eq!(spec.target_language_version, glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_5);
+    assert_eq!(spec.source_path, Some(PathBuf::from("shader.glsl")));
+    assert_eq!(spec.output_path, Some(PathBuf::from("out.spv")));
+
+    assert_eq!(options.defines, vec![("USE_FOG".to_owned(), Some("1".to_owned())), ("HAS_SHADOWS".to_owned(), None)]);
+    assert_eq!(options.undefines, vec!["DEBUG".to_owned()]);
+    assert_eq!(options.include_dirs, vec![PathBuf::from("./inc")]);
+    assert_eq!(options.entry_point.as_deref(), Some("mainPS"));
+    assert!(options.optimize_size && !options.disable_optimizer);
+    assert!(options.flags.contains(CompileOptionFlags::GenerateDebugInfo));
+  }
+
+  #[test]
+  fn explicit_spirv_version_overrides_target_env() {
+    let (spec, _) = CompileOptions::from_validator_args(&["--target-env", "spirv1.4", "--target-env", "vulkan1.1"]).unwrap();
+    assert_eq!(spec.client_version, glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_1);
+    assert_eq!(spec.target_language_version, glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_4);
+  }
+
+  #[test]
+  fn target_env_before_semantics() {
+    let (spec, _) = CompileOptions::from_validator_args(&["--target-env", "vulkan1.2", "-V"]).unwrap();
+    assert_eq!(spec.client_version, glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_2);
+    assert_eq!(spec.target_language_version, glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_5);
+
+    let (spec, _) = CompileOptions::from_validator_args(&["--target-env", "opengl", "-V"]).unwrap();
+    assert_eq!((spec.client, spec.client_version), (Client::OpenGL, glslang_target_client_version_t_GLSLANG_TARGET_OPENGL_450));
+    let (spec, _) = CompileOptions::from_validator_args(&["-G"]).unwrap();
+    assert_eq!((spec.client, spec.client_version), (Client::OpenGL, glslang_target_client_version_t_GLSLANG_TARGET_OPENGL_450));
+
+    let input = CompileInput::new().source("#version 450\nvoid main() {}\n").stage(ShaderStage::Fragment)
+      .target_env(TargetEnv { client: spec.client, client_version: spec.client_version, spirv_version: spec.target_language_version });
+    assert!(compile_input(&input, CompileOptionFlags::empty()).is_ok());
+    let (spec, _) = CompileOptions::from_validator_args(&["--target-env", "vulkan1.2", "-V"]).unwrap();
+    let input = input.target_env(TargetEnv { client: spec.client, client_version: spec.client_version, spirv_version: spec.target_language_version });
+    assert_eq!(compile_input(&input, CompileOptionFlags::empty()).unwrap().spirv[1], 0x0001_0500);
+  }
+
+  #[test]
+  fn keep_uncalled() {
+    let source = "#version 450\nlayout(location = 0) out vec4 color;\nvec4 unused_tint() { return vec4(0.5); }\nvoid main() { color = vec4(1.0); }\n";
+    let compile = |args: &[&str]| {
+      let (_, options) = CompileOptions::from_validator_args(args).unwrap();
+      let input = CompileInput::new().source(source).stage(ShaderStage::Fragment).keep_uncalled(options.keep_uncalled);
+      compile_input(&input, CompileOptionFlags::empty()).unwrap().spirv
+    };
+    // glslang names functions with `OpName`, e.g. `unused_tint(`.
+    let contains_unused_tint = |spirv: &Spirv| spirv.as_bytes().windows(b"unused_tint(".len()).any(|bytes| bytes == b"unused_tint(");
+    assert!(!contains_unused_tint(&compile(&["-S", "frag"])));
+    assert!(contains_unused_tint(&compile(&["-S", "frag", "--keep-uncalled"])));
+    assert!(contains_unused_tint(&compile(&["-S", "frag", "--ku"])));
+  }
+
+  #[test]
+  fn hlsl_input() {
+    let (spec, _) = CompileOptions::from_validator_args(&["-D", "-S", "vert", "-e", "VSMain", "shader.txt"]).unwrap();
+    assert_eq!(spec.language, SourceLanguage::Hlsl);
+
+    let (spec, _) = CompileOptions::from_validator_args(&["-S", "vert", "shader.hlsl"]).unwrap();
+    assert_eq!(spec.language, SourceLanguage::Hlsl);
+  }
+
+  #[test]
+  fn parse_errors() {
+    assert_eq!(CompileOptions::from_validator_args(&["-V", "--invert-y"]), Err(ArgParseError::UnknownFlag("--invert-y".to_owned())));
+    assert_eq!(CompileOptions::from_validator_args(&["-S"]), Err(ArgParseError::MissingValue("-S".to_owned())));
+    assert_eq!(
+      CompileOptions::from_validator_args(&["--target-env", "vulkan9"]),
+      Err(ArgParseError::InvalidValue { flag: "--target-env".to_owned(), value: "vulkan9".to_owned() }),
+    );
+    assert_eq!(CompileOptions::from_validator_args(&["a.vert", "b.vert"]), Err(ArgParseError::MultipleInputFiles("b.vert".to_owned())));
+
+    let error = CompileOptions::from_validator_args(&["-x"]).unwrap_err();
+    assert!(error.to_string().contains("-x"));
+  }
+
+  #[test]
+  fn stage_from_input_file() {
+    let (spec, _) = CompileOptions::from_validator_args(&["-V", "shaders/closest_hit.rchit"]).unwrap();
+    assert_eq!(spec.stage, Some(ShaderStage::ClosestHit));
+    let (spec, _) = CompileOptions::from_validator_args(&["-D", "-e", "main", "vertex.vert.hlsl"]).unwrap();
+    assert_eq!((spec.language, spec.stage), (SourceLanguage::Hlsl, Some(ShaderStage::Vertex)));
+    // `-S` overrides the extension.
+    let (spec, _) = CompileOptions::from_validator_args(&["-S", "mesh", "meshlet.glsl"]).unwrap();
+    assert_eq!(spec.stage, Some(ShaderStage::Mesh));
+
+    let error = CompileOptions::from_validator_args(&["meshlet.glsl"]).unwrap_err();
+    assert_eq!(error, ArgParseError::UnknownStage(PathBuf::from("meshlet.glsl")));
+    let message = error.to_string();
+    assert!(message.contains("meshlet.glsl") && message.contains("-S"), "{}", message);
+    for &stage in ShaderStage::ALL {
+      assert!(message.contains(&format!(".{}", stage.extension())), "{}", message);
+    }
+  }
+
+  #[test]
+  fn enhanced_messages() {
+    let options = CompileOptions::default().enhanced_messages(true);
+    assert!(options.flags.contains(CompileOptionFlags::EnhancedMessages));
+    assert!(!options.enhanced_messages(false).flags.contains(CompileOptionFlags::EnhancedMessages));
+
+    let (_, options) = CompileOptions::from_validator_args(&["--enhanced-msgs"]).unwrap();
+    assert!(options.flags.contains(CompileOptionFlags::EnhancedMessages));
+  }
+}
@@ -0,0 +1,106 @@
+use std::ffi::CString;
+use std::sync::Mutex;
+
+use crate::*;
+
+/// Compiles many sources, e.g. generated permutations, with the settings of one [`CompileInput`] template.
+///
+/// glslang's shader and program objects are single-use (a `TShader` can only be parsed once and a `TProgram` linked once),
+/// so they are created per compile like with [`compile_input`]. What the pool saves instead:
+/// - The glslang process initialization, held with a [`GlslangProcess`] for the lifetime of the pool.
+/// - Building the template: its preamble, entry point names, resource limits and include handler are converted once and
+///   shared by every compile, while only the source differs.
+/// - Source buffers for the null-terminated shader source, whose allocations are reused instead of creating a `CString` per
+///   compile. Concurrent callers each take their own buffer.
+pub struct CompilerPool {
+  template: CompileInput,
+  option_flags: CompileOptionFlags,
+  source_buffers: Mutex<Vec<Vec<u8>>>,
+  _process: GlslangProcess,
+}
+
+impl CompilerPool {
+  /// Initial capacity of each source buffer, in bytes.
+  const SOURCE_BUFFER_CAPACITY: usize = 16 * 1024;
+
+  /// Creates a pool compiling with the settings of `template`, and `buffer_count` source buffers allocated up front, e.g.
+  /// one per thread compiling with it. The source of `template` is ignored.
+  ///
+  /// Fails like [`CompileInput::as_raw`] if the settings of `template` are invalid.
+  pub fn new(template: CompileInput, option_flags: CompileOptionFlags, buffer_count: usize) -> Result<Self, CompileError> {
+    let template = template.source("");
+    template.as_raw()?;
+    let source_buffers = (0..buffer_count).map(|_| Vec::with_capacity(Self::SOURCE_BUFFER_CAPACITY)).collect();
+    Ok(CompilerPool {
+      template,
+      option_flags,
+      source_buffers: Mutex::new(source_buffers),
+      _process: GlslangProcess::new(),
+    })
+  }
+
+  /// Compiles `source` with the pool's template, like [`compile_input`] with `template.source(source)`.
+  pub fn compile(&self, source: &str) -> Result<CompileOutput, CompileError> {
+    let mut buffer = self.source_buffers.lock().unwrap().pop().unwrap_or_else(|| Vec::with_capacity(Self::SOURCE_BUFFER_CAPACITY));
+    buffer.clear();
+    buffer.reserve(source.len() + 1);
+    buffer.extend_from_slice(source.as_bytes());
+
+    let (result, buffer) = match CString::new(buffer) {
+      Ok(source_c_string) => {
+        let result = self.template.as_raw().and_then(|raw| {
+          let input = glslang_input_t {
+            code: source_c_string.as_ptr(),
+            ..*raw
+          };
+          // SAFETY: `input` is the validated template, pointing into `self.template`, plus a source that lives until the
+          // end of this scope. The process is initialized by `self._process`.
+          unsafe { compile_shader(&input, self.option_flags, &self.template.shader_settings()) }
+        });
+        (result.map(|output| input::add_target_env_warning(&self.template, output)), source_c_string.into_bytes())
+      },
+      Err(error) => (Err(CompileError::nul_in(InputKind::Source, &error)), error.into_vec()),
+    };
+
+    self.source_buffers.lock().unwrap().push(buffer);
+    result
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn make_template() -> CompileInput {
+    CompileInput::new()
+      .stage(ShaderStage::Fragment)
+      .target_env(TargetEnv::vulkan_1_1())
+      .preamble(Preamble::new().define("SCALE", Some("0.5")))
+  }
+
+  #[test]
+  fn pooled_matches_unpooled() {
+    let pool = CompilerPool::new(make_template().source("ignored"), CompileOptionFlags::empty(), 2).unwrap();
+
+    for i in 0..4 {
+      let source = format!("#version 450\nlayout(location = 0) out vec4 out_color;\nvoid main() {{ out_color = vec4({}.0 * SCALE); }}\n", i);
+
+      let pooled = pool.compile(&source).unwrap();
+      let unpooled = compile_input(&make_template().source(&source), CompileOptionFlags::empty()).unwrap();
+      assert_eq!(pooled, unpooled);
+    }
+  }
+
+  #[test]
+  fn invalid_template() {
+    let template = CompileInput::new().stage(ShaderStage::RayGen).target_env(TargetEnv::vulkan_1_0());
+    assert!(matches!(CompilerPool::new(template, CompileOptionFlags::empty(), 1), Err(CompileError::InvalidTargetEnv(_))));
+  }
+
+  #[test]
+  fn nul_in_source() {
+    let pool = CompilerPool::new(make_template(), CompileOptionFlags::empty(), 1).unwrap();
+
+    assert!(matches!(pool.compile("#version 450\n\0"), Err(CompileError::InvalidInput { input: InputKind::Source, offset: 13 })));
+  }
+}
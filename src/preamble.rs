@@ -0,0 +1,149 @@
+use std::ffi::{CStr, CString};
+
+use crate::*;
+
+/// Code preprocessed before the shader source, composed of `#define`s and free-form fragments.
+///
+/// Defines always come first, followed by the fragments in the order they were added, each on its own line.
+/// This allows e.g. engine-wide defines, per-material defines and extension enables to be collected separately.
+///
+/// ```
+/// # use glslang_sys_2022::*;
+/// let preamble = Preamble::new()
+///   .define("MAX_LIGHTS", Some("16"))
+///   .fragment("#extension GL_EXT_nonuniform_qualifier : require");
+/// assert_eq!(
+///   preamble.as_c_str().unwrap().unwrap().to_str(),
+///   Ok("#define MAX_LIGHTS 16\n#extension GL_EXT_nonuniform_qualifier : require\n"),
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Preamble {
+  defines: Vec<(String, Option<String>)>,
+  fragments: Vec<String>,
+  /// The joined preamble, or `None` if it is empty.
+  c_string: Option<CString>,
+  /// An invalid define or an interior NUL byte, reported by `as_c_str()`.
+  error: Option<CompileError>,
+}
+impl From<&str> for Preamble {
+  fn from(fragment: &str) -> Self {
+    Preamble::new().fragment(fragment)
+  }
+}
+impl Preamble {
+  #[must_use]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Defines the macro `name` as `value`, like `-D<name>=<value>` of glslangValidator. `None` defines it as empty, like `-D<name>`.
+  ///
+  /// Line breaks in `value` become line continuations. A `name` that is empty or contains whitespace or NUL bytes is
+  /// reported by [`Preamble::as_c_str`].
+  #[must_use]
+  pub fn define(mut self, name: &str, value: Option<&str>) -> Self {
+    self.defines.push((name.to_owned(), value.map(str::to_owned)));
+    self.update();
+    self
+  }
+
+  /// Appends `fragment`, followed by a line break if it does not end with one. Empty fragments are ignored.
+  ///
+  /// An interior NUL byte in `fragment` is reported by [`Preamble::as_c_str`].
+  #[must_use]
+  pub fn fragment(mut self, fragment: &str) -> Self {
+    if !fragment.is_empty() {
+      self.fragments.push(fragment.to_owned());
+      self.update();
+    }
+    self
+  }
+
+  /// Appends the defines and fragments of `other`.
+  pub fn extend(&mut self, other: &Preamble) {
+    self.defines.extend(other.defines.iter().cloned());
+    self.fragments.extend(other.fragments.iter().cloned());
+    self.update();
+  }
+
+  #[must_use]
+  pub fn is_empty(&self) -> bool {
+    self.defines.is_empty() && self.fragments.is_empty()
+  }
+
+  /// The joined preamble, valid for as long as `self` is borrowed. `None` if the preamble is empty.
+  ///
+  /// Fails if a define name is invalid or the preamble contains an interior NUL byte.
+  pub fn as_c_str(&self) -> Result<Option<&CStr>, CompileError> {
+    match &self.error {
+      Some(error) => Err(error.clone()),
+      None => Ok(self.c_string.as_deref()),
+    }
+  }
+
+  fn update(&mut self) {
+    self.c_string = None;
+    self.error = None;
+    if self.is_empty() {
+      return;
+    }
+
+    let mut text = String::new();
+    for (name, value) in &self.defines {
+      if name.is_empty() || name.chars().any(|c| c.is_whitespace() || c == '\0') {
+        self.error = Some(CompileError::InvalidDefineName(name.clone()));
+        return;
+      }
+      text.push_str("#define ");
+      text.push_str(name);
+      if let Some(value) = value {
+        text.push(' ');
+        text.push_str(&value.replace("\r\n", "\n").replace('\n', "\\\n"));
+      }
+      text.push('\n');
+    }
+    for fragment in &self.fragments {
+      text.push_str(fragment);
+      if !fragment.ends_with('\n') {
+        text.push('\n');
+      }
+    }
+
+    match CString::new(text) {
+      Ok(c_string) => self.c_string = Some(c_string),
+      Err(error) => self.error = Some(CompileError::nul_in(InputKind::Preamble, &error)),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn defines_come_first() {
+    let mut preamble = Preamble::new()
+      .fragment("#define B A")
+      .define("A", Some("1"));
+    preamble.extend(&Preamble::from("#define C B\n").define("D", None));
+
+    assert_eq!(preamble.as_c_str().unwrap().unwrap().to_str(), Ok("#define A 1\n#define D\n#define B A\n#define C B\n"));
+  }
+
+  #[test]
+  fn empty() {
+    let preamble = Preamble::new().fragment("");
+    assert!(preamble.is_empty());
+    assert_eq!(preamble.as_c_str().unwrap(), None);
+  }
+
+  #[test]
+  fn invalid_fragments() {
+    let preamble = Preamble::new().fragment("#define A 1").fragment("#define B\0");
+    assert!(matches!(preamble.as_c_str(), Err(CompileError::InvalidInput { input: InputKind::Preamble, offset: 21 })));
+
+    let preamble = Preamble::new().define("", None);
+    assert!(matches!(preamble.as_c_str(), Err(CompileError::InvalidDefineName(_))));
+  }
+}
@@ -0,0 +1,90 @@
+use std::sync::OnceLock;
+
+use crate::*;
+
+/// Keeps the glslang process initialized while it lives (`glslang_initialize_process` and `glslang_finalize_process`).
+///
+/// Compiles initialize the process for their duration; holding a guard across them avoids setting up glslang's built-in
+/// symbol tables every time. glslang reference-counts initialization under its global lock, so guards can nest and
+/// overlap, e.g. one per subsystem: only dropping the last one finalizes the process.
+///
+/// [`GlslangProcess::ensure_initialized`] keeps the process initialized for good instead.
+///
+/// Guards are `Send + Sync`; the count is process-wide, so a guard can be dropped on any thread.
+#[derive(Debug)]
+pub struct GlslangProcess {
+  _private: (),
+}
+impl GlslangProcess {
+  #[must_use]
+  pub fn new() -> Self {
+    unsafe {
+      glslang_initialize_process();
+    }
+    GlslangProcess { _private: () }
+  }
+
+  /// Initializes the process for the rest of the program, e.g. in plugins and long-lived engines. The first call
+  /// initializes it; the process is never finalized.
+  ///
+  /// This holds one reference of glslang's count that is never released, so it coexists with guards: dropping the last
+  /// guard, whether created before or after, leaves the process initialized.
+  pub fn ensure_initialized() {
+    static INITIALIZED: OnceLock<()> = OnceLock::new();
+    INITIALIZED.get_or_init(|| unsafe {
+      glslang_initialize_process();
+    });
+  }
+}
+impl Default for GlslangProcess {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+impl Drop for GlslangProcess {
+  fn drop(&mut self) {
+    unsafe {
+      glslang_finalize_process();
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn overlapping_guards() {
+    let first = GlslangProcess::new();
+    let second = GlslangProcess::default();
+    drop(first);
+
+    // The process is still initialized for the shader and program objects created here.
+    let input = CompileInput::new().source("#version 450\nvoid main() {}\n").stage(ShaderStage::Compute);
+    let mut shader = Shader::new(&input).unwrap();
+    shader.parse().unwrap();
+    drop(shader);
+    assert!(compile_input(&input, CompileOptionFlags::empty()).is_ok());
+    drop(second);
+
+    // Compiles initialize the process again by themselves.
+    assert!(compile_input(&input, CompileOptionFlags::empty()).is_ok());
+  }
+
+  #[test]
+  fn global_initialization_outlives_guards() {
+    let guard = GlslangProcess::new();
+    GlslangProcess::ensure_initialized();
+    GlslangProcess::ensure_initialized();
+    drop(guard);
+
+    let input = CompileInput::new().source("#version 450\nvoid main() {}\n").stage(ShaderStage::Compute);
+    let mut shader = Shader::new(&input).unwrap();
+    shader.parse().unwrap();
+    drop(shader);
+    assert!(compile_input(&input, CompileOptionFlags::empty()).is_ok());
+
+    drop(GlslangProcess::new());
+    assert!(compile_input(&input, CompileOptionFlags::empty()).is_ok());
+  }
+}
@@ -0,0 +1,456 @@
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::os::raw::c_char;
+
+use crate::*;
+
+/// A glslang program object linking parsed [`Shader`]s of one or more stages, validating the interfaces between them.
+///
+/// The glslang process is kept initialized for the lifetime of the program. Like [`Shader`], a program is not `Send`: IO
+/// mapping and SPIR-V generation allocate from the calling thread's pool, which is the program's own only on the thread
+/// that linked it.
+pub struct Program<'a> {
+  raw: *mut glslang_program_t,
+  /// glslang's program refers to the shaders it links, so they are dropped after it.
+  shaders: Vec<Shader<'a>>,
+  linked: bool,
+  /// The sources of the added shaders, per stage.
+  sources: Vec<StageSource<'a>>,
+  /// glslang can only build the reflection of a program once.
+  reflected: bool,
+}
+impl Default for Program<'_> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+impl<'a> Program<'a> {
+  #[must_use]
+  pub fn new() -> Self {
+    unsafe {
+      glslang_initialize_process();
+      Program {
+        raw: glslang_program_create(),
+        shaders: Vec::new(),
+        linked: false,
+        sources: Vec::new(),
+        reflected: false,
+      }
+    }
+  }
+
+  /// Adds `shader`, parsing it first if it has not been.
+  ///
+  /// # Panics
+  /// - If the program has already been linked.
+  pub fn add_shader(&mut self, mut shader: Shader<'a>) -> Result<(), CompileError> {
+    assert!(!self.linked, "Program already linked");
+    if !shader.is_parsed() {
+      shader.parse()?;
+    }
+    let stage = glslang_stage_t::from(shader.stage());
+    let input: &'a CompileInput = shader.input();
+    // SAFETY: the code is owned by `input`, which outlives the program.
+    let code = unsafe { CStr::from_ptr(input.as_raw()?.code) };
+    let source = match self.sources.iter_mut().find(|source| source.stage == stage) {
+      Some(source) => source,
+      None => {
+        self.sources.push(StageSource::new(stage));
+        self.sources.last_mut().unwrap()
+      },
+    };
+    source.add(code, input.shader_settings().source_file_name);
+    unsafe {
+      glslang_program_add_shader(self.raw, shader.as_raw());
+    }
+    self.shaders.push(shader);
+    Ok(())
+  }
+
+  /// Links the added shaders, using the message options of the first one.
+  ///
+  /// glslang can only link a program once.
+  ///
+  /// # Panics
+  /// - If the program has already been linked.
+  pub fn link(&mut self) -> Result<(), CompileError> {
+    assert!(!self.linked, "Program already linked");
+    let messages = match self.shaders.first() {
+      Some(shader) => Messages::from(shader.input().as_raw()?.messages),
+      None => Messages::empty(),
+    };
+    unsafe {
+      if glslang_program_link(self.raw, messages.to_c_int()) == 0 {
+        return Err(CompileError::Link(GlslangErrorLog::from_program("glslang_program_link".to_string(), self.raw)));
+      }
+    }
+    self.linked = true;
+    Ok(())
+  }
+
+  /// Assigns bindings and locations to the resources and interface variables lacking them, for the shaders created with
+  /// [`ShaderOptions::AutoMapBindings`] or [`ShaderOptions::AutoMapLocations`], and applies [`HlslOptions::binding_shifts`].
+  /// Without it, those options have no effect.
+  ///
+  /// # Panics
+  /// - If the program has not been linked.
+  pub fn map_io(&mut self) -> Result<(), CompileError> {
+    assert!(self.linked, "Program not linked");
+    unsafe {
+      if glslang_program_map_io(self.raw) == 0 {
+        return Err(CompileError::MapIo(GlslangErrorLog::from_program("glslang_program_map_io".to_string(), self.raw)));
+      }
+    }
+    Ok(())
+  }
+
+  /// The distinct stages of the added shaders, in the order they were added.
+  #[must_use]
+  pub fn stages(&self) -> Vec<ShaderStage> {
+    let mut stages = Vec::new();
+    for shader in &self.shaders {
+      if !stages.contains(&shader.stage()) {
+        stages.push(shader.stage());
+      }
+    }
+    stages
+  }
+
+  /// Generates SPIR-V for `stage`, with the [`CompileInput::spv_options`] of its first shader. `warnings` contains the info
+  /// logs of the shaders of `stage` and of the program, and `includes` the includes of those shaders.
+  ///
+  /// Fails with [`CompileError::StageNotInProgram`] if no added shader is of `stage`.
+  ///
+  /// # Panics
+  /// - If the program has not been linked.
+  pub fn generate_spirv(&mut self, stage: ShaderStage, option_flags: CompileOptionFlags) -> Result<CompileOutput, CompileError> {
+    assert!(self.linked, "Program not linked");
+    if !self.stages().contains(&stage) {
+      return Err(CompileError::StageNotInProgram(stage));
+    }
+
+    unsafe {
+      let raw_stage = glslang_stage_t::from(stage);
+      if let Some(source) = self.sources.iter_mut().find(|source| source.stage == raw_stage) {
+        source.apply(self.raw, option_flags);
+      }
+
+      let input = self.shaders.iter().find(|shader| shader.stage() == stage).unwrap().input();
+      let target_language_version = input.as_raw()?.target_language_version;
+      let mut spirv = Vec::new();
+      let mut output = generate_spirv(self.raw, stage.into(), target_language_version, option_flags, input.shader_settings().spv_options, &mut spirv)?;
+      output.spirv = spirv.into();
+
+      for shader in self.shaders.iter().filter(|shader| shader.stage() == stage) {
+        push_log_lines(&mut output.warnings, glslang_shader_get_info_log(shader.as_raw()));
+        output.includes.extend_from_slice(shader.includes());
+      }
+      push_log_lines(&mut output.warnings, glslang_program_get_info_log(self.raw));
+
+      Ok(output)
+    }
+  }
+
+  /// The uniforms, uniform blocks, inputs and outputs the linked shaders use, from glslang's reflection, and the spec
+  /// constants and local size of each stage.
+  ///
+  /// glslang builds the reflection once, on the first call, so call [`Program::map_io`] first to have the locations it
+  /// assigns in [`StageInput::location`].
+  ///
+  /// # Panics
+  /// - If the program has not been linked.
+  pub fn reflect(&mut self) -> Reflection {
+    assert!(self.linked, "Program not linked");
+    unsafe {
+      if !self.reflected {
+        assert!(glslang_sys_program_build_reflection(self.raw) != 0, "glslang failed to build the reflection");
+        self.reflected = true;
+      }
+      Reflection::from_program(self.raw, &self.stages())
+    }
+  }
+
+  /// The textual dump of the linked AST of `stage`, like `glslangValidator -i` prints, e.g. for finding out why glslang
+  /// generated some SPIR-V. Its format depends on the glslang version.
+  ///
+  /// Fails with [`CompileError::StageNotInProgram`] if no added shader is of `stage`.
+  ///
+  /// # Panics
+  /// - If the program has not been linked.
+  pub fn dump_ast(&self, stage: ShaderStage) -> Result<String, CompileError> {
+    assert!(self.linked, "Program not linked");
+    if !self.stages().contains(&stage) {
+      return Err(CompileError::StageNotInProgram(stage));
+    }
+    unsafe {
+      let text = glslang_sys_program_dump_ast(self.raw, stage.into());
+      if text.is_null() {
+        return Err(CompileError::StageNotInProgram(stage));
+      }
+      let dump = log_text(text).into_owned();
+      glslang_sys_free_string(text);
+      Ok(dump)
+    }
+  }
+
+  /// The SPIR-V of the last [`Program::generate_spirv`] without copying it, e.g. for hashing or creating a shader module.
+  /// Empty if no SPIR-V has been generated.
+  ///
+  /// This is glslang's own buffer, so it does not reflect [`SpvOptions::strip_debug_info`], which is applied to the copy.
+  /// The slice borrows the program, so it cannot outlive it:
+  ///
+  /// ```compile_fail
+  /// # use glslang_sys_2022::*;
+  /// let input = CompileInput::new().source("#version 450\nvoid main() {}\n").stage(ShaderStage::Vertex);
+  /// let mut program = Program::new();
+  /// program.add_shader(Shader::new(&input).unwrap()).unwrap();
+  /// program.link().unwrap();
+  /// program.generate_spirv(ShaderStage::Vertex, CompileOptionFlags::empty()).unwrap();
+  /// let words = program.spirv_words();
+  /// drop(program);
+  /// println!("{}", words.len());
+  /// ```
+  #[must_use]
+  pub fn spirv_words(&self) -> &[u32] {
+    unsafe {
+      let size = glslang_program_SPIRV_get_size(self.raw) as usize;
+      if size == 0 {
+        return &[];
+      }
+      // SAFETY: glslang's buffer only changes in `generate_spirv`, which takes `&mut self`.
+      std::slice::from_raw_parts(glslang_program_SPIRV_get_ptr(self.raw), size)
+    }
+  }
+
+  /// The `glslang_program_t`, valid for as long as `self` is borrowed.
+  #[must_use]
+  pub fn as_raw(&self) -> *mut glslang_program_t {
+    self.raw
+  }
+}
+impl Drop for Program<'_> {
+  fn drop(&mut self) {
+    unsafe {
+      glslang_program_delete(self.raw);
+      glslang_finalize_process();
+    }
+  }
+}
+
+/// The source text and file name glslang embeds in the SPIR-V of one stage (`OpSource` and `OpString`) with debug info.
+///
+/// glslang keeps them in the intermediate of the stage, so each stage of a program has its own, made of the shaders of
+/// that stage only.
+pub(crate) struct StageSource<'a> {
+  stage: glslang_stage_t,
+  /// The codes of the shaders of the stage, in the order they were added.
+  codes: Vec<&'a CStr>,
+  /// The file name of the first shader of the stage having one.
+  file_name: Option<&'a CStr>,
+  /// glslang appends the text, so it must be added only once.
+  text_added: bool,
+}
+impl<'a> StageSource<'a> {
+  pub(crate) fn new(stage: glslang_stage_t) -> Self {
+    StageSource { stage, codes: Vec::new(), file_name: None, text_added: false }
+  }
+
+  /// Adds the source of a shader of the stage.
+  pub(crate) fn add(&mut self, code: &'a CStr, file_name: Option<&'a CStr>) {
+    self.codes.push(code);
+    self.file_name = self.file_name.or(file_name);
+  }
+
+  /// Sets the source file of the stage in `program`, and adds the text with [`CompileOptionFlags::AddOpSource`].
+  ///
+  /// # Safety
+  /// - `program` MUST be a linked program containing a shader of the stage.
+  pub(crate) unsafe fn apply(&mut self, program: *mut glslang_program_t, option_flags: CompileOptionFlags) {
+    if option_flags.contains(CompileOptionFlags::AddOpSource) && !self.text_added {
+      for (index, code) in self.codes.iter().enumerate() {
+        // Keeps the last line of a shader from running into the first one of the next.
+        if index > 0 && !self.codes[index - 1].to_bytes().ends_with(b"\n") {
+          glslang_program_add_source_text(program, self.stage, b"\n".as_ptr() as *const c_char, 1);
+        }
+        glslang_program_add_source_text(program, self.stage, code.as_ptr(), code.to_bytes().len() as size_t);
+      }
+      self.text_added = true;
+    }
+    if let Some(file_name) = self.file_name {
+      glslang_program_set_source_file(program, self.stage, file_name.as_ptr());
+    }
+  }
+}
+
+/// Parses every input, links them into one program and generates SPIR-V for each stage.
+///
+/// Runs [`Program::map_io`] if any input requests auto-mapping with [`CompileInput::shader_options`], or shifts HLSL
+/// bindings with [`HlslOptions::binding_shifts`].
+///
+/// Unlike compiling each stage separately, this validates the interfaces between stages, e.g. that a uniform block
+/// declared in several stages has the same layout everywhere.
+///
+/// Conversely, [`compile_input`] links a program of the single stage, so per-stage SPIR-V, e.g. for a cache keyed by
+/// stage, never depends on the other stages. glslang 11.10 has no `compile_only` SPIR-V option; that is the closest equivalent.
+pub fn link_program(inputs: &[&CompileInput], option_flags: CompileOptionFlags) -> Result<HashMap<ShaderStage, CompileOutput>, CompileError> {
+  let mut program = Program::new();
+  for input in inputs {
+    program.add_shader(Shader::new(input)?)?;
+  }
+  program.link()?;
+  if inputs.iter().any(|input| input.needs_io_mapping()) {
+    program.map_io()?;
+  }
+
+  program.stages()
+    .into_iter()
+    .map(|stage| Ok((stage, program.generate_spirv(stage, option_flags)?)))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const VERTEX_SOURCE: &str = "#version 450\nlayout(set = 0, binding = 0) uniform Globals { vec4 tint; } globals;\nlayout(location = 0) out vec4 out_color;\nvoid main() {\n  out_color = globals.tint;\n  gl_Position = vec4(0.0);\n}\n";
+  const FRAGMENT_SOURCE: &str = "#version 450\nlayout(set = 0, binding = 0) uniform Globals { vec4 tint; } globals;\nlayout(location = 0) in vec4 in_color;\nlayout(location = 0) out vec4 out_color;\nvoid main() {\n  out_color = in_color * globals.tint;\n}\n";
+
+  #[test]
+  fn link_vertex_and_fragment() {
+    let vertex = CompileInput::new().source(VERTEX_SOURCE).stage(ShaderStage::Vertex);
+    let fragment = CompileInput::new().source(FRAGMENT_SOURCE).stage(ShaderStage::Fragment);
+
+    let outputs = link_program(&[&vertex, &fragment], CompileOptionFlags::empty()).unwrap();
+    assert_eq!(outputs.len(), 2);
+    for stage in [ShaderStage::Vertex, ShaderStage::Fragment] {
+      assert_eq!(outputs[&stage].spirv[0], 0x07230203);
+    }
+    assert_ne!(outputs[&ShaderStage::Vertex].spirv, outputs[&ShaderStage::Fragment].spirv);
+  }
+
+  #[test]
+  fn borrowed_spirv_words() {
+    let vertex = CompileInput::new().source(VERTEX_SOURCE).stage(ShaderStage::Vertex);
+    let fragment = CompileInput::new().source(FRAGMENT_SOURCE).stage(ShaderStage::Fragment);
+    let mut program = Program::new();
+    program.add_shader(Shader::new(&vertex).unwrap()).unwrap();
+    program.add_shader(Shader::new(&fragment).unwrap()).unwrap();
+    program.link().unwrap();
+    assert_eq!(program.spirv_words(), &[] as &[u32]);
+
+    for stage in [ShaderStage::Vertex, ShaderStage::Fragment] {
+      let output = program.generate_spirv(stage, CompileOptionFlags::empty()).unwrap();
+      assert_eq!(program.spirv_words(), output.spirv.words());
+    }
+  }
+
+  #[test]
+  fn stage_not_in_program() {
+    let vertex = CompileInput::new().source(VERTEX_SOURCE).stage(ShaderStage::Vertex);
+    let mut program = Program::new();
+    program.add_shader(Shader::new(&vertex).unwrap()).unwrap();
+    program.link().unwrap();
+    let spirv = program.generate_spirv(ShaderStage::Vertex, CompileOptionFlags::empty()).unwrap().spirv;
+
+    match program.generate_spirv(ShaderStage::Fragment, CompileOptionFlags::empty()) {
+      Err(error @ CompileError::StageNotInProgram(ShaderStage::Fragment)) => assert_eq!(error.to_string(), "The program has no Fragment shader"),
+      result => panic!("Unexpected result: {:?}", result),
+    }
+    // glslang was not called, so the buffer still holds the vertex shader.
+    assert_eq!(program.spirv_words(), spirv.words());
+  }
+
+  #[test]
+  fn mismatched_uniform_block() {
+    let vertex = CompileInput::new().source(VERTEX_SOURCE).stage(ShaderStage::Vertex);
+    let fragment = CompileInput::new()
+      .source(&FRAGMENT_SOURCE.replace("vec4 tint;", "float tint;").replace("in_color * globals.tint", "in_color * vec4(globals.tint)"))
+      .stage(ShaderStage::Fragment);
+
+    // Each stage compiles on its own.
+    assert!(compile_input(&fragment, CompileOptionFlags::empty()).is_ok());
+    match link_program(&[&vertex, &fragment], CompileOptionFlags::empty()) {
+      Err(CompileError::Link(error_log)) => assert!(error_log.info_log.to_lowercase().contains("globals"), "{}", error_log.info_log),
+      result => panic!("Unexpected result: {:?}", result.map(|outputs| outputs.len())),
+    }
+  }
+
+  #[test]
+  fn map_io_assigns_bindings() {
+    let source = "#version 450\nlayout(set = 0) uniform sampler2D tex;\nlayout(set = 0) uniform Params { vec4 tint; };\nlayout(location = 0) out vec4 out_color;\nvoid main() {\n  out_color = texture(tex, vec2(0.5)) * tint;\n}\n";
+    let input = CompileInput::new().source(source).stage(ShaderStage::Fragment).shader_options(ShaderOptions::AutoMapBindings);
+
+    let generate = |map_io: bool| {
+      let mut program = Program::new();
+      program.add_shader(Shader::new(&input).unwrap()).unwrap();
+      program.link().unwrap();
+      if map_io {
+        program.map_io().unwrap();
+      }
+      program.generate_spirv(ShaderStage::Fragment, CompileOptionFlags::empty()).unwrap().spirv
+    };
+
+    assert_eq!(crate::tests::binding_decorations(&generate(false)), vec![]);
+    let mut bindings = crate::tests::binding_decorations(&generate(true)).into_iter().map(|(_, binding)| binding).collect::<Vec<_>>();
+    bindings.sort_unstable();
+    assert_eq!(bindings, vec![0, 1]);
+
+    // `link_program` maps automatically.
+    let outputs = link_program(&[&input], CompileOptionFlags::empty()).unwrap();
+    assert_eq!(crate::tests::binding_decorations(&outputs[&ShaderStage::Fragment].spirv).len(), 2);
+  }
+
+  #[test]
+  fn single_stage_ignores_other_stages() {
+    // No vertex shader produces `in_color`, or declares `Globals` with a `float tint`.
+    let fragment = CompileInput::new()
+      .source(&FRAGMENT_SOURCE.replace("vec4 tint;", "float tint;").replace("in_color * globals.tint", "in_color * vec4(globals.tint)"))
+      .stage(ShaderStage::Fragment);
+    assert!(compile_input(&fragment, CompileOptionFlags::empty()).is_ok());
+    assert!(link_program(&[&fragment], CompileOptionFlags::empty()).is_ok());
+
+    let vertex = CompileInput::new().source(VERTEX_SOURCE).stage(ShaderStage::Vertex);
+    assert!(link_program(&[&vertex, &fragment], CompileOptionFlags::empty()).is_err());
+  }
+
+  #[test]
+  fn op_source_per_stage() {
+    let vertex = CompileInput::new().source(VERTEX_SOURCE).stage(ShaderStage::Vertex).source_file_name("shader.vert");
+    let fragment = CompileInput::new().source(FRAGMENT_SOURCE).stage(ShaderStage::Fragment).source_file_name("shader.frag");
+    let outputs = link_program(&[&vertex, &fragment], CompileOptionFlags::AddOpSource).unwrap();
+
+    let contains = |stage: ShaderStage, text: &str| {
+      let bytes: Vec<u8> = outputs[&stage].spirv.iter().flat_map(|word| word.to_le_bytes()).collect();
+      bytes.windows(text.len()).any(|window| window == text.as_bytes())
+    };
+    assert!(contains(ShaderStage::Vertex, "shader.vert") && contains(ShaderStage::Vertex, "gl_Position = vec4(0.0);"));
+    assert!(!contains(ShaderStage::Vertex, "shader.frag") && !contains(ShaderStage::Vertex, "in_color * globals.tint"));
+    assert!(contains(ShaderStage::Fragment, "shader.frag") && contains(ShaderStage::Fragment, "in_color * globals.tint"));
+    assert!(!contains(ShaderStage::Fragment, "shader.vert") && !contains(ShaderStage::Fragment, "gl_Position = vec4(0.0);"));
+  }
+
+  #[test]
+  fn dump_ast() {
+    let vertex = CompileInput::new().source(VERTEX_SOURCE).stage(ShaderStage::Vertex);
+    let fragment = CompileInput::new().source(FRAGMENT_SOURCE).stage(ShaderStage::Fragment);
+    let mut program = Program::new();
+    program.add_shader(Shader::new(&vertex).unwrap()).unwrap();
+    program.add_shader(Shader::new(&fragment).unwrap()).unwrap();
+    program.link().unwrap();
+
+    for stage in [ShaderStage::Vertex, ShaderStage::Fragment] {
+      let dump = program.dump_ast(stage).unwrap();
+      assert!(dump.contains("Shader version: 450") && dump.contains("main("), "{}", dump);
+    }
+    assert!(program.dump_ast(ShaderStage::Vertex).unwrap().contains("gl_Position"));
+    assert!(!program.dump_ast(ShaderStage::Fragment).unwrap().contains("gl_Position"));
+    assert!(matches!(program.dump_ast(ShaderStage::Compute), Err(CompileError::StageNotInProgram(ShaderStage::Compute))));
+  }
+
+  #[test]
+  fn parse_error_before_link() {
+    let vertex = CompileInput::new().source(VERTEX_SOURCE).stage(ShaderStage::Vertex);
+    let fragment = CompileInput::new().source("#version 450\nvoid main() { undefined(); }\n").stage(ShaderStage::Fragment);
+    assert!(matches!(link_program(&[&vertex, &fragment], CompileOptionFlags::empty()), Err(CompileError::Parse(_))));
+  }
+}
@@ -0,0 +1,848 @@
+use std::ffi::CStr;
+use std::os::raw::{c_int, c_void};
+
+use crate::*;
+
+/// A uniform of a [`Reflection`]: a member of a uniform block, or an opaque uniform like a sampler.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ReflectedUniform {
+  /// Members of blocks are named after the block name and any enclosing structs, e.g. `Scene.light.direction` for a
+  /// member of `uniform Scene { Light light; } scene;`. Arrays are named without `[0]`.
+  pub name: String,
+  /// The OpenGL type enum glslang reports, e.g. `0x8B52` (`GL_FLOAT_VEC4`) for a `vec4`.
+  pub gl_type: u32,
+  /// The byte offset in its block, for members of blocks.
+  pub offset: Option<u32>,
+  /// 1 if not an array.
+  pub array_size: u32,
+  /// The index of its block in [`Reflection::uniform_blocks`], for members of blocks.
+  pub block_index: Option<usize>,
+  /// `layout(binding)`, if declared on the uniform. Members of blocks have none; their block has it.
+  pub binding: Option<u32>,
+  /// `layout(set)`, if declared on the uniform. Like `binding`, none for members of blocks.
+  pub set: Option<u32>,
+  /// The stages using the uniform, in the order of [`ShaderStage::ALL`].
+  pub stages: Vec<ShaderStage>,
+}
+
+/// A uniform block of a [`Reflection`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ReflectedUniformBlock {
+  /// The block name, e.g. `Scene` for `uniform Scene { ... } scene;`.
+  pub name: String,
+  /// The size in bytes.
+  pub size: u32,
+  /// `layout(binding)`, if declared.
+  pub binding: Option<u32>,
+  /// `layout(set)`, if declared.
+  pub set: Option<u32>,
+  /// Whether this is a `layout(push_constant)` block, which has no binding.
+  pub push_constant: bool,
+  /// The stages using the block, in the order of [`ShaderStage::ALL`].
+  pub stages: Vec<ShaderStage>,
+}
+
+/// The scalar type of a [`StageInput`] or [`SpecConstant`], see [`StageInput::vector_type`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ScalarType {
+  Float,
+  Double,
+  Int,
+  Uint,
+  Bool,
+}
+
+/// An input or output of a [`Reflection`], e.g. a vertex attribute.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StageInput {
+  pub name: String,
+  /// `layout(location)`, or the location assigned by [`Program::map_io`] with [`ShaderOptions::AutoMapLocations`].
+  /// `None` for built-ins, e.g. `gl_Position`.
+  pub location: Option<u32>,
+  /// The OpenGL type enum glslang reports, e.g. `0x8B51` (`GL_FLOAT_VEC3`) for a `vec3`.
+  pub gl_type: u32,
+  /// The stage the input or output belongs to.
+  pub stage: Option<ShaderStage>,
+}
+impl StageInput {
+  /// The scalar type and component count of a scalar or vector, e.g. `(ScalarType::Float, 3)` for a `vec3`, for choosing
+  /// the format of a vertex attribute. `None` for other types, e.g. matrices.
+  #[must_use]
+  pub fn vector_type(&self) -> Option<(ScalarType, u32)> {
+    gl_vector_type(self.gl_type)
+  }
+}
+
+/// A specialization constant, `layout(constant_id = <id>) const <type> <name> = <default>;`, of a [`StageReflection`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SpecConstant {
+  pub name: String,
+  /// `layout(constant_id)`, the `constantID` of `VkSpecializationMapEntry`.
+  pub id: u32,
+  /// The OpenGL type enum of its scalar type, e.g. `0x1406` (`GL_FLOAT`).
+  pub gl_type: u32,
+}
+impl SpecConstant {
+  /// The scalar type, e.g. for the size of its `VkSpecializationMapEntry`.
+  #[must_use]
+  pub fn scalar_type(&self) -> Option<ScalarType> {
+    gl_vector_type(self.gl_type).map(|(scalar_type, _)| scalar_type)
+  }
+}
+
+/// What one stage of a [`Reflection`] declares, apart from the resources and inputs it shares with the others.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StageReflection {
+  pub stage: ShaderStage,
+  /// Sorted by id.
+  pub spec_constants: Vec<SpecConstant>,
+  /// `layout(local_size_x, local_size_y, local_size_z)` of compute, task and mesh shaders, with 1 for the omitted ones.
+  /// `None` for other stages.
+  pub local_size: Option<[u32; 3]>,
+}
+
+/// The type of a [`DescriptorBinding`], in the order of `VkDescriptorType`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum DescriptorType {
+  /// `sampler`.
+  Sampler,
+  /// `sampler2D` and the like.
+  CombinedImageSampler,
+  /// `texture2D` and the like.
+  SampledImage,
+  /// `image2D` and the like.
+  StorageImage,
+  /// `samplerBuffer` and `textureBuffer`.
+  UniformTexelBuffer,
+  /// `imageBuffer`.
+  StorageTexelBuffer,
+  /// A `uniform` block.
+  UniformBuffer,
+  /// A `buffer` block.
+  StorageBuffer,
+  /// `subpassInput`.
+  InputAttachment,
+  /// `accelerationStructureEXT`.
+  AccelerationStructure,
+}
+impl DescriptorType {
+  fn from_object(object: &glslang_sys_reflection_object_t) -> Option<Self> {
+    // The casts are needed where `glslang_sys_descriptor_type_t` (being an enum) is `i32`.
+    #[allow(clippy::unnecessary_cast)]
+    let descriptor_type = match object.descriptor_type as glslang_sys_descriptor_type_t {
+      glslang_sys_descriptor_type_t_GLSLANG_SYS_DESCRIPTOR_SAMPLER => DescriptorType::Sampler,
+      glslang_sys_descriptor_type_t_GLSLANG_SYS_DESCRIPTOR_COMBINED_IMAGE_SAMPLER => DescriptorType::CombinedImageSampler,
+      glslang_sys_descriptor_type_t_GLSLANG_SYS_DESCRIPTOR_SAMPLED_IMAGE => DescriptorType::SampledImage,
+      glslang_sys_descriptor_type_t_GLSLANG_SYS_DESCRIPTOR_STORAGE_IMAGE => DescriptorType::StorageImage,
+      glslang_sys_descriptor_type_t_GLSLANG_SYS_DESCRIPTOR_UNIFORM_TEXEL_BUFFER => DescriptorType::UniformTexelBuffer,
+      glslang_sys_descriptor_type_t_GLSLANG_SYS_DESCRIPTOR_STORAGE_TEXEL_BUFFER => DescriptorType::StorageTexelBuffer,
+      glslang_sys_descriptor_type_t_GLSLANG_SYS_DESCRIPTOR_UNIFORM_BUFFER => DescriptorType::UniformBuffer,
+      glslang_sys_descriptor_type_t_GLSLANG_SYS_DESCRIPTOR_STORAGE_BUFFER => DescriptorType::StorageBuffer,
+      glslang_sys_descriptor_type_t_GLSLANG_SYS_DESCRIPTOR_INPUT_ATTACHMENT => DescriptorType::InputAttachment,
+      glslang_sys_descriptor_type_t_GLSLANG_SYS_DESCRIPTOR_ACCELERATION_STRUCTURE => DescriptorType::AccelerationStructure,
+      _ => return None,
+    };
+    Some(descriptor_type)
+  }
+}
+
+/// A binding of a descriptor set layout: a uniform or storage block, or an opaque uniform, see
+/// [`Reflection::descriptor_bindings`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DescriptorBinding {
+  /// `layout(set)`, 0 if not declared.
+  pub set: u32,
+  /// `layout(binding)`, or the binding assigned by [`Program::map_io`] with [`ShaderOptions::AutoMapBindings`].
+  pub binding: u32,
+  pub descriptor_type: DescriptorType,
+  /// The number of descriptors, e.g. 4 for `sampler2D textures[4]`, or `None` for a runtime-sized array like
+  /// `texture2D textures[]`.
+  pub count: Option<u32>,
+  /// The stages using the binding, in the order of [`ShaderStage::ALL`].
+  pub stages: Vec<ShaderStage>,
+}
+impl DescriptorBinding {
+  fn from_object(object: &glslang_sys_reflection_object_t) -> Option<Self> {
+    Some(DescriptorBinding {
+      set: non_negative(object.set).unwrap_or(0),
+      binding: non_negative(object.binding)?,
+      descriptor_type: DescriptorType::from_object(object)?,
+      count: non_negative(object.descriptor_count).filter(|&count| count != 0),
+      stages: stages_of(object),
+    })
+  }
+
+  /// Adds the stages of `other`, the same binding used by other stages.
+  fn merge(&mut self, other: DescriptorBinding) {
+    let mut stages = std::mem::take(&mut self.stages);
+    stages.extend(other.stages);
+    self.stages = ShaderStage::ALL.iter().copied().filter(|stage| stages.contains(stage)).collect();
+    self.count = self.count.zip(other.count).map(|(count, other_count)| count.max(other_count));
+  }
+}
+
+/// The uniforms and uniform blocks a linked [`Program`] uses, from glslang's reflection (`TProgram::buildReflection`), e.g.
+/// for creating descriptor set layouts without parsing the SPIR-V. See [`Program::reflect`].
+///
+/// Only uniforms the shaders access are reflected. Storage buffers are only included in `descriptor_bindings`. Push
+/// constants are the uniform blocks with [`ReflectedUniformBlock::push_constant`], see [`Reflection::push_constants`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Reflection {
+  pub uniforms: Vec<ReflectedUniform>,
+  pub uniform_blocks: Vec<ReflectedUniformBlock>,
+  /// The blocks and opaque uniforms with a binding, sorted by set, then binding. A binding used by several stages is
+  /// listed once, with all of them in [`DescriptorBinding::stages`].
+  pub descriptor_bindings: Vec<DescriptorBinding>,
+  /// The inputs of the first stage of the program, e.g. the vertex attributes of a vertex shader.
+  pub inputs: Vec<StageInput>,
+  /// The outputs of the last stage of the program, e.g. the color attachments of a fragment shader.
+  pub outputs: Vec<StageInput>,
+  /// The stages of the program, in the order of [`ShaderStage::ALL`].
+  pub stages: Vec<StageReflection>,
+}
+impl Reflection {
+  /// # Safety
+  /// - `program` MUST be a program reflected with `glslang_sys_program_build_reflection`, with shaders of `stages`.
+  pub(crate) unsafe fn from_program(program: *mut glslang_program_t, stages: &[ShaderStage]) -> Self {
+    let uniforms = objects(program, glslang_sys_program_get_num_uniforms, glslang_sys_program_get_uniform)
+      .map(|object| ReflectedUniform {
+        name: object_name(&object),
+        gl_type: object.gl_type as u32,
+        offset: non_negative(object.offset),
+        array_size: non_negative(object.size).unwrap_or(1),
+        block_index: non_negative(object.index).map(|index| index as usize),
+        binding: non_negative(object.binding),
+        set: non_negative(object.set),
+        stages: stages_of(&object),
+      })
+      .collect();
+    let uniform_blocks = objects(program, glslang_sys_program_get_num_uniform_blocks, glslang_sys_program_get_uniform_block)
+      .map(|object| ReflectedUniformBlock {
+        name: object_name(&object),
+        size: non_negative(object.size).unwrap_or(0),
+        binding: non_negative(object.binding),
+        set: non_negative(object.set),
+        push_constant: object.push_constant != 0,
+        stages: stages_of(&object),
+      })
+      .collect();
+    let stage_input = |object: glslang_sys_reflection_object_t| StageInput {
+      name: object_name(&object),
+      location: non_negative(object.location),
+      gl_type: object.gl_type as u32,
+      stage: stages_of(&object).first().copied(),
+    };
+    let inputs = objects(program, glslang_sys_program_get_num_pipe_inputs, glslang_sys_program_get_pipe_input).map(stage_input).collect();
+    let outputs = objects(program, glslang_sys_program_get_num_pipe_outputs, glslang_sys_program_get_pipe_output).map(stage_input).collect();
+    let stages = ShaderStage::ALL.iter().copied().filter(|stage| stages.contains(stage)).map(|stage| stage_reflection(program, stage)).collect();
+    Reflection { uniforms, uniform_blocks, descriptor_bindings: descriptor_bindings(program), inputs, outputs, stages }
+  }
+
+  /// The bindings of descriptor set `set`, sorted by binding.
+  pub fn descriptor_set(&self, set: u32) -> impl Iterator<Item = &DescriptorBinding> {
+    self.descriptor_bindings.iter().filter(move |binding| binding.set == set)
+  }
+
+  /// The uniform named `name`, e.g. `Scene.light.direction`.
+  #[must_use]
+  pub fn uniform(&self, name: &str) -> Option<&ReflectedUniform> {
+    self.uniforms.iter().find(|uniform| uniform.name == name)
+  }
+
+  /// The uniform block named `name`.
+  #[must_use]
+  pub fn uniform_block(&self, name: &str) -> Option<&ReflectedUniformBlock> {
+    self.uniform_blocks.iter().find(|block| block.name == name)
+  }
+
+  /// The input named `name`, of the first stage.
+  #[must_use]
+  pub fn input(&self, name: &str) -> Option<&StageInput> {
+    self.inputs.iter().find(|input| input.name == name)
+  }
+
+  /// The output named `name`, of the last stage.
+  #[must_use]
+  pub fn output(&self, name: &str) -> Option<&StageInput> {
+    self.outputs.iter().find(|output| output.name == name)
+  }
+
+  /// The push constant block `stage` uses, if any.
+  #[must_use]
+  pub fn push_constants(&self, stage: ShaderStage) -> Option<&ReflectedUniformBlock> {
+    self.uniform_blocks.iter().find(|block| block.push_constant && block.stages.contains(&stage))
+  }
+
+  /// The spec constants and local size of `stage`, if the program has a shader of it.
+  #[must_use]
+  pub fn stage(&self, stage: ShaderStage) -> Option<&StageReflection> {
+    self.stages.iter().find(|stage_reflection| stage_reflection.stage == stage)
+  }
+}
+
+#[cfg(feature = "serde")]
+impl Reflection {
+  /// The `schema_version` of [`Reflection::to_json`]. Incremented whenever a field is renamed, removed or changes meaning;
+  /// fields may be added without a new version.
+  pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+  /// The reflection as pretty-printed JSON, e.g. for tools not written in Rust. Stages and types are the names of the
+  /// variants of [`ShaderStage`], [`DescriptorType`] and [`ScalarType`], e.g. `"Fragment"`, and absent values are `null`.
+  ///
+  /// - `schema_version`: [`Reflection::JSON_SCHEMA_VERSION`].
+  /// - `stages`: an object with a key per stage of the program, e.g. `"Vertex"`, in the order of [`ShaderStage::ALL`], each
+  ///   with what the stage uses:
+  ///   - `descriptor_bindings`: the fields of [`DescriptorBinding`].
+  ///   - `uniform_blocks`: `name`, `size`, `binding`, `set` and `members`, the uniforms of the block with `name`,
+  ///     `gl_type`, `offset` and `array_size` like [`ReflectedUniform`].
+  ///   - `push_constants`: the push constant block, like an element of `uniform_blocks`, or `null`.
+  ///   - `uniforms`: the uniforms outside of blocks, with `name`, `gl_type`, `array_size`, `binding` and `set`.
+  ///   - `inputs` and `outputs`: `name`, `location` and `gl_type` of the inputs of the first stage and the outputs of the
+  ///     last, e.g. vertex attributes.
+  ///   - `spec_constants`: `name`, `id`, `gl_type` and `scalar_type`, sorted by id.
+  ///   - `local_size`: `[x, y, z]` of compute, task and mesh shaders, `null` for other stages.
+  #[must_use]
+  pub fn to_json(&self) -> String {
+    let stages = self.stages.iter().map(|stage| (stage.stage, json::Stage::new(self, stage))).collect();
+    serde_json::to_string_pretty(&json::Reflection { schema_version: Self::JSON_SCHEMA_VERSION, stages: json::Stages(stages) })
+      .expect("reflection data has no maps with non-string keys, so it always serializes to JSON")
+  }
+}
+
+/// The schema of [`Reflection::to_json`], borrowing a [`Reflection`].
+#[cfg(feature = "serde")]
+mod json {
+  use serde::ser::{Serialize, Serializer};
+
+  use crate::*;
+
+  #[derive(serde::Serialize)]
+  pub(super) struct Reflection<'a> {
+    pub schema_version: u32,
+    pub stages: Stages<'a>,
+  }
+
+  /// Serialized as an object keyed by stage, in order.
+  pub(super) struct Stages<'a>(pub Vec<(ShaderStage, Stage<'a>)>);
+  impl Serialize for Stages<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+      serializer.collect_map(self.0.iter().map(|(stage, json)| (stage, json)))
+    }
+  }
+
+  #[derive(serde::Serialize)]
+  pub(super) struct Stage<'a> {
+    descriptor_bindings: Vec<&'a DescriptorBinding>,
+    uniform_blocks: Vec<Block<'a>>,
+    push_constants: Option<Block<'a>>,
+    uniforms: Vec<Uniform<'a>>,
+    inputs: Vec<Input<'a>>,
+    outputs: Vec<Input<'a>>,
+    spec_constants: Vec<SpecConstant<'a>>,
+    local_size: Option<[u32; 3]>,
+  }
+  impl<'a> Stage<'a> {
+    pub(super) fn new(reflection: &'a crate::Reflection, stage_reflection: &'a StageReflection) -> Self {
+      let stage = stage_reflection.stage;
+      let blocks = reflection.uniform_blocks.iter().enumerate().filter(|(_, block)| block.stages.contains(&stage));
+      let block = |(index, block): (usize, &'a ReflectedUniformBlock)| Block {
+        name: &block.name,
+        size: block.size,
+        binding: block.binding,
+        set: block.set,
+        members: reflection
+          .uniforms
+          .iter()
+          .filter(|uniform| uniform.block_index == Some(index))
+          .map(|uniform| Member { name: &uniform.name, gl_type: uniform.gl_type, offset: uniform.offset, array_size: uniform.array_size })
+          .collect(),
+      };
+      let input = |input: &'a StageInput| Input { name: &input.name, location: input.location, gl_type: input.gl_type };
+      Stage {
+        descriptor_bindings: reflection.descriptor_bindings.iter().filter(|binding| binding.stages.contains(&stage)).collect(),
+        uniform_blocks: blocks.clone().filter(|(_, block)| !block.push_constant).map(block).collect(),
+        push_constants: blocks.clone().find(|(_, block)| block.push_constant).map(block),
+        uniforms: reflection
+          .uniforms
+          .iter()
+          .filter(|uniform| uniform.block_index.is_none() && uniform.stages.contains(&stage))
+          .map(|uniform| Uniform { name: &uniform.name, gl_type: uniform.gl_type, array_size: uniform.array_size, binding: uniform.binding, set: uniform.set })
+          .collect(),
+        inputs: reflection.inputs.iter().filter(|input| input.stage == Some(stage)).map(input).collect(),
+        outputs: reflection.outputs.iter().filter(|output| output.stage == Some(stage)).map(input).collect(),
+        spec_constants: stage_reflection
+          .spec_constants
+          .iter()
+          .map(|spec_constant| SpecConstant { name: &spec_constant.name, id: spec_constant.id, gl_type: spec_constant.gl_type, scalar_type: spec_constant.scalar_type() })
+          .collect(),
+        local_size: stage_reflection.local_size,
+      }
+    }
+  }
+
+  #[derive(serde::Serialize)]
+  struct Block<'a> {
+    name: &'a str,
+    size: u32,
+    binding: Option<u32>,
+    set: Option<u32>,
+    members: Vec<Member<'a>>,
+  }
+
+  #[derive(serde::Serialize)]
+  struct Member<'a> {
+    name: &'a str,
+    gl_type: u32,
+    offset: Option<u32>,
+    array_size: u32,
+  }
+
+  #[derive(serde::Serialize)]
+  struct Uniform<'a> {
+    name: &'a str,
+    gl_type: u32,
+    array_size: u32,
+    binding: Option<u32>,
+    set: Option<u32>,
+  }
+
+  #[derive(serde::Serialize)]
+  struct Input<'a> {
+    name: &'a str,
+    location: Option<u32>,
+    gl_type: u32,
+  }
+
+  #[derive(serde::Serialize)]
+  struct SpecConstant<'a> {
+    name: &'a str,
+    id: u32,
+    gl_type: u32,
+    scalar_type: Option<ScalarType>,
+  }
+}
+
+/// The descriptor bindings of the uniforms, uniform blocks and storage blocks of a reflected program, merged by set and
+/// binding.
+///
+/// # Safety
+/// - `program` MUST be a program reflected with `glslang_sys_program_build_reflection`.
+unsafe fn descriptor_bindings(program: *mut glslang_program_t) -> Vec<DescriptorBinding> {
+  let uniforms = objects(program, glslang_sys_program_get_num_uniforms, glslang_sys_program_get_uniform);
+  let uniform_blocks = objects(program, glslang_sys_program_get_num_uniform_blocks, glslang_sys_program_get_uniform_block);
+  let buffer_blocks = objects(program, glslang_sys_program_get_num_buffer_blocks, glslang_sys_program_get_buffer_block);
+
+  let mut bindings: Vec<DescriptorBinding> = Vec::new();
+  for binding in uniforms.chain(uniform_blocks).chain(buffer_blocks).filter_map(|object| DescriptorBinding::from_object(&object)) {
+    match bindings.iter_mut().find(|existing| (existing.set, existing.binding) == (binding.set, binding.binding)) {
+      Some(existing) => existing.merge(binding),
+      None => bindings.push(binding),
+    }
+  }
+  bindings.sort_by_key(|binding| (binding.set, binding.binding));
+  bindings
+}
+
+/// The spec constants and local size of the shader of `stage` in a linked program.
+///
+/// # Safety
+/// - `program` MUST be a linked program with a shader of `stage`.
+unsafe fn stage_reflection(program: *mut glslang_program_t, stage: ShaderStage) -> StageReflection {
+  let mut count = 0;
+  let raw_spec_constants = glslang_sys_program_get_spec_constants(program, stage.into(), &mut count);
+  let spec_constants = if raw_spec_constants.is_null() {
+    Vec::new()
+  }
+  else {
+    let spec_constants = std::slice::from_raw_parts(raw_spec_constants, count)
+      .iter()
+      .map(|spec_constant| SpecConstant {
+        name: CStr::from_ptr(spec_constant.name).to_string_lossy().into_owned(),
+        id: spec_constant.id,
+        gl_type: spec_constant.gl_type as u32,
+      })
+      .collect();
+    glslang_sys_free(raw_spec_constants as *mut c_void);
+    spec_constants
+  };
+
+  let mut local_size = [1; 3];
+  let has_local_size = matches!(stage, ShaderStage::Compute | ShaderStage::Task | ShaderStage::Mesh)
+    && glslang_sys_program_get_local_size(program, stage.into(), local_size.as_mut_ptr()) != 0;
+  StageReflection { stage, spec_constants, local_size: has_local_size.then_some(local_size) }
+}
+
+/// The stages using `object`, in the order of [`ShaderStage::ALL`].
+fn stages_of(object: &glslang_sys_reflection_object_t) -> Vec<ShaderStage> {
+  ShaderStage::ALL.iter().copied().filter(|&stage| object.stages & (1 << glslang_stage_t::from(stage)) != 0).collect()
+}
+
+/// The objects of one kind of a reflected program, read with `get_count` and `get`.
+///
+/// # Safety
+/// - `program` MUST be a program reflected with `glslang_sys_program_build_reflection`.
+unsafe fn objects(
+  program: *mut glslang_program_t,
+  get_count: unsafe extern "C" fn(*mut glslang_program_t) -> c_int,
+  get: unsafe extern "C" fn(*mut glslang_program_t, c_int, *mut glslang_sys_reflection_object_t),
+) -> impl Iterator<Item = glslang_sys_reflection_object_t> {
+  (0..get_count(program)).map(move |index| {
+    let mut object = std::mem::zeroed();
+    get(program, index, &mut object);
+    object
+  })
+}
+
+/// # Safety
+/// - The name of `object` MUST point to a valid, null-terminated C string.
+unsafe fn object_name(object: &glslang_sys_reflection_object_t) -> String {
+  CStr::from_ptr(object.name).to_string_lossy().into_owned()
+}
+
+/// The scalar type and component count of the OpenGL type enum of a scalar or vector, see [`StageInput::vector_type`].
+fn gl_vector_type(gl_type: u32) -> Option<(ScalarType, u32)> {
+  let vector_type = match gl_type {
+    0x1406 => (ScalarType::Float, 1),
+    0x8B50 => (ScalarType::Float, 2),
+    0x8B51 => (ScalarType::Float, 3),
+    0x8B52 => (ScalarType::Float, 4),
+    0x140A => (ScalarType::Double, 1),
+    0x8FFC => (ScalarType::Double, 2),
+    0x8FFD => (ScalarType::Double, 3),
+    0x8FFE => (ScalarType::Double, 4),
+    0x1404 => (ScalarType::Int, 1),
+    0x8B53 => (ScalarType::Int, 2),
+    0x8B54 => (ScalarType::Int, 3),
+    0x8B55 => (ScalarType::Int, 4),
+    0x1405 => (ScalarType::Uint, 1),
+    0x8DC6 => (ScalarType::Uint, 2),
+    0x8DC7 => (ScalarType::Uint, 3),
+    0x8DC8 => (ScalarType::Uint, 4),
+    0x8B56 => (ScalarType::Bool, 1),
+    0x8B57 => (ScalarType::Bool, 2),
+    0x8B58 => (ScalarType::Bool, 3),
+    0x8B59 => (ScalarType::Bool, 4),
+    _ => return None,
+  };
+  Some(vector_type)
+}
+
+/// glslang's reflection uses -1 for absent values.
+fn non_negative(value: c_int) -> Option<u32> {
+  u32::try_from(value).ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const GL_FLOAT: u32 = 0x1406;
+  #[cfg(feature = "serde")]
+  const GL_UNSIGNED_INT: u32 = 0x1405;
+  const GL_FLOAT_VEC3: u32 = 0x8B51;
+  const GL_FLOAT_VEC4: u32 = 0x8B52;
+  const GL_FLOAT_MAT4: u32 = 0x8B5C;
+  const GL_SAMPLER_2D: u32 = 0x8B5E;
+
+  const SOURCE: &str = r##"
+    #version 450
+    struct Light {
+      vec3 direction;
+      float intensity;
+    };
+    layout(set = 1, binding = 2) uniform Scene {
+      mat4 view_projection;
+      Light light;
+      vec4 colors[4];
+    } scene;
+    layout(set = 0, binding = 0) uniform sampler2D albedo;
+    layout(location = 0) in vec2 in_uv;
+    layout(location = 0) out vec4 out_color;
+    void main() {
+      vec4 lit = scene.colors[1] * scene.light.intensity * max(dot(scene.light.direction, vec3(0.0, 0.0, 1.0)), 0.0);
+      out_color = scene.view_projection * texture(albedo, in_uv) + lit;
+    }
+  "##;
+
+  fn reflect(source: &str) -> Reflection {
+    let input = CompileInput::new().source(source).stage(ShaderStage::Fragment);
+    let mut program = Program::new();
+    program.add_shader(Shader::new(&input).unwrap()).unwrap();
+    program.link().unwrap();
+    let reflection = program.reflect();
+    // glslang builds the reflection once; it is read again.
+    assert_eq!(program.reflect(), reflection);
+    reflection
+  }
+
+  #[test]
+  fn uniform_block_with_struct_and_array() {
+    let reflection = reflect(SOURCE);
+
+    assert_eq!(reflection.uniform_blocks.len(), 1, "{:?}", reflection);
+    // std140: a mat4, a vec3 and a float in 16 bytes, then 4 vec4s.
+    assert_eq!(reflection.uniform_blocks[0], ReflectedUniformBlock {
+      name: "Scene".to_owned(),
+      size: 144,
+      binding: Some(2),
+      set: Some(1),
+      push_constant: false,
+      stages: vec![ShaderStage::Fragment],
+    });
+
+    let member = |name: &str, gl_type: u32, offset: u32, array_size: u32| ReflectedUniform {
+      name: name.to_owned(),
+      gl_type,
+      offset: Some(offset),
+      array_size,
+      block_index: Some(0),
+      binding: None,
+      set: None,
+      stages: vec![ShaderStage::Fragment],
+    };
+    assert_eq!(reflection.uniform("Scene.view_projection"), Some(&member("Scene.view_projection", GL_FLOAT_MAT4, 0, 1)));
+    assert_eq!(reflection.uniform("Scene.light.direction"), Some(&member("Scene.light.direction", GL_FLOAT_VEC3, 64, 1)));
+    assert_eq!(reflection.uniform("Scene.light.intensity"), Some(&member("Scene.light.intensity", GL_FLOAT, 76, 1)));
+    assert_eq!(reflection.uniform("Scene.colors"), Some(&member("Scene.colors", GL_FLOAT_VEC4, 80, 4)));
+
+    let albedo = reflection.uniform("albedo").unwrap();
+    assert_eq!((albedo.gl_type, albedo.offset, albedo.block_index), (GL_SAMPLER_2D, None, None));
+    assert_eq!((albedo.binding, albedo.set), (Some(0), Some(0)));
+  }
+
+  #[test]
+  fn unused_uniforms_are_not_reflected() {
+    let reflection = reflect("#version 450\nlayout(binding = 0) uniform Unused { vec4 value; } unused;\nlayout(location = 0) out vec4 out_color;\nvoid main() { out_color = vec4(1.0); }\n");
+    assert_eq!((reflection.uniforms, reflection.uniform_blocks), (Vec::new(), Vec::new()));
+  }
+
+  #[test]
+  fn descriptor_bindings_of_vertex_and_fragment() {
+    let vertex = r##"
+      #version 450
+      layout(set = 0, binding = 0) uniform Camera {
+        mat4 view_projection;
+      } camera;
+      layout(location = 0) in vec3 in_position;
+      layout(location = 0) out vec2 out_uv;
+      void main() {
+        out_uv = in_position.xy;
+        gl_Position = camera.view_projection * vec4(in_position, 1.0);
+      }
+    "##;
+    let fragment = r##"
+      #version 450
+      #extension GL_EXT_nonuniform_qualifier : require
+      layout(set = 0, binding = 0) uniform Camera {
+        mat4 view_projection;
+      } camera;
+      layout(set = 1, binding = 0) uniform sampler2D material_textures[4];
+      layout(set = 1, binding = 1) uniform texture2D bindless_textures[];
+      layout(set = 1, binding = 2) uniform sampler linear_sampler;
+      layout(location = 0) in vec2 in_uv;
+      layout(location = 0) out vec4 out_color;
+      void main() {
+        int index = int(in_uv.x * 4.0);
+        vec4 bindless = texture(sampler2D(bindless_textures[nonuniformEXT(index)], linear_sampler), in_uv);
+        out_color = camera.view_projection[0] * texture(material_textures[index], in_uv) + bindless;
+      }
+    "##;
+    let inputs = [
+      CompileInput::new().source(vertex).stage(ShaderStage::Vertex),
+      CompileInput::new().source(fragment).stage(ShaderStage::Fragment),
+    ];
+    let mut program = Program::new();
+    for input in &inputs {
+      program.add_shader(Shader::new(input).unwrap()).unwrap();
+    }
+    program.link().unwrap();
+    let reflection = program.reflect();
+
+    let binding = |set: u32, binding: u32, descriptor_type: DescriptorType, count: Option<u32>, stages: &[ShaderStage]| DescriptorBinding {
+      set,
+      binding,
+      descriptor_type,
+      count,
+      stages: stages.to_vec(),
+    };
+    let fragment = &[ShaderStage::Fragment];
+    assert_eq!(reflection.descriptor_bindings, vec![
+      binding(0, 0, DescriptorType::UniformBuffer, Some(1), &[ShaderStage::Vertex, ShaderStage::Fragment]),
+      binding(1, 0, DescriptorType::CombinedImageSampler, Some(4), fragment),
+      binding(1, 1, DescriptorType::SampledImage, None, fragment),
+      binding(1, 2, DescriptorType::Sampler, Some(1), fragment),
+    ]);
+    assert_eq!(reflection.descriptor_set(1).map(|binding| binding.binding).collect::<Vec<_>>(), vec![0, 1, 2]);
+    assert_eq!(reflection.descriptor_set(2).count(), 0);
+  }
+
+  #[test]
+  fn vertex_attributes() {
+    let source = r##"
+      #version 450
+      layout(location = 1) in vec3 in_position;
+      layout(location = 2) in uvec4 in_joints;
+      in vec2 in_uv;
+      layout(location = 0) out vec2 out_uv;
+      void main() {
+        out_uv = in_uv + vec2(in_joints.xy);
+        gl_Position = vec4(in_position, 1.0);
+      }
+    "##;
+    let input = CompileInput::new().source(source).stage(ShaderStage::Vertex).shader_options(ShaderOptions::AutoMapLocations);
+    let mut program = Program::new();
+    program.add_shader(Shader::new(&input).unwrap()).unwrap();
+    program.link().unwrap();
+    program.map_io().unwrap();
+    let reflection = program.reflect();
+
+    assert_eq!(reflection.inputs.len(), 3, "{:?}", reflection.inputs);
+    let attribute = |name: &str| {
+      let input = reflection.input(name).unwrap_or_else(|| panic!("No input {} in {:?}", name, reflection.inputs));
+      assert_eq!(input.stage, Some(ShaderStage::Vertex));
+      (input.location, input.vector_type())
+    };
+    assert_eq!(attribute("in_position"), (Some(1), Some((ScalarType::Float, 3))));
+    assert_eq!(attribute("in_joints"), (Some(2), Some((ScalarType::Uint, 4))));
+    // glslang's default resolver assigns locations from 0, ignoring the explicit ones, which therefore start at 1 here.
+    assert_eq!(attribute("in_uv"), (Some(0), Some((ScalarType::Float, 2))));
+
+    let out_uv = reflection.output("out_uv").unwrap();
+    assert_eq!((out_uv.location, out_uv.vector_type()), (Some(0), Some((ScalarType::Float, 2))));
+  }
+
+  #[test]
+  fn push_constants_spec_constants_and_local_size() {
+    let source = r##"
+      #version 450
+      layout(local_size_x = 8, local_size_y = 4) in;
+      layout(constant_id = 1) const float SCALE = 1.0;
+      layout(constant_id = 0) const uint ITERATIONS = 4;
+      layout(push_constant) uniform Params {
+        uvec2 size;
+        float time;
+      } params;
+      layout(set = 0, binding = 0, rgba8) uniform writeonly image2D target;
+      void main() {
+        vec4 color = vec4(0.0);
+        for (uint i = 0; i < ITERATIONS; ++i) {
+          color += vec4(params.time * SCALE);
+        }
+        if (all(lessThan(gl_GlobalInvocationID.xy, params.size))) {
+          imageStore(target, ivec2(gl_GlobalInvocationID.xy), color);
+        }
+      }
+    "##;
+    let input = CompileInput::new().source(source).stage(ShaderStage::Compute);
+    let mut program = Program::new();
+    program.add_shader(Shader::new(&input).unwrap()).unwrap();
+    program.link().unwrap();
+    let reflection = program.reflect();
+
+    let params = reflection.push_constants(ShaderStage::Compute).unwrap();
+    assert_eq!((params.name.as_str(), params.size, params.binding, params.push_constant), ("Params", 12, None, true));
+    assert_eq!(reflection.uniform("Params.time").map(|time| time.offset), Some(Some(8)));
+    assert_eq!(reflection.push_constants(ShaderStage::Fragment), None);
+    // Push constants have no descriptor.
+    assert_eq!(reflection.descriptor_bindings.len(), 1, "{:?}", reflection.descriptor_bindings);
+
+    let compute = reflection.stage(ShaderStage::Compute).unwrap();
+    assert_eq!(compute.local_size, Some([8, 4, 1]));
+    let spec_constants = compute.spec_constants.iter().map(|spec_constant| (spec_constant.name.as_str(), spec_constant.id, spec_constant.scalar_type())).collect::<Vec<_>>();
+    assert_eq!(spec_constants, [("ITERATIONS", 0, Some(ScalarType::Uint)), ("SCALE", 1, Some(ScalarType::Float))]);
+    assert_eq!(reflection.stage(ShaderStage::Vertex), None);
+  }
+
+  /// Catches changes of the format of [`Reflection::to_json`], which tools outside Rust depend on. Regenerate the golden
+  /// with `UPDATE_GOLDENS=1 cargo test --features serde to_json_golden`, and increment [`Reflection::JSON_SCHEMA_VERSION`]
+  /// if a field was renamed, removed or changed meaning.
+  #[cfg(feature = "serde")]
+  #[test]
+  fn to_json_golden() {
+    let vertex_and_fragment = vec![ShaderStage::Vertex, ShaderStage::Fragment];
+    let reflection = Reflection {
+      uniforms: vec![
+        ReflectedUniform {
+          name: "Scene.view_projection".to_owned(),
+          gl_type: GL_FLOAT_MAT4,
+          offset: Some(0),
+          array_size: 1,
+          block_index: Some(0),
+          binding: None,
+          set: None,
+          stages: vertex_and_fragment.clone(),
+        },
+        ReflectedUniform {
+          name: "Material.tint".to_owned(),
+          gl_type: GL_FLOAT_VEC4,
+          offset: Some(0),
+          array_size: 1,
+          block_index: Some(1),
+          binding: None,
+          set: None,
+          stages: vec![ShaderStage::Fragment],
+        },
+        ReflectedUniform {
+          name: "albedo".to_owned(),
+          gl_type: GL_SAMPLER_2D,
+          offset: None,
+          array_size: 1,
+          block_index: None,
+          binding: Some(0),
+          set: Some(0),
+          stages: vec![ShaderStage::Fragment],
+        },
+      ],
+      uniform_blocks: vec![
+        ReflectedUniformBlock { name: "Scene".to_owned(), size: 64, binding: Some(2), set: Some(1), push_constant: false, stages: vertex_and_fragment.clone() },
+        ReflectedUniformBlock { name: "Material".to_owned(), size: 16, binding: None, set: None, push_constant: true, stages: vec![ShaderStage::Fragment] },
+      ],
+      descriptor_bindings: vec![
+        DescriptorBinding { set: 0, binding: 0, descriptor_type: DescriptorType::CombinedImageSampler, count: None, stages: vec![ShaderStage::Fragment] },
+        DescriptorBinding { set: 1, binding: 2, descriptor_type: DescriptorType::UniformBuffer, count: Some(1), stages: vertex_and_fragment },
+      ],
+      inputs: vec![StageInput { name: "in_position".to_owned(), location: Some(0), gl_type: GL_FLOAT_VEC3, stage: Some(ShaderStage::Vertex) }],
+      outputs: vec![StageInput { name: "out_color".to_owned(), location: Some(0), gl_type: GL_FLOAT_VEC4, stage: Some(ShaderStage::Fragment) }],
+      stages: vec![
+        StageReflection { stage: ShaderStage::Vertex, spec_constants: Vec::new(), local_size: None },
+        StageReflection {
+          stage: ShaderStage::Fragment,
+          spec_constants: vec![SpecConstant { name: "SAMPLE_COUNT".to_owned(), id: 0, gl_type: GL_UNSIGNED_INT }],
+          local_size: None,
+        },
+      ],
+    };
+    let json = format!("{}\n", reflection.to_json());
+
+    let golden_path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/reflection/scene.json");
+    if std::env::var_os("UPDATE_GOLDENS").is_some_and(|value| value == "1") {
+      std::fs::write(&golden_path, &json).unwrap();
+    }
+    assert_eq!(json, std::fs::read_to_string(&golden_path).unwrap(), "Run with UPDATE_GOLDENS=1 to accept the new output.");
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn to_json_of_linked_program() {
+    let json: serde_json::Value = serde_json::from_str(&reflect(SOURCE).to_json()).unwrap();
+    assert_eq!(json["schema_version"], Reflection::JSON_SCHEMA_VERSION);
+    let fragment = &json["stages"]["Fragment"];
+    assert_eq!(fragment["uniform_blocks"][0]["name"], "Scene");
+    let members = fragment["uniform_blocks"][0]["members"].as_array().unwrap();
+    let direction = members.iter().find(|member| member["name"] == "Scene.light.direction").unwrap();
+    assert_eq!(direction["offset"], 64);
+    assert_eq!(fragment["descriptor_bindings"][0]["descriptor_type"], "CombinedImageSampler");
+    assert_eq!(fragment["inputs"][0]["name"], "in_uv");
+    assert_eq!((&fragment["push_constants"], &fragment["local_size"]), (&serde_json::Value::Null, &serde_json::Value::Null));
+    assert_eq!(json["stages"].as_object().unwrap().len(), 1);
+  }
+}
@@ -0,0 +1,122 @@
+//! glslang's SPIR-V remapper (SPVRemapper), linked with the `remapper` feature, which builds glslang with
+//! `ENABLE_SPVREMAPPER=ON`.
+
+use std::ptr;
+
+use bitflags::bitflags;
+
+use crate::*;
+
+bitflags! {
+  /// Options of [`Spirv::remap`] (`spv::spirvbin_base_t::Options`), like the flags of glslang's `spirv-remap` tool.
+  // The casts are needed where `glslang_sys_remap_options_t` (being an enum) is `i32`.
+  pub struct RemapOptions: u32 {
+    /// Removes debug instructions, e.g. `OpName` and `OpLine`.
+    #[allow(clippy::unnecessary_cast)]
+    const Strip = glslang_sys_remap_options_t_GLSLANG_SYS_REMAP_STRIP as u32;
+    /// Numbers the ids of types after a hash of their definitions.
+    #[allow(clippy::unnecessary_cast)]
+    const MapTypes = glslang_sys_remap_options_t_GLSLANG_SYS_REMAP_MAP_TYPES as u32;
+    /// Numbers named ids after a hash of their names.
+    #[allow(clippy::unnecessary_cast)]
+    const MapNames = glslang_sys_remap_options_t_GLSLANG_SYS_REMAP_MAP_NAMES as u32;
+    /// Numbers the ids within functions after a hash of their instructions.
+    #[allow(clippy::unnecessary_cast)]
+    const MapFuncs = glslang_sys_remap_options_t_GLSLANG_SYS_REMAP_MAP_FUNCS as u32;
+    /// Removes functions that are never called.
+    #[allow(clippy::unnecessary_cast)]
+    const DceFuncs = glslang_sys_remap_options_t_GLSLANG_SYS_REMAP_DCE_FUNCS as u32;
+    /// Removes unused variables.
+    #[allow(clippy::unnecessary_cast)]
+    const DceVars = glslang_sys_remap_options_t_GLSLANG_SYS_REMAP_DCE_VARS as u32;
+    /// Removes unused types.
+    #[allow(clippy::unnecessary_cast)]
+    const DceTypes = glslang_sys_remap_options_t_GLSLANG_SYS_REMAP_DCE_TYPES as u32;
+    /// Removes loads and stores of function-local variables that are only stored once.
+    #[allow(clippy::unnecessary_cast)]
+    const OptLoadStore = glslang_sys_remap_options_t_GLSLANG_SYS_REMAP_OPT_LOADSTORE as u32;
+
+    /// Canonical id numbering, so that modules differing only in ids remap to the same words (`--map all`).
+    const MapAll = Self::MapTypes.bits | Self::MapNames.bits | Self::MapFuncs.bits;
+    /// `--dce all`.
+    const DceAll = Self::DceFuncs.bits | Self::DceVars.bits | Self::DceTypes.bits;
+    /// Everything but `Strip`, keeping the module debuggable.
+    const AllButStrip = Self::MapAll.bits | Self::DceAll.bits | Self::OptLoadStore.bits;
+    /// The default of `spirv-remap`: everything, e.g. to make compiles of equivalent shaders hit the same cache entry.
+    const DoEverything = Self::Strip.bits | Self::AllButStrip.bits;
+  }
+}
+
+impl Spirv {
+  /// Remaps the module with glslang's remapper: canonical id numbering, dead code elimination and stripping, as selected
+  /// by `options`. Fails with the remapper's message, e.g. for words without a SPIR-V header.
+  pub fn remap(&self, options: RemapOptions) -> Result<Spirv, String> {
+    unsafe {
+      let mut remapped_word_count: size_t = 0;
+      let mut error: *mut c_char = ptr::null_mut();
+      let remapped = glslang_sys_remap_spirv(self.as_ptr(), self.len() as size_t, options.bits(), &mut remapped_word_count, &mut error);
+      if remapped.is_null() {
+        let message = log_text(error).into_owned();
+        glslang_sys_free_string(error);
+        return Err(message);
+      }
+      let words = std::slice::from_raw_parts(remapped, remapped_word_count as usize).to_vec();
+      glslang_sys_free(remapped as *mut std::os::raw::c_void);
+      Ok(Spirv::from(words))
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// An empty compute shader, with the ids of `main`, `void`, its function type and the function's label.
+  fn empty_compute(main: u32, void: u32, function_type: u32, label: u32) -> Spirv {
+    const MAIN: u32 = u32::from_le_bytes(*b"main");
+    Spirv::from(vec![
+      Spirv::MAGIC_NUMBER, 0x0001_0000, 0, 5, 0,
+      // OpCapability Shader
+      (2 << 16) | 17, 1,
+      // OpMemoryModel Logical GLSL450
+      (3 << 16) | 14, 0, 1,
+      // OpEntryPoint GLCompute %main "main"
+      (5 << 16) | 15, 5, main, MAIN, 0,
+      // OpExecutionMode %main LocalSize 1 1 1
+      (6 << 16) | 16, main, 17, 1, 1, 1,
+      // %void = OpTypeVoid
+      (2 << 16) | 19, void,
+      // %function_type = OpTypeFunction %void
+      (3 << 16) | 33, function_type, void,
+      // %main = OpFunction %void None %function_type
+      (5 << 16) | 54, void, main, 0, function_type,
+      // %label = OpLabel
+      (2 << 16) | 248, label,
+      // OpReturn
+      (1 << 16) | 253,
+      // OpFunctionEnd
+      (1 << 16) | 56,
+    ])
+  }
+
+  #[test]
+  fn remap_canonicalizes_ids() {
+    let a = empty_compute(1, 2, 3, 4);
+    let b = empty_compute(3, 4, 2, 1);
+    assert_ne!(a, b);
+    assert_eq!(a.remap(RemapOptions::MapAll).unwrap(), b.remap(RemapOptions::MapAll).unwrap());
+    assert_eq!(a.remap(RemapOptions::DoEverything).unwrap(), b.remap(RemapOptions::DoEverything).unwrap());
+  }
+
+  #[test]
+  fn remap_compiled_shader() {
+    let source = "#version 450\nlayout(location = 0) out vec4 color;\nvoid main() { color = vec4(0.5); }\n";
+    let spirv = compile_glsl_to_spirv(source, ShaderStage::Fragment, &TargetEnv::default()).unwrap();
+    let remapped = spirv.remap(RemapOptions::DoEverything).unwrap();
+    assert_eq!(remapped[0], Spirv::MAGIC_NUMBER);
+    // `OpName`s are stripped.
+    assert!(remapped.len() < spirv.len());
+
+    assert!(Spirv::from(vec![1, 2, 3]).remap(RemapOptions::MapAll).is_err());
+  }
+}
@@ -0,0 +1,486 @@
+use std::ffi::{CStr, CString};
+use std::hash::{Hash, Hasher};
+use std::os::raw::{c_int, c_void};
+
+use thiserror::Error;
+
+use crate::*;
+
+/// A `glslang_resource_t` that can be validated before being handed to glslang.
+///
+/// `Hash` covers every limit, e.g. for cache keys.
+#[derive(Debug, Clone, Copy)]
+#[repr(transparent)]
+pub struct ResourceLimits(pub glslang_resource_t);
+
+impl Default for ResourceLimits {
+  fn default() -> Self {
+    ResourceLimits(DEFAULT_RESOURCE_LIMITS)
+  }
+}
+impl From<glslang_resource_t> for ResourceLimits {
+  fn from(resource: glslang_resource_t) -> Self {
+    ResourceLimits(resource)
+  }
+}
+impl std::ops::Deref for ResourceLimits {
+  type Target = glslang_resource_t;
+  fn deref(&self) -> &Self::Target {
+    &self.0
+  }
+}
+impl std::ops::DerefMut for ResourceLimits {
+  fn deref_mut(&mut self) -> &mut Self::Target {
+    &mut self.0
+  }
+}
+
+/// A rule a resource limit value is checked against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitConstraint {
+  /// Must be `>= 0`. Negative values usually come from a bad config parse.
+  NonNegative,
+  /// Must be `> 0`; glslang misbehaves with zero.
+  Positive,
+  /// Must be `<= 0`.
+  NonPositive,
+  /// Should be at least the GL/Vulkan minimum-spec value. Violations are warnings.
+  MinimumSpec(c_int),
+  /// Should be at most the GL/Vulkan minimum-spec value (for negative limits). Violations are warnings.
+  MaximumSpec(c_int),
+}
+impl LimitConstraint {
+  fn is_satisfied_by(self, value: c_int) -> bool {
+    match self {
+      LimitConstraint::NonNegative => value >= 0,
+      LimitConstraint::Positive => value > 0,
+      LimitConstraint::NonPositive => value <= 0,
+      LimitConstraint::MinimumSpec(minimum) => value >= minimum,
+      LimitConstraint::MaximumSpec(maximum) => value <= maximum,
+    }
+  }
+
+  /// Whether violating this constraint is an error rather than a warning.
+  #[must_use]
+  pub fn is_error(self) -> bool {
+    !matches!(self, LimitConstraint::MinimumSpec(_) | LimitConstraint::MaximumSpec(_))
+  }
+}
+impl std::fmt::Display for LimitConstraint {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      LimitConstraint::NonNegative => write!(f, "must not be negative"),
+      LimitConstraint::Positive => write!(f, "must be greater than zero"),
+      LimitConstraint::NonPositive => write!(f, "must not be positive"),
+      LimitConstraint::MinimumSpec(minimum) => write!(f, "should be at least the minimum-spec value {}", minimum),
+      LimitConstraint::MaximumSpec(maximum) => write!(f, "should be at most the minimum-spec value {}", maximum),
+    }
+  }
+}
+
+/// A resource limit field whose value violates a [`LimitConstraint`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LimitViolation {
+  /// The field name in `glslang_resource_t`.
+  pub field_name: &'static str,
+  pub value: c_int,
+  pub constraint: LimitConstraint,
+}
+impl LimitViolation {
+  #[must_use]
+  pub fn is_error(&self) -> bool {
+    self.constraint.is_error()
+  }
+}
+impl std::fmt::Display for LimitViolation {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{} = {} {}", self.field_name, self.value, self.constraint)
+  }
+}
+
+/// A malformed `.conf` file passed to [`ResourceLimits::from_config`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ResourceConfigError {
+  #[error("Unknown resource limit: {0}")]
+  UnknownLimit(String),
+  #[error("Resource limit {0} requires a value")]
+  MissingValue(String),
+  #[error("Invalid value for resource limit {name}: {value}")]
+  InvalidValue { name: String, value: String },
+}
+
+/// The whitespace `DecodeResourceLimits` splits `.conf` files on.
+const CONFIG_DELIMITERS: &[char] = &[' ', '\t', '\n', '\r'];
+
+/// Generates `check_resource_limits`, the `Hash` impl of [`ResourceLimits`] and the field lookup of its serde impls from a
+/// table of `field: constraints;`.
+/// The table must name every field of `glslang_resource_t` (except `limits`), which the exhaustive destructuring enforces.
+macro_rules! resource_limit_checks {
+  ($($field:ident: $($constraint:expr),+;)*) => {
+    impl Hash for ResourceLimits {
+      fn hash<H: Hasher>(&self, state: &mut H) {
+        $(self.0.$field.hash(state);)*
+        let glslang_limits_t {
+          non_inductive_for_loops,
+          while_loops,
+          do_while_loops,
+          general_uniform_indexing,
+          general_attribute_matrix_vector_indexing,
+          general_varying_indexing,
+          general_sampler_indexing,
+          general_variable_indexing,
+          general_constant_matrix_vector_indexing,
+        } = self.0.limits;
+        [
+          non_inductive_for_loops,
+          while_loops,
+          do_while_loops,
+          general_uniform_indexing,
+          general_attribute_matrix_vector_indexing,
+          general_varying_indexing,
+          general_sampler_indexing,
+          general_variable_indexing,
+          general_constant_matrix_vector_indexing,
+        ].hash(state);
+      }
+    }
+
+    fn check_resource_limits(resource: &glslang_resource_t) -> Vec<LimitViolation> {
+      let glslang_resource_t { $($field: _,)* limits: _ } = resource;
+
+      let mut violations = Vec::new();
+      $(
+        $(
+          if !$constraint.is_satisfied_by(resource.$field) {
+            violations.push(LimitViolation {
+              field_name: stringify!($field),
+              value: resource.$field,
+              constraint: $constraint,
+            });
+          }
+        )+
+      )*
+      violations
+    }
+
+    /// The names of the integer fields of `glslang_resource_t`, for [`resource_limit_mut`].
+    #[cfg(feature = "serde")]
+    pub(crate) const RESOURCE_LIMIT_NAMES: &[&str] = &[$(stringify!($field),)*];
+
+    /// The integer field of `resource` named `field_name`.
+    #[cfg(feature = "serde")]
+    pub(crate) fn resource_limit_mut<'a>(resource: &'a mut glslang_resource_t, field_name: &str) -> Option<&'a mut c_int> {
+      match field_name {
+        $(stringify!($field) => Some(&mut resource.$field),)*
+        _ => None,
+      }
+    }
+  };
+}
+
+use LimitConstraint::*;
+
+// Minimum-spec values are from the Vulkan 1.0 required limits, or OpenGL 4.5 where Vulkan has no counterpart.
+resource_limit_checks! {
+  max_lights: NonNegative;
+  max_clip_planes: NonNegative;
+  max_texture_units: NonNegative;
+  max_texture_coords: NonNegative;
+  max_vertex_attribs: Positive, MinimumSpec(16);
+  max_vertex_uniform_components: NonNegative;
+  max_varying_floats: NonNegative;
+  max_vertex_texture_image_units: NonNegative;
+  max_combined_texture_image_units: Positive;
+  max_texture_image_units: Positive;
+  max_fragment_uniform_components: NonNegative;
+  max_draw_buffers: Positive, MinimumSpec(4);
+  max_vertex_uniform_vectors: NonNegative;
+  max_varying_vectors: NonNegative;
+  max_fragment_uniform_vectors: NonNegative;
+  max_vertex_output_vectors: NonNegative;
+  max_fragment_input_vectors: NonNegative;
+  min_program_texel_offset: NonPositive, MaximumSpec(-8);
+  max_program_texel_offset: NonNegative, MinimumSpec(7);
+  max_clip_distances: NonNegative, MinimumSpec(8);
+  max_compute_work_group_count_x: Positive, MinimumSpec(65535);
+  max_compute_work_group_count_y: Positive, MinimumSpec(65535);
+  max_compute_work_group_count_z: Positive, MinimumSpec(65535);
+  max_compute_work_group_size_x: Positive, MinimumSpec(128);
+  max_compute_work_group_size_y: Positive, MinimumSpec(128);
+  max_compute_work_group_size_z: Positive, MinimumSpec(64);
+  max_compute_uniform_components: NonNegative;
+  max_compute_texture_image_units: NonNegative;
+  max_compute_image_uniforms: NonNegative;
+  max_compute_atomic_counters: NonNegative;
+  max_compute_atomic_counter_buffers: NonNegative;
+  max_varying_components: NonNegative;
+  max_vertex_output_components: Positive, MinimumSpec(64);
+  max_geometry_input_components: NonNegative;
+  max_geometry_output_components: NonNegative;
+  max_fragment_input_components: Positive, MinimumSpec(64);
+  max_image_units: NonNegative;
+  max_combined_image_units_and_fragment_outputs: NonNegative;
+  max_combined_shader_output_resources: NonNegative;
+  max_image_samples: NonNegative;
+  max_vertex_image_uniforms: NonNegative;
+  max_tess_control_image_uniforms: NonNegative;
+  max_tess_evaluation_image_uniforms: NonNegative;
+  max_geometry_image_uniforms: NonNegative;
+  max_fragment_image_uniforms: NonNegative;
+  max_combined_image_uniforms: NonNegative;
+  max_geometry_texture_image_units: NonNegative;
+  max_geometry_output_vertices: Positive, MinimumSpec(256);
+  max_geometry_total_output_components: NonNegative;
+  max_geometry_uniform_components: NonNegative;
+  max_geometry_varying_components: NonNegative;
+  max_tess_control_input_components: NonNegative;
+  max_tess_control_output_components: NonNegative;
+  max_tess_control_texture_image_units: NonNegative;
+  max_tess_control_uniform_components: NonNegative;
+  max_tess_control_total_output_components: NonNegative;
+  max_tess_evaluation_input_components: NonNegative;
+  max_tess_evaluation_output_components: NonNegative;
+  max_tess_evaluation_texture_image_units: NonNegative;
+  max_tess_evaluation_uniform_components: NonNegative;
+  max_tess_patch_components: NonNegative;
+  max_patch_vertices: Positive, MinimumSpec(32);
+  max_tess_gen_level: Positive, MinimumSpec(64);
+  max_viewports: Positive;
+  max_vertex_atomic_counters: NonNegative;
+  max_tess_control_atomic_counters: NonNegative;
+  max_tess_evaluation_atomic_counters: NonNegative;
+  max_geometry_atomic_counters: NonNegative;
+  max_fragment_atomic_counters: NonNegative;
+  max_combined_atomic_counters: NonNegative;
+  max_atomic_counter_bindings: NonNegative;
+  max_vertex_atomic_counter_buffers: NonNegative;
+  max_tess_control_atomic_counter_buffers: NonNegative;
+  max_tess_evaluation_atomic_counter_buffers: NonNegative;
+  max_geometry_atomic_counter_buffers: NonNegative;
+  max_fragment_atomic_counter_buffers: NonNegative;
+  max_combined_atomic_counter_buffers: NonNegative;
+  max_atomic_counter_buffer_size: NonNegative;
+  max_transform_feedback_buffers: NonNegative;
+  max_transform_feedback_interleaved_components: NonNegative;
+  max_cull_distances: NonNegative, MinimumSpec(8);
+  max_combined_clip_and_cull_distances: NonNegative, MinimumSpec(8);
+  max_samples: NonNegative;
+  max_mesh_output_vertices_nv: NonNegative;
+  max_mesh_output_primitives_nv: NonNegative;
+  max_mesh_work_group_size_x_nv: NonNegative;
+  max_mesh_work_group_size_y_nv: NonNegative;
+  max_mesh_work_group_size_z_nv: NonNegative;
+  max_task_work_group_size_x_nv: NonNegative;
+  max_task_work_group_size_y_nv: NonNegative;
+  max_task_work_group_size_z_nv: NonNegative;
+  max_mesh_view_count_nv: NonNegative;
+  maxDualSourceDrawBuffersEXT: NonNegative;
+}
+
+/// Setters for the commonly changed limits, starting from e.g. [`ResourceLimits::default`]:
+///
+/// ```
+/// # use glslang_sys_2022::*;
+/// let limits = ResourceLimits::default()
+///   .with_max_draw_buffers(4)
+///   .modify(|limits| limits.max_viewports = 1);
+/// let input = CompileInput::new().resource(&limits);
+/// ```
+impl ResourceLimits {
+  #[must_use]
+  pub fn with_max_draw_buffers(mut self, max_draw_buffers: c_int) -> Self {
+    self.0.max_draw_buffers = max_draw_buffers;
+    self
+  }
+
+  /// `gl_MaxComputeWorkGroupSize`.
+  #[must_use]
+  pub fn with_max_compute_work_group_size(mut self, x: c_int, y: c_int, z: c_int) -> Self {
+    self.0.max_compute_work_group_size_x = x;
+    self.0.max_compute_work_group_size_y = y;
+    self.0.max_compute_work_group_size_z = z;
+    self
+  }
+
+  /// `gl_MaxComputeWorkGroupCount`.
+  #[must_use]
+  pub fn with_max_compute_work_group_count(mut self, x: c_int, y: c_int, z: c_int) -> Self {
+    self.0.max_compute_work_group_count_x = x;
+    self.0.max_compute_work_group_count_y = y;
+    self.0.max_compute_work_group_count_z = z;
+    self
+  }
+
+  /// `gl_MaxTextureImageUnits`, i.e. for fragment shaders.
+  #[must_use]
+  pub fn with_max_texture_image_units(mut self, max_texture_image_units: c_int) -> Self {
+    self.0.max_texture_image_units = max_texture_image_units;
+    self
+  }
+
+  #[must_use]
+  pub fn with_max_combined_texture_image_units(mut self, max_combined_texture_image_units: c_int) -> Self {
+    self.0.max_combined_texture_image_units = max_combined_texture_image_units;
+    self
+  }
+
+  #[must_use]
+  pub fn with_max_clip_and_cull_distances(mut self, max_clip_distances: c_int, max_cull_distances: c_int, max_combined_clip_and_cull_distances: c_int) -> Self {
+    self.0.max_clip_distances = max_clip_distances;
+    self.0.max_cull_distances = max_cull_distances;
+    self.0.max_combined_clip_and_cull_distances = max_combined_clip_and_cull_distances;
+    self
+  }
+
+  /// Changes any other limit.
+  #[must_use]
+  pub fn modify(mut self, f: impl FnOnce(&mut glslang_resource_t)) -> Self {
+    f(&mut self.0);
+    self
+  }
+
+  /// Decodes a glslangValidator `.conf` file: whitespace-separated `Name value` pairs, e.g. `MaxLights 16`.
+  /// Limits the config does not name keep their default.
+  ///
+  /// The whole config is checked before it is handed to glslang, which only prints warnings for unknown names and
+  /// stops at the first bad value.
+  ///
+  /// ```
+  /// # use glslang_sys_2022::*;
+  /// let limits = ResourceLimits::from_config("MaxDrawBuffers 4\nMaxViewports 1\n")?;
+  /// let input = CompileInput::new().resource(&limits);
+  /// # Ok::<(), ResourceConfigError>(())
+  /// ```
+  pub fn from_config(config: &str) -> Result<Self, ResourceConfigError> {
+    Self::default().apply_config(config)
+  }
+
+  /// Same as [`ResourceLimits::from_config`], but limits the config does not name keep their value in `self`.
+  pub fn apply_config(mut self, config: &str) -> Result<Self, ResourceConfigError> {
+    let names = default_resource_string().split(CONFIG_DELIMITERS).filter(|token| !token.is_empty()).step_by(2).map(str::to_owned).collect::<Vec<_>>();
+    let mut tokens = config.split(CONFIG_DELIMITERS).filter(|token| !token.is_empty());
+    while let Some(name) = tokens.next() {
+      if !names.iter().any(|known| known == name) {
+        return Err(ResourceConfigError::UnknownLimit(name.to_owned()));
+      }
+      match tokens.next() {
+        Some(value) if value.parse::<c_int>().is_ok() => (),
+        Some(value) => return Err(ResourceConfigError::InvalidValue { name: name.to_owned(), value: value.to_owned() }),
+        None => return Err(ResourceConfigError::MissingValue(name.to_owned())),
+      }
+    }
+
+    // Any NUL byte would be part of a token, which was rejected above. glslang takes a mutable pointer, so give it its own copy.
+    let config = CString::new(config).unwrap().into_raw();
+    unsafe {
+      glslang_decode_resource_limits(&mut self.0, config);
+      drop(CString::from_raw(config));
+    }
+    Ok(self)
+  }
+
+  /// Checks every limit against its documented constraint.
+  ///
+  /// Violations of hard constraints (see [`LimitConstraint::is_error`]) come first, followed by minimum-spec warnings.
+  #[must_use]
+  pub fn validate(&self) -> Vec<LimitViolation> {
+    let mut violations = check_resource_limits(&self.0);
+    violations.sort_by_key(|violation| !violation.is_error());
+    violations
+  }
+}
+
+/// glslang's default limits in glslangValidator's `.conf` format, one `Name value` line per limit, as `glslangValidator -c`
+/// prints them.
+#[must_use]
+pub fn default_resource_string() -> String {
+  unsafe {
+    let raw = glslang_default_resource_string();
+    let string = CStr::from_ptr(raw).to_string_lossy().into_owned();
+    glslang_sys_free(raw as *mut c_void);
+    string
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn default_limits_are_valid() {
+    assert_eq!(ResourceLimits::default().validate(), vec![]);
+  }
+
+  #[test]
+  fn lowered_limit_is_enforced() {
+    let source = "#version 450\nlayout(local_size_x = 128) in;\nvoid main() {}\n";
+    let input = CompileInput::new().source(source).stage(ShaderStage::Compute);
+    assert!(compile_input(&input, CompileOptionFlags::empty()).is_ok());
+
+    let limits = ResourceLimits::default().with_max_compute_work_group_size(64, 64, 64);
+    let input = input.resource(&limits);
+    assert!(matches!(compile_input(&input, CompileOptionFlags::empty()), Err(CompileError::Parse(_))));
+
+    let limits = limits.modify(|limits| limits.max_compute_work_group_size_x = 128);
+    let input = input.resource(&limits);
+    assert!(compile_input(&input, CompileOptionFlags::empty()).is_ok());
+  }
+
+  #[test]
+  fn default_resource_string_lists_limits() {
+    let string = default_resource_string();
+    assert!(string.lines().any(|line| line.starts_with("MaxLights ")), "{}", string);
+    assert!(string.contains(&format!("MaxDrawBuffers {}", DEFAULT_RESOURCE_LIMITS.max_draw_buffers)), "{}", string);
+  }
+
+  #[test]
+  fn limits_from_config() {
+    let limits = ResourceLimits::from_config("MaxComputeWorkGroupSizeX 64\r\n\tMaxLights 8\n").unwrap();
+    assert_eq!(limits.max_compute_work_group_size_x, 64);
+    assert_eq!(limits.max_lights, 8);
+    assert_eq!(limits.max_draw_buffers, DEFAULT_RESOURCE_LIMITS.max_draw_buffers);
+    // Negative values are parsed; `MaxLights -1` is then rejected by `ResourceLimits::validate` when compiling.
+    assert_eq!(ResourceLimits::from_config("MaxLights -1").unwrap().max_lights, -1);
+
+    let source = "#version 450\nlayout(local_size_x = 128) in;\nvoid main() {}\n";
+    let input = CompileInput::new().source(source).stage(ShaderStage::Compute).resource(&limits);
+    assert!(matches!(compile_input(&input, CompileOptionFlags::empty()), Err(CompileError::Parse(_))));
+
+    // The default config round-trips.
+    let limits = ResourceLimits::from_config(&default_resource_string()).unwrap();
+    assert_eq!(limits.max_compute_work_group_size_x, DEFAULT_RESOURCE_LIMITS.max_compute_work_group_size_x);
+  }
+
+  #[test]
+  fn malformed_config() {
+    assert_eq!(ResourceLimits::from_config("MaxLigths 1").unwrap_err(), ResourceConfigError::UnknownLimit("MaxLigths".to_owned()));
+    assert_eq!(ResourceLimits::from_config("MaxLights 1 MaxDrawBuffers").unwrap_err(), ResourceConfigError::MissingValue("MaxDrawBuffers".to_owned()));
+    assert_eq!(
+      ResourceLimits::from_config("MaxLights 12abc").unwrap_err(),
+      ResourceConfigError::InvalidValue { name: "MaxLights".to_owned(), value: "12abc".to_owned() },
+    );
+    assert!(matches!(ResourceLimits::from_config("MaxLights 1\0"), Err(ResourceConfigError::InvalidValue { .. })));
+    assert_eq!(ResourceLimits::from_config("").unwrap().max_lights, DEFAULT_RESOURCE_LIMITS.max_lights);
+  }
+
+  #[test]
+  fn violations_name_the_field() {
+    let limits = ResourceLimits(glslang_resource_t {
+      max_draw_buffers: 0,
+      max_lights: -1,
+      max_tess_gen_level: 32,
+      ..DEFAULT_RESOURCE_LIMITS
+    });
+
+    let violations = limits.validate();
+    assert_eq!(violations, vec![
+      LimitViolation { field_name: "max_lights", value: -1, constraint: NonNegative },
+      LimitViolation { field_name: "max_draw_buffers", value: 0, constraint: Positive },
+      LimitViolation { field_name: "max_draw_buffers", value: 0, constraint: MinimumSpec(4) },
+      LimitViolation { field_name: "max_tess_gen_level", value: 32, constraint: MinimumSpec(64) },
+    ]);
+    assert!(violations[1].is_error());
+    assert!(!violations[3].is_error());
+    assert_eq!(violations[1].to_string(), "max_draw_buffers = 0 must be greater than zero");
+  }
+}
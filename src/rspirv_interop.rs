@@ -0,0 +1,41 @@
+//! Conversion of compiled SPIR-V to [`rspirv`]'s data representation, for post-processing modules.
+
+use crate::*;
+
+impl Spirv {
+  /// Parses the module into an [`rspirv::dr::Module`].
+  ///
+  /// Fails with [`CompileError::RspirvParse`] if rspirv cannot parse the module, e.g. for instructions newer than the
+  /// SPIR-V grammar of the rspirv version in use.
+  pub fn to_rspirv(&self) -> Result<rspirv::dr::Module, CompileError> {
+    rspirv::dr::load_words(self.words()).map_err(|error| CompileError::RspirvParse(error.to_string()))
+  }
+}
+
+/// [`compile_input`], returning the SPIR-V as an [`rspirv::dr::Module`]. Warnings are dropped; use [`compile_input`] and
+/// [`Spirv::to_rspirv`] to keep them.
+pub fn compile_to_rspirv(input: &CompileInput, option_flags: CompileOptionFlags) -> Result<rspirv::dr::Module, CompileError> {
+  compile_input(input, option_flags)?.spirv.to_rspirv()
+}
+
+#[cfg(test)]
+mod tests {
+  use rspirv::dr::Operand;
+  use rspirv::spirv::ExecutionModel;
+
+  use super::*;
+
+  #[test]
+  fn entry_point() {
+    let input = CompileInput::new().source("#version 450\nlayout(local_size_x = 8) in;\nvoid main() {}\n").stage(ShaderStage::Compute);
+    let module = compile_to_rspirv(&input, CompileOptionFlags::empty()).unwrap();
+    assert_eq!(module.entry_points.len(), 1);
+    let operands = &module.entry_points[0].operands;
+    assert_eq!(operands[0], Operand::ExecutionModel(ExecutionModel::GLCompute));
+    assert_eq!(operands[2], Operand::LiteralString("main".to_owned()));
+    assert_eq!(module.header.as_ref().unwrap().version(), (1, 0));
+
+    let error = Spirv::from(vec![Spirv::MAGIC_NUMBER, 0x0001_0000]).to_rspirv().unwrap_err();
+    assert!(matches!(error, CompileError::RspirvParse(_)), "{:?}", error);
+  }
+}
@@ -0,0 +1,255 @@
+//! `Serialize` and `Deserialize` for the option types whose Rust representation is not human-editable: flags as lists of
+//! names, target environments with glslangValidator's `--target-env` names, and resource limits as the overrides of the
+//! defaults.
+
+use std::collections::BTreeMap;
+use std::os::raw::c_int;
+
+use serde::de::Error as _;
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::*;
+use crate::resource_limits::{RESOURCE_LIMIT_NAMES, resource_limit_mut};
+
+/// Serializes a bitflags type as the list of the names of its set flags, e.g. `["GenerateDebugInfo", "AddOpSource"]`.
+macro_rules! serde_flags {
+  ($name:ident { $($flag:ident,)+ }) => {
+    impl Serialize for $name {
+      fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let names: Vec<&str> = [$((stringify!($flag), $name::$flag),)+].into_iter().filter(|(_, flag)| self.contains(*flag)).map(|(name, _)| name).collect();
+        names.serialize(serializer)
+      }
+    }
+    impl<'de> Deserialize<'de> for $name {
+      fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<String>::deserialize(deserializer)?.iter().try_fold($name::empty(), |flags, name| match name.as_str() {
+          $(stringify!($flag) => Ok(flags | $name::$flag),)+
+          _ => Err(D::Error::unknown_variant(name, &[$(stringify!($flag),)+])),
+        })
+      }
+    }
+  };
+}
+
+serde_flags!(CompileOptionFlags {
+  GenerateDebugInfo,
+  AddOpSource,
+  ResourceLimitErrorsAsWarnings,
+  EnhancedMessages,
+  StripDebugInfo,
+});
+serde_flags!(ShaderOptions {
+  AutoMapBindings,
+  AutoMapLocations,
+  VulkanRulesRelaxed,
+});
+
+const CLIENT_VERSION_NAMES: &[(&str, glslang_target_client_version_t)] = &[
+  ("vulkan1.0", glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_0),
+  ("vulkan1.1", glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_1),
+  ("vulkan1.2", glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_2),
+  ("vulkan1.3", glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_3),
+  ("opengl", glslang_target_client_version_t_GLSLANG_TARGET_OPENGL_450),
+];
+const SPIRV_VERSION_NAMES: &[(&str, glslang_target_language_version_t)] = &[
+  ("spirv1.0", glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_0),
+  ("spirv1.1", glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_1),
+  ("spirv1.2", glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_2),
+  ("spirv1.3", glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_3),
+  ("spirv1.4", glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_4),
+  ("spirv1.5", glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_5),
+  ("spirv1.6", glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_6),
+];
+
+fn version_name<T: PartialEq + std::fmt::Debug>(names: &[(&'static str, T)], version: T) -> Result<&'static str, String> {
+  names.iter().find(|(_, known)| *known == version).map(|(name, _)| *name).ok_or_else(|| format!("Unknown version: {:?}", version))
+}
+
+fn version_from_name<T: Copy, E: serde::de::Error>(names: &[(&'static str, T)], name: &str) -> Result<T, E> {
+  names.iter().find(|(known, _)| *known == name).map(|(_, version)| *version).ok_or_else(|| {
+    let expected = names.iter().map(|(known, _)| format!("`{}`", known)).collect::<Vec<_>>().join(", ");
+    E::custom(format!("unknown version `{}`, expected one of {}", name, expected))
+  })
+}
+
+/// A [`TargetEnv`] as e.g. `{ "client": "Vulkan", "client_version": "vulkan1.2", "spirv_version": "spirv1.5" }`.
+/// A missing `spirv_version` is the canonical one of the client version, see [`TargetEnv::for_client_version`].
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "TargetEnv")]
+struct TargetEnvRepr {
+  client: Client,
+  client_version: String,
+  #[serde(default)]
+  spirv_version: Option<String>,
+}
+
+impl Serialize for TargetEnv {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    TargetEnvRepr {
+      client: self.client,
+      client_version: version_name(CLIENT_VERSION_NAMES, self.client_version).map_err(S::Error::custom)?.to_owned(),
+      spirv_version: Some(version_name(SPIRV_VERSION_NAMES, self.spirv_version).map_err(S::Error::custom)?.to_owned()),
+    }
+    .serialize(serializer)
+  }
+}
+impl<'de> Deserialize<'de> for TargetEnv {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let repr = TargetEnvRepr::deserialize(deserializer)?;
+    let target_env = TargetEnv::for_client_version(repr.client, version_from_name(CLIENT_VERSION_NAMES, &repr.client_version)?);
+    match repr.spirv_version {
+      Some(spirv_version) => Ok(target_env.with_spirv_version(version_from_name(SPIRV_VERSION_NAMES, &spirv_version)?)),
+      None => Ok(target_env),
+    }
+  }
+}
+
+const LIMIT_FLAG_NAMES: &[&str] = &[
+  "non_inductive_for_loops",
+  "while_loops",
+  "do_while_loops",
+  "general_uniform_indexing",
+  "general_attribute_matrix_vector_indexing",
+  "general_varying_indexing",
+  "general_sampler_indexing",
+  "general_variable_indexing",
+  "general_constant_matrix_vector_indexing",
+];
+
+fn limit_flag_mut<'a>(limits: &'a mut glslang_limits_t, name: &str) -> Option<&'a mut bool> {
+  match name {
+    "non_inductive_for_loops" => Some(&mut limits.non_inductive_for_loops),
+    "while_loops" => Some(&mut limits.while_loops),
+    "do_while_loops" => Some(&mut limits.do_while_loops),
+    "general_uniform_indexing" => Some(&mut limits.general_uniform_indexing),
+    "general_attribute_matrix_vector_indexing" => Some(&mut limits.general_attribute_matrix_vector_indexing),
+    "general_varying_indexing" => Some(&mut limits.general_varying_indexing),
+    "general_sampler_indexing" => Some(&mut limits.general_sampler_indexing),
+    "general_variable_indexing" => Some(&mut limits.general_variable_indexing),
+    "general_constant_matrix_vector_indexing" => Some(&mut limits.general_constant_matrix_vector_indexing),
+    _ => None,
+  }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum LimitValue {
+  Integer(c_int),
+  Flag(bool),
+}
+
+/// [`ResourceLimits`] as a map from the field names of `glslang_resource_t` and `glslang_limits_t` to the values that differ
+/// from [`ResourceLimits::default`], e.g. `{ "max_draw_buffers": 4, "while_loops": false }`. Deserializing applies the map to
+/// the defaults.
+impl Serialize for ResourceLimits {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let (mut resource, mut defaults) = (self.0, ResourceLimits::default().0);
+    let mut overrides = BTreeMap::new();
+    for &name in RESOURCE_LIMIT_NAMES {
+      let value = *resource_limit_mut(&mut resource, name).unwrap();
+      if value != *resource_limit_mut(&mut defaults, name).unwrap() {
+        overrides.insert(name, LimitValue::Integer(value));
+      }
+    }
+    for &name in LIMIT_FLAG_NAMES {
+      let value = *limit_flag_mut(&mut resource.limits, name).unwrap();
+      if value != *limit_flag_mut(&mut defaults.limits, name).unwrap() {
+        overrides.insert(name, LimitValue::Flag(value));
+      }
+    }
+    overrides.serialize(serializer)
+  }
+}
+impl<'de> Deserialize<'de> for ResourceLimits {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let mut limits = ResourceLimits::default();
+    for (name, value) in BTreeMap::<String, LimitValue>::deserialize(deserializer)? {
+      match value {
+        LimitValue::Integer(value) => *resource_limit_mut(&mut limits.0, &name).ok_or_else(|| D::Error::custom(format!("Unknown integer resource limit: {}", name)))? = value,
+        LimitValue::Flag(value) => *limit_flag_mut(&mut limits.0.limits, &name).ok_or_else(|| D::Error::custom(format!("Unknown boolean resource limit: {}", name)))? = value,
+      }
+    }
+    Ok(limits)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn round_trip<T: Serialize + for<'de> Deserialize<'de> + PartialEq + std::fmt::Debug>(value: &T) -> String {
+    let json = serde_json::to_string(value).unwrap();
+    assert_eq!(&serde_json::from_str::<T>(&json).unwrap(), value, "{}", json);
+    json
+  }
+
+  #[test]
+  fn option_round_trips() {
+    assert_eq!(round_trip(&(CompileOptionFlags::AddOpSource | CompileOptionFlags::StripDebugInfo)), r#"["AddOpSource","StripDebugInfo"]"#);
+    assert_eq!(round_trip(&CompileOptionFlags::empty()), "[]");
+    assert_eq!(round_trip(&ShaderOptions::AutoMapLocations), r#"["AutoMapLocations"]"#);
+    assert_eq!(round_trip(&ShaderStage::ClosestHit), r#""ClosestHit""#);
+    assert_eq!(round_trip(&DebugInfo::Lines), r#""Lines""#);
+    round_trip(&SpvOptions { optimize_size: true, ..Default::default() });
+    round_trip(&HlslOptions { entry_point: Some("PSMain".to_owned()), invert_y: true, ..Default::default() });
+
+    assert_eq!(round_trip(&TargetEnv::vulkan_1_2()), r#"{"client":"Vulkan","client_version":"vulkan1.2","spirv_version":"spirv1.5"}"#);
+    round_trip(&TargetEnv::opengl_450());
+    round_trip(&TargetEnv::vulkan_1_3().with_spirv_version(glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_0));
+    assert!(serde_json::to_string(&TargetEnv::vulkan_1_0().with_spirv_version(0)).is_err());
+
+    let limits = ResourceLimits::default().with_max_draw_buffers(4).modify(|limits| limits.limits.while_loops = false);
+    let json = serde_json::to_string(&limits).unwrap();
+    assert_eq!(json, r#"{"max_draw_buffers":4,"while_loops":false}"#);
+    let deserialized: ResourceLimits = serde_json::from_str(&json).unwrap();
+    assert_eq!((deserialized.max_draw_buffers, deserialized.limits.while_loops), (4, false));
+    assert_eq!(serde_json::to_string(&deserialized).unwrap(), json);
+    assert_eq!(serde_json::to_string(&ResourceLimits::default()).unwrap(), "{}");
+  }
+
+  #[test]
+  fn invalid_names() {
+    assert!(serde_json::from_str::<CompileOptionFlags>(r#"["GenerateDebugInfo","Optimize"]"#).unwrap_err().to_string().contains("Optimize"));
+    assert!(serde_json::from_str::<CompileOptionFlags>("3").is_err());
+    assert!(serde_json::from_str::<TargetEnv>(r#"{"client":"Vulkan","client_version":"vulkan9"}"#).is_err());
+    assert!(serde_json::from_str::<ResourceLimits>(r#"{"max_lights":"many"}"#).is_err());
+    assert!(serde_json::from_str::<ResourceLimits>(r#"{"max_draw_buffers":true}"#).unwrap_err().to_string().contains("max_draw_buffers"));
+    assert!(serde_json::from_str::<ResourceLimits>(r#"{"max_bananas":1}"#).unwrap_err().to_string().contains("max_bananas"));
+  }
+
+  #[test]
+  fn compile_hand_written_job() {
+    let manifest = r##"{
+      "target_env": { "client": "Vulkan", "client_version": "vulkan1.1" },
+      "option_flags": ["GenerateDebugInfo"],
+      "jobs": [
+        {
+          "source": "#version 450\nlayout(location = 0) out vec4 out_color;\nvoid main() {\n  out_color = vec4(SCALE);\n}\n",
+          "stage": "Fragment",
+          "defines": [["SCALE", "2.0"]],
+          "option_flags": []
+        }
+      ]
+    }"##;
+    #[derive(Deserialize)]
+    struct Manifest {
+      target_env: TargetEnv,
+      option_flags: CompileOptionFlags,
+      jobs: Vec<CompileJob>,
+    }
+    let manifest: Manifest = serde_json::from_str(manifest).unwrap();
+    assert_eq!(manifest.target_env, TargetEnv::vulkan_1_1());
+    assert_eq!(manifest.jobs[0].stage(), ShaderStage::Fragment);
+
+    let defaults = CompileInput::new().target_env(manifest.target_env);
+    let results = compile_batch_with_defaults(&defaults, manifest.option_flags, &manifest.jobs);
+    let expected = CompileInput::new().source(manifest.jobs[0].source()).stage(ShaderStage::Fragment).target_env(TargetEnv::vulkan_1_1()).add_define("SCALE", Some("2.0"));
+    assert_eq!(results[0].as_ref().unwrap().spirv, compile_input(&expected, CompileOptionFlags::empty()).unwrap().spirv);
+
+    // The job round-trips, omitting what it does not set.
+    let job = CompileJob::new("void main() {}", ShaderStage::Compute);
+    assert_eq!(round_trip(&job), r#"{"source":"void main() {}","stage":"Compute"}"#);
+    round_trip(&manifest.jobs[0]);
+  }
+}
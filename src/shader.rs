@@ -0,0 +1,249 @@
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int};
+
+use crate::*;
+
+/// A glslang shader object created from a [`CompileInput`], for running individual compile stages.
+///
+/// The glslang process is kept initialized for the lifetime of the shader.
+///
+/// Not `Send`: glslang's pool-allocated containers bind to the creating thread's pool allocator, so a shader MUST be used
+/// and dropped on the thread that created it. To compile on several threads, create the shaders on each of them from a
+/// shared [`CompileInput`]:
+///
+/// ```compile_fail
+/// # use glslang_sys_2022::*;
+/// let input = CompileInput::new().source("#version 450\nvoid main() {}\n").stage(ShaderStage::Vertex);
+/// let shader = Shader::new(&input).unwrap();
+/// std::thread::scope(|scope| {
+///   scope.spawn(move || drop(shader));
+/// });
+/// ```
+pub struct Shader<'a> {
+  raw: *mut glslang_shader_t,
+  /// glslang reads the input again in every stage, and keeps pointers to the preamble.
+  input: &'a CompileInput,
+  /// glslang keeps a pointer to the names of the source, see [`CompileInput::source_file_name`].
+  _source_names: Option<Box<[*const c_char; 1]>>,
+  preprocessed: bool,
+  parsed: bool,
+  /// The includes resolved by the last `preprocess()`.
+  includes: Vec<IncludeRecord>,
+}
+impl<'a> Shader<'a> {
+  /// Fails if `input` is invalid (see [`CompileInput::as_raw`]), or requests HLSL without the `hlsl` feature.
+  pub fn new(input: &'a CompileInput) -> Result<Self, CompileError> {
+    let raw_input = input.as_raw()?;
+    if !cfg!(feature = "hlsl") && raw_input.language == glslang_source_t_GLSLANG_SOURCE_HLSL {
+      return Err(CompileError::HlslSupportDisabled);
+    }
+
+    unsafe {
+      glslang_initialize_process();
+      let raw = glslang_shader_create(raw_input);
+      let settings = input.shader_settings();
+      let source_names = settings.source_file_name.map(|source_file_name| Box::new([source_file_name.as_ptr()]));
+      if let Some(source_names) = &source_names {
+        // `raw_input` is owned by `input`, which outlives `raw`.
+        glslang_sys_shader_set_source_name(raw, raw_input, source_names.as_ptr());
+      }
+      if !settings.shader_options.is_empty() {
+        glslang_shader_set_options(raw, settings.shader_options.bits() as c_int);
+      }
+      if let Some(glsl_version) = settings.glsl_version {
+        glslang_shader_set_glsl_version(raw, glsl_version as c_int);
+      }
+      if let Some(preamble) = settings.preamble {
+        // The preamble is owned by `input`, which outlives `raw`.
+        glslang_sys_shader_set_preamble(raw, preamble.as_ptr());
+      }
+      if let Some(auto_map_bases) = settings.auto_map_bases {
+        auto_map_bases.apply(raw);
+      }
+      if raw_input.language == glslang_source_t_GLSLANG_SOURCE_HLSL {
+        if let Err(error) = settings.hlsl_options.unwrap_or(&HlslOptions::default()).apply(raw) {
+          glslang_shader_delete(raw);
+          glslang_finalize_process();
+          return Err(error);
+        }
+      }
+      settings.apply_entry_point(raw, raw_input.language);
+      Ok(Shader { raw, input, _source_names: source_names, preprocessed: false, parsed: false, includes: Vec::new() })
+    }
+  }
+
+  /// Replaces the options taken from [`CompileInput::shader_options`]. Takes effect in the following stages.
+  pub fn set_options(&mut self, options: ShaderOptions) {
+    unsafe {
+      glslang_shader_set_options(self.raw, options.bits() as c_int);
+    }
+  }
+
+  /// Replaces the version taken from [`CompileInput::override_glsl_version`]. `0` removes the override.
+  ///
+  /// Must be called before [`Shader::preprocess`] to take effect.
+  pub fn override_glsl_version(&mut self, version: u32) {
+    unsafe {
+      glslang_shader_set_glsl_version(self.raw, version as c_int);
+    }
+  }
+
+  /// Runs the preprocessor, returning the preprocessed source.
+  pub fn preprocess(&mut self) -> Result<String, CompileError> {
+    let raw_input = self.input.as_raw()?;
+    self.preprocessed = false;
+    self.includes.clear();
+    unsafe {
+      let settings = self.input.shader_settings();
+      let preprocessed = match settings.include_handler {
+        Some(include_handler) => include::preprocess_with_include_handler(self.raw, raw_input, include_handler, settings.max_include_depth, &mut self.includes)?,
+        None => glslang_shader_preprocess(self.raw, raw_input) != 0,
+      };
+      if !preprocessed {
+        return Err(CompileError::Preprocess(GlslangErrorLog::from_shader("glslang_shader_preprocess".to_string(), self.raw)));
+      }
+    }
+    self.preprocessed = true;
+    Ok(self.preprocessed_code().unwrap_or_default())
+  }
+
+  /// Parses the shader, running [`Shader::preprocess`] first if it has not been. Required for adding it to a [`Program`].
+  ///
+  /// glslang can only parse a shader once.
+  ///
+  /// # Panics
+  /// - If the shader has already been parsed.
+  pub fn parse(&mut self) -> Result<(), CompileError> {
+    assert!(!self.parsed, "Shader already parsed");
+    if !self.preprocessed {
+      self.preprocess()?;
+    }
+    let raw_input = self.input.as_raw()?;
+    unsafe {
+      if glslang_shader_parse(self.raw, raw_input) == 0 {
+        return Err(CompileError::Parse(GlslangErrorLog::from_shader("glslang_shader_parse".to_string(), self.raw)));
+      }
+    }
+    self.parsed = true;
+    Ok(())
+  }
+
+  #[must_use]
+  pub fn is_parsed(&self) -> bool {
+    self.parsed
+  }
+
+  #[must_use]
+  pub fn stage(&self) -> ShaderStage {
+    self.input.shader_stage()
+  }
+
+  /// The input the shader was created from.
+  #[must_use]
+  pub fn input(&self) -> &'a CompileInput {
+    self.input
+  }
+
+  /// The output of the last successful [`Shader::preprocess`], converted lossily if it is not valid UTF-8.
+  ///
+  /// `None` if the shader has not been preprocessed.
+  #[must_use]
+  pub fn preprocessed_code(&self) -> Option<String> {
+    if !self.preprocessed {
+      return None;
+    }
+    unsafe {
+      let code = glslang_shader_get_preprocessed_code(self.raw);
+      if code.is_null() {
+        return None;
+      }
+      Some(CStr::from_ptr(code).to_string_lossy().into_owned())
+    }
+  }
+
+  /// The includes resolved by the last [`Shader::preprocess`], see [`CompileOutput::includes`].
+  #[must_use]
+  pub fn includes(&self) -> &[IncludeRecord] {
+    &self.includes
+  }
+
+  /// The `glslang_shader_t`, valid for as long as `self` is borrowed.
+  #[must_use]
+  pub fn as_raw(&self) -> *mut glslang_shader_t {
+    self.raw
+  }
+}
+impl Drop for Shader<'_> {
+  fn drop(&mut self) {
+    unsafe {
+      glslang_shader_delete(self.raw);
+      glslang_finalize_process();
+    }
+  }
+}
+
+/// Runs only the preprocessor on `input`, returning the preprocessed source with macros expanded.
+///
+/// Useful for debugging macro-heavy shaders, or as a cache key.
+pub fn preprocess(input: &CompileInput) -> Result<String, CompileError> {
+  Shader::new(input)?.preprocess()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SOURCE: &str = "#version 450\nlayout(location = 0) out float out_value;\nvoid main() {\n  out_value = SCALE;\n}\n";
+
+  #[test]
+  fn preamble_is_expanded() {
+    let input = CompileInput::new()
+      .source(SOURCE)
+      .stage(ShaderStage::Fragment)
+      .preamble("#define SCALE 2.5\n");
+
+    let preprocessed = preprocess(&input).unwrap();
+    assert!(preprocessed.contains("out_value = 2.5;"), "{}", preprocessed);
+    assert!(!preprocessed.contains("SCALE"), "{}", preprocessed);
+
+    // The compile path honors the preamble too.
+    assert!(compile_input(&input, CompileOptionFlags::empty()).is_ok());
+    let input = CompileInput::new().source(SOURCE).stage(ShaderStage::Fragment);
+    assert!(matches!(compile_input(&input, CompileOptionFlags::empty()), Err(CompileError::Parse(_))));
+  }
+
+  #[test]
+  fn preprocessed_code_round_trip() {
+    let source = "#version 450\nlayout(local_size_x = 8) in;\nshared float values[8];\nvoid main() {\n  values[gl_LocalInvocationIndex] = 1.0;\n}\n";
+    let input = CompileInput::new().source(source).stage(ShaderStage::Compute);
+    let mut shader = Shader::new(&input).unwrap();
+    assert_eq!(shader.preprocessed_code(), None);
+
+    shader.preprocess().unwrap();
+    // The preprocessor does not preserve whitespace exactly.
+    let preprocessed = shader.preprocessed_code().unwrap();
+    assert_eq!(preprocessed.split_whitespace().collect::<Vec<_>>(), source.split_whitespace().collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn preprocess_error() {
+    let input = CompileInput::new().source("#version 450\n#if\nvoid main() {}\n");
+    assert!(matches!(preprocess(&input), Err(CompileError::Preprocess(_))));
+
+    let input = CompileInput::new().source(SOURCE).preamble("#define SCALE\0");
+    assert!(matches!(preprocess(&input), Err(CompileError::InvalidInput { input: InputKind::Preamble, .. })));
+  }
+
+  #[cfg(feature = "hlsl")]
+  #[test]
+  fn preprocess_hlsl() {
+    let input = CompileInput::new()
+      .source("float4 main() : SV_Target { return float4(SCALE, SCALE, SCALE, 1.0); }")
+      .language(SourceLanguage::Hlsl)
+      .stage(ShaderStage::Fragment)
+      .preamble("#define SCALE 0.5\n");
+
+    let preprocessed = preprocess(&input).unwrap();
+    assert!(preprocessed.contains("float4(0.5, 0.5, 0.5, 1.0)"), "{}", preprocessed);
+  }
+}
@@ -0,0 +1,431 @@
+#include "shim.h"
+
+#include <algorithm>
+#include <cstdlib>
+#include <cstring>
+#include <mutex>
+#include <sstream>
+#include <string>
+#include <vector>
+
+#include "glslang/Include/InfoSink.h"
+#include "glslang/Include/Types.h"
+#include "glslang/MachineIndependent/localintermediate.h"
+#include "glslang/Public/ShaderLang.h"
+#include "glslang/SPIRV/disassemble.h"
+#ifdef GLSLANG_SYS_SPVREMAPPER
+#include "glslang/SPIRV/SPVRemapper.h"
+#endif
+
+// Mirrors the definition in `glslang/CInterface/glslang_c_interface.cpp`, which is not installed.
+// MUST be kept in sync with the glslang commit the crate is built against.
+struct glslang_shader_s {
+  glslang::TShader* shader;
+  std::string preprocessedGLSL;
+};
+struct glslang_program_s {
+  glslang::TProgram* program;
+  std::vector<unsigned int> spirv;
+  std::string loggerMessages;
+};
+
+void glslang_sys_get_version(int* major, int* minor, int* patch, const char** flavor) {
+  const glslang::Version version = glslang::GetVersion();
+  *major = version.major;
+  *minor = version.minor;
+  *patch = version.patch;
+  *flavor = version.flavor;
+}
+
+void glslang_sys_shader_set_invert_y(glslang_shader_t* shader, bool invert) {
+  shader->shader->setInvertY(invert);
+}
+
+void glslang_sys_shader_set_dx_position_w(glslang_shader_t* shader, bool dx_position_w) {
+  shader->shader->setDxPositionW(dx_position_w);
+}
+
+// Without `ENABLE_HLSL`, glslang has no HLSL frontend, and the HLSL setters do not exist.
+void glslang_sys_shader_set_hlsl_functionality1(glslang_shader_t* shader, bool enabled) {
+#ifdef ENABLE_HLSL
+  // glslang has no way to disable it again, but it is off for new shaders.
+  if (enabled) {
+    shader->shader->setEnvTargetHlslFunctionality1();
+  }
+#endif
+}
+
+void glslang_sys_shader_set_hlsl_io_mapping(glslang_shader_t* shader, bool hlsl_io_mapping) {
+#ifdef ENABLE_HLSL
+  shader->shader->setHlslIoMapping(hlsl_io_mapping);
+#endif
+}
+
+void glslang_sys_shader_set_shift_texture_binding(glslang_shader_t* shader, unsigned int base) {
+  shader->shader->setShiftBinding(glslang::EResTexture, base);
+}
+
+void glslang_sys_shader_set_shift_sampler_binding(glslang_shader_t* shader, unsigned int base) {
+  shader->shader->setShiftBinding(glslang::EResSampler, base);
+}
+
+void glslang_sys_shader_set_shift_ubo_binding(glslang_shader_t* shader, unsigned int base) {
+  shader->shader->setShiftBinding(glslang::EResUbo, base);
+}
+
+void glslang_sys_shader_set_shift_ssbo_binding(glslang_shader_t* shader, unsigned int base) {
+  shader->shader->setShiftBinding(glslang::EResSsbo, base);
+}
+
+void glslang_sys_shader_set_shift_image_binding(glslang_shader_t* shader, unsigned int base) {
+  shader->shader->setShiftBinding(glslang::EResImage, base);
+}
+
+void glslang_sys_shader_set_shift_uav_binding(glslang_shader_t* shader, unsigned int base) {
+  shader->shader->setShiftBinding(glslang::EResUav, base);
+}
+
+void glslang_sys_shader_set_binding_base_for_set(glslang_shader_t* shader, unsigned int base, unsigned int set) {
+  for (int resource = 0; resource < glslang::EResCount; ++resource) {
+    shader->shader->setShiftBindingForSet(static_cast<glslang::TResourceType>(resource), base, set);
+  }
+}
+
+void glslang_sys_shader_set_uniform_location_base(glslang_shader_t* shader, int base) {
+  shader->shader->setUniformLocationBase(base);
+}
+
+void glslang_sys_shader_set_resource_set_binding(glslang_shader_t* shader, const char* const* bindings, size_t count) {
+  shader->shader->setResourceSetBinding(std::vector<std::string>(bindings, bindings + count));
+}
+
+void glslang_sys_shader_set_entry_point(glslang_shader_t* shader, const char* name) {
+  shader->shader->setEntryPoint(name);
+}
+
+void glslang_sys_shader_set_source_entry_point(glslang_shader_t* shader, const char* name) {
+  shader->shader->setSourceEntryPoint(name);
+}
+
+void glslang_sys_shader_set_source_name(glslang_shader_t* shader, const glslang_input_t* input, const char* const* names) {
+  shader->shader->setStringsWithLengthsAndNames(&input->code, nullptr, names, 1);
+}
+
+void glslang_sys_shader_set_preamble(glslang_shader_t* shader, const char* preamble) {
+  shader->shader->setPreamble(preamble);
+}
+
+namespace {
+
+// Forwards include requests to `glsl_include_callbacks_t`, like the includer of later glslang versions' C interface.
+class CallbackIncluder : public glslang::TShader::Includer {
+public:
+  CallbackIncluder(const glsl_include_callbacks_t& callbacks, void* context) : callbacks(callbacks), context(context) {}
+
+  IncludeResult* includeSystem(const char* headerName, const char* includerName, size_t inclusionDepth) override {
+    if (callbacks.include_system == nullptr) {
+      return nullptr;
+    }
+    return wrap(callbacks.include_system(context, headerName, includerName, inclusionDepth));
+  }
+
+  IncludeResult* includeLocal(const char* headerName, const char* includerName, size_t inclusionDepth) override {
+    if (callbacks.include_local == nullptr) {
+      return nullptr;
+    }
+    return wrap(callbacks.include_local(context, headerName, includerName, inclusionDepth));
+  }
+
+  // Called by glslang for every result returned above, whether the include succeeded or not.
+  void releaseInclude(IncludeResult* result) override {
+    if (result == nullptr) {
+      return;
+    }
+    if (callbacks.free_include_result != nullptr && result->userData != nullptr) {
+      callbacks.free_include_result(context, static_cast<glsl_include_result_t*>(result->userData));
+    }
+    delete result;
+  }
+
+private:
+  // A result with an empty `header_name` is a failure, with the error message in `header_data`.
+  static IncludeResult* wrap(glsl_include_result_t* result) {
+    if (result == nullptr) {
+      return nullptr;
+    }
+    return new IncludeResult(result->header_name != nullptr ? result->header_name : "", result->header_data, result->header_length, result);
+  }
+
+  glsl_include_callbacks_t callbacks;
+  void* context;
+};
+
+}
+
+// Mirrors `glslang_shader_preprocess` in `glslang/CInterface/glslang_c_interface.cpp`, which uses a `DirStackFileIncluder`.
+// The profile and message enums of the C interface have the same values as their C++ counterparts.
+int glslang_sys_shader_preprocess_with_callbacks(glslang_shader_t* shader, const glslang_input_t* input, const glsl_include_callbacks_t* callbacks, void* callbacks_ctx) {
+  CallbackIncluder includer(*callbacks, callbacks_ctx);
+  return shader->shader->preprocess(
+    reinterpret_cast<const TBuiltInResource*>(input->resource),
+    input->default_version,
+    static_cast<EProfile>(input->default_profile),
+    input->force_default_version_and_profile != 0,
+    input->forward_compatible != 0,
+    static_cast<EShMessages>(input->messages),
+    &shader->preprocessedGLSL,
+    includer
+  );
+}
+
+int glslang_sys_program_build_reflection(glslang_program_t* program) {
+  return program->program->buildReflection();
+}
+
+int glslang_sys_program_get_num_uniforms(glslang_program_t* program) {
+  return program->program->getNumUniformVariables();
+}
+
+int glslang_sys_program_get_num_uniform_blocks(glslang_program_t* program) {
+  return program->program->getNumUniformBlocks();
+}
+
+namespace {
+
+glslang_sys_descriptor_type_t descriptor_type(const glslang::TType& type) {
+  switch (type.getBasicType()) {
+  case glslang::EbtBlock:
+    if (type.getQualifier().storage == glslang::EvqUniform) {
+      return GLSLANG_SYS_DESCRIPTOR_UNIFORM_BUFFER;
+    }
+    return type.getQualifier().storage == glslang::EvqBuffer ? GLSLANG_SYS_DESCRIPTOR_STORAGE_BUFFER : GLSLANG_SYS_DESCRIPTOR_NONE;
+  case glslang::EbtSampler: {
+    const glslang::TSampler& sampler = type.getSampler();
+    if (sampler.isSubpass()) {
+      return GLSLANG_SYS_DESCRIPTOR_INPUT_ATTACHMENT;
+    }
+    if (sampler.isPureSampler()) {
+      return GLSLANG_SYS_DESCRIPTOR_SAMPLER;
+    }
+    if (sampler.isImage()) {
+      return sampler.isBuffer() ? GLSLANG_SYS_DESCRIPTOR_STORAGE_TEXEL_BUFFER : GLSLANG_SYS_DESCRIPTOR_STORAGE_IMAGE;
+    }
+    if (sampler.isBuffer()) {
+      return GLSLANG_SYS_DESCRIPTOR_UNIFORM_TEXEL_BUFFER;
+    }
+    return sampler.isCombined() ? GLSLANG_SYS_DESCRIPTOR_COMBINED_IMAGE_SAMPLER : GLSLANG_SYS_DESCRIPTOR_SAMPLED_IMAGE;
+  }
+  case glslang::EbtAccStruct:
+    return GLSLANG_SYS_DESCRIPTOR_ACCELERATION_STRUCTURE;
+  default:
+    return GLSLANG_SYS_DESCRIPTOR_NONE;
+  }
+}
+
+void copy_reflection_object(const glslang::TObjectReflection& object, glslang_sys_reflection_object_t* out) {
+  out->name = object.name.c_str();
+  out->gl_type = object.glDefineType;
+  out->offset = object.offset;
+  out->size = object.size;
+  out->index = object.index;
+  const glslang::TType* type = object.getType();
+  out->binding = object.getBinding();
+  out->set = type != nullptr && type->getQualifier().hasSet() ? static_cast<int>(type->getQualifier().layoutSet) : -1;
+  out->location = type != nullptr && type->getQualifier().hasLocation() ? static_cast<int>(type->getQualifier().layoutLocation) : -1;
+  out->stages = object.stages;
+  out->descriptor_type = type != nullptr ? descriptor_type(*type) : GLSLANG_SYS_DESCRIPTOR_NONE;
+  if (type == nullptr || !type->isArray()) {
+    out->descriptor_count = 1;
+  }
+  else {
+    out->descriptor_count = type->isSizedArray() ? type->getCumulativeArraySize() : 0;
+  }
+  out->push_constant = type != nullptr && type->getQualifier().isPushConstant() ? 1 : 0;
+}
+
+}
+
+void glslang_sys_program_get_uniform(glslang_program_t* program, int index, glslang_sys_reflection_object_t* uniform) {
+  copy_reflection_object(program->program->getUniform(index), uniform);
+}
+
+void glslang_sys_program_get_uniform_block(glslang_program_t* program, int index, glslang_sys_reflection_object_t* block) {
+  copy_reflection_object(program->program->getUniformBlock(index), block);
+}
+
+int glslang_sys_program_get_num_buffer_blocks(glslang_program_t* program) {
+  return program->program->getNumBufferBlocks();
+}
+
+void glslang_sys_program_get_buffer_block(glslang_program_t* program, int index, glslang_sys_reflection_object_t* block) {
+  copy_reflection_object(program->program->getBufferBlock(index), block);
+}
+
+int glslang_sys_program_get_num_pipe_inputs(glslang_program_t* program) {
+  return program->program->getNumPipeInputs();
+}
+
+void glslang_sys_program_get_pipe_input(glslang_program_t* program, int index, glslang_sys_reflection_object_t* input) {
+  copy_reflection_object(program->program->getPipeInput(index), input);
+}
+
+int glslang_sys_program_get_num_pipe_outputs(glslang_program_t* program) {
+  return program->program->getNumPipeOutputs();
+}
+
+void glslang_sys_program_get_pipe_output(glslang_program_t* program, int index, glslang_sys_reflection_object_t* output) {
+  copy_reflection_object(program->program->getPipeOutput(index), output);
+}
+
+namespace {
+
+// The disassembler and the remapper fill the same opcode tables (`spv::Parameterize`) on first use without synchronization.
+std::mutex spirv_tables_mutex;
+
+// A copy of `text` to be freed with `glslang_sys_free_string`.
+char* copy_string(const std::string& text) {
+  char* string = new char[text.size() + 1];
+  std::memcpy(string, text.c_str(), text.size() + 1);
+  return string;
+}
+
+}
+
+// `glslang_stage_t` mirrors `EShLanguage`.
+static_assert(GLSLANG_STAGE_VERTEX == static_cast<int>(EShLangVertex), "");
+static_assert(GLSLANG_STAGE_FRAGMENT == static_cast<int>(EShLangFragment), "");
+static_assert(GLSLANG_STAGE_CALLABLE_NV == static_cast<int>(EShLangCallableNV), "");
+static_assert(GLSLANG_STAGE_MESH_NV == static_cast<int>(EShLangMeshNV), "");
+static_assert(GLSLANG_STAGE_COUNT == static_cast<int>(EShLangCount), "");
+
+namespace {
+
+// Collects the symbols with `layout(constant_id)`, which the linker objects of the tree declare even if unused.
+class SpecConstantTraverser : public glslang::TIntermTraverser {
+public:
+  std::vector<glslang_sys_spec_constant_t> spec_constants;
+
+  void visitSymbol(glslang::TIntermSymbol* symbol) override {
+    const glslang::TQualifier& qualifier = symbol->getQualifier();
+    if (!qualifier.hasSpecConstantId()) {
+      return;
+    }
+    for (const glslang_sys_spec_constant_t& spec_constant : spec_constants) {
+      if (spec_constant.id == qualifier.layoutSpecConstantId) {
+        return;
+      }
+    }
+    spec_constants.push_back({ symbol->getName().c_str(), qualifier.layoutSpecConstantId, scalar_gl_type(symbol->getBasicType()) });
+  }
+
+private:
+  static int scalar_gl_type(glslang::TBasicType basic_type) {
+    switch (basic_type) {
+    case glslang::EbtFloat: return 0x1406;  // GL_FLOAT
+    case glslang::EbtDouble: return 0x140A; // GL_DOUBLE
+    case glslang::EbtInt: return 0x1404;    // GL_INT
+    case glslang::EbtUint: return 0x1405;   // GL_UNSIGNED_INT
+    case glslang::EbtBool: return 0x8B56;   // GL_BOOL
+    default: return 0;
+    }
+  }
+};
+
+}
+
+glslang_sys_spec_constant_t* glslang_sys_program_get_spec_constants(glslang_program_t* program, glslang_stage_t stage, size_t* count) {
+  *count = 0;
+  glslang::TIntermediate* intermediate = program->program->getIntermediate(static_cast<EShLanguage>(stage));
+  if (intermediate == nullptr || intermediate->getTreeRoot() == nullptr) {
+    return nullptr;
+  }
+  SpecConstantTraverser traverser;
+  intermediate->getTreeRoot()->traverse(&traverser);
+  if (traverser.spec_constants.empty()) {
+    return nullptr;
+  }
+  std::sort(traverser.spec_constants.begin(), traverser.spec_constants.end(), [](const glslang_sys_spec_constant_t& a, const glslang_sys_spec_constant_t& b) {
+    return a.id < b.id;
+  });
+  size_t size = traverser.spec_constants.size() * sizeof(glslang_sys_spec_constant_t);
+  auto* spec_constants = static_cast<glslang_sys_spec_constant_t*>(std::malloc(size));
+  std::memcpy(spec_constants, traverser.spec_constants.data(), size);
+  *count = traverser.spec_constants.size();
+  return spec_constants;
+}
+
+int glslang_sys_program_get_local_size(glslang_program_t* program, glslang_stage_t stage, unsigned int* local_size) {
+  glslang::TIntermediate* intermediate = program->program->getIntermediate(static_cast<EShLanguage>(stage));
+  if (intermediate == nullptr) {
+    return 0;
+  }
+  for (int dim = 0; dim < 3; ++dim) {
+    local_size[dim] = intermediate->getLocalSize(dim);
+  }
+  return 1;
+}
+
+char* glslang_sys_program_dump_ast(glslang_program_t* program, glslang_stage_t stage) {
+  glslang::TIntermediate* intermediate = program->program->getIntermediate(static_cast<EShLanguage>(stage));
+  if (intermediate == nullptr) {
+    return nullptr;
+  }
+  glslang::TInfoSink info_sink;
+  intermediate->output(info_sink, true);
+  return copy_string(std::string(info_sink.info.c_str()) + info_sink.debug.c_str());
+}
+
+char* glslang_sys_disassemble_spirv(const unsigned int* words, size_t word_count) {
+  std::ostringstream out;
+  {
+    std::lock_guard<std::mutex> lock(spirv_tables_mutex);
+    spv::Disassemble(out, std::vector<unsigned int>(words, words + word_count));
+  }
+  return copy_string(out.str());
+}
+
+#ifdef GLSLANG_SYS_SPVREMAPPER
+static_assert(GLSLANG_SYS_REMAP_STRIP == spv::spirvbin_base_t::STRIP, "");
+static_assert(GLSLANG_SYS_REMAP_MAP_TYPES == spv::spirvbin_base_t::MAP_TYPES, "");
+static_assert(GLSLANG_SYS_REMAP_MAP_NAMES == spv::spirvbin_base_t::MAP_NAMES, "");
+static_assert(GLSLANG_SYS_REMAP_MAP_FUNCS == spv::spirvbin_base_t::MAP_FUNCS, "");
+static_assert(GLSLANG_SYS_REMAP_DCE_FUNCS == spv::spirvbin_base_t::DCE_FUNCS, "");
+static_assert(GLSLANG_SYS_REMAP_DCE_VARS == spv::spirvbin_base_t::DCE_VARS, "");
+static_assert(GLSLANG_SYS_REMAP_DCE_TYPES == spv::spirvbin_base_t::DCE_TYPES, "");
+static_assert(GLSLANG_SYS_REMAP_OPT_LOADSTORE == spv::spirvbin_base_t::OPT_LOADSTORE, "");
+
+unsigned int* glslang_sys_remap_spirv(const unsigned int* words, size_t word_count, unsigned int options, size_t* remapped_word_count, char** error) {
+  // The error handler is a static of `spirvbin_t`, and its default exits the process.
+  static std::string first_error;
+
+  std::vector<std::uint32_t> spirv(words, words + word_count);
+  {
+    std::lock_guard<std::mutex> lock(spirv_tables_mutex);
+    first_error.clear();
+    spv::spirvbin_t::registerErrorHandler([](const std::string& message) {
+      if (first_error.empty()) {
+        first_error = message;
+      }
+    });
+    // The remapper stops at its first error.
+    spv::spirvbin_t().remap(spirv, options);
+    if (!first_error.empty()) {
+      *error = copy_string(first_error);
+      return nullptr;
+    }
+  }
+
+  unsigned int* remapped = static_cast<unsigned int*>(std::malloc(spirv.size() * sizeof(unsigned int)));
+  std::memcpy(remapped, spirv.data(), spirv.size() * sizeof(unsigned int));
+  *remapped_word_count = spirv.size();
+  return remapped;
+}
+#endif
+
+void glslang_sys_free_string(char* string) {
+  delete[] string;
+}
+
+void glslang_sys_free(void* ptr) {
+  std::free(ptr);
+}
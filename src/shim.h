@@ -0,0 +1,155 @@
+#ifndef GLSLANG_SYS_SHIM_H_
+#define GLSLANG_SYS_SHIM_H_
+
+// Functionality of glslang's C++ API that its C interface does not expose.
+// Implemented in `shim.cpp`.
+
+#include "glslang/Include/glslang_c_interface.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// `glslang::GetVersion`: the version of the linked glslang, e.g. 11.10.0. `flavor` is a static string, empty for releases.
+void glslang_sys_get_version(int* major, int* minor, int* patch, const char** flavor);
+// `TShader::setInvertY`. Must be called before `glslang_shader_parse`.
+void glslang_sys_shader_set_invert_y(glslang_shader_t* shader, bool invert);
+// `TShader::setDxPositionW`. Must be called before `glslang_shader_parse`.
+void glslang_sys_shader_set_dx_position_w(glslang_shader_t* shader, bool dx_position_w);
+// `TShader::setEnvTargetHlslFunctionality1` when `enabled`: emits the `SPV_GOOGLE_hlsl_functionality1` decorations.
+void glslang_sys_shader_set_hlsl_functionality1(glslang_shader_t* shader, bool enabled);
+// `TShader::setHlslIoMapping`: assigns bindings from HLSL `register`s when mapping IO.
+void glslang_sys_shader_set_hlsl_io_mapping(glslang_shader_t* shader, bool hlsl_io_mapping);
+// `TShader::setShiftBinding` per resource class: added to the explicit bindings of the class, e.g. of HLSL `register(t0)`
+// for textures, and the first binding auto-mapped to it, when mapping IO. Must be called before `glslang_shader_parse`.
+void glslang_sys_shader_set_shift_texture_binding(glslang_shader_t* shader, unsigned int base);
+void glslang_sys_shader_set_shift_sampler_binding(glslang_shader_t* shader, unsigned int base);
+void glslang_sys_shader_set_shift_ubo_binding(glslang_shader_t* shader, unsigned int base);
+void glslang_sys_shader_set_shift_ssbo_binding(glslang_shader_t* shader, unsigned int base);
+void glslang_sys_shader_set_shift_image_binding(glslang_shader_t* shader, unsigned int base);
+void glslang_sys_shader_set_shift_uav_binding(glslang_shader_t* shader, unsigned int base);
+// `TShader::setShiftBindingForSet` for every resource class: the binding of the explicit bindings and first auto-mapped
+// binding of descriptor set `set`, replacing the shifts above for the set. Must be called before `glslang_shader_parse`.
+void glslang_sys_shader_set_binding_base_for_set(glslang_shader_t* shader, unsigned int base, unsigned int set);
+// `TShader::setUniformLocationBase`: the first location auto-mapped to uniforms outside of blocks (OpenGL).
+void glslang_sys_shader_set_uniform_location_base(glslang_shader_t* shader, int base);
+// `TShader::setResourceSetBinding`, like glslangValidator's `--resource-set-binding`: `count` strings, either one descriptor
+// set for all resources, or `register set binding` triples. Copied. Must be called before `glslang_shader_parse`.
+void glslang_sys_shader_set_resource_set_binding(glslang_shader_t* shader, const char* const* bindings, size_t count);
+// `TShader::setEntryPoint`: the entry point name in the generated SPIR-V.
+void glslang_sys_shader_set_entry_point(glslang_shader_t* shader, const char* name);
+// `TShader::setSourceEntryPoint`: the source function compiled as the entry point, renamed to the `glslang_sys_shader_set_entry_point` name.
+void glslang_sys_shader_set_source_entry_point(glslang_shader_t* shader, const char* name);
+// `TShader::setStringsWithLengthsAndNames` for the source of `input`, naming it in diagnostics and as the includer of its
+// `#include`s. Not copied; `input` and `names` (one name) MUST outlive the shader.
+void glslang_sys_shader_set_source_name(glslang_shader_t* shader, const glslang_input_t* input, const char* const* names);
+// `TShader::setPreamble`: code preprocessed before the source, e.g. `#define`s. Not copied; MUST outlive the shader.
+void glslang_sys_shader_set_preamble(glslang_shader_t* shader, const char* preamble);
+// Same as `glslang_shader_preprocess`, resolving `#include`s with `callbacks` instead of the filesystem.
+// Every non-null result of `include_system`/`include_local` is passed to `free_include_result` exactly once.
+int glslang_sys_shader_preprocess_with_callbacks(glslang_shader_t* shader, const glslang_input_t* input, const glsl_include_callbacks_t* callbacks, void* callbacks_ctx);
+// The Vulkan descriptor type of a resource, in the order of `VkDescriptorType`, or `GLSLANG_SYS_DESCRIPTOR_NONE`.
+typedef enum {
+  GLSLANG_SYS_DESCRIPTOR_SAMPLER,
+  GLSLANG_SYS_DESCRIPTOR_COMBINED_IMAGE_SAMPLER,
+  GLSLANG_SYS_DESCRIPTOR_SAMPLED_IMAGE,
+  GLSLANG_SYS_DESCRIPTOR_STORAGE_IMAGE,
+  GLSLANG_SYS_DESCRIPTOR_UNIFORM_TEXEL_BUFFER,
+  GLSLANG_SYS_DESCRIPTOR_STORAGE_TEXEL_BUFFER,
+  GLSLANG_SYS_DESCRIPTOR_UNIFORM_BUFFER,
+  GLSLANG_SYS_DESCRIPTOR_STORAGE_BUFFER,
+  GLSLANG_SYS_DESCRIPTOR_INPUT_ATTACHMENT,
+  GLSLANG_SYS_DESCRIPTOR_ACCELERATION_STRUCTURE,
+  GLSLANG_SYS_DESCRIPTOR_NONE,
+} glslang_sys_descriptor_type_t;
+// An object of `TProgram`'s reflection (`TObjectReflection`). `name` is valid as long as the program.
+typedef struct glslang_sys_reflection_object_s {
+  const char* name;
+  // The OpenGL type enum, e.g. `GL_FLOAT_VEC4`. Meaningless for blocks.
+  int gl_type;
+  // The byte offset in its block, or -1.
+  int offset;
+  // The byte size of a block, or the array size of a uniform (1 if not an array).
+  int size;
+  // The index of the block of a uniform, or -1.
+  int index;
+  // `layout(binding)`, `layout(set)` and `layout(location)`, or -1 if not declared or assigned by IO mapping.
+  int binding;
+  int set;
+  int location;
+  // The stages using the object, as a mask of `1 << glslang_stage_t`.
+  unsigned int stages;
+  // A `glslang_sys_descriptor_type_t`, for blocks and opaque uniforms.
+  int descriptor_type;
+  // The number of descriptors: the array size, 1 if not an array, or 0 for a runtime-sized array.
+  int descriptor_count;
+  // 1 for a `layout(push_constant)` block, 0 otherwise.
+  int push_constant;
+} glslang_sys_reflection_object_t;
+// `TProgram::buildReflection` with the default options. Returns 0 if the program is not linked, or was already reflected.
+int glslang_sys_program_build_reflection(glslang_program_t* program);
+// The uniforms and uniform blocks of a program reflected with `glslang_sys_program_build_reflection`.
+int glslang_sys_program_get_num_uniforms(glslang_program_t* program);
+void glslang_sys_program_get_uniform(glslang_program_t* program, int index, glslang_sys_reflection_object_t* uniform);
+int glslang_sys_program_get_num_uniform_blocks(glslang_program_t* program);
+void glslang_sys_program_get_uniform_block(glslang_program_t* program, int index, glslang_sys_reflection_object_t* block);
+// The storage buffer blocks of a reflected program.
+int glslang_sys_program_get_num_buffer_blocks(glslang_program_t* program);
+void glslang_sys_program_get_buffer_block(glslang_program_t* program, int index, glslang_sys_reflection_object_t* block);
+// The inputs of the first stage and the outputs of the last stage of a reflected program, e.g. vertex attributes.
+int glslang_sys_program_get_num_pipe_inputs(glslang_program_t* program);
+void glslang_sys_program_get_pipe_input(glslang_program_t* program, int index, glslang_sys_reflection_object_t* input);
+int glslang_sys_program_get_num_pipe_outputs(glslang_program_t* program);
+void glslang_sys_program_get_pipe_output(glslang_program_t* program, int index, glslang_sys_reflection_object_t* output);
+// A specialization constant, `layout(constant_id)`, of a linked program. `name` is valid as long as the program.
+typedef struct glslang_sys_spec_constant_s {
+  const char* name;
+  unsigned int id;
+  // The OpenGL type enum of its scalar type, e.g. `GL_FLOAT`.
+  int gl_type;
+} glslang_sys_spec_constant_t;
+// The specialization constants of the linked intermediate of `stage`, sorted by id. Returns `*count` constants allocated
+// with `malloc`, to be freed with `glslang_sys_free`, or null if there are none or the program has no shader of `stage`.
+glslang_sys_spec_constant_t* glslang_sys_program_get_spec_constants(glslang_program_t* program, glslang_stage_t stage, size_t* count);
+// `TIntermediate::getLocalSize` of the linked intermediate of `stage`, written to `local_size[0..3]`: the workgroup size of
+// a compute, task or mesh shader, and 1s for other stages. Returns 0 if the program has no shader of `stage`.
+int glslang_sys_program_get_local_size(glslang_program_t* program, glslang_stage_t stage, unsigned int* local_size);
+// `TIntermediate::output` of the linked intermediate of `stage`: the AST dump of `glslangValidator -i`. Returns null-terminated
+// text to be freed with `glslang_sys_free_string`, or null if the program has no shader of `stage`.
+char* glslang_sys_program_dump_ast(glslang_program_t* program, glslang_stage_t stage);
+// `spv::Disassemble` of glslang's own SPIR-V library, which does not need SPIRV-Tools.
+// Returns null-terminated text to be freed with `glslang_sys_free_string`.
+char* glslang_sys_disassemble_spirv(const unsigned int* words, size_t word_count);
+void glslang_sys_free_string(char* string);
+#ifdef GLSLANG_SYS_SPVREMAPPER
+// `spv::spirvbin_base_t::Options`, checked against glslang's in `shim.cpp`.
+typedef enum {
+  GLSLANG_SYS_REMAP_STRIP = 1 << 0,
+  GLSLANG_SYS_REMAP_MAP_TYPES = 1 << 1,
+  GLSLANG_SYS_REMAP_MAP_NAMES = 1 << 2,
+  GLSLANG_SYS_REMAP_MAP_FUNCS = 1 << 3,
+  GLSLANG_SYS_REMAP_DCE_FUNCS = 1 << 4,
+  GLSLANG_SYS_REMAP_DCE_VARS = 1 << 5,
+  GLSLANG_SYS_REMAP_DCE_TYPES = 1 << 6,
+  GLSLANG_SYS_REMAP_OPT_LOADSTORE = 1 << 7,
+} glslang_sys_remap_options_t;
+// `spv::spirvbin_t::remap` of glslang's SPVRemapper with `options`, a combination of `glslang_sys_remap_options_t`.
+// Returns the remapped words, allocated with `malloc`, and sets `*remapped_word_count`. On failure, returns null and sets
+// `*error` to the remapper's message, to be freed with `glslang_sys_free_string`.
+unsigned int* glslang_sys_remap_spirv(const unsigned int* words, size_t word_count, unsigned int options, size_t* remapped_word_count, char** error);
+#endif
+// `free`, for memory glslang allocates with `malloc`.
+void glslang_sys_free(void* ptr);
+
+// Declared in `StandAlone/resource_limits_c.h`, which is not installed. Defined in the `glslang-default-resource-limits` library.
+// The default limits in glslangValidator's `.conf` format. Allocated with `malloc`.
+const char* glslang_default_resource_string(void);
+// Sets the limits named in `config`, in glslangValidator's `.conf` format, leaving the others unchanged.
+// Malformed input is reported on stdout, or throws.
+void glslang_decode_resource_limits(glslang_resource_t* resources, char* config);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif // GLSLANG_SYS_SHIM_H_
@@ -0,0 +1,157 @@
+use std::io;
+use std::ops::Deref;
+use std::path::Path;
+
+use crate::glslang_target_language_version_t;
+
+/// A SPIR-V module, as words in host byte order.
+///
+/// Dereferences to `[u32]`, e.g. for `vkCreateShaderModule`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Spirv(Vec<u32>);
+impl Spirv {
+  /// The first word of every SPIR-V module.
+  pub const MAGIC_NUMBER: u32 = 0x07230203;
+
+  #[must_use]
+  pub fn words(&self) -> &[u32] {
+    &self.0
+  }
+
+  /// The words as bytes in host byte order, without copying.
+  #[must_use]
+  pub fn as_bytes(&self) -> &[u8] {
+    // SAFETY: `u8` has no alignment requirement, and the length covers exactly the words.
+    unsafe { std::slice::from_raw_parts(self.0.as_ptr() as *const u8, std::mem::size_of_val(self.0.as_slice())) }
+  }
+
+  #[must_use]
+  pub fn into_words(self) -> Vec<u32> {
+    self.0
+  }
+
+  /// Writes the module to `path` in little-endian byte order, like glslangValidator's `.spv` files.
+  pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+    let bytes = self.0.iter().flat_map(|word| word.to_le_bytes()).collect::<Vec<_>>();
+    std::fs::write(path, bytes)
+  }
+
+  /// Reads a module written in either byte order, e.g. by [`Spirv::save`].
+  ///
+  /// Fails with [`io::ErrorKind::InvalidData`] if the file does not start with the SPIR-V magic number, or its length is not
+  /// a multiple of 4.
+  pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+    let bytes = std::fs::read(path)?;
+    if bytes.len() % 4 != 0 {
+      return Err(io::Error::new(io::ErrorKind::InvalidData, "SPIR-V byte length must be a multiple of 4"));
+    }
+
+    let words = bytes.chunks_exact(4).map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()));
+    match words.clone().next() {
+      Some(Self::MAGIC_NUMBER) => Ok(Spirv(words.collect())),
+      Some(magic_number) if magic_number.swap_bytes() == Self::MAGIC_NUMBER => Ok(Spirv(words.map(u32::swap_bytes).collect())),
+      _ => Err(io::Error::new(io::ErrorKind::InvalidData, "Missing SPIR-V magic number")),
+    }
+  }
+}
+impl Deref for Spirv {
+  type Target = [u32];
+  fn deref(&self) -> &[u32] {
+    &self.0
+  }
+}
+impl AsRef<[u32]> for Spirv {
+  fn as_ref(&self) -> &[u32] {
+    &self.0
+  }
+}
+impl From<Vec<u32>> for Spirv {
+  fn from(words: Vec<u32>) -> Self {
+    Spirv(words)
+  }
+}
+impl From<Spirv> for Vec<u32> {
+  fn from(spirv: Spirv) -> Self {
+    spirv.0
+  }
+}
+
+/// Checks that `words` start with a header for SPIR-V `version`, whose values are the version words, e.g. `0x00010300`
+/// for SPIR-V 1.3: the magic number, the version, and a nonzero bound. Catches sizes and pointers mishandled when copying a
+/// module out of glslang.
+pub(crate) fn check_header(words: &[u32], version: glslang_target_language_version_t) -> Result<(), String> {
+  const HEADER_WORDS: usize = 5;
+
+  if words.len() < HEADER_WORDS {
+    return Err(format!("{} words are shorter than the header", words.len()));
+  }
+  if words[0] != Spirv::MAGIC_NUMBER {
+    return Err(format!("Magic number {:#010x} instead of {:#010x}", words[0], Spirv::MAGIC_NUMBER));
+  }
+  // The casts are needed where `glslang_target_language_version_t` (being an enum) is `i32`.
+  #[allow(clippy::unnecessary_cast)]
+  if words[1] != version as u32 {
+    return Err(format!("Version word {:#010x} instead of {:#010x}", words[1], version as u32));
+  }
+  if words[3] == 0 {
+    return Err("Bound 0".to_owned());
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::check_header;
+  use crate::*;
+
+  #[test]
+  fn save_and_load() {
+    let spirv = compile_glsl_to_spirv("#version 450\nvoid main() {}\n", ShaderStage::Vertex, &TargetEnv::default()).unwrap();
+    assert_eq!(spirv[0], Spirv::MAGIC_NUMBER);
+    assert_eq!(spirv.as_bytes().len(), spirv.len() * 4);
+    assert_eq!(spirv.as_bytes()[..4], Spirv::MAGIC_NUMBER.to_ne_bytes());
+
+    let path = std::env::temp_dir().join(format!("glslang-sys-save-and-load-{}.spv", std::process::id()));
+    spirv.save(&path).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    assert_eq!(bytes[..4], [0x03, 0x02, 0x23, 0x07]);
+    assert_eq!(Spirv::load(&path).unwrap(), spirv);
+
+    // Big-endian files are converted.
+    std::fs::write(&path, spirv.iter().flat_map(|word| word.to_be_bytes()).collect::<Vec<_>>()).unwrap();
+    assert_eq!(Spirv::load(&path).unwrap(), spirv);
+
+    std::fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+    assert_eq!(Spirv::load(&path).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    std::fs::write(&path, [0u8; 8]).unwrap();
+    assert_eq!(Spirv::load(&path).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(spirv.clone().into_words(), spirv.words());
+  }
+
+  #[test]
+  fn header_check() {
+    let version = glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_3;
+    // Magic number, SPIR-V 1.3, glslang's generator id, bound, schema.
+    let valid = [Spirv::MAGIC_NUMBER, 0x0001_0300, 0x0008_000a, 12, 0];
+    assert_eq!(check_header(&valid, version), Ok(()));
+    assert_eq!(check_header(&[valid.as_slice(), &[0x0002_0011, 1]].concat(), version), Ok(()));
+
+    let with = |index: usize, word: u32| {
+      let mut words = valid;
+      words[index] = word;
+      words
+    };
+    assert!(check_header(&valid[..4], version).unwrap_err().contains("shorter than the header"));
+    assert!(check_header(&[], version).is_err());
+    assert!(check_header(&with(0, Spirv::MAGIC_NUMBER.swap_bytes()), version).unwrap_err().starts_with("Magic number"));
+    assert!(check_header(&with(1, 0x0001_0000), version).unwrap_err().starts_with("Version word"));
+    assert!(check_header(&valid, glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_5).unwrap_err().starts_with("Version word"));
+    assert_eq!(check_header(&with(3, 0), version), Err("Bound 0".to_owned()));
+
+    // The compile helpers return modules that pass.
+    let spirv = compile_glsl_to_spirv("#version 450\nvoid main() {}\n", ShaderStage::Compute, &TargetEnv::vulkan_1_1()).unwrap();
+    assert_eq!(check_header(&spirv, TargetEnv::vulkan_1_1().spirv_version), Ok(()));
+  }
+}
@@ -0,0 +1,381 @@
+//! SPIRV-Tools, linked with the `spirv-tools` feature, which builds glslang with `ENABLE_OPT=ON`.
+
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::io;
+use std::path::Path;
+use std::ptr;
+
+use thiserror::Error;
+
+use crate::*;
+
+/// The passes [`optimize`] runs, like the optimization flags of spirv-opt.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum OptimizationLevel {
+  /// No passes. The module is still validated, and [`OptimizeOptions::legalize_hlsl`] and
+  /// [`OptimizeOptions::strip_debug_info`] apply.
+  #[default]
+  None,
+  /// `-O` of spirv-opt.
+  Performance,
+  /// `-Os` of spirv-opt.
+  Size,
+  /// Passes by their spirv-opt flags, in order, e.g. `--eliminate-dead-code-aggressive` or `--scalar-replacement=100`.
+  Passes(Vec<String>),
+}
+
+/// Options of [`optimize_with_options`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[must_use]
+pub struct OptimizeOptions {
+  pub level: OptimizationLevel,
+  /// Runs the legalization passes before `level`, which HLSL compiled by glslang needs to be valid for Vulkan, e.g. to
+  /// remove local variables of resource types. Like `--legalize-hlsl` of spirv-opt.
+  pub legalize_hlsl: bool,
+  /// Removes debug instructions after `level`. Like `--strip-debug` of spirv-opt.
+  pub strip_debug_info: bool,
+  /// The environment the module is validated for before the passes run. `None` uses the universal rules of the module's
+  /// SPIR-V version.
+  pub target_env: Option<TargetEnv>,
+}
+
+/// Why [`optimize`] failed.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum OptimizeError {
+  /// A flag of [`OptimizationLevel::Passes`] SPIRV-Tools does not know.
+  #[error("Unknown optimizer pass {0:?}")]
+  UnknownPass(String),
+  /// The module failed validation, or a pass failed. Contains the optimizer's errors, formatted like those of
+  /// [`validate_spirv`].
+  #[error("SPIR-V optimization failed: {}", .0.join("\n"))]
+  Failed(Vec<String>),
+}
+
+/// Options of [`disassemble_spirv`]. The default is the output of spirv-dis without flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[must_use]
+pub struct DisassembleOptions {
+  /// Names ids after their `OpName`s and types, e.g. `%main` and `%v4float`, instead of numbers. Unset for `--raw-id`.
+  pub friendly_names: bool,
+  /// Aligns the `=` of instructions. Unset for `--no-indent`.
+  pub indent: bool,
+  /// Starts with the header as comments, e.g. `; Version: 1.0`. Unset for `--no-header`.
+  pub header: bool,
+  /// Comments each instruction with its byte offset. `--offsets`.
+  pub byte_offsets: bool,
+}
+impl Default for DisassembleOptions {
+  fn default() -> Self {
+    DisassembleOptions { friendly_names: true, indent: true, header: true, byte_offsets: false }
+  }
+}
+
+impl Spirv {
+  /// The module as spirv-dis prints it, see [`disassemble_spirv`].
+  pub fn disassemble(&self, options: DisassembleOptions) -> Result<String, String> {
+    disassemble_spirv(self.words(), options)
+  }
+
+  /// Writes the module as spirv-dis prints it by default to `path`, e.g. `shader.spvasm`.
+  ///
+  /// Fails with [`io::ErrorKind::InvalidData`] if the module cannot be disassembled.
+  pub fn save_spvasm(&self, path: impl AsRef<Path>) -> io::Result<()> {
+    let text = self.disassemble(DisassembleOptions::default()).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    std::fs::write(path, text)
+  }
+}
+
+/// Disassembles `words` with SPIRV-Tools, like spirv-dis. Unlike [`SpvOptions::disassemble`], `words` need not come from
+/// glslang: a module that fails to parse is an error, formatted like those of [`validate_spirv`].
+pub fn disassemble_spirv(words: &[u32], options: DisassembleOptions) -> Result<String, String> {
+  let mut flags = spv_binary_to_text_options_t_SPV_BINARY_TO_TEXT_OPTION_NONE;
+  for (enabled, flag) in [
+    (options.friendly_names, spv_binary_to_text_options_t_SPV_BINARY_TO_TEXT_OPTION_FRIENDLY_NAMES),
+    (options.indent, spv_binary_to_text_options_t_SPV_BINARY_TO_TEXT_OPTION_INDENT),
+    (!options.header, spv_binary_to_text_options_t_SPV_BINARY_TO_TEXT_OPTION_NO_HEADER),
+    (options.byte_offsets, spv_binary_to_text_options_t_SPV_BINARY_TO_TEXT_OPTION_SHOW_BYTE_OFFSET),
+  ] {
+    if enabled {
+      flags |= flag;
+    }
+  }
+
+  unsafe {
+    let context = spvContextCreate(module_env(words));
+    let mut text: spv_text = ptr::null_mut();
+    let mut diagnostic: spv_diagnostic = ptr::null_mut();
+    // The cast is needed where `spv_binary_to_text_options_t` (being an enum) is `i32`.
+    #[allow(clippy::unnecessary_cast)]
+    let result = spvBinaryToText(context, words.as_ptr(), words.len() as size_t, flags as u32, &mut text, &mut diagnostic);
+    let outcome = if result == spv_result_t_SPV_SUCCESS && !text.is_null() {
+      let bytes = std::slice::from_raw_parts((*text).str_ as *const u8, (*text).length as usize);
+      Ok(String::from_utf8_lossy(bytes).into_owned())
+    }
+    else if diagnostic.is_null() {
+      Err(format!("SPIRV-Tools failed with {}", result))
+    }
+    else {
+      Err(diagnostic_message((*diagnostic).position.index, &log_text((*diagnostic).error)))
+    };
+    spvTextDestroy(text);
+    spvDiagnosticDestroy(diagnostic);
+    spvContextDestroy(context);
+    outcome
+  }
+}
+
+/// Validates `words` with SPIRV-Tools' validator (`spirv-val`), with the rules of `target_env`, e.g. the SPIR-V versions
+/// and capabilities Vulkan 1.1 allows.
+///
+/// Each message starts with the index of the offending instruction, or of the word for a module that fails to parse, e.g.
+/// `instruction 12: ...`. SPIRV-Tools stops at the first error, so there is one message unless future versions report more.
+pub fn validate_spirv(words: &[u32], target_env: TargetEnv) -> Result<(), Vec<String>> {
+  unsafe {
+    let context = spvContextCreate(spv_target_env_for(&target_env));
+    if context.is_null() {
+      return Err(vec![format!("SPIRV-Tools does not support the target environment {:?}", target_env)]);
+    }
+    let mut diagnostic: spv_diagnostic = ptr::null_mut();
+    let result = spvValidateBinary(context, words.as_ptr(), words.len() as size_t, &mut diagnostic);
+    let outcome = if result == spv_result_t_SPV_SUCCESS {
+      Ok(())
+    }
+    else if diagnostic.is_null() {
+      Err(vec![format!("SPIRV-Tools failed with {}", result)])
+    }
+    else {
+      Err(vec![diagnostic_message((*diagnostic).position.index, &log_text((*diagnostic).error))])
+    };
+    spvDiagnosticDestroy(diagnostic);
+    spvContextDestroy(context);
+    outcome
+  }
+}
+
+/// Runs the passes of `level` over `spirv` with spirv-opt, after validating it.
+pub fn optimize(spirv: &Spirv, level: OptimizationLevel) -> Result<Spirv, OptimizeError> {
+  optimize_with_options(spirv, &OptimizeOptions { level, ..Default::default() })
+}
+
+/// [`optimize`], with HLSL legalization, debug info stripping or a target environment.
+pub fn optimize_with_options(spirv: &Spirv, options: &OptimizeOptions) -> Result<Spirv, OptimizeError> {
+  let env = options.target_env.as_ref().map_or_else(|| module_env(spirv), spv_target_env_for);
+  unsafe {
+    let optimizer = spvOptimizerCreate(env);
+    spvOptimizerSetMessageConsumer(optimizer, Some(push_optimizer_message));
+    let result = register_passes(optimizer, options).and_then(|()| run_optimizer(optimizer, spirv.words()));
+    spvOptimizerDestroy(optimizer);
+    result
+  }
+}
+
+thread_local! {
+  /// The errors of the optimizer running on this thread, as `spvOptimizerSetMessageConsumer` takes no context pointer.
+  static OPTIMIZER_ERRORS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+unsafe extern "C" fn push_optimizer_message(level: spv_message_level_t, _source: *const c_char, position: *const spv_position_t, message: *const c_char) {
+  if level > spv_message_level_t_SPV_MSG_ERROR {
+    return;
+  }
+  // Messages of passes have no position.
+  let message = match position.as_ref() {
+    Some(position) if position.index != 0 => diagnostic_message(position.index, &log_text(message)),
+    _ => log_text(message).trim_end().to_owned(),
+  };
+  OPTIMIZER_ERRORS.with(|errors| errors.borrow_mut().push(message));
+}
+
+/// # Safety
+/// - `optimizer` MUST be a valid optimizer.
+unsafe fn register_passes(optimizer: *mut spv_optimizer_t, options: &OptimizeOptions) -> Result<(), OptimizeError> {
+  let register_flag = |flag: &str| match CString::new(flag) {
+    Ok(c_flag) if spvOptimizerRegisterPassFromFlag(optimizer, c_flag.as_ptr()) => Ok(()),
+    _ => Err(OptimizeError::UnknownPass(flag.to_owned())),
+  };
+
+  if options.legalize_hlsl {
+    spvOptimizerRegisterLegalizationPasses(optimizer);
+  }
+  match &options.level {
+    OptimizationLevel::None => {},
+    OptimizationLevel::Performance => spvOptimizerRegisterPerformancePasses(optimizer),
+    OptimizationLevel::Size => spvOptimizerRegisterSizePasses(optimizer),
+    OptimizationLevel::Passes(flags) => {
+      for flag in flags {
+        register_flag(flag)?;
+      }
+    },
+  }
+  if options.strip_debug_info {
+    register_flag("--strip-debug")?;
+  }
+  Ok(())
+}
+
+/// # Safety
+/// - `optimizer` MUST be a valid optimizer, with [`push_optimizer_message`] as its message consumer.
+unsafe fn run_optimizer(optimizer: *mut spv_optimizer_t, words: &[u32]) -> Result<Spirv, OptimizeError> {
+  OPTIMIZER_ERRORS.with(|errors| errors.borrow_mut().clear());
+  // Validates the module before the passes run, by default.
+  let optimizer_options = spvOptimizerOptionsCreate();
+  let mut binary: spv_binary = ptr::null_mut();
+  let result = spvOptimizerRun(optimizer, words.as_ptr(), words.len() as size_t, &mut binary, optimizer_options);
+  spvOptimizerOptionsDestroy(optimizer_options);
+  let errors = OPTIMIZER_ERRORS.with(|errors| errors.take());
+
+  if result != spv_result_t_SPV_SUCCESS || binary.is_null() {
+    spvBinaryDestroy(binary);
+    return Err(OptimizeError::Failed(if errors.is_empty() { vec![format!("SPIRV-Tools failed with {}", result)] } else { errors }));
+  }
+  let optimized = std::slice::from_raw_parts((*binary).code, (*binary).wordCount as usize).to_vec();
+  spvBinaryDestroy(binary);
+  Ok(Spirv::from(optimized))
+}
+
+/// A message of SPIRV-Tools about the instruction at `index`.
+fn diagnostic_message(index: size_t, message: &str) -> String {
+  format!("instruction {}: {}", index, message.trim_end())
+}
+
+/// The SPIRV-Tools environment of `target_env`. Client versions SPIRV-Tools has no environment for, including those of
+/// [`Client::None`], are validated with the universal rules of the SPIR-V version.
+fn spv_target_env_for(target_env: &TargetEnv) -> spv_target_env {
+  match target_env.client_version {
+    glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_0 => spv_target_env_SPV_ENV_VULKAN_1_0,
+    glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_1 if target_env.spirv_version >= glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_4 => spv_target_env_SPV_ENV_VULKAN_1_1_SPIRV_1_4,
+    glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_1 => spv_target_env_SPV_ENV_VULKAN_1_1,
+    glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_2 => spv_target_env_SPV_ENV_VULKAN_1_2,
+    glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_3 => spv_target_env_SPV_ENV_VULKAN_1_3,
+    glslang_target_client_version_t_GLSLANG_TARGET_OPENGL_450 => spv_target_env_SPV_ENV_OPENGL_4_5,
+    _ => universal_env(target_env.spirv_version),
+  }
+}
+
+/// The universal environment of the SPIR-V version in the header of `words`.
+fn module_env(words: &[u32]) -> spv_target_env {
+  // The cast is needed where `glslang_target_language_version_t` (being an enum) is `i32`.
+  #[allow(clippy::unnecessary_cast)]
+  universal_env(words.get(1).map_or(glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_0, |&version| version as glslang_target_language_version_t))
+}
+
+fn universal_env(spirv_version: glslang_target_language_version_t) -> spv_target_env {
+  match spirv_version {
+    glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_0 => spv_target_env_SPV_ENV_UNIVERSAL_1_0,
+    glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_1 => spv_target_env_SPV_ENV_UNIVERSAL_1_1,
+    glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_2 => spv_target_env_SPV_ENV_UNIVERSAL_1_2,
+    glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_3 => spv_target_env_SPV_ENV_UNIVERSAL_1_3,
+    glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_4 => spv_target_env_SPV_ENV_UNIVERSAL_1_4,
+    glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_5 => spv_target_env_SPV_ENV_UNIVERSAL_1_5,
+    _ => spv_target_env_SPV_ENV_UNIVERSAL_1_6,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn compute_shader(target_env: TargetEnv) -> Spirv {
+    let input = CompileInput::new()
+      .source("#version 450\nlayout(local_size_x = 64) in;\nlayout(set = 0, binding = 0) buffer Data { uint values[]; };\nvoid main() { values[gl_GlobalInvocationID.x] *= 2u; }\n")
+      .stage(ShaderStage::Compute)
+      .target_env(target_env);
+    compile_input(&input, CompileOptionFlags::empty()).unwrap().spirv
+  }
+
+  #[test]
+  fn valid_module() {
+    for target_env in [TargetEnv::vulkan_1_0(), TargetEnv::vulkan_1_1().with_spirv_version(glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_4), TargetEnv::vulkan_1_3()] {
+      let spirv = compute_shader(target_env);
+      assert_eq!(validate_spirv(&spirv, target_env), Ok(()), "{:?}", target_env);
+    }
+  }
+
+  #[test]
+  fn corrupted_module() {
+    let spirv = compute_shader(TargetEnv::vulkan_1_0());
+
+    // An opcode SPIR-V does not define, in place of the first `OpCapability`.
+    let mut words = spirv.words().to_vec();
+    words[5] |= 0xffff;
+    let errors = validate_spirv(&words, TargetEnv::vulkan_1_0()).unwrap_err();
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(errors[0].starts_with("instruction "), "{:?}", errors);
+
+    // Truncated in the middle of the first instruction, a two-word `OpCapability`.
+    let errors = validate_spirv(&spirv[..6], TargetEnv::vulkan_1_0()).unwrap_err();
+    assert!(errors[0].starts_with("instruction "), "{:?}", errors);
+
+    // A valid SPIR-V 1.6 module, but Vulkan 1.0 only allows SPIR-V 1.0.
+    let spirv = compute_shader(TargetEnv::vulkan_1_3());
+    assert!(validate_spirv(&spirv, TargetEnv::vulkan_1_0()).is_err());
+  }
+
+  #[test]
+  fn optimize_for_size() {
+    let input = CompileInput::new()
+      .source("#version 450\nlayout(location = 0) out vec4 color;\nconst float unused[4] = float[](1.0, 2.0, 3.0, 4.0);\nvec4 shade(float x) { float y = x * 2.0; return vec4(y, y, y, 1.0); }\nvoid main() { color = shade(0.5); }\n")
+      .stage(ShaderStage::Fragment);
+    let spirv = compile_input(&input, CompileOptionFlags::empty()).unwrap().spirv;
+
+    let optimized = optimize(&spirv, OptimizationLevel::Size).unwrap();
+    assert!(optimized.len() <= spirv.len(), "{} > {} words", optimized.len(), spirv.len());
+    assert_eq!(optimized[0], Spirv::MAGIC_NUMBER);
+    assert_eq!(validate_spirv(&optimized, TargetEnv::vulkan_1_0()), Ok(()));
+
+    let with_debug_info = compile_input(&input, CompileOptionFlags::GenerateDebugInfo).unwrap().spirv;
+    let stripped = optimize_with_options(&with_debug_info, &OptimizeOptions { strip_debug_info: true, ..Default::default() }).unwrap();
+    assert!(stripped.len() < with_debug_info.len());
+  }
+
+  #[test]
+  fn explicit_passes() {
+    let spirv = compute_shader(TargetEnv::vulkan_1_3());
+    let options = OptimizeOptions {
+      level: OptimizationLevel::Passes(vec!["--eliminate-dead-code-aggressive".to_owned(), "--scalar-replacement=100".to_owned()]),
+      target_env: Some(TargetEnv::vulkan_1_3()),
+      ..Default::default()
+    };
+    assert_eq!(optimize_with_options(&spirv, &options).unwrap()[0], Spirv::MAGIC_NUMBER);
+
+    assert_eq!(optimize(&spirv, OptimizationLevel::Passes(vec!["--no-such-pass".to_owned()])), Err(OptimizeError::UnknownPass("--no-such-pass".to_owned())));
+    // Validated for Vulkan 1.0, which does not allow SPIR-V 1.6.
+    let options = OptimizeOptions { target_env: Some(TargetEnv::vulkan_1_0()), ..Default::default() };
+    assert!(matches!(optimize_with_options(&spirv, &options), Err(OptimizeError::Failed(errors)) if !errors.is_empty()));
+  }
+
+  #[cfg(feature = "hlsl")]
+  #[test]
+  fn legalize_hlsl() {
+    let input = CompileInput::new()
+      .source("Texture2D tex : register(t0);\nSamplerState smp : register(s0);\nfloat4 sample_local(float2 uv) { Texture2D t = tex; return t.Sample(smp, uv); }\nfloat4 main(float2 uv : TEXCOORD0) : SV_Target { return sample_local(uv); }\n")
+      .stage(ShaderStage::Fragment)
+      .language(SourceLanguage::Hlsl)
+      .spv_options(SpvOptions { validate: false, ..Default::default() });
+    let spirv = compile_input(&input, CompileOptionFlags::empty()).unwrap().spirv;
+    let options = OptimizeOptions { level: OptimizationLevel::Performance, legalize_hlsl: true, ..Default::default() };
+    let legalized = optimize_with_options(&spirv, &options).unwrap();
+    assert_eq!(validate_spirv(&legalized, TargetEnv::vulkan_1_0()), Ok(()));
+  }
+
+  #[test]
+  fn disassemble_vertex_shader() {
+    let spirv = compile_glsl_to_spirv("#version 450\nlayout(location = 0) in vec4 position;\nvoid main() { gl_Position = position; }\n", ShaderStage::Vertex, &TargetEnv::default()).unwrap();
+    let text = spirv.disassemble(DisassembleOptions::default()).unwrap();
+    assert!(text.starts_with("; SPIR-V"), "{}", text);
+    assert!(text.contains("OpEntryPoint Vertex %main \"main\""), "{}", text);
+
+    let raw = disassemble_spirv(&spirv, DisassembleOptions { friendly_names: false, header: false, ..Default::default() }).unwrap();
+    assert!(raw.contains("OpEntryPoint Vertex %4 \"main\""), "{}", raw);
+    assert!(!raw.contains("; SPIR-V"), "{}", raw);
+
+    // Not a module produced by this crate.
+    assert!(disassemble_spirv(&spirv[..6], DisassembleOptions::default()).unwrap_err().starts_with("instruction "));
+
+    let path = std::env::temp_dir().join(format!("glslang-sys-save-spvasm-{}.spvasm", std::process::id()));
+    spirv.save_spvasm(&path).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), text);
+    std::fs::remove_file(&path).unwrap();
+  }
+}
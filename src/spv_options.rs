@@ -0,0 +1,299 @@
+use std::ffi::CStr;
+
+use crate::*;
+
+/// Options of SPIR-V generation (`glslang_spv_options_t`).
+///
+/// Unless the `spirv-tools` feature is enabled, glslang is built with `ENABLE_OPT=OFF`, i.e. without SPIRV-Tools, so
+/// `disable_optimizer`, `optimize_size` and `validate` are passed on but have no effect. `strip_debug_info` and
+/// `disassemble` are implemented by this crate instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct SpvOptions {
+  /// Also enabled by [`CompileOptionFlags::GenerateDebugInfo`] and [`CompileOptionFlags::AddOpSource`].
+  pub generate_debug_info: bool,
+  /// Removes debug instructions, e.g. `OpName`, `OpLine` and `OpSource`, from the generated SPIR-V.
+  pub strip_debug_info: bool,
+  /// Skips SPIRV-Tools' optimizer, which otherwise legalizes HLSL and runs for `optimize_size`. Like `-Od` of glslangValidator.
+  pub disable_optimizer: bool,
+  /// Optimizes for size unless `disable_optimizer` is set. Like `-Os` of glslangValidator.
+  pub optimize_size: bool,
+  /// Returns the SPIR-V in textual form in [`CompileOutput::disassembly`], instead of printing it to stdout as glslang does.
+  pub disassemble: bool,
+  /// Runs the SPIR-V validator, reporting its messages as [`CompileError::Validation`].
+  pub validate: bool,
+  /// Validates the generated SPIR-V with naga, as [`Spirv::validate_with_naga`] does, reporting a rejection as
+  /// [`CompileError::Naga`]. Not part of `glslang_spv_options_t`.
+  #[cfg(feature = "naga")]
+  pub validate_with_naga: bool,
+}
+impl Default for SpvOptions {
+  /// The options [`compile`] has always used: no optimization, with validation.
+  fn default() -> Self {
+    SpvOptions {
+      validate: true,
+      ..SpvOptions::from(glslang_spv_options_t::default())
+    }
+  }
+}
+impl From<glslang_spv_options_t> for SpvOptions {
+  fn from(raw: glslang_spv_options_t) -> Self {
+    SpvOptions {
+      generate_debug_info: raw.generate_debug_info,
+      strip_debug_info: raw.strip_debug_info,
+      disable_optimizer: raw.disable_optimizer,
+      optimize_size: raw.optimize_size,
+      disassemble: raw.disassemble,
+      validate: raw.validate,
+      #[cfg(feature = "naga")]
+      validate_with_naga: false,
+    }
+  }
+}
+impl From<SpvOptions> for glslang_spv_options_t {
+  fn from(options: SpvOptions) -> Self {
+    glslang_spv_options_t {
+      generate_debug_info: options.generate_debug_info,
+      strip_debug_info: options.strip_debug_info,
+      disable_optimizer: options.disable_optimizer,
+      optimize_size: options.optimize_size,
+      disassemble: options.disassemble,
+      validate: options.validate,
+    }
+  }
+}
+impl SpvOptions {
+  /// The raw options for a compile with `option_flags`, which can only enable more debug info or stripping.
+  pub(crate) fn to_raw(self, option_flags: CompileOptionFlags) -> glslang_spv_options_t {
+    glslang_spv_options_t {
+      generate_debug_info: self.generate_debug_info || option_flags.debug_info() != DebugInfo::None,
+      strip_debug_info: self.strip_debug_info || option_flags.contains(CompileOptionFlags::StripDebugInfo),
+      ..self.into()
+    }
+  }
+}
+
+/// How much debug info the generated SPIR-V contains, as set by [`CompileOptionFlags`].
+///
+/// glslang 11.10 cannot emit `NonSemantic.Shader.DebugInfo.100`; source-level debugging in e.g. RenderDoc relies on `Full`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DebugInfo {
+  /// Only names, e.g. `OpName`, and the source language in `OpSource`.
+  None,
+  /// Adds the file and line of each instruction (`OpLine`). [`CompileOptionFlags::GenerateDebugInfo`].
+  Lines,
+  /// Adds the source text to `OpSource` as well. [`CompileOptionFlags::AddOpSource`].
+  Full,
+}
+impl From<DebugInfo> for CompileOptionFlags {
+  fn from(debug_info: DebugInfo) -> Self {
+    match debug_info {
+      DebugInfo::None => CompileOptionFlags::empty(),
+      DebugInfo::Lines => CompileOptionFlags::GenerateDebugInfo,
+      DebugInfo::Full => CompileOptionFlags::GenerateDebugInfo | CompileOptionFlags::AddOpSource,
+    }
+  }
+}
+impl CompileOptionFlags {
+  /// The debug info level of these flags. [`CompileOptionFlags::StripDebugInfo`] is not taken into account.
+  #[must_use]
+  pub fn debug_info(&self) -> DebugInfo {
+    if self.contains(CompileOptionFlags::AddOpSource) {
+      DebugInfo::Full
+    }
+    else if self.contains(CompileOptionFlags::GenerateDebugInfo) {
+      DebugInfo::Lines
+    }
+    else {
+      DebugInfo::None
+    }
+  }
+}
+
+/// Removes the debug instructions from the module `spirv`, as `spirv-opt --strip-debug` does.
+///
+/// glslang only strips debug info through SPIRV-Tools, which it is built without.
+pub(crate) fn strip_debug_info(spirv: &mut Vec<u32>) {
+  const OP_SOURCE_CONTINUED: u32 = 2;
+  const OP_SOURCE: u32 = 3;
+  const OP_SOURCE_EXTENSION: u32 = 4;
+  const OP_NAME: u32 = 5;
+  const OP_MEMBER_NAME: u32 = 6;
+  const OP_STRING: u32 = 7;
+  const OP_LINE: u32 = 8;
+  const OP_NO_LINE: u32 = 317;
+  const OP_MODULE_PROCESSED: u32 = 330;
+  const HEADER_LENGTH: usize = 5;
+
+  if spirv.len() < HEADER_LENGTH {
+    return;
+  }
+  // A malformed module is left for the consumer to reject.
+  let mut read = HEADER_LENGTH;
+  while read < spirv.len() {
+    let word_count = (spirv[read] >> 16) as usize;
+    if word_count == 0 || read + word_count > spirv.len() {
+      return;
+    }
+    read += word_count;
+  }
+
+  // Compacts in place, keeping the allocation.
+  let (mut read, mut write) = (HEADER_LENGTH, HEADER_LENGTH);
+  while read < spirv.len() {
+    let (word_count, opcode) = ((spirv[read] >> 16) as usize, spirv[read] & 0xffff);
+    let is_debug = matches!(
+      opcode,
+      OP_SOURCE_CONTINUED | OP_SOURCE | OP_SOURCE_EXTENSION | OP_NAME | OP_MEMBER_NAME | OP_STRING | OP_LINE | OP_NO_LINE | OP_MODULE_PROCESSED
+    );
+    if !is_debug {
+      spirv.copy_within(read..read + word_count, write);
+      write += word_count;
+    }
+    read += word_count;
+  }
+  spirv.truncate(write);
+}
+
+/// The textual form of the module `spirv`, from glslang's own disassembler.
+///
+/// `spirv` MUST be a module generated by glslang; the disassembler does not check instruction lengths.
+pub(crate) fn disassemble(spirv: &[u32]) -> String {
+  unsafe {
+    let text = glslang_sys_disassemble_spirv(spirv.as_ptr(), spirv.len() as size_t);
+    let disassembly = CStr::from_ptr(text).to_string_lossy().into_owned();
+    glslang_sys_free_string(text);
+    disassembly
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SOURCE: &str = "#version 450\nlayout(location = 0) out vec4 out_color;\nvoid main() {\n  out_color = vec4(1.0);\n}\n";
+
+  /// The opcodes of the instructions in `spirv`.
+  fn opcodes(spirv: &[u32]) -> Vec<u32> {
+    let mut opcodes = Vec::new();
+    let mut words = &spirv[5..];
+    while let Some(&first) = words.first() {
+      opcodes.push(first & 0xffff);
+      words = &words[(first >> 16) as usize..];
+    }
+    opcodes
+  }
+
+  #[test]
+  fn default_matches_previous_behavior() {
+    let raw = glslang_spv_options_t::from(SpvOptions::default());
+    assert!(raw.validate && raw.disable_optimizer);
+    assert!(!raw.generate_debug_info && !raw.strip_debug_info && !raw.optimize_size && !raw.disassemble);
+
+    assert!(SpvOptions::default().to_raw(CompileOptionFlags::AddOpSource).generate_debug_info);
+  }
+
+  #[test]
+  fn optimizer_options_round_trip() {
+    let (_, options) = CompileOptions::from_validator_args(&["-Os"]).unwrap();
+    let raw = options.spv_options().to_raw(CompileOptionFlags::empty());
+    assert!(raw.optimize_size && !raw.disable_optimizer);
+    assert!(raw.validate);
+
+    let (_, options) = CompileOptions::from_validator_args(&["-Os", "-Od"]).unwrap();
+    let raw = options.spv_options().to_raw(CompileOptionFlags::GenerateDebugInfo);
+    assert!(!raw.optimize_size && raw.disable_optimizer);
+    assert!(raw.generate_debug_info);
+
+    assert_eq!(SpvOptions::from(raw), SpvOptions { generate_debug_info: true, ..CompileOptions::default().spv_options() });
+  }
+
+  #[test]
+  fn debug_info_levels() {
+    for debug_info in [DebugInfo::None, DebugInfo::Lines, DebugInfo::Full] {
+      assert_eq!(CompileOptionFlags::from(debug_info).debug_info(), debug_info);
+    }
+    // `AddOpSource` implies `GenerateDebugInfo`.
+    assert_eq!(CompileOptionFlags::AddOpSource.debug_info(), DebugInfo::Full);
+
+    let input = CompileInput::new().source(SOURCE).stage(ShaderStage::Fragment);
+    let spirv = |debug_info: DebugInfo| compile_input(&input, debug_info.into()).unwrap().spirv;
+    // `OpLine` and `OpSourceContinued` or the source text in `OpSource`.
+    assert!(!opcodes(&spirv(DebugInfo::None)).contains(&8));
+    assert!(opcodes(&spirv(DebugInfo::Lines)).contains(&8));
+    assert!(spirv(DebugInfo::Full).len() > spirv(DebugInfo::Lines).len());
+  }
+
+  #[test]
+  fn strip_debug_info() {
+    let input = CompileInput::new().source(SOURCE).stage(ShaderStage::Fragment);
+    let debug = compile_input(&input, CompileOptionFlags::AddOpSource).unwrap().spirv;
+    // `OpString` and `OpName`.
+    assert!(opcodes(&debug).contains(&7) && opcodes(&debug).contains(&5));
+
+    let input = input.spv_options(SpvOptions { strip_debug_info: true, ..Default::default() });
+    let stripped = compile_input(&input, CompileOptionFlags::AddOpSource).unwrap().spirv;
+    assert!(stripped.len() < debug.len());
+    assert!(opcodes(&stripped).iter().all(|opcode| !(2..=8).contains(opcode)), "{:?}", opcodes(&stripped));
+    assert_eq!(stripped[..5], debug[..5]);
+  }
+
+  #[test]
+  fn strip_debug_info_flag() {
+    let input = CompileInput::new().source(SOURCE).stage(ShaderStage::Fragment);
+    let plain = compile_input(&input, CompileOptionFlags::empty()).unwrap().spirv;
+    // glslang emits `OpSource` and `OpName` even without debug info.
+    assert!(opcodes(&plain).contains(&3) && opcodes(&plain).contains(&5));
+
+    for option_flags in [CompileOptionFlags::StripDebugInfo, CompileOptionFlags::StripDebugInfo | CompileOptionFlags::AddOpSource] {
+      let stripped = compile_input(&input, option_flags).unwrap().spirv;
+      assert!(!opcodes(&stripped).contains(&3) && !opcodes(&stripped).contains(&5), "{:?}", opcodes(&stripped));
+    }
+  }
+
+  #[test]
+  fn disassemble() {
+    let input = CompileInput::new().source(SOURCE).stage(ShaderStage::Fragment);
+    assert_eq!(compile_input(&input, CompileOptionFlags::empty()).unwrap().disassembly, None);
+
+    let input = input.spv_options(SpvOptions { disassemble: true, ..Default::default() });
+    let disassembly = compile_input(&input, CompileOptionFlags::empty()).unwrap().disassembly.unwrap();
+    assert!(disassembly.contains("EntryPoint Fragment"), "{}", disassembly);
+    assert!(disassembly.contains("out_color"), "{}", disassembly);
+  }
+
+  #[test]
+  fn disassemble_concurrently() {
+    let sources = (0..8).map(|index| SOURCE.replace("out_color", &format!("out_color_{}", index))).collect::<Vec<_>>();
+    let disassemblies = std::thread::scope(|scope| {
+      let threads = sources.iter()
+        .map(|source| scope.spawn(move || {
+          let input = CompileInput::new()
+            .source(source)
+            .stage(ShaderStage::Fragment)
+            .spv_options(SpvOptions { disassemble: true, ..Default::default() });
+          compile_input(&input, CompileOptionFlags::empty()).unwrap().disassembly.unwrap()
+        }))
+        .collect::<Vec<_>>();
+      threads.into_iter().map(|thread| thread.join().unwrap()).collect::<Vec<_>>()
+    });
+
+    // Each disassembly is complete and only contains its own module.
+    for (index, disassembly) in disassemblies.iter().enumerate() {
+      assert_eq!(disassembly.matches("EntryPoint").count(), 1, "{}", disassembly);
+      for other in 0..sources.len() {
+        assert_eq!(disassembly.contains(&format!("out_color_{}", other)), other == index, "{}", disassembly);
+      }
+    }
+  }
+
+  #[test]
+  fn options_without_spirv_tools() {
+    // Without SPIRV-Tools, glslang ignores these.
+    let input = CompileInput::new().source(SOURCE).stage(ShaderStage::Fragment);
+    let validated = compile_input(&input, CompileOptionFlags::empty()).unwrap().spirv;
+    let input = input.spv_options(SpvOptions { validate: false, optimize_size: true, disable_optimizer: false, ..Default::default() });
+    assert_eq!(compile_input(&input, CompileOptionFlags::empty()).unwrap().spirv, validated);
+  }
+}
@@ -0,0 +1,361 @@
+use thiserror::Error;
+
+use crate::*;
+
+/// The client API version and SPIR-V version to compile for, which must be compatible with each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TargetEnv {
+  pub client: Client,
+  pub client_version: glslang_target_client_version_t,
+  pub spirv_version: glslang_target_language_version_t,
+}
+impl Default for TargetEnv {
+  fn default() -> Self {
+    Self::vulkan_1_0()
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum TargetEnvError {
+  #[error("{} is not a {:?} version", client_version_name(*.client_version), .client)]
+  ClientVersionMismatch {
+    client: Client,
+    client_version: glslang_target_client_version_t,
+  },
+  #[error("Unknown SPIR-V version: {0:#x}")]
+  UnknownSpirvVersion(glslang_target_language_version_t),
+  #[error("{} supports SPIR-V up to {}, but {} was requested", client_version_name(*.client_version), spirv_version_name(*.max_spirv_version), spirv_version_name(*.spirv_version))]
+  SpirvVersionTooNew {
+    client_version: glslang_target_client_version_t,
+    spirv_version: glslang_target_language_version_t,
+    max_spirv_version: glslang_target_language_version_t,
+  },
+  #[error("{stage:?} shaders require {} or newer, but {} was requested", spirv_version_name(*.min_spirv_version), spirv_version_name(*.spirv_version))]
+  SpirvVersionTooOld {
+    stage: ShaderStage,
+    spirv_version: glslang_target_language_version_t,
+    min_spirv_version: glslang_target_language_version_t,
+  },
+}
+
+impl TargetEnv {
+  /// Vulkan 1.0 with SPIR-V 1.0.
+  #[must_use]
+  pub const fn vulkan_1_0() -> Self {
+    Self::vulkan(glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_0, glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_0)
+  }
+  /// Vulkan 1.1 with SPIR-V 1.3.
+  #[must_use]
+  pub const fn vulkan_1_1() -> Self {
+    Self::vulkan(glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_1, glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_3)
+  }
+  /// Vulkan 1.2 with SPIR-V 1.5.
+  #[must_use]
+  pub const fn vulkan_1_2() -> Self {
+    Self::vulkan(glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_2, glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_5)
+  }
+  /// Vulkan 1.3 with SPIR-V 1.6.
+  #[must_use]
+  pub const fn vulkan_1_3() -> Self {
+    Self::vulkan(glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_3, glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_6)
+  }
+  /// OpenGL 4.5 with SPIR-V 1.0 (`GL_ARB_gl_spirv`).
+  #[must_use]
+  pub const fn opengl_450() -> Self {
+    TargetEnv {
+      client: Client::OpenGL,
+      client_version: glslang_target_client_version_t_GLSLANG_TARGET_OPENGL_450,
+      spirv_version: glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_0,
+    }
+  }
+
+  /// `client_version` with its [`TargetEnv::canonical_spirv_version`], or SPIR-V 1.0 if it has none.
+  ///
+  /// A `client_version` of another client is reported by [`TargetEnv::validate`].
+  #[must_use]
+  pub fn for_client_version(client: Client, client_version: glslang_target_client_version_t) -> Self {
+    TargetEnv {
+      client,
+      client_version,
+      spirv_version: Self::canonical_spirv_version(client_version).unwrap_or(glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_0),
+    }
+  }
+
+  /// The SPIR-V version usually paired with `client_version`: the newest a Vulkan version is guaranteed to consume, and 1.0
+  /// for OpenGL (`GL_ARB_gl_spirv`).
+  #[must_use]
+  pub fn canonical_spirv_version(client_version: glslang_target_client_version_t) -> Option<glslang_target_language_version_t> {
+    match client_version {
+      glslang_target_client_version_t_GLSLANG_TARGET_OPENGL_450 => Some(glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_0),
+      _ => vulkan_max_spirv_version(client_version),
+    }
+  }
+
+  const fn vulkan(client_version: glslang_target_client_version_t, spirv_version: glslang_target_language_version_t) -> Self {
+    TargetEnv {
+      client: Client::Vulkan,
+      client_version,
+      spirv_version,
+    }
+  }
+
+  /// Same client, with a different SPIR-V version, e.g. SPIR-V 1.0 for Vulkan 1.1 drivers with a buggy 1.3 consumer.
+  #[must_use]
+  pub const fn with_spirv_version(self, spirv_version: glslang_target_language_version_t) -> Self {
+    TargetEnv {
+      spirv_version,
+      ..self
+    }
+  }
+
+  /// The message bits of the client's semantics: [`Messages::SpvRules`], plus [`Messages::VulkanRules`] for Vulkan.
+  ///
+  /// For building a `glslang_input_t` by hand; [`CompileInput::target_env`] sets them itself. The Vulkan rules reject
+  /// valid OpenGL GLSL, e.g. uniforms outside of blocks.
+  #[must_use]
+  pub fn messages(&self) -> Messages {
+    match self.client {
+      Client::Vulkan => Messages::SpvRules | Messages::VulkanRules,
+      Client::OpenGL | Client::None => Messages::SpvRules,
+    }
+  }
+
+  /// The newest SPIR-V version `client_version` is guaranteed to consume, if there is such a limit.
+  ///
+  /// OpenGL drivers may consume newer SPIR-V than 1.0 through extensions, so no limit is imposed for OpenGL.
+  #[must_use]
+  pub fn max_spirv_version(&self) -> Option<glslang_target_language_version_t> {
+    vulkan_max_spirv_version(self.client_version)
+  }
+
+  /// A warning if the SPIR-V version is older than the [`TargetEnv::canonical_spirv_version`], which is valid, but
+  /// usually a leftover rather than a choice: newer SPIR-V features are unavailable, and extensions may be used instead.
+  ///
+  /// [`compile_input`] adds it to [`CompileOutput::warnings`].
+  #[must_use]
+  pub fn spirv_version_warning(&self) -> Option<String> {
+    let canonical_spirv_version = Self::canonical_spirv_version(self.client_version)?;
+    let older = spirv_minor_version(self.spirv_version)? < spirv_minor_version(canonical_spirv_version)?;
+    older.then(|| format!(
+      "WARNING: {} is targeted with {}, older than its {}",
+      client_version_name(self.client_version), spirv_version_name(self.spirv_version), spirv_version_name(canonical_spirv_version),
+    ))
+  }
+
+  /// Rejects client versions of another client, and SPIR-V versions newer than the client version can consume.
+  pub fn validate(&self) -> Result<(), TargetEnvError> {
+    let client_matches = match self.client {
+      Client::None => true,
+      Client::Vulkan => matches!(
+        self.client_version,
+        glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_0
+          | glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_1
+          | glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_2
+          | glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_3
+      ),
+      Client::OpenGL => self.client_version == glslang_target_client_version_t_GLSLANG_TARGET_OPENGL_450,
+    };
+    if !client_matches {
+      return Err(TargetEnvError::ClientVersionMismatch { client: self.client, client_version: self.client_version });
+    }
+
+    let spirv_minor = spirv_minor_version(self.spirv_version).ok_or(TargetEnvError::UnknownSpirvVersion(self.spirv_version))?;
+    if let Some(max_spirv_version) = self.max_spirv_version() {
+      if spirv_minor > spirv_minor_version(max_spirv_version).unwrap() {
+        return Err(TargetEnvError::SpirvVersionTooNew { client_version: self.client_version, spirv_version: self.spirv_version, max_spirv_version });
+      }
+    }
+    Ok(())
+  }
+
+  /// [`TargetEnv::validate`], also rejecting SPIR-V versions older than `stage` needs: ray tracing (`GL_EXT_ray_tracing`)
+  /// needs SPIR-V 1.4, and thus Vulkan 1.2 or newer.
+  pub fn validate_for_stage(&self, stage: ShaderStage) -> Result<(), TargetEnvError> {
+    self.validate()?;
+    if stage.is_ray_tracing() {
+      let min_spirv_version = glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_4;
+      // `validate` rejected unknown versions.
+      if spirv_minor_version(self.spirv_version).unwrap() < spirv_minor_version(min_spirv_version).unwrap() {
+        return Err(TargetEnvError::SpirvVersionTooOld { stage, spirv_version: self.spirv_version, min_spirv_version });
+      }
+    }
+    Ok(())
+  }
+}
+
+fn vulkan_max_spirv_version(client_version: glslang_target_client_version_t) -> Option<glslang_target_language_version_t> {
+  match client_version {
+    glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_0 => Some(glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_0),
+    glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_1 => Some(glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_3),
+    glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_2 => Some(glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_5),
+    glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_3 => Some(glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_6),
+    _ => None,
+  }
+}
+
+fn spirv_minor_version(spirv_version: glslang_target_language_version_t) -> Option<u32> {
+  let minor = match spirv_version {
+    glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_0 => 0,
+    glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_1 => 1,
+    glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_2 => 2,
+    glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_3 => 3,
+    glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_4 => 4,
+    glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_5 => 5,
+    glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_6 => 6,
+    _ => return None,
+  };
+  Some(minor)
+}
+
+fn spirv_version_name(spirv_version: glslang_target_language_version_t) -> String {
+  match spirv_minor_version(spirv_version) {
+    Some(minor) => format!("SPIR-V 1.{}", minor),
+    None => format!("SPIR-V {:#x}", spirv_version),
+  }
+}
+
+fn client_version_name(client_version: glslang_target_client_version_t) -> &'static str {
+  match client_version {
+    glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_0 => "Vulkan 1.0",
+    glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_1 => "Vulkan 1.1",
+    glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_2 => "Vulkan 1.2",
+    glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_3 => "Vulkan 1.3",
+    glslang_target_client_version_t_GLSLANG_TARGET_OPENGL_450 => "OpenGL 4.5",
+    _ => "Unknown client version",
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn presets_are_valid() {
+    for target_env in [TargetEnv::vulkan_1_0(), TargetEnv::vulkan_1_1(), TargetEnv::vulkan_1_2(), TargetEnv::vulkan_1_3(), TargetEnv::opengl_450()] {
+      assert_eq!(target_env.validate(), Ok(()), "{:?}", target_env);
+    }
+    assert_eq!(TargetEnv::vulkan_1_2().with_spirv_version(glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_0).validate(), Ok(()));
+  }
+
+  #[test]
+  fn spirv_version_too_new() {
+    let error = TargetEnv::vulkan_1_1().with_spirv_version(glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_6).validate().unwrap_err();
+    assert_eq!(error, TargetEnvError::SpirvVersionTooNew {
+      client_version: glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_1,
+      spirv_version: glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_6,
+      max_spirv_version: glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_3,
+    });
+    assert_eq!(error.to_string(), "Vulkan 1.1 supports SPIR-V up to SPIR-V 1.3, but SPIR-V 1.6 was requested");
+
+    assert!(TargetEnv::vulkan_1_0().with_spirv_version(glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_5).validate().is_err());
+  }
+
+  #[test]
+  fn client_version_mismatch() {
+    let target_env = TargetEnv { client: Client::OpenGL, ..TargetEnv::vulkan_1_2() };
+    assert!(matches!(target_env.validate(), Err(TargetEnvError::ClientVersionMismatch { client: Client::OpenGL, .. })));
+
+    let target_env = TargetEnv { client: Client::Vulkan, ..TargetEnv::opengl_450() };
+    assert!(matches!(target_env.validate(), Err(TargetEnvError::ClientVersionMismatch { client: Client::Vulkan, .. })));
+  }
+
+  #[test]
+  fn opengl_fragment_shader() {
+    let source = r##"
+      #version 450
+      layout(location = 0) in vec2 uv;
+      layout(location = 0) out vec4 out_color;
+      layout(binding = 0) uniform sampler2D color_texture;
+      layout(location = 0) uniform vec4 tint;
+      void main() {
+        out_color = texture(color_texture, uv) * tint;
+      }
+    "##;
+
+    assert_eq!(TargetEnv::opengl_450().messages(), Messages::SpvRules);
+    let spirv = compile_glsl_to_spirv(source, ShaderStage::Fragment, &TargetEnv::opengl_450()).unwrap();
+    assert_eq!(spirv[0], Spirv::MAGIC_NUMBER);
+    // `GL_ARB_gl_spirv` consumes SPIR-V 1.0.
+    assert_eq!(spirv[1], 0x0001_0000);
+
+    // The same source with the Vulkan message bits, as in examples written for Vulkan.
+    let input = CompileInput::new().source(source).stage(ShaderStage::Fragment).target_env(TargetEnv::opengl_450());
+    assert_eq!(Messages::from(input.as_raw().unwrap().messages), TargetEnv::opengl_450().messages());
+    let input = input.messages(TargetEnv::vulkan_1_0().messages());
+    assert!(compile_input(&input, CompileOptionFlags::empty()).is_err());
+    assert!(compile_glsl_to_spirv(source, ShaderStage::Fragment, &TargetEnv::vulkan_1_0()).is_err());
+  }
+
+  #[test]
+  fn canonical_spirv_versions() {
+    let mappings = [
+      (glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_0, glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_0, TargetEnv::vulkan_1_0()),
+      (glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_1, glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_3, TargetEnv::vulkan_1_1()),
+      (glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_2, glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_5, TargetEnv::vulkan_1_2()),
+      (glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_3, glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_6, TargetEnv::vulkan_1_3()),
+      (glslang_target_client_version_t_GLSLANG_TARGET_OPENGL_450, glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_0, TargetEnv::opengl_450()),
+    ];
+    for (client_version, spirv_version, preset) in mappings {
+      assert_eq!(TargetEnv::canonical_spirv_version(client_version), Some(spirv_version));
+      assert_eq!(TargetEnv::for_client_version(preset.client, client_version), preset);
+      assert_eq!(preset.spirv_version_warning(), None);
+    }
+    assert_eq!(TargetEnv::canonical_spirv_version(0), None);
+  }
+
+  #[test]
+  fn spirv_version_older_than_canonical() {
+    let target_env = TargetEnv::vulkan_1_3().with_spirv_version(glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_0);
+    assert_eq!(target_env.validate(), Ok(()));
+    assert_eq!(target_env.spirv_version_warning().as_deref(), Some("WARNING: Vulkan 1.3 is targeted with SPIR-V 1.0, older than its SPIR-V 1.6"));
+
+    let input = CompileInput::new().source("#version 450\nvoid main() {}\n").stage(ShaderStage::Compute).target_env(target_env);
+    let output = compile_input(&input, CompileOptionFlags::empty()).unwrap();
+    assert_eq!(output.warnings, [target_env.spirv_version_warning().unwrap()]);
+
+    // Changing the client picks its canonical version again.
+    let input = input.client(Client::Vulkan, glslang_target_client_version_t_GLSLANG_TARGET_VULKAN_1_2);
+    assert_eq!(input.as_raw().unwrap().target_language_version, glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_5);
+  }
+
+  #[test]
+  fn ray_tracing_spirv_version() {
+    let error = TargetEnv::vulkan_1_1().validate_for_stage(ShaderStage::RayGen).unwrap_err();
+    assert_eq!(error.to_string(), "RayGen shaders require SPIR-V 1.4 or newer, but SPIR-V 1.3 was requested");
+    assert!(TargetEnv::vulkan_1_1().validate_for_stage(ShaderStage::Fragment).is_ok());
+    assert!(TargetEnv::vulkan_1_2().with_spirv_version(glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_4).validate_for_stage(ShaderStage::Miss).is_ok());
+
+    let ray_gen = r##"
+      #version 460
+      #extension GL_EXT_ray_tracing : require
+      layout(set = 0, binding = 0) uniform accelerationStructureEXT scene;
+      layout(location = 0) rayPayloadEXT vec4 payload;
+      void main() {
+        traceRayEXT(scene, gl_RayFlagsOpaqueEXT, 0xff, 0, 0, 0, vec3(0.0), 0.001, vec3(0.0, 0.0, 1.0), 100.0, 0);
+      }
+    "##;
+    let closest_hit = r##"
+      #version 460
+      #extension GL_EXT_ray_tracing : require
+      layout(location = 0) rayPayloadInEXT vec4 payload;
+      hitAttributeEXT vec2 attributes;
+      void main() {
+        payload = vec4(attributes, 0.0, 1.0);
+      }
+    "##;
+    // The default target environment is raised to Vulkan 1.2, with SPIR-V 1.5.
+    for (source, stage) in [(ray_gen, ShaderStage::RayGen), (closest_hit, ShaderStage::ClosestHit)] {
+      let input = CompileInput::new().source(source).stage(stage);
+      let spirv = compile_input(&input, CompileOptionFlags::empty()).unwrap().spirv;
+      assert_eq!(spirv[1], 0x0001_0500);
+    }
+
+    let input = CompileInput::new().target_env(TargetEnv::vulkan_1_1()).source(ray_gen).stage(ShaderStage::RayGen);
+    assert!(matches!(input.as_raw(), Err(CompileError::InvalidTargetEnv(TargetEnvError::SpirvVersionTooOld { stage: ShaderStage::RayGen, .. }))));
+  }
+
+  #[test]
+  fn unknown_spirv_version() {
+    assert_eq!(TargetEnv::vulkan_1_3().with_spirv_version(0).validate(), Err(TargetEnvError::UnknownSpirvVersion(0)));
+  }
+}
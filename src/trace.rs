@@ -0,0 +1,152 @@
+//! Reports compiles through `tracing`: a span per compile, and events for the messages a compile returns.
+
+use std::ffi::CStr;
+use std::time::Instant;
+
+use tracing::field;
+use tracing::span::EnteredSpan;
+
+use crate::*;
+
+/// The `glslang_compile` span of a compile, entered until [`CompileSpan::finish`].
+pub(crate) struct CompileSpan {
+  span: EnteredSpan,
+  start: Instant,
+}
+impl CompileSpan {
+  pub(crate) fn enter(stage: glslang_stage_t, source_name: Option<&CStr>) -> Self {
+    let span = match ShaderStage::try_from(stage) {
+      Ok(stage) => tracing::info_span!("glslang_compile", stage = ?stage, source_name = field::Empty, duration_us = field::Empty),
+      Err(_) => tracing::info_span!("glslang_compile", stage, source_name = field::Empty, duration_us = field::Empty),
+    };
+    if let Some(source_name) = source_name {
+      span.record("source_name", source_name.to_string_lossy().as_ref());
+    }
+    CompileSpan { span: span.entered(), start: Instant::now() }
+  }
+
+  /// Records the duration, and emits an event per warning and SPIR-V message of `result`, or for its error.
+  pub(crate) fn finish(self, result: &Result<CompileOutput, CompileError>) {
+    self.span.record("duration_us", self.start.elapsed().as_micros() as u64);
+    match result {
+      Ok(output) => {
+        for warning in &output.warnings {
+          warning_event(warning);
+        }
+        for message in &output.spirv_messages {
+          if message.starts_with("warning:") {
+            tracing::warn!(target: "glslang_sys_2022::spirv", "{}", message);
+          }
+          else {
+            tracing::info!(target: "glslang_sys_2022::spirv", "{}", message);
+          }
+        }
+      },
+      Err(CompileError::Validation(errors)) => {
+        for error in errors.lines() {
+          tracing::error!(target: "glslang_sys_2022::validation", "{}", error);
+        }
+      },
+      Err(error) => tracing::debug!(%error, "compile failed"),
+    }
+  }
+}
+
+/// A line of an info log, or a warning added by this crate, e.g. [`TargetEnv::spirv_version_warning`].
+pub(crate) fn warning_event(warning: &str) {
+  tracing::warn!("{}", warning);
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::{Arc, Mutex};
+
+  use tracing::field::{Field, Visit};
+  use tracing::span::{Attributes, Id, Record};
+  use tracing::{Event, Level, Metadata, Subscriber};
+
+  use super::*;
+
+  /// Collects the span fields and events of the current thread as text.
+  #[derive(Default, Clone)]
+  struct Collector {
+    span_fields: Arc<Mutex<Vec<String>>>,
+    events: Arc<Mutex<Vec<(Level, String)>>>,
+  }
+  struct FieldsToText<'a>(&'a mut Vec<String>);
+  impl Visit for FieldsToText<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+      self.0.push(format!("{}={:?}", field.name(), value));
+    }
+  }
+  impl Subscriber for Collector {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+      true
+    }
+    fn new_span(&self, attributes: &Attributes<'_>) -> Id {
+      attributes.record(&mut FieldsToText(&mut self.span_fields.lock().unwrap()));
+      Id::from_u64(1)
+    }
+    fn record(&self, _span: &Id, values: &Record<'_>) {
+      values.record(&mut FieldsToText(&mut self.span_fields.lock().unwrap()));
+    }
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+    fn event(&self, event: &Event<'_>) {
+      let mut fields = Vec::new();
+      event.record(&mut FieldsToText(&mut fields));
+      self.events.lock().unwrap().push((*event.metadata().level(), fields.join(" ")));
+    }
+    fn enter(&self, _span: &Id) {}
+    fn exit(&self, _span: &Id) {}
+  }
+
+  #[test]
+  fn warnings_are_events() {
+    let collector = Collector::default();
+    let input = CompileInput::new()
+      .source("#version 450\n#extension GL_EXT_no_such_extension : enable\nvoid main() {}\n")
+      .stage(ShaderStage::Compute)
+      .source_file_name("warn.comp");
+    let output = tracing::subscriber::with_default(collector.clone(), || compile_input(&input, CompileOptionFlags::empty()).unwrap());
+
+    let events = collector.events.lock().unwrap();
+    assert_eq!(events.len(), output.warnings.len(), "{:?}", events);
+    assert_eq!(events[0].0, Level::WARN);
+    assert!(events[0].1.contains("GL_EXT_no_such_extension"), "{:?}", events);
+    let span_fields = collector.span_fields.lock().unwrap();
+    assert!(span_fields.contains(&"stage=Compute".to_owned()), "{:?}", span_fields);
+    assert!(span_fields.contains(&"source_name=\"warn.comp\"".to_owned()), "{:?}", span_fields);
+    assert!(span_fields.iter().any(|field| field.starts_with("duration_us=")), "{:?}", span_fields);
+  }
+
+  #[test]
+  fn target_env_warning_and_errors_are_events() {
+    let collector = Collector::default();
+    let target_env = TargetEnv::vulkan_1_3().with_spirv_version(glslang_target_language_version_t_GLSLANG_TARGET_SPV_1_0);
+    let input = CompileInput::new().source("#version 450\nvoid main() {}\n").stage(ShaderStage::Compute).target_env(target_env);
+    let invalid = CompileInput::new().source("#version 450\nvoid main() {\n").stage(ShaderStage::Compute);
+    tracing::subscriber::with_default(collector.clone(), || {
+      compile_input(&input, CompileOptionFlags::empty()).unwrap();
+      compile_input(&invalid, CompileOptionFlags::empty()).unwrap_err();
+    });
+
+    let events = collector.events.lock().unwrap();
+    assert_eq!(events.len(), 2, "{:?}", events);
+    assert_eq!(events[0], (Level::WARN, format!("message={}", target_env.spirv_version_warning().unwrap())));
+    assert_eq!(events[1].0, Level::DEBUG);
+    assert!(events[1].1.contains("compile failed"), "{:?}", events);
+  }
+
+  #[test]
+  fn resource_limit_warnings_are_events() {
+    let collector = Collector::default();
+    let limits = ResourceLimits::default().with_max_draw_buffers(2);
+    let input = CompileInput::new().source("#version 450\nvoid main() {}\n").stage(ShaderStage::Compute).resource(&limits);
+    tracing::subscriber::with_default(collector.clone(), || {
+      compile_input(&input, CompileOptionFlags::empty()).unwrap();
+    });
+
+    let events = collector.events.lock().unwrap();
+    assert_eq!(*events, [(Level::WARN, "message=WARNING: Resource limit max_draw_buffers = 2 should be at least the minimum-spec value 4".to_owned())]);
+  }
+}
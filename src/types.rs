@@ -0,0 +1,217 @@
+//! Typed counterparts of the raw binding enums.
+
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::*;
+
+/// A raw binding value with no counterpart in the typed enum, e.g. one added by a newer glslang.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("Unknown {type_name} value: {value}")]
+pub struct UnknownEnumValue {
+  pub type_name: &'static str,
+  pub value: i64,
+}
+
+/// Defines a Rust enum mapping one-to-one onto some of the constants of a raw binding enum,
+/// with `From<$name> for $raw` and `TryFrom<$raw> for $name`.
+macro_rules! raw_enum {
+  (
+    $(#[$meta:meta])*
+    pub enum $name:ident: $raw:ty {
+      $($(#[$variant_meta:meta])* $variant:ident = $constant:ident,)+
+    }
+  ) => {
+    $(#[$meta])*
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub enum $name {
+      $($(#[$variant_meta])* $variant,)+
+    }
+    impl $name {
+      /// Every variant, in the order of the raw constants.
+      pub const ALL: &'static [$name] = &[$($name::$variant,)+];
+    }
+    impl From<$name> for $raw {
+      fn from(value: $name) -> Self {
+        match value {
+          $($name::$variant => $constant,)+
+        }
+      }
+    }
+    impl TryFrom<$raw> for $name {
+      type Error = UnknownEnumValue;
+      fn try_from(value: $raw) -> Result<Self, Self::Error> {
+        match value {
+          $($constant => Ok($name::$variant),)+
+          _ => Err(UnknownEnumValue { type_name: stringify!($name), value: value.into() }),
+        }
+      }
+    }
+  };
+}
+
+raw_enum! {
+  /// `glslang_stage_t`.
+  pub enum ShaderStage: glslang_stage_t {
+    Vertex = glslang_stage_t_GLSLANG_STAGE_VERTEX,
+    TessControl = glslang_stage_t_GLSLANG_STAGE_TESSCONTROL,
+    TessEvaluation = glslang_stage_t_GLSLANG_STAGE_TESSEVALUATION,
+    Geometry = glslang_stage_t_GLSLANG_STAGE_GEOMETRY,
+    Fragment = glslang_stage_t_GLSLANG_STAGE_FRAGMENT,
+    Compute = glslang_stage_t_GLSLANG_STAGE_COMPUTE,
+    RayGen = glslang_stage_t_GLSLANG_STAGE_RAYGEN_NV,
+    Intersect = glslang_stage_t_GLSLANG_STAGE_INTERSECT_NV,
+    AnyHit = glslang_stage_t_GLSLANG_STAGE_ANYHIT_NV,
+    ClosestHit = glslang_stage_t_GLSLANG_STAGE_CLOSESTHIT_NV,
+    Miss = glslang_stage_t_GLSLANG_STAGE_MISS_NV,
+    Callable = glslang_stage_t_GLSLANG_STAGE_CALLABLE_NV,
+    /// `GL_NV_mesh_shader`. `GL_EXT_mesh_shader` needs a newer glslang than the one this crate is built against.
+    Task = glslang_stage_t_GLSLANG_STAGE_TASK_NV,
+    /// `GL_NV_mesh_shader`, see [`ShaderStage::Task`].
+    Mesh = glslang_stage_t_GLSLANG_STAGE_MESH_NV,
+  }
+}
+
+raw_enum! {
+  /// `glslang_source_t`.
+  pub enum SourceLanguage: glslang_source_t {
+    Glsl = glslang_source_t_GLSLANG_SOURCE_GLSL,
+    Hlsl = glslang_source_t_GLSLANG_SOURCE_HLSL,
+  }
+}
+
+raw_enum! {
+  /// `glslang_client_t`: the API whose semantics the shader is compiled for.
+  pub enum Client: glslang_client_t {
+    /// Neither; e.g. GLSL for OpenGL without SPIR-V.
+    None = glslang_client_t_GLSLANG_CLIENT_NONE,
+    Vulkan = glslang_client_t_GLSLANG_CLIENT_VULKAN,
+    OpenGL = glslang_client_t_GLSLANG_CLIENT_OPENGL,
+  }
+}
+
+raw_enum! {
+  /// `glslang_profile_t`: the GLSL profile, as in `#version 450 core`.
+  pub enum Profile: glslang_profile_t {
+    /// No profile; e.g. desktop GLSL before 150.
+    None = glslang_profile_t_GLSLANG_NO_PROFILE,
+    Core = glslang_profile_t_GLSLANG_CORE_PROFILE,
+    Compatibility = glslang_profile_t_GLSLANG_COMPATIBILITY_PROFILE,
+    Es = glslang_profile_t_GLSLANG_ES_PROFILE,
+  }
+}
+
+impl ShaderStage {
+  /// Whether this is one of the `GL_EXT_ray_tracing` stages.
+  #[must_use]
+  pub fn is_ray_tracing(self) -> bool {
+    matches!(self, ShaderStage::RayGen | ShaderStage::Intersect | ShaderStage::AnyHit | ShaderStage::ClosestHit | ShaderStage::Miss | ShaderStage::Callable)
+  }
+
+  /// The file extension glslangValidator uses for the stage, without the dot, e.g. `vert`. Also the stage name of `-S`.
+  #[must_use]
+  pub fn extension(self) -> &'static str {
+    match self {
+      ShaderStage::Vertex => "vert",
+      ShaderStage::TessControl => "tesc",
+      ShaderStage::TessEvaluation => "tese",
+      ShaderStage::Geometry => "geom",
+      ShaderStage::Fragment => "frag",
+      ShaderStage::Compute => "comp",
+      ShaderStage::RayGen => "rgen",
+      ShaderStage::Intersect => "rint",
+      ShaderStage::AnyHit => "rahit",
+      ShaderStage::ClosestHit => "rchit",
+      ShaderStage::Miss => "rmiss",
+      ShaderStage::Callable => "rcall",
+      ShaderStage::Task => "task",
+      ShaderStage::Mesh => "mesh",
+    }
+  }
+
+  /// The stage of a file extension without the dot, e.g. `frag`; the inverse of [`ShaderStage::extension`].
+  #[must_use]
+  pub fn from_extension(extension: &str) -> Option<ShaderStage> {
+    ShaderStage::ALL.iter().copied().find(|stage| stage.extension() == extension)
+  }
+
+  /// The stage of a file, from its extension like glslangValidator.
+  ///
+  /// Generic `.glsl` and `.hlsl` files name their stage with the extension before, e.g. `shadow.frag.glsl`. Files with
+  /// neither, e.g. `common.glsl`, have no stage to infer; name one explicitly instead.
+  #[must_use]
+  pub fn from_path(path: &Path) -> Option<ShaderStage> {
+    let extension = path.extension()?.to_str()?;
+    if extension == "glsl" || extension == "hlsl" {
+      return Self::from_path(Path::new(path.file_stem()?));
+    }
+    Self::from_extension(extension)
+  }
+
+  /// The extensions [`ShaderStage::from_path`] recognizes, for messages, e.g. `.vert, .tesc, ...`.
+  pub(crate) fn supported_extensions() -> String {
+    ShaderStage::ALL.iter().map(|stage| format!(".{}", stage.extension())).collect::<Vec<_>>().join(", ")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn shader_stage_round_trip() {
+    assert_eq!(ShaderStage::ALL.len() as glslang_stage_t, glslang_stage_t_GLSLANG_STAGE_COUNT);
+    for &stage in ShaderStage::ALL {
+      assert_eq!(ShaderStage::try_from(glslang_stage_t::from(stage)), Ok(stage));
+    }
+    assert_eq!(glslang_stage_t::from(ShaderStage::Fragment), glslang_stage_t_GLSLANG_STAGE_FRAGMENT);
+  }
+
+  #[test]
+  fn unknown_shader_stage() {
+    let error = ShaderStage::try_from(glslang_stage_t_GLSLANG_STAGE_COUNT).unwrap_err();
+    assert_eq!(error, UnknownEnumValue { type_name: "ShaderStage", value: glslang_stage_t_GLSLANG_STAGE_COUNT.into() });
+    assert!(error.to_string().contains("ShaderStage"));
+  }
+
+  #[test]
+  fn stage_from_path() {
+    for &stage in ShaderStage::ALL {
+      assert_eq!(ShaderStage::from_extension(stage.extension()), Some(stage));
+      assert_eq!(ShaderStage::from_path(Path::new(&format!("shaders/main.{}", stage.extension()))), Some(stage));
+    }
+    assert_eq!(ShaderStage::from_path(Path::new("shadow.frag.glsl")), Some(ShaderStage::Fragment));
+    assert_eq!(ShaderStage::from_path(Path::new("blur.comp.hlsl")), Some(ShaderStage::Compute));
+    assert_eq!(ShaderStage::from_path(Path::new("common.glsl")), None);
+    assert_eq!(ShaderStage::from_path(Path::new("shader.txt")), None);
+    assert_eq!(ShaderStage::from_path(Path::new("vert")), None);
+    assert_eq!(ShaderStage::from_extension("VERT"), None);
+  }
+
+  #[test]
+  fn source_language_and_client_round_trip() {
+    for &language in SourceLanguage::ALL {
+      assert_eq!(SourceLanguage::try_from(glslang_source_t::from(language)), Ok(language));
+    }
+    for &client in Client::ALL {
+      assert_eq!(Client::try_from(glslang_client_t::from(client)), Ok(client));
+    }
+  }
+
+  #[test]
+  fn profile_round_trip() {
+    for &profile in Profile::ALL {
+      assert_eq!(Profile::try_from(glslang_profile_t::from(profile)), Ok(profile));
+    }
+    assert!(Profile::try_from(glslang_profile_t_GLSLANG_BAD_PROFILE).is_err());
+  }
+
+  #[test]
+  fn out_of_range_source_language_and_client() {
+    assert!(SourceLanguage::try_from(glslang_source_t_GLSLANG_SOURCE_NONE).is_err());
+    assert!(SourceLanguage::try_from(glslang_source_t_GLSLANG_SOURCE_COUNT).is_err());
+    assert_eq!(Client::try_from(glslang_client_t_GLSLANG_CLIENT_COUNT).unwrap_err().type_name, "Client");
+  }
+}
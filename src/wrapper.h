@@ -1,4 +1,9 @@
+#include "glslang/build_info.h"
 
 #include "glslang/Include/glslang_c_shader_types.h"
 #include "glslang/Include/glslang_c_interface.h"
+#include "shim.h"
 
+#ifdef GLSLANG_SYS_SPIRV_TOOLS
+#include "spirv-tools/libspirv.h"
+#endif
@@ -0,0 +1,108 @@
+//! Drives the `glslang-rs` binary of the `cli` feature over the fixture shaders.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use glslang_sys_2022 as glslang_sys;
+use glslang_sys::*;
+
+fn fixture_path(name: &str) -> PathBuf {
+  Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+fn glslang_rs(args: &[&str]) -> Output {
+  Command::new(env!("CARGO_BIN_EXE_glslang-rs")).args(args).output().unwrap()
+}
+
+/// A directory for the outputs of one test, removed with `scopeguard::defer!`.
+fn output_dir(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("glslang-sys-cli-{}-{}", name, std::process::id()));
+  std::fs::create_dir_all(&dir).unwrap();
+  dir
+}
+
+#[test]
+fn compile_to_output_file() {
+  let dir = output_dir("output-file");
+  scopeguard::defer! {
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+  let output_path = dir.join("triangle.spv");
+  let output = glslang_rs(&["-V", "-o", output_path.to_str().unwrap(), fixture_path("triangle.vert").to_str().unwrap()]);
+  assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+  let expected = compile_file(fixture_path("triangle.vert"), CompileOptionFlags::empty()).unwrap().spirv;
+  assert_eq!(Spirv::load(&output_path).unwrap(), expected);
+}
+
+#[test]
+fn defines_include_dirs_and_target_env() {
+  let dir = output_dir("options");
+  scopeguard::defer! {
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+  let output_path = dir.join("tinted.spv");
+  let include_dir = format!("-I{}", fixture_path("include").display());
+  let output = glslang_rs(&[
+    "-V", "--target-env", "vulkan1.2", "-g", "-DSCALE=2.0", &include_dir,
+    "-o", output_path.to_str().unwrap(), fixture_path("tinted.frag").to_str().unwrap(),
+  ]);
+  assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+  let spirv = Spirv::load(&output_path).unwrap();
+  assert_eq!(spirv[1], 0x0001_0500);
+  // `-g` adds the source file name for `OpLine`.
+  let file_name = fixture_path("tinted.frag").to_string_lossy().into_owned().into_bytes();
+  assert!(spirv.as_bytes().windows(file_name.len()).any(|bytes| bytes == file_name));
+}
+
+#[test]
+fn entry_point_of_glsl_input() {
+  let dir = output_dir("entry-point");
+  scopeguard::defer! {
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+  let output_path = dir.join("triangle.spv");
+  let output = glslang_rs(&["-V", "-e", "mainVS", "-o", output_path.to_str().unwrap(), fixture_path("triangle.vert").to_str().unwrap()]);
+  assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+  let input = CompileInput::from_file(fixture_path("triangle.vert")).unwrap().entry_point("mainVS");
+  let expected = compile_input(&input, CompileOptionFlags::empty()).unwrap().spirv;
+  let spirv = Spirv::load(&output_path).unwrap();
+  assert_eq!(spirv, expected);
+  assert!(spirv.as_bytes().windows(b"mainVS\0".len()).any(|bytes| bytes == b"mainVS\0"));
+}
+
+#[test]
+fn diagnostics_on_failure() {
+  let include_dir = format!("-I{}", fixture_path("include").display());
+  let frag = fixture_path("tinted.frag");
+
+  // `SCALE` is not defined.
+  let output = glslang_rs(&["-V", &include_dir, frag.to_str().unwrap()]);
+  assert_eq!(output.status.code(), Some(1));
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  assert!(stderr.contains("error: 'SCALE' : undeclared identifier"), "{}", stderr);
+  assert!(stderr.contains("| ") && stderr.contains("* TINT * SCALE;"), "{}", stderr);
+
+  // `tint.glsl` is not found without `-I`.
+  let output = glslang_rs(&["-V", "-DSCALE=2.0", frag.to_str().unwrap()]);
+  assert_eq!(output.status.code(), Some(1));
+  assert!(String::from_utf8_lossy(&output.stderr).contains("tint.glsl"), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn invalid_arguments() {
+  let output = glslang_rs(&["-V", "--no-such-flag", "shader.vert"]);
+  assert_eq!(output.status.code(), Some(2));
+  assert!(String::from_utf8_lossy(&output.stderr).contains("--no-such-flag"));
+
+  let output = glslang_rs(&["-V"]);
+  assert_eq!(output.status.code(), Some(2));
+  assert!(String::from_utf8_lossy(&output.stderr).contains("No input file"));
+
+  // Stage inference fails before the file is read.
+  let output = glslang_rs(&[fixture_path("include/kernel.glsl").to_str().unwrap()]);
+  assert_eq!(output.status.code(), Some(2));
+  assert!(String::from_utf8_lossy(&output.stderr).contains("-S"));
+}
@@ -0,0 +1,17 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform Camera {
+  mat4 view_projection;
+};
+layout(push_constant) uniform Model {
+  mat4 model;
+};
+
+layout(location = 0) in vec3 in_position;
+layout(location = 1) in vec3 in_normal;
+layout(location = 0) out vec3 out_normal;
+
+void main() {
+  out_normal = mat3(model) * in_normal;
+  gl_Position = view_projection * model * vec4(in_position, 1.0);
+}
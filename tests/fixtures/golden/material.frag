@@ -0,0 +1,19 @@
+#version 450
+#extension GL_GOOGLE_include_directive : require
+
+#include "material_common.glsl"
+
+layout(constant_id = 0) const float EXPOSURE = 1.0;
+
+layout(set = 1, binding = 2) uniform sampler2D textures[4];
+
+layout(location = 0) in vec2 in_uv;
+layout(location = 1) flat in int in_texture;
+layout(location = 0) out vec4 out_color;
+layout(location = 1) out vec4 out_emissive;
+
+void main() {
+  vec4 color = texture(textures[in_texture], in_uv);
+  out_color = tonemap(color * EXPOSURE);
+  out_emissive = vec4(0.0);
+}
@@ -0,0 +1,3 @@
+vec4 tonemap(vec4 color) {
+  return color / (color + vec4(1.0));
+}
@@ -0,0 +1,7 @@
+Texture2D<float4> scene_color : register(t0);
+SamplerState scene_sampler : register(s1);
+
+float4 main(float2 uv : TEXCOORD0) : SV_Target0 {
+  float4 color = scene_color.Sample(scene_sampler, uv);
+  return color / (color + 1.0);
+}
@@ -0,0 +1,6 @@
+#define KERNEL_SIZE 5
+
+float kernel_weight(int offset) {
+  const float weights[KERNEL_SIZE] = float[](0.0625, 0.25, 0.375, 0.25, 0.0625);
+  return weights[offset];
+}
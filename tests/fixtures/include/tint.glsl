@@ -0,0 +1 @@
+#define TINT vec4(1.0, 0.5, 0.25, 1.0)
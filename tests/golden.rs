@@ -0,0 +1,239 @@
+//! Compiles the shaders in `tests/fixtures/golden` and compares their SPIR-V with the `.golden` file next to each.
+//!
+//! A golden holds properties that survive glslang updates: the SPIR-V version, the entry points, the instruction count
+//! (within a tolerance), and the decorations a pipeline depends on, e.g. `Location` and `Binding`. Regenerate them with:
+//!
+//! ```text
+//! UPDATE_GOLDENS=1 cargo test --test golden
+//! ```
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use glslang_sys_2022 as glslang_sys;
+use glslang_sys::*;
+
+/// The generator id of glslang, registered with Khronos, in the upper half of the generator word of the header.
+const GLSLANG_GENERATOR_ID: u32 = 8;
+
+/// The properties of a module compared with a golden.
+#[derive(Debug, PartialEq, Eq)]
+struct Golden {
+  /// `major.minor`.
+  version: String,
+  /// (execution model, name)
+  entry_points: Vec<(String, String)>,
+  instructions: usize,
+  /// `<decoration> <literals>`, sorted.
+  decorations: Vec<String>,
+}
+
+impl Golden {
+  /// Checks the header of `spirv` and reads its properties.
+  fn from_spirv(spirv: &[u32]) -> Result<Self, String> {
+    if spirv.len() < 5 || spirv[0] != Spirv::MAGIC_NUMBER {
+      return Err("Missing SPIR-V header".to_owned());
+    }
+    if spirv[2] >> 16 != GLSLANG_GENERATOR_ID {
+      return Err(format!("Unexpected generator {:#010x}", spirv[2]));
+    }
+    if spirv[3] == 0 || spirv[4] != 0 {
+      return Err(format!("Invalid bound {} or schema {}", spirv[3], spirv[4]));
+    }
+
+    let mut golden = Golden {
+      version: format!("{}.{}", (spirv[1] >> 16) & 0xff, (spirv[1] >> 8) & 0xff),
+      entry_points: Vec::new(),
+      instructions: 0,
+      decorations: Vec::new(),
+    };
+    let mut words = &spirv[5..];
+    while let Some(&first) = words.first() {
+      let (word_count, opcode) = ((first >> 16) as usize, first & 0xffff);
+      if word_count == 0 || word_count > words.len() {
+        return Err(format!("Truncated instruction {} after {} instructions", opcode, golden.instructions));
+      }
+      let instruction = &words[..word_count];
+      match opcode {
+        OP_ENTRY_POINT => {
+          let name = instruction[3..].iter().flat_map(|word| word.to_le_bytes()).take_while(|&byte| byte != 0).collect::<Vec<_>>();
+          golden.entry_points.push((execution_model_name(instruction[1]), String::from_utf8_lossy(&name).into_owned()));
+        },
+        OP_DECORATE => {
+          if let Some(name) = decoration_name(instruction[2]) {
+            let literals = instruction[3..].iter().map(|literal| format!(" {}", literal)).collect::<String>();
+            golden.decorations.push(format!("{}{}", name, literals));
+          }
+        },
+        _ => {},
+      }
+      golden.instructions += 1;
+      words = &words[word_count..];
+    }
+    golden.decorations.sort();
+    Ok(golden)
+  }
+
+  fn parse(text: &str) -> Result<Self, String> {
+    let mut golden = Golden { version: String::new(), entry_points: Vec::new(), instructions: 0, decorations: Vec::new() };
+    for line in text.lines().filter(|line| !line.is_empty() && !line.starts_with('#')) {
+      let (key, value) = line.split_once(' ').ok_or_else(|| format!("Invalid line {:?}", line))?;
+      match key {
+        "version" => golden.version = value.to_owned(),
+        "entry_point" => {
+          let (model, name) = value.split_once(' ').ok_or_else(|| format!("Invalid entry point {:?}", value))?;
+          golden.entry_points.push((model.to_owned(), name.to_owned()));
+        },
+        "instructions" => golden.instructions = value.parse().map_err(|_| format!("Invalid instruction count {:?}", value))?,
+        "decoration" => golden.decorations.push(value.to_owned()),
+        _ => return Err(format!("Unknown key {:?}", key)),
+      }
+    }
+    golden.decorations.sort();
+    Ok(golden)
+  }
+
+  /// The differences from `expected`, with the instruction count allowed to differ by a quarter, or by 8 for small modules.
+  fn differences(&self, expected: &Golden) -> Vec<String> {
+    let mut differences = Vec::new();
+    if self.version != expected.version {
+      differences.push(format!("version {} instead of {}", self.version, expected.version));
+    }
+    if self.entry_points != expected.entry_points {
+      differences.push(format!("entry points {:?} instead of {:?}", self.entry_points, expected.entry_points));
+    }
+    if self.instructions.abs_diff(expected.instructions) > (expected.instructions / 4).max(8) {
+      differences.push(format!("{} instructions instead of about {}", self.instructions, expected.instructions));
+    }
+    if self.decorations != expected.decorations {
+      differences.push(format!("decorations {:?} instead of {:?}", self.decorations, expected.decorations));
+    }
+    differences
+  }
+}
+
+impl std::fmt::Display for Golden {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    writeln!(f, "# Regenerate with `UPDATE_GOLDENS=1 cargo test --test golden`.")?;
+    writeln!(f, "version {}", self.version)?;
+    for (model, name) in &self.entry_points {
+      writeln!(f, "entry_point {} {}", model, name)?;
+    }
+    writeln!(f, "instructions {}", self.instructions)?;
+    for decoration in &self.decorations {
+      writeln!(f, "decoration {}", decoration)?;
+    }
+    Ok(())
+  }
+}
+
+const OP_ENTRY_POINT: u32 = 15;
+const OP_DECORATE: u32 = 71;
+
+fn execution_model_name(model: u32) -> String {
+  let name = match model {
+    0 => "Vertex",
+    1 => "TessellationControl",
+    2 => "TessellationEvaluation",
+    3 => "Geometry",
+    4 => "Fragment",
+    5 => "GLCompute",
+    5267 => "TaskNV",
+    5268 => "MeshNV",
+    5313 => "RayGenerationKHR",
+    5314 => "IntersectionKHR",
+    5315 => "AnyHitKHR",
+    5316 => "ClosestHitKHR",
+    5317 => "MissKHR",
+    5318 => "CallableKHR",
+    _ => return model.to_string(),
+  };
+  name.to_owned()
+}
+
+/// The decorations recorded in goldens: those of the interface of a shader, not the ones glslang adds for its own
+/// bookkeeping or that depend on how it lays out types, e.g. `BuiltIn` or `ArrayStride`.
+fn decoration_name(decoration: u32) -> Option<&'static str> {
+  match decoration {
+    1 => Some("SpecId"),
+    2 => Some("Block"),
+    3 => Some("BufferBlock"),
+    14 => Some("Flat"),
+    30 => Some("Location"),
+    31 => Some("Component"),
+    33 => Some("Binding"),
+    34 => Some("DescriptorSet"),
+    _ => None,
+  }
+}
+
+fn golden_dir() -> PathBuf {
+  Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/golden")
+}
+
+/// The shaders of the golden directory: files with a stage, skipping includes like `material_common.glsl`.
+fn golden_shaders() -> Vec<PathBuf> {
+  let mut paths: Vec<PathBuf> = std::fs::read_dir(golden_dir())
+    .unwrap()
+    .map(|entry| entry.unwrap().path())
+    .filter(|path| ShaderStage::from_path(path).is_some())
+    .filter(|path| cfg!(feature = "hlsl") || path.extension().is_some_and(|extension| extension != "hlsl"))
+    .collect();
+  paths.sort();
+  paths
+}
+
+#[test]
+fn goldens() {
+  let update = std::env::var_os("UPDATE_GOLDENS").is_some_and(|value| value == "1");
+  let mut failures = String::new();
+  for path in &golden_shaders() {
+    let name = path.file_name().unwrap().to_string_lossy();
+    let golden_path = path.with_file_name(format!("{}.golden", name));
+    let actual = match compile_file(path, CompileOptionFlags::empty()).map_err(|error| error.to_string()).and_then(|output| Golden::from_spirv(&output.spirv)) {
+      Ok(actual) => actual,
+      Err(error) => {
+        writeln!(failures, "{}: {}", name, error).unwrap();
+        continue;
+      },
+    };
+    if update {
+      std::fs::write(&golden_path, actual.to_string()).unwrap();
+      continue;
+    }
+
+    let expected = match std::fs::read_to_string(&golden_path) {
+      Ok(text) => Golden::parse(&text),
+      Err(error) => Err(error.to_string()),
+    };
+    match expected {
+      Ok(expected) => {
+        for difference in actual.differences(&expected) {
+          writeln!(failures, "{}: {}", name, difference).unwrap();
+        }
+      },
+      Err(error) => writeln!(failures, "{}: Invalid golden {}: {}", name, golden_path.display(), error).unwrap(),
+    }
+  }
+  assert!(failures.is_empty(), "{}Run with UPDATE_GOLDENS=1 to accept the new output.", failures);
+}
+
+#[test]
+fn every_stage_has_a_golden() {
+  let stages: Vec<ShaderStage> = golden_shaders().iter().filter_map(|path| ShaderStage::from_path(path)).collect();
+  for stage in ShaderStage::ALL {
+    assert!(stages.contains(stage), "No golden shader for {:?}", stage);
+  }
+}
+
+#[test]
+fn golden_round_trips() {
+  let golden = Golden {
+    version: "1.5".to_owned(),
+    entry_points: vec![("RayGenerationKHR".to_owned(), "main".to_owned())],
+    instructions: 84,
+    decorations: vec!["Binding 0".to_owned(), "DescriptorSet 0".to_owned()],
+  };
+  assert_eq!(Golden::parse(&golden.to_string()), Ok(golden));
+  assert!(Golden::parse("version 1.0\nunknown 1\n").is_err());
+}